@@ -0,0 +1,2705 @@
+mod session;
+#[cfg(all(feature = "test-util", not(target_arch = "wasm32")))]
+pub mod test_util;
+#[cfg(all(feature = "vcr", not(target_arch = "wasm32")))]
+pub mod vcr;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use session::CookieFile;
+pub use session::SessionStore;
+#[cfg(all(feature = "browser-cookies", not(target_arch = "wasm32")))]
+pub use session::{Browser, BrowserProfile};
+
+#[derive(Debug)]
+pub struct PostClient {
+    client: reqwest::Client,
+    strict: bool,
+    referer: RefererPolicy,
+    accept_language: String,
+    rate_limiter: std::sync::Arc<RateLimiter>,
+    /// Whether `rate_limiter` was given by `PostClient::rate_limiter`, which `interval` and
+    /// `jitter` must not replace
+    shared_rate_limiter: bool,
+    block_retries: u32,
+    retries: u32,
+    listing_concurrency: usize,
+    #[cfg(not(target_arch = "wasm32"))]
+    api_timeout: std::time::Duration,
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    idle_timeout: std::time::Duration,
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    copy_buffer_size: usize,
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    memory_limit: Option<MemoryLimit>,
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    fsync: bool,
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    transfer_stats: std::sync::Arc<TransferStats>,
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    response_cache: Option<ResponseCache>,
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    recent_requests: Option<RecentRequests>,
+    #[cfg(not(target_arch = "wasm32"))]
+    offline: bool,
+    #[cfg(all(feature = "tower", not(target_arch = "wasm32")))]
+    service: HttpService,
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    cancellation_token: Option<tokio_util::sync::CancellationToken>,
+}
+
+/// Cap of memory buffering downloads shared by clones of `PostClient`.
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone)]
+struct MemoryLimit {
+    /// Permits of the semaphore are bytes
+    semaphore: std::sync::Arc<tokio::sync::Semaphore>,
+    bytes: usize,
+}
+
+/// Directory caching bodies of API responses, which are fresh for `ttl` since written.
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone)]
+struct ResponseCache {
+    dir: std::path::PathBuf,
+    ttl: std::time::Duration,
+}
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+impl ResponseCache {
+    /// Returns the path caching the response of `url`.
+    fn path(&self, url: &str) -> std::path::PathBuf {
+        // FNV-1a, which is stable across builds unlike DefaultHasher
+        let hash = url.bytes().fold(0xcbf29ce484222325_u64, |hash, b| {
+            (hash ^ u64::from(b)).wrapping_mul(0x100000001b3)
+        });
+        self.dir.join(format!("{:016x}.json", hash))
+    }
+
+    /// Reads the cached response of `url` unless it's missing, or expired when `fresh` is set.
+    async fn read(&self, url: &str, fresh: bool) -> Option<Vec<u8>> {
+        let path = self.path(url);
+        if fresh {
+            let modified = tokio::fs::metadata(&path).await.ok()?.modified().ok()?;
+            if modified.elapsed().map_or(true, |age| age > self.ttl) {
+                return None;
+            }
+        }
+        tokio::fs::read(&path).await.ok()
+    }
+
+    async fn write(&self, url: &str, body: &[u8]) -> Result<(), std::io::Error> {
+        let path = self.path(url);
+        let tmp_path = path.with_extension("json.part");
+        tokio::fs::create_dir_all(&self.dir).await?;
+        tokio::fs::write(&tmp_path, body).await?;
+        tokio::fs::rename(&tmp_path, &path).await
+    }
+}
+
+/// Responses of API calls and files downloaded by recent requests, which are shared with identical
+/// requests instead of sending them again.
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+#[derive(Debug, Default)]
+struct RecentRequests {
+    /// Bodies of API responses by URL, with the time when first requested
+    responses:
+        std::sync::Mutex<std::collections::HashMap<String, (std::time::Instant, SharedResponse)>>,
+    /// Files downloaded from each URL, with the result of the download
+    downloads:
+        std::sync::Mutex<std::collections::HashMap<String, (std::path::PathBuf, DownloadedMedia)>>,
+}
+
+/// Body of an API response shared by identical requests, which is set by the first one
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+type SharedResponse = std::sync::Arc<tokio::sync::OnceCell<bytes::Bytes>>;
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+impl RecentRequests {
+    /// Returns the cell of the response of `url`, which is shared while requested within
+    /// `RECENT_RESPONSE_TTL`.
+    fn response(&self, url: &str) -> SharedResponse {
+        let now = std::time::Instant::now();
+        let mut responses = self.responses.lock().unwrap();
+        responses.retain(|_, (requested_at, _)| now - *requested_at < RECENT_RESPONSE_TTL);
+        responses
+            .entry(url.to_owned())
+            .or_insert_with(|| (now, Default::default()))
+            .1
+            .clone()
+    }
+
+    /// Returns the file downloaded from `url` if it's still there as downloaded.
+    fn download(&self, url: &str) -> Option<(std::path::PathBuf, DownloadedMedia)> {
+        let (path, media) = self.downloads.lock().unwrap().get(url)?.clone();
+        let len = std::fs::metadata(&path).ok()?.len();
+        (len == media.bytes).then_some((path, media))
+    }
+}
+
+/// Throughput of downloads, which can be shared by multiple `PostClient`s to aggregate their
+/// concurrent downloads.
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+#[derive(Debug)]
+pub struct TransferStats {
+    started: std::time::Instant,
+    bytes: std::sync::atomic::AtomicU64,
+    files: std::sync::atomic::AtomicU64,
+    active_files: std::sync::atomic::AtomicU64,
+    remaining_bytes: std::sync::atomic::AtomicU64,
+}
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+impl Default for TransferStats {
+    fn default() -> Self {
+        Self {
+            started: std::time::Instant::now(),
+            bytes: Default::default(),
+            files: Default::default(),
+            active_files: Default::default(),
+            remaining_bytes: Default::default(),
+        }
+    }
+}
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+impl TransferStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the current numbers.
+    pub fn snapshot(&self) -> TransferSnapshot {
+        use std::sync::atomic::Ordering;
+
+        TransferSnapshot {
+            bytes: self.bytes.load(Ordering::Relaxed),
+            files: self.files.load(Ordering::Relaxed),
+            active_files: self.active_files.load(Ordering::Relaxed),
+            remaining_bytes: self.remaining_bytes.load(Ordering::Relaxed),
+            elapsed: self.started.elapsed(),
+        }
+    }
+}
+
+/// Numbers of `TransferStats` at a point of time.
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferSnapshot {
+    /// Bytes received by all downloads, including ones in progress
+    pub bytes: u64,
+    /// Number of completed downloads
+    pub files: u64,
+    /// Number of downloads in progress
+    pub active_files: u64,
+    /// Bytes yet to be received by the downloads in progress whose sizes are known
+    pub remaining_bytes: u64,
+    /// Time since the stats were created
+    pub elapsed: std::time::Duration,
+}
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+impl TransferSnapshot {
+    /// Returns bytes per second received since `earlier`.
+    pub fn speed_since(&self, earlier: &Self) -> f64 {
+        let seconds = self.elapsed.saturating_sub(earlier.elapsed).as_secs_f64();
+        if seconds > 0.0 {
+            self.bytes.saturating_sub(earlier.bytes) as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the estimated time to finish the downloads in progress at `speed` bytes per
+    /// second.
+    pub fn eta(&self, speed: f64) -> Option<std::time::Duration> {
+        if speed > 0.0 {
+            Some(std::time::Duration::from_secs_f64(
+                self.remaining_bytes as f64 / speed,
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Download in progress counted by `TransferStats` until dropped.
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+struct ActiveTransfer<'a> {
+    stats: &'a TransferStats,
+    remaining: u64,
+}
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+impl<'a> ActiveTransfer<'a> {
+    fn new(stats: &'a TransferStats, size: Option<u64>) -> Self {
+        use std::sync::atomic::Ordering;
+
+        let remaining = size.unwrap_or(0);
+        stats.active_files.fetch_add(1, Ordering::Relaxed);
+        stats
+            .remaining_bytes
+            .fetch_add(remaining, Ordering::Relaxed);
+        Self { stats, remaining }
+    }
+
+    fn received(&mut self, bytes: u64) {
+        use std::sync::atomic::Ordering;
+
+        self.stats.bytes.fetch_add(bytes, Ordering::Relaxed);
+        let done = bytes.min(self.remaining);
+        self.stats
+            .remaining_bytes
+            .fetch_sub(done, Ordering::Relaxed);
+        self.remaining -= done;
+    }
+
+    fn finish(self) {
+        self.stats
+            .files
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+impl Drop for ActiveTransfer<'_> {
+    fn drop(&mut self) {
+        use std::sync::atomic::Ordering;
+
+        self.stats.active_files.fetch_sub(1, Ordering::Relaxed);
+        self.stats
+            .remaining_bytes
+            .fetch_sub(self.remaining, Ordering::Relaxed);
+    }
+}
+
+/// HTTP stack which API calls and downloads are sent through, wrapped by `PostClient::layer`.
+#[cfg(all(feature = "tower", not(target_arch = "wasm32")))]
+pub type HttpService =
+    tower::util::BoxCloneSyncService<reqwest::Request, reqwest::Response, tower::BoxError>;
+
+/// Innermost service of `HttpService` sending requests with reqwest.
+#[cfg(all(feature = "tower", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone)]
+struct ReqwestService(reqwest::Client);
+
+#[cfg(all(feature = "tower", not(target_arch = "wasm32")))]
+impl tower::Service<reqwest::Request> for ReqwestService {
+    type Response = reqwest::Response;
+    type Error = tower::BoxError;
+    type Future = std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>,
+    >;
+
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: reqwest::Request) -> Self::Future {
+        let client = self.0.clone();
+        Box::pin(async move { Ok(client.execute(request).await?) })
+    }
+}
+
+/// Throttles API calls and downloads, which can be shared by multiple `PostClient`s to keep the
+/// aggregate rate under the limit.
+///
+/// Waiting is skipped on wasm32 or without the `tokio` feature.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    interval: std::time::Duration,
+    jitter: f64,
+    /// Earliest time when the next request can be sent
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    next_request: std::sync::Mutex<Option<std::time::Instant>>,
+}
+
+impl RateLimiter {
+    /// Waits at least `interval` between requests.
+    pub fn new(interval: std::time::Duration) -> Self {
+        Self {
+            interval,
+            ..Default::default()
+        }
+    }
+
+    /// Randomizes each interval by up to `ratio` of it in both directions, e.g. 0.4 for ±40%.
+    pub fn jitter(mut self, ratio: f64) -> Self {
+        self.jitter = ratio.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Waits for the interval since the previous request.
+    pub async fn wait(&self) {
+        #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+        {
+            if self.interval.is_zero() {
+                return;
+            }
+            let interval = self
+                .interval
+                .mul_f64(1.0 + self.jitter * (fastrand::f64() * 2.0 - 1.0));
+            let now = std::time::Instant::now();
+            let at = {
+                let mut next_request = self.next_request.lock().unwrap();
+                let at = next_request.map_or(now, |t| t.max(now));
+                *next_request = Some(at + interval);
+                at
+            };
+            if at > now {
+                tokio::time::sleep_until(at.into()).await;
+            }
+        }
+    }
+}
+
+/// Referer header sent on media downloads.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum RefererPolicy {
+    /// URL of the post containing the media, or https://www.fanbox.cc/ when it's unknown
+    #[default]
+    Post,
+    /// https://www.fanbox.cc/
+    Top,
+    /// No Referer header
+    None,
+    /// The given URL
+    Custom(String),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("failed to send request: {0}")]
+    HttpRequestError(reqwest::Error),
+    #[error("fanbox returned error: {source}{}", format_body_snippet(body))]
+    HttpStatusError {
+        source: reqwest::Error,
+        status: u16,
+        /// Beginning of the response body on a single line, truncated to
+        /// `MAX_BODY_SNIPPET_CHARS`
+        body: String,
+    },
+    #[error("failed to read response: {0}")]
+    HttpReadError(reqwest::Error),
+    #[error("{0}")]
+    IoError(#[from] std::io::Error),
+    #[error("{url} is not cached, which can't be fetched offline")]
+    NotCached { url: String },
+    #[error("{url} was rejected as unauthenticated: {code}")]
+    Unauthorized { url: String, code: String },
+    #[error("{url} is forbidden: {code}")]
+    Forbidden { url: String, code: String },
+    #[error("{url} was not found: {code}")]
+    NotFound { url: String, code: String },
+    /// Error envelope returned with other statuses, including successful ones
+    #[error("{url} returned error: {code}")]
+    ApiError { url: String, code: String },
+    #[error("failed to parse response of {url}: {source}")]
+    InvalidResponse {
+        url: String,
+        source: serde_json::Error,
+    },
+    #[error("unknown {kind} type in post {post_id}: {raw}")]
+    UnknownType {
+        post_id: String,
+        kind: &'static str,
+        raw: serde_json::Value,
+    },
+    #[error("{url} returned an HTML page instead of the media ({content_type})")]
+    UnexpectedHtml { url: String, content_type: String },
+    #[error("{url} returned a block or challenge page instead of the content (status {status})")]
+    Blocked { url: String, status: u16 },
+    #[error("no data was received from {url} for {} seconds", timeout.as_secs())]
+    IdleTimeout {
+        url: String,
+        timeout: std::time::Duration,
+    },
+    #[error("too many redirects from {url}")]
+    TooManyRedirects { url: String },
+    #[error("failed to load the session from {store}: {message}")]
+    LoadSession { store: String, message: String },
+    #[error("{url} was cut off at {actual} of {expected} bytes")]
+    IncompleteDownload {
+        url: String,
+        expected: u64,
+        actual: u64,
+    },
+    #[error("cancelled")]
+    Cancelled,
+    #[cfg(all(feature = "tower", not(target_arch = "wasm32")))]
+    #[error("middleware failed: {0}")]
+    MiddlewareError(tower::BoxError),
+}
+
+/// Parses the response of the API at `url`, failing with the error in the envelope if any.
+fn parse_api_response<T>(url: &str, body: &[u8]) -> Result<T, Error>
+where
+    T: serde::de::DeserializeOwned,
+{
+    if let Some(code) = parse_error_envelope(body) {
+        return Err(Error::ApiError {
+            url: url.to_owned(),
+            code,
+        });
+    }
+    serde_json::from_slice(body).map_err(|source| Error::InvalidResponse {
+        url: url.to_owned(),
+        source,
+    })
+}
+
+/// Returns the code of the error envelope `{"error": "..."}` which the API returns instead of
+/// `{"body": ...}`.
+fn parse_error_envelope(body: &[u8]) -> Option<String> {
+    #[derive(serde::Deserialize)]
+    struct Envelope {
+        error: Option<String>,
+    }
+
+    serde_json::from_slice::<Envelope>(body).ok()?.error
+}
+
+/// Maps the error envelope returned with `status` to the error.
+fn api_error(url: String, status: u16, code: String) -> Error {
+    match status {
+        401 => Error::Unauthorized { url, code },
+        403 => Error::Forbidden { url, code },
+        404 => Error::NotFound { url, code },
+        _ => Error::ApiError { url, code },
+    }
+}
+
+/// Number of characters of response bodies kept in `Error::HttpStatusError`
+const MAX_BODY_SNIPPET_CHARS: usize = 256;
+
+/// Returns the beginning of the response body on a single line.
+fn body_snippet(body: &str) -> String {
+    let body = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    match body.char_indices().nth(MAX_BODY_SNIPPET_CHARS) {
+        Some((i, _)) => format!("{}...", &body[..i]),
+        None => body,
+    }
+}
+
+fn format_body_snippet(body: &str) -> String {
+    if body.is_empty() {
+        String::new()
+    } else {
+        format!(": {}", body)
+    }
+}
+
+/// Sequential id of requests attached to their tracing spans
+static NEXT_REQUEST_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+
+/// Number of retries of requests answered with block or challenge pages by default
+pub const DEFAULT_BLOCK_RETRIES: u32 = 3;
+
+/// Number of retries of requests failing with transient errors by default
+pub const DEFAULT_RETRIES: u32 = 3;
+
+/// Timeout of API calls by default
+#[cfg(not(target_arch = "wasm32"))]
+pub const DEFAULT_API_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(20);
+
+/// Longest time without receiving data of downloads by default
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+pub const DEFAULT_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Size of the buffer of each download written to disk by default
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+pub const DEFAULT_COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Number of listing pages of a creator fetched at the same time by default
+pub const DEFAULT_LISTING_CONCURRENCY: usize = 4;
+
+/// Number of posts in each page of the home feed and tagged posts
+const FEED_PAGE_LIMIT: usize = 10;
+
+/// Wait before the first retry of a blocked request, doubled on each retry
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+const BLOCK_BACKOFF: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Wait before the first retry of a request failing with a transient error, doubled on each retry
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+const RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Period for which the response of an API call is shared with identical calls
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+const RECENT_RESPONSE_TTL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// APIs of posts and creators shared with identical calls, unlike listings and notifications
+/// which change between calls
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+const SHARED_API_PATHS: &[&str] = &["/post.info", "/creator.get"];
+
+/// Number of redirects followed by each request
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_REDIRECTS: usize = 10;
+
+/// Accept-Language header sent by default
+pub const DEFAULT_ACCEPT_LANGUAGE: &str = "ja";
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+const TOP_URL: &str = "https://www.fanbox.cc/";
+
+#[cfg(not(target_arch = "wasm32"))]
+const USER_AGENT: &str = concat!(
+    env!("CARGO_PKG_NAME"),
+    "/",
+    env!("CARGO_PKG_VERSION"),
+    " (+https://github.com/eagletmt/fanbox-dl)"
+);
+
+impl PostClient {
+    /// Builds the client sending the session loaded from `store`.
+    pub fn from_session_store<S>(store: &S) -> Result<Self, Error>
+    where
+        S: SessionStore + ?Sized,
+    {
+        Self::new(&store.session_id()?).map_err(Error::HttpRequestError)
+    }
+
+    pub fn new(session_id: &str) -> Result<Self, reqwest::Error> {
+        Self::with_client_builder(session_id, reqwest::ClientBuilder::new())
+    }
+
+    /// Builds the client on `builder`, e.g. sending requests to the fake FANBOX of `test_util`.
+    pub(crate) fn with_client_builder(
+        session_id: &str,
+        builder: reqwest::ClientBuilder,
+    ) -> Result<Self, reqwest::Error> {
+        // Timeouts and User-Agent are controlled by the browser on wasm32
+        #[cfg(not(target_arch = "wasm32"))]
+        let builder = builder
+            .connect_timeout(std::time::Duration::from_secs(5))
+            .user_agent(USER_AGENT)
+            .redirect(redirect_policy());
+        let client = builder
+            .default_headers(reqwest::header::HeaderMap::from_iter([
+                (
+                    reqwest::header::ORIGIN,
+                    reqwest::header::HeaderValue::from_static("https://www.fanbox.cc"),
+                ),
+                (
+                    reqwest::header::COOKIE,
+                    reqwest::header::HeaderValue::from_str(&format!(
+                        "FANBOXSESSID={};",
+                        session_id
+                    ))
+                    .unwrap(),
+                ),
+            ]))
+            .build()?;
+        Ok(Self {
+            #[cfg(all(feature = "tower", not(target_arch = "wasm32")))]
+            service: HttpService::new(ReqwestService(client.clone())),
+            client,
+            strict: false,
+            referer: RefererPolicy::default(),
+            accept_language: DEFAULT_ACCEPT_LANGUAGE.to_owned(),
+            rate_limiter: Default::default(),
+            shared_rate_limiter: false,
+            block_retries: DEFAULT_BLOCK_RETRIES,
+            retries: DEFAULT_RETRIES,
+            listing_concurrency: DEFAULT_LISTING_CONCURRENCY,
+            #[cfg(not(target_arch = "wasm32"))]
+            api_timeout: DEFAULT_API_TIMEOUT,
+            #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+            copy_buffer_size: DEFAULT_COPY_BUFFER_SIZE,
+            #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+            memory_limit: None,
+            #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+            fsync: false,
+            #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+            transfer_stats: Default::default(),
+            #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+            response_cache: None,
+            #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+            recent_requests: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            offline: false,
+            #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+            cancellation_token: None,
+        })
+    }
+
+    /// Makes `get_post` fail with `Error::UnknownType` when the post contains unsupported types.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets the Referer header sent on media downloads.
+    pub fn referer(mut self, referer: RefererPolicy) -> Self {
+        self.referer = referer;
+        self
+    }
+
+    /// Sets the Accept-Language header, which localizes some strings returned by the API.
+    pub fn accept_language(mut self, accept_language: &str) -> Self {
+        self.accept_language = accept_language.to_owned();
+        self
+    }
+
+    /// Waits at least `interval` between API calls and downloads.
+    ///
+    /// The interval is ignored on wasm32 or without the `tokio` feature. It is also ignored with a
+    /// warning after `rate_limiter`, whose interval is set by `RateLimiter::new` instead.
+    pub fn interval(mut self, interval: std::time::Duration) -> Self {
+        if self.shared_rate_limiter {
+            tracing::warn!(
+                "Ignored the interval {:?} since the client shares a rate limiter",
+                interval
+            );
+            return self;
+        }
+        let jitter = self.rate_limiter.jitter;
+        self.rate_limiter = std::sync::Arc::new(RateLimiter::new(interval).jitter(jitter));
+        self
+    }
+
+    /// Randomizes each interval by up to `ratio` of it in both directions, e.g. 0.4 for ±40%.
+    ///
+    /// The ratio is ignored with a warning after `rate_limiter`, whose jitter is set by
+    /// `RateLimiter::jitter` instead.
+    pub fn jitter(mut self, ratio: f64) -> Self {
+        if self.shared_rate_limiter {
+            tracing::warn!(
+                "Ignored the jitter {} since the client shares a rate limiter",
+                ratio
+            );
+            return self;
+        }
+        let interval = self.rate_limiter.interval;
+        self.rate_limiter = std::sync::Arc::new(RateLimiter::new(interval).jitter(ratio));
+        self
+    }
+
+    /// Shares `rate_limiter` with other clients instead of throttling independently, replacing
+    /// `interval` and `jitter`, which are ignored afterwards.
+    pub fn rate_limiter(mut self, rate_limiter: std::sync::Arc<RateLimiter>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self.shared_rate_limiter = true;
+        self
+    }
+
+    /// Retries requests answered with block or challenge pages of Cloudflare up to `retries`
+    /// times with exponential backoff before failing with `Error::Blocked`.
+    ///
+    /// Blocked requests are not retried on wasm32 or without the `tokio` feature.
+    pub fn block_retries(mut self, retries: u32) -> Self {
+        self.block_retries = retries;
+        self
+    }
+
+    /// Retries requests failing with transient errors, i.e. 429 and 5xx responses, timeouts and
+    /// connection errors, up to `retries` times with exponential backoff. Downloads whose body is
+    /// cut off are also retried from the start.
+    ///
+    /// Requests are not retried on wasm32 or without the `tokio` feature.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Fetches up to `concurrency` listing pages of a creator at the same time in `pages` and
+    /// `paginate_creator`, which still yield them in order.
+    ///
+    /// Pages after the one being consumed are fetched ahead, so streams stopped early may have
+    /// fetched up to `concurrency - 1` pages more than needed.
+    pub fn listing_concurrency(mut self, concurrency: usize) -> Self {
+        self.listing_concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets the timeout of each API call, from connecting until the response is read.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn api_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.api_timeout = timeout;
+        self
+    }
+
+    /// Sets how long downloads wait for the response or the next chunk of the body before
+    /// failing with `Error::IdleTimeout`.
+    ///
+    /// Downloads have no total timeout, so large files can take as long as data keeps arriving.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    pub fn idle_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Sets the size of the buffer which downloaded data are collected into before written to disk.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    pub fn copy_buffer_size(mut self, bytes: usize) -> Self {
+        self.copy_buffer_size = bytes.max(1);
+        self
+    }
+
+    /// Caps the memory used by buffers of concurrent downloads of this client and its clones.
+    ///
+    /// Each download reserves its copy buffer from `bytes` and waits until enough of it is
+    /// released by other downloads, so small caps serialize downloads.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    pub fn memory_limit(mut self, bytes: usize) -> Self {
+        let bytes = bytes.clamp(1, tokio::sync::Semaphore::MAX_PERMITS);
+        self.memory_limit = Some(MemoryLimit {
+            semaphore: std::sync::Arc::new(tokio::sync::Semaphore::new(bytes)),
+            bytes,
+        });
+        self
+    }
+
+    /// Counts downloads into `stats`, which can be shared with other clients. Each client has its
+    /// own stats by default.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    pub fn transfer_stats(mut self, stats: std::sync::Arc<TransferStats>) -> Self {
+        self.transfer_stats = stats;
+        self
+    }
+
+    /// Caches responses of post listings and post.info in `dir`, which are used instead of
+    /// calling the API again for `ttl`, e.g. by runs repeated shortly or after a crash.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    pub fn response_cache(mut self, dir: &std::path::Path, ttl: std::time::Duration) -> Self {
+        self.response_cache = Some(ResponseCache {
+            dir: dir.to_path_buf(),
+            ttl,
+        });
+        self
+    }
+
+    /// Sends identical calls of posts and creators in flight or sent within the last minute once,
+    /// sharing the response, and copies media downloaded earlier by the client instead of
+    /// downloading them again, e.g. posts embedded in multiple posts in a run.
+    ///
+    /// Listings and notifications are always sent. Downloads are shared until
+    /// `clear_recent_requests`, so clients reused across runs should call it at the start of each.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    pub fn dedup_requests(mut self, enabled: bool) -> Self {
+        self.recent_requests = enabled.then(Default::default);
+        self
+    }
+
+    /// Forgets responses and downloads shared by `dedup_requests`.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    pub fn clear_recent_requests(&self) {
+        if let Some(recent) = &self.recent_requests {
+            recent.responses.lock().unwrap().clear();
+            recent.downloads.lock().unwrap().clear();
+        }
+    }
+
+    /// Reads API responses only from `response_cache` regardless of their TTL and never sends
+    /// requests, failing with `Error::NotCached` instead.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn offline(mut self, enabled: bool) -> Self {
+        self.offline = enabled;
+        self
+    }
+
+    /// Flushes data of downloaded files and their directory entries to disk before returning, so
+    /// that files reported as downloaded survive a power loss.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    pub fn fsync(mut self, enabled: bool) -> Self {
+        self.fsync = enabled;
+        self
+    }
+
+    /// Wraps the HTTP stack with the tower `layer`, e.g. retries, tracing or metrics.
+    ///
+    /// Layers added later wrap the earlier ones.
+    #[cfg(all(feature = "tower", not(target_arch = "wasm32")))]
+    pub fn layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<HttpService>,
+        L::Service: tower::Service<reqwest::Request, Response = reqwest::Response, Error = tower::BoxError>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        <L::Service as tower::Service<reqwest::Request>>::Future: Send + 'static,
+    {
+        self.service = HttpService::new(layer.layer(self.service));
+        self
+    }
+
+    /// Makes requests and downloads in progress fail with `Error::Cancelled` when `token` is
+    /// cancelled, which also stops streams like `paginate_creator`.
+    ///
+    /// Downloads are written to temporary files which are removed on cancellation, including when
+    /// the future is dropped, so no half-written files remain at the destination.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    pub fn cancellation_token(mut self, token: tokio_util::sync::CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// Runs `future` until it completes or the cancellation token is cancelled.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    async fn until_cancelled<F>(&self, future: F) -> Result<F::Output, Error>
+    where
+        F: std::future::Future,
+    {
+        let token = match &self.cancellation_token {
+            Some(token) => token,
+            None => return Ok(future.await),
+        };
+        let cancelled = token.cancelled();
+        futures::pin_mut!(future, cancelled);
+        match futures::future::select(future, cancelled).await {
+            futures::future::Either::Left((output, _)) => Ok(output),
+            futures::future::Either::Right(_) => Err(Error::Cancelled),
+        }
+    }
+
+    #[cfg(not(all(feature = "tokio", not(target_arch = "wasm32"))))]
+    async fn until_cancelled<F>(&self, future: F) -> Result<F::Output, Error>
+    where
+        F: std::future::Future,
+    {
+        Ok(future.await)
+    }
+
+    /// Waits before retrying a blocked request, returning false when no more retries are allowed.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    async fn backoff_blocked(&self, error: &Error, attempt: u32) -> Result<bool, Error> {
+        if attempt > self.block_retries {
+            return Ok(false);
+        }
+        let delay = BLOCK_BACKOFF * 2u32.saturating_pow(attempt - 1);
+        tracing::warn!("{}. Retry in {} seconds", error, delay.as_secs());
+        self.until_cancelled(tokio::time::sleep(delay)).await?;
+        Ok(true)
+    }
+
+    #[cfg(not(all(feature = "tokio", not(target_arch = "wasm32"))))]
+    async fn backoff_blocked(&self, _error: &Error, _attempt: u32) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    /// Waits before retrying a request failing with a transient error, returning false when the
+    /// error isn't transient or no more retries are allowed.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    async fn backoff_transient(&self, error: &Error, attempt: u32) -> Result<bool, Error> {
+        if attempt > self.retries || !is_transient(error) {
+            return Ok(false);
+        }
+        let delay = RETRY_BACKOFF * 2u32.saturating_pow(attempt - 1);
+        tracing::warn!("{}. Retry in {} seconds", error, delay.as_secs());
+        self.until_cancelled(tokio::time::sleep(delay)).await?;
+        Ok(true)
+    }
+
+    #[cfg(not(all(feature = "tokio", not(target_arch = "wasm32"))))]
+    async fn backoff_transient(&self, _error: &Error, _attempt: u32) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    /// Sends the API call with `api_timeout`.
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response, Error> {
+        #[cfg(not(target_arch = "wasm32"))]
+        let request = request.timeout(self.api_timeout);
+        self.send_request(request).await
+    }
+
+    /// Runs `future` until it completes, failing when it takes longer than `idle_timeout`.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    async fn until_idle<F>(&self, url: &str, future: F) -> Result<F::Output, Error>
+    where
+        F: std::future::Future,
+    {
+        self.until_cancelled(tokio::time::timeout(self.idle_timeout, future))
+            .await?
+            .map_err(|_| Error::IdleTimeout {
+                url: url.to_owned(),
+                timeout: self.idle_timeout,
+            })
+    }
+
+    /// Sends the request after waiting for the interval since the previous request.
+    ///
+    /// Requests answered with block or challenge pages are retried with `backoff_blocked`, and
+    /// those failing with transient errors with `backoff_transient`.
+    async fn send_request(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, Error> {
+        let mut request = request
+            .header(reqwest::header::ACCEPT_LANGUAGE, &self.accept_language)
+            .build()
+            .map_err(Error::HttpRequestError)?;
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.offline {
+            return Err(Error::NotCached {
+                url: request.url().to_string(),
+            });
+        }
+        let request_id = NEXT_REQUEST_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let mut attempt = 1;
+        // Attempts failed by blocks and by transient errors, which are retried independently
+        let mut blocked = 0;
+        let mut failed = 0;
+        loop {
+            let retry = request.try_clone();
+            match (self.send_once(request, request_id, attempt).await, retry) {
+                (Err(e @ Error::Blocked { .. }), Some(retry)) => {
+                    blocked += 1;
+                    if !self.backoff_blocked(&e, blocked).await? {
+                        return Err(e);
+                    }
+                    request = retry;
+                }
+                (Err(e), Some(retry)) => {
+                    failed += 1;
+                    if !self.backoff_transient(&e, failed).await? {
+                        return Err(e);
+                    }
+                    request = retry;
+                }
+                (result, _) => return result,
+            }
+            attempt += 1;
+        }
+    }
+
+    async fn send_once(
+        &self,
+        request: reqwest::Request,
+        request_id: u64,
+        attempt: u32,
+    ) -> Result<reqwest::Response, Error> {
+        use tracing::Instrument as _;
+
+        let span = tracing::debug_span!(
+            "request",
+            request_id,
+            method = %request.method(),
+            url = %request.url(),
+            attempt,
+            status = tracing::field::Empty,
+        );
+        let send = async move {
+            self.rate_limiter.wait().await;
+            #[cfg(all(feature = "tower", not(target_arch = "wasm32")))]
+            let response = tower::ServiceExt::oneshot(self.service.clone(), request)
+                .await
+                .map_err(|e| match e.downcast::<reqwest::Error>() {
+                    Ok(e) => Error::HttpRequestError(*e),
+                    Err(e) => Error::MiddlewareError(e),
+                })?;
+            #[cfg(not(all(feature = "tower", not(target_arch = "wasm32"))))]
+            let response = self
+                .client
+                .execute(request)
+                .await
+                .map_err(Error::HttpRequestError)?;
+            tracing::Span::current().record("status", response.status().as_u16());
+            tracing::debug!("Received response");
+            if is_blocked(&response) {
+                return Err(Error::Blocked {
+                    url: response.url().to_string(),
+                    status: response.status().as_u16(),
+                });
+            }
+            match response.error_for_status_ref() {
+                Ok(_) => Ok(response),
+                Err(source) => {
+                    let status = response.status().as_u16();
+                    let url = response.url().to_string();
+                    // The body is only for diagnosis, so failures to read it are ignored
+                    let body = response.text().await.unwrap_or_default();
+                    if let Some(code) = parse_error_envelope(body.as_bytes()) {
+                        return Err(api_error(url, status, code));
+                    }
+                    Err(Error::HttpStatusError {
+                        source,
+                        status,
+                        body: body_snippet(&body),
+                    })
+                }
+            }
+        }
+        .instrument(span);
+        self.until_cancelled(send).await?
+    }
+
+    /// Sends the download request of `url`, following redirects to other Fanbox hosts which are
+    /// stopped by `redirect_policy`, so that the session cookie is sent to them again.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    async fn send_download(
+        &self,
+        url: &str,
+        referer: Option<&str>,
+        resume: Option<&PartialDownload>,
+    ) -> Result<reqwest::Response, Error> {
+        let mut url = url.to_owned();
+        for _ in 0..MAX_REDIRECTS {
+            let mut request = self.client.get(&url);
+            if let Some(referer) = referer {
+                request = request.header(reqwest::header::REFERER, referer);
+            }
+            if let Some(resume) = resume {
+                // The whole content is returned with 200 when it has changed since
+                request = request
+                    .header(reqwest::header::RANGE, format!("bytes={}-", resume.offset))
+                    .header(reqwest::header::IF_RANGE, &resume.validator);
+            }
+            let response = self.send_request(request).await?;
+            match fanbox_redirect(&response) {
+                Some(location) => {
+                    tracing::debug!(
+                        "Follow redirect from {} to {} with the session",
+                        url,
+                        location
+                    );
+                    url = location.to_string();
+                }
+                None => return Ok(response),
+            }
+        }
+        Err(Error::TooManyRedirects { url })
+    }
+
+    /// Sends a GET request to `url` and reads the body, which is shared with identical requests
+    /// of posts and creators with `dedup_requests`.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    async fn get_bytes(&self, url: &str) -> Result<bytes::Bytes, Error> {
+        let fetch = || async {
+            self.send(self.client.get(url))
+                .await?
+                .bytes()
+                .await
+                .map_err(Error::HttpReadError)
+        };
+        let shared =
+            reqwest::Url::parse(url).is_ok_and(|url| SHARED_API_PATHS.contains(&url.path()));
+        match self.recent_requests.as_ref().filter(|_| shared) {
+            Some(recent) => {
+                let response = recent.response(url);
+                if response.initialized() {
+                    tracing::debug!("Use the recent response of {}", url);
+                }
+                response.get_or_try_init(fetch).await.cloned()
+            }
+            None => fetch().await,
+        }
+    }
+
+    /// Calls the API at `url` and parses the response, which is read from and written to
+    /// `response_cache` if set.
+    ///
+    /// Identical calls of posts and creators are sent once with `dedup_requests`.
+    async fn get_json<T>(&self, url: &str) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+        {
+            if let Some(cache) = &self.response_cache {
+                if let Some(body) = cache.read(url, !self.offline).await {
+                    match serde_json::from_slice(&body) {
+                        Ok(resp) => {
+                            tracing::debug!("Use cached response of {}", url);
+                            return Ok(resp);
+                        }
+                        Err(e) => tracing::debug!("Ignore broken cache of {}: {}", url, e),
+                    }
+                }
+            }
+            let body = self.get_bytes(url).await?;
+            let resp = parse_api_response(url, &body)?;
+            if let Some(cache) = &self.response_cache {
+                if let Err(e) = cache.write(url, &body).await {
+                    tracing::warn!("Failed to cache response of {}: {}", url, e);
+                }
+            }
+            Ok(resp)
+        }
+        #[cfg(not(all(feature = "tokio", not(target_arch = "wasm32"))))]
+        self.fetch_json(url).await
+    }
+
+    /// Calls the API at `url` and parses the response without the response cache and
+    /// de-duplication.
+    async fn fetch_json<T>(&self, url: &str) -> Result<T, Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let body = self
+            .send(self.client.get(url))
+            .await?
+            .bytes()
+            .await
+            .map_err(Error::HttpReadError)?;
+        parse_api_response(url, &body)
+    }
+
+    pub async fn paginate_creator<'a>(
+        &'a self,
+        creator_id: &str,
+    ) -> Result<impl futures::stream::Stream<Item = Result<ListCreatorItem, Error>> + 'a, Error>
+    {
+        use futures::stream::TryStreamExt as _;
+
+        let pages = self.pages(creator_id, None).await?;
+        Ok(pages
+            .map_ok(|page| futures::stream::iter(page.items.into_iter().map(Ok)))
+            .try_flatten())
+    }
+
+    /// Lists the page URLs of the creator's posts, newest first.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn list_page_urls(&self, creator_id: &str) -> Result<Vec<String>, Error> {
+        let url = reqwest::Url::parse_with_params(
+            "https://api.fanbox.cc/post.paginateCreator",
+            &[("creatorId", creator_id)],
+        )
+        .expect("URL of post.paginateCreator is valid");
+        let resp: PaginateCreatorResponse = self.get_json(url.as_str()).await?;
+        Ok(resp.body)
+    }
+
+    /// Yields the creator's posts page by page, newest first.
+    ///
+    /// When `start` is given, pages before it are skipped. The page is looked up by its URL
+    /// first and by its index when the URL is no longer listed (e.g. new posts shifted pages).
+    ///
+    /// Pages are fetched ahead with `listing_concurrency`.
+    pub async fn pages<'a>(
+        &'a self,
+        creator_id: &str,
+        start: Option<&PageCursor>,
+    ) -> Result<impl futures::stream::Stream<Item = Result<Page, Error>> + 'a, Error> {
+        use futures::stream::StreamExt as _;
+
+        let urls = self.list_page_urls(creator_id).await?;
+        let start_index = match start {
+            Some(cursor) => urls
+                .iter()
+                .position(|url| *url == cursor.url)
+                .unwrap_or(cursor.index),
+            None => 0,
+        };
+        Ok(
+            futures::stream::iter(urls.into_iter().enumerate().skip(start_index))
+                .map(move |(index, url)| async move {
+                    let items = self.list_page(&url).await?;
+                    Ok(Page {
+                        cursor: PageCursor { index, url },
+                        items,
+                    })
+                })
+                .buffered(self.listing_concurrency),
+        )
+    }
+
+    /// Fetches posts in the page URL returned by `list_page_urls`.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn list_page(&self, url: &str) -> Result<Vec<ListCreatorItem>, Error> {
+        tracing::debug!("Listing posts in {}", url);
+        let raw: serde_json::Value = self.get_json(url).await?;
+        if let Some(items) = raw["body"]["items"].as_array() {
+            for item in items {
+                log_unknown_fields::<ListCreatorItem>("post.listCreator", item);
+            }
+        }
+        let resp: ListCreatorResponse =
+            serde_json::from_value(raw).map_err(|source| Error::InvalidResponse {
+                url: url.to_owned(),
+                source,
+            })?;
+        Ok(resp.body.items)
+    }
+
+    /// Returns the newest post of the creator.
+    pub async fn latest_post(&self, creator_id: &str) -> Result<Option<ListCreatorItem>, Error> {
+        match self.list_page_urls(creator_id).await?.first() {
+            Some(url) => Ok(self.list_page(url).await?.into_iter().next()),
+            None => Ok(None),
+        }
+    }
+
+    /// Yields posts in the home feed of the session user across creators, newest first.
+    pub fn home_items(&self) -> impl futures::stream::Stream<Item = Result<FeedItem, Error>> + '_ {
+        self.feed_items(format!(
+            "https://api.fanbox.cc/post.listHome?limit={}",
+            FEED_PAGE_LIMIT
+        ))
+    }
+
+    /// Yields posts tagged with `tag`, newest first, only of the creator who is the pixiv user
+    /// `user_id` if given.
+    pub fn tagged_items(
+        &self,
+        tag: &str,
+        user_id: Option<&str>,
+    ) -> impl futures::stream::Stream<Item = Result<FeedItem, Error>> + '_ {
+        let mut params = vec![("tag", tag.to_owned())];
+        if let Some(user_id) = user_id {
+            params.push(("userId", user_id.to_owned()));
+        }
+        params.push(("limit", FEED_PAGE_LIMIT.to_string()));
+        let url = reqwest::Url::parse_with_params("https://api.fanbox.cc/post.listTagged", params)
+            .expect("URL of post.listTagged is valid");
+        self.feed_items(url.into())
+    }
+
+    /// Yields posts announced by notifications of the session user, newest first.
+    ///
+    /// Notifications are cheaper to poll than listings of every followed creator, and
+    /// notifications other than new posts are skipped.
+    pub fn bell_items(&self) -> impl futures::stream::Stream<Item = Result<FeedItem, Error>> + '_ {
+        async_stream::try_stream! {
+            let mut url = Some(
+                "https://api.fanbox.cc/bell.list?page=1&skipConvertUnreadNotification=0&commentOnly=0"
+                    .to_owned(),
+            );
+            while let Some(page_url) = url {
+                let body = self.list_bell_page(&page_url).await?;
+                for item in body.items {
+                    if let Some(post) = item.post {
+                        yield post;
+                    }
+                }
+                url = body.next_url;
+            }
+        }
+    }
+
+    #[tracing::instrument(level = "debug", skip(self))]
+    async fn list_bell_page(&self, url: &str) -> Result<ListBellResponseBody, Error> {
+        let resp: ListBellResponse = self.get_json(url).await?;
+        Ok(resp.body)
+    }
+
+    /// Yields posts in the listing at `url` and the following pages linked by `nextUrl`.
+    fn feed_items(
+        &self,
+        url: String,
+    ) -> impl futures::stream::Stream<Item = Result<FeedItem, Error>> + '_ {
+        async_stream::try_stream! {
+            let mut url = Some(url);
+            while let Some(page_url) = url {
+                let body = self.list_feed_page(&page_url).await?;
+                for item in body.items {
+                    yield item;
+                }
+                url = body.next_url;
+            }
+        }
+    }
+
+    #[tracing::instrument(level = "debug", skip(self))]
+    async fn list_feed_page(&self, url: &str) -> Result<ListFeedResponseBody, Error> {
+        let resp: ListFeedResponse = self.get_json(url).await?;
+        Ok(resp.body)
+    }
+
+    /// Lists plans the session user supports.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn list_supporting_plans(&self) -> Result<Vec<SupportingPlan>, Error> {
+        let resp: ListSupportingResponse = self
+            .get_json("https://api.fanbox.cc/plan.listSupporting")
+            .await?;
+        Ok(resp.body)
+    }
+
+    /// Checks that the session is valid by an API requiring it, e.g. before a long run, returning
+    /// `Error::Unauthorized` when it's missing or has expired.
+    ///
+    /// The response cache is bypassed, since a cached response says nothing of the session now.
+    pub async fn check_session(&self) -> Result<(), Error> {
+        const URL: &str = "https://api.fanbox.cc/plan.listSupporting";
+        match self.fetch_json::<ListSupportingResponse>(URL).await {
+            Ok(_) => Ok(()),
+            Err(Error::HttpStatusError { status: 401, .. }) => Err(Error::Unauthorized {
+                url: URL.to_owned(),
+                code: "unauthorized".to_owned(),
+            }),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Lists ids of creators the session user supports, in the order of their plans without
+    /// duplicates.
+    pub async fn list_supporting_creators(&self) -> Result<Vec<String>, Error> {
+        let mut creator_ids: Vec<String> = Vec::new();
+        for plan in self.list_supporting_plans().await? {
+            if !creator_ids.contains(&plan.creator_id) {
+                creator_ids.push(plan.creator_id);
+            }
+        }
+        Ok(creator_ids)
+    }
+
+    /// Lists creators the session user follows.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn list_following_creators(&self) -> Result<Vec<FollowingCreator>, Error> {
+        let resp: ListFollowingResponse = self
+            .get_json("https://api.fanbox.cc/creator.listFollowing")
+            .await?;
+        Ok(resp.body)
+    }
+
+    /// Fetches the creator's profile.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn get_creator(&self, creator_id: &str) -> Result<Creator, Error> {
+        let url = reqwest::Url::parse_with_params(
+            "https://api.fanbox.cc/creator.get",
+            &[("creatorId", creator_id)],
+        )
+        .expect("URL of creator.get is valid");
+        let resp: GetCreatorResponse = self.get_json(url.as_str()).await?;
+        Ok(resp.body)
+    }
+
+    /// Fetches the profile of the creator who is the pixiv user.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn get_creator_by_user_id(&self, user_id: &str) -> Result<Creator, Error> {
+        let url = reqwest::Url::parse_with_params(
+            "https://api.fanbox.cc/creator.get",
+            &[("userId", user_id)],
+        )
+        .expect("URL of creator.get is valid");
+        let resp: GetCreatorResponse = self.get_json(url.as_str()).await?;
+        Ok(resp.body)
+    }
+
+    #[tracing::instrument(
+        level = "debug",
+        skip(self, id),
+        fields(post_id = id, creator_id = tracing::field::Empty)
+    )]
+    pub async fn get_post(&self, id: &str) -> Result<Post, Error> {
+        let raw = self.get_post_raw(id).await?;
+        // Types of restricted posts without bodies are dropped
+        if !raw["body"].is_null() {
+            log_unknown_fields::<Post>("post.info", &raw);
+        }
+        let post: Post = serde_json::from_value(raw).map_err(|source| Error::InvalidResponse {
+            url: post_info_url(id).into(),
+            source,
+        })?;
+        tracing::Span::current().record("creator_id", post.info.creator_id.as_str());
+        if self.strict {
+            if let Some((kind, raw)) = post.unknowns().into_iter().next() {
+                return Err(Error::UnknownType {
+                    post_id: post.info.id.clone(),
+                    kind,
+                    raw: raw.clone(),
+                });
+            }
+        }
+        Ok(post)
+    }
+
+    /// Fetches the raw JSON of the post as returned by post.info, before parsed into `Post`.
+    pub async fn get_post_raw(&self, id: &str) -> Result<serde_json::Value, Error> {
+        let mut resp: serde_json::Value = self.get_json(post_info_url(id).as_str()).await?;
+        Ok(resp["body"].take())
+    }
+
+    /// Downloads `url` to `path` and sets its mtime.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    pub async fn download_to<P, T>(&self, url: &str, path: P, mtime: &T) -> Result<(), Error>
+    where
+        P: AsRef<std::path::Path>,
+        T: Mtime + ?Sized,
+    {
+        self.download_media_to(url, path, mtime, None).await?;
+        Ok(())
+    }
+
+    /// Downloads media linked from `page_url` to `path` and sets its mtime.
+    ///
+    /// Responses cut off are resumed from `<path>.part`, also by later calls, and the file is
+    /// renamed to `path` only after its size matches the response.
+    ///
+    /// `page_url` is sent as Referer when the referer policy is `RefererPolicy::Post`.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    #[tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(url, path = %path.as_ref().display(), bytes = tracing::field::Empty)
+    )]
+    pub async fn download_media_to<P, T>(
+        &self,
+        url: &str,
+        path: P,
+        mtime: &T,
+        page_url: Option<&str>,
+    ) -> Result<DownloadedMedia, Error>
+    where
+        P: AsRef<std::path::Path>,
+        T: Mtime + ?Sized,
+    {
+        let path = path.as_ref();
+        let referer = match &self.referer {
+            RefererPolicy::Post => Some(page_url.unwrap_or(TOP_URL)),
+            RefererPolicy::Top => Some(TOP_URL),
+            RefererPolicy::None => None,
+            RefererPolicy::Custom(url) => Some(url.as_str()),
+        };
+        if let Some(media) = self.copy_recent_download(url, path, mtime).await? {
+            return Ok(media);
+        }
+        let _permit = match &self.memory_limit {
+            Some(limit) => {
+                let permits = self
+                    .copy_buffer_size
+                    .min(limit.bytes)
+                    .min(u32::MAX as usize);
+                Some(
+                    self.until_cancelled(limit.semaphore.acquire_many(permits as u32))
+                        .await?
+                        .map_err(|_| Error::Cancelled)?,
+                )
+            }
+            None => None,
+        };
+        let started = std::time::Instant::now();
+        let mut failed = 0;
+        let (status, bytes) = loop {
+            match self.download_once(url, path, mtime, referer).await {
+                // Failed requests are retried by `send_request`, while responses cut off are
+                // resumed here
+                Err(
+                    e @ (Error::HttpReadError(_)
+                    | Error::IdleTimeout { .. }
+                    | Error::IncompleteDownload { .. }),
+                ) => {
+                    failed += 1;
+                    if !self.backoff_transient(&e, failed).await? {
+                        return Err(e);
+                    }
+                }
+                result => break result?,
+            }
+        };
+        let elapsed = started.elapsed();
+        tracing::Span::current().record("bytes", bytes);
+        tracing::debug!(
+            "Downloaded {} bytes in {:.1} seconds ({:.0} bytes/s)",
+            bytes,
+            elapsed.as_secs_f64(),
+            bytes as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+        );
+        let media = DownloadedMedia {
+            status,
+            bytes,
+            elapsed,
+        };
+        if let Some(recent) = &self.recent_requests {
+            recent
+                .downloads
+                .lock()
+                .unwrap()
+                .insert(url.to_owned(), (path.to_path_buf(), media));
+        }
+        Ok(media)
+    }
+
+    /// Downloads `url` to `path` once, returning the status and the size of the file.
+    ///
+    /// The content is written to `<path>.part`, which is renamed to `path` after its size is
+    /// verified against the response. When the response is cut off, the partial file is kept with
+    /// the URL and the validator of the response, i.e. its strong ETag or Last-Modified, and
+    /// resumed by a range request with If-Range next time. It's downloaded from the start when
+    /// the URL or the content has changed, or the server doesn't support ranges.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    async fn download_once<T>(
+        &self,
+        url: &str,
+        path: &std::path::Path,
+        mtime: &T,
+        referer: Option<&str>,
+    ) -> Result<(u16, u64), Error>
+    where
+        T: Mtime + ?Sized,
+    {
+        use futures::stream::TryStreamExt as _;
+
+        let mut attempt = 1;
+        let (status, offset, expected, content_length, validator, first_chunk, mut stream) = loop {
+            let resume = PartialDownload::load(path, url).await;
+            let offset = resume.as_ref().map_or(0, |resume| resume.offset);
+            let response = match self
+                .until_idle(url, self.send_download(url, referer, resume.as_ref()))
+                .await?
+            {
+                Err(Error::HttpStatusError { status: 416, .. }) if offset > 0 => {
+                    tracing::debug!("Discard the partial download of {} not matching", url);
+                    TemporaryFile::remove(path).await?;
+                    continue;
+                }
+                result => result?,
+            };
+            let status = response.status().as_u16();
+            let content_length = response.content_length();
+            let validator = response_validator(response.headers());
+            // Servers not supporting ranges or contents changed since the partial download return
+            // the whole content with 200
+            let (offset, expected) = if status == 206 {
+                let same_content = match (&resume, &validator) {
+                    (Some(resume), Some(validator)) => resume.validator == *validator,
+                    _ => true,
+                };
+                match parse_content_range(response.headers()) {
+                    Some((start, total)) if start == offset && same_content => {
+                        tracing::debug!("Resume {} from {} bytes", url, offset);
+                        (
+                            offset,
+                            total.or(content_length.map(|length| offset + length)),
+                        )
+                    }
+                    _ => {
+                        tracing::debug!("Discard the partial download of {} not matching", url);
+                        TemporaryFile::remove(path).await?;
+                        continue;
+                    }
+                }
+            } else {
+                if offset > 0 {
+                    tracing::debug!("Download {} from the start", url);
+                }
+                (0, content_length)
+            };
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or("")
+                .to_owned();
+            let mut stream = response.bytes_stream().map_err(Error::HttpReadError);
+            // CDN returns login or error pages with 200 when the session lapses
+            let first_chunk = self
+                .until_idle(url, stream.try_next())
+                .await??
+                .unwrap_or_default();
+            if expects_html(path) || !(is_html_type(&content_type) || looks_like_html(&first_chunk))
+            {
+                break (
+                    status,
+                    offset,
+                    expected,
+                    content_length,
+                    validator,
+                    first_chunk,
+                    stream,
+                );
+            }
+            if !looks_like_challenge(&first_chunk) {
+                return Err(Error::UnexpectedHtml {
+                    url: url.to_owned(),
+                    content_type,
+                });
+            }
+            let e = Error::Blocked {
+                url: url.to_owned(),
+                status,
+            };
+            if !self.backoff_blocked(&e, attempt).await? {
+                return Err(e);
+            }
+            attempt += 1;
+        };
+        let temporary_file = TemporaryFile::new(path);
+        let file = if offset > 0 {
+            tokio::fs::OpenOptions::new()
+                .append(true)
+                .open(&temporary_file.path)
+                .await?
+        } else {
+            let file = tokio::fs::File::create(&temporary_file.path).await?;
+            PartialDownload::save(path, url, validator.as_deref()).await?;
+            file
+        };
+        // Responses without validators can't be resumed safely
+        let resumable = offset > 0 || validator.is_some();
+        let mut file = tokio::io::BufWriter::with_capacity(self.copy_buffer_size, file);
+        let mut transfer = ActiveTransfer::new(&self.transfer_stats, content_length);
+        let mut bytes = offset;
+        let received = async {
+            tokio::io::AsyncWriteExt::write_all(&mut file, &first_chunk).await?;
+            bytes += first_chunk.len() as u64;
+            transfer.received(first_chunk.len() as u64);
+            while let Some(chunk) = self.until_idle(url, stream.try_next()).await?? {
+                tokio::io::AsyncWriteExt::write_all(&mut file, &chunk).await?;
+                bytes += chunk.len() as u64;
+                transfer.received(chunk.len() as u64);
+            }
+            match expected {
+                Some(expected) if bytes != expected => Err(Error::IncompleteDownload {
+                    url: url.to_owned(),
+                    expected,
+                    actual: bytes,
+                }),
+                _ => Ok(()),
+            }
+        }
+        .await;
+        tokio::io::AsyncWriteExt::flush(&mut file).await?;
+        if let Err(e) = received {
+            // Kept to be resumed unless it's broken by more bytes than expected
+            if resumable
+                && matches!(
+                    e,
+                    Error::HttpReadError(_)
+                        | Error::IdleTimeout { .. }
+                        | Error::IncompleteDownload { .. }
+                )
+                && expected.is_none_or(|expected| bytes < expected)
+            {
+                temporary_file.keep();
+            }
+            return Err(e);
+        }
+        let (seconds, nanos) = mtime.unix_time();
+        filetime::set_file_mtime(
+            &temporary_file.path,
+            filetime::FileTime::from_unix_time(seconds, nanos),
+        )?;
+        if self.fsync {
+            file.get_ref().sync_all().await?;
+        }
+        drop(file);
+        temporary_file.persist(path)?;
+        if self.fsync {
+            sync_parent_dir(path)?;
+        }
+
+        transfer.finish();
+        Ok((status, bytes))
+    }
+
+    /// Copies the file downloaded from `url` earlier to `path` with `dedup_requests`, returning
+    /// `None` when it's not downloaded yet.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    async fn copy_recent_download<T>(
+        &self,
+        url: &str,
+        path: &std::path::Path,
+        mtime: &T,
+    ) -> Result<Option<DownloadedMedia>, Error>
+    where
+        T: Mtime + ?Sized,
+    {
+        let (source, media) = match self
+            .recent_requests
+            .as_ref()
+            .and_then(|recent| recent.download(url))
+        {
+            Some(download) => download,
+            None => return Ok(None),
+        };
+        let started = std::time::Instant::now();
+        if source != path {
+            tracing::debug!("Copy {} downloaded from {}", source.display(), url);
+            let temporary_file = TemporaryFile::new(path);
+            tokio::fs::copy(&source, &temporary_file.path).await?;
+            temporary_file.persist(path)?;
+        }
+        let (seconds, nanos) = mtime.unix_time();
+        filetime::set_file_mtime(path, filetime::FileTime::from_unix_time(seconds, nanos))?;
+        Ok(Some(DownloadedMedia {
+            elapsed: started.elapsed(),
+            ..media
+        }))
+    }
+}
+
+/// Result of `PostClient::download_media_to`.
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadedMedia {
+    /// HTTP status of the response
+    pub status: u16,
+    /// Size of the downloaded file, including bytes resumed from an interrupted download
+    pub bytes: u64,
+    /// Time taken to download, including retries of blocked and failed requests
+    pub elapsed: std::time::Duration,
+}
+
+/// File written during a download, which is removed unless persisted.
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+struct TemporaryFile {
+    path: std::path::PathBuf,
+    resume_path: std::path::PathBuf,
+    persisted: bool,
+}
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+impl TemporaryFile {
+    /// Extension appended to the destination path
+    const EXTENSION: &'static str = "part";
+
+    fn new(path: &std::path::Path) -> Self {
+        Self {
+            path: Self::path_for(path),
+            resume_path: Self::resume_path_for(path),
+            persisted: false,
+        }
+    }
+
+    /// Returns `<path>.part`.
+    fn path_for(path: &std::path::Path) -> std::path::PathBuf {
+        Self::with_suffix(path, "")
+    }
+
+    /// Returns `<path>.resume.part` storing `PartialDownload` of `<path>.part`, which is also
+    /// cleaned up as a temporary file.
+    fn resume_path_for(path: &std::path::Path) -> std::path::PathBuf {
+        Self::with_suffix(path, ".resume")
+    }
+
+    fn with_suffix(path: &std::path::Path, suffix: &str) -> std::path::PathBuf {
+        let mut temporary_path = path.as_os_str().to_owned();
+        temporary_path.push(suffix);
+        temporary_path.push(".");
+        temporary_path.push(Self::EXTENSION);
+        temporary_path.into()
+    }
+
+    /// Removes the partial download of `path` with its `PartialDownload`.
+    async fn remove(path: &std::path::Path) -> Result<(), std::io::Error> {
+        for temporary_path in [Self::path_for(path), Self::resume_path_for(path)] {
+            match tokio::fs::remove_file(&temporary_path).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Leaves the file to be resumed by the next download.
+    fn keep(mut self) {
+        self.persisted = true;
+    }
+
+    fn persist(mut self, path: &std::path::Path) -> Result<(), std::io::Error> {
+        std::fs::rename(&self.path, path)?;
+        self.persisted = true;
+        let _ = std::fs::remove_file(&self.resume_path);
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+impl Drop for TemporaryFile {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = std::fs::remove_file(&self.path);
+            let _ = std::fs::remove_file(&self.resume_path);
+        }
+    }
+}
+
+/// Source of `<path>.part`, against which the partial download is resumed.
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct PartialDownload {
+    url: String,
+    /// Strong ETag or Last-Modified of the response, sent as If-Range
+    validator: String,
+    /// Size of `<path>.part`
+    #[serde(skip)]
+    offset: u64,
+}
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+impl PartialDownload {
+    /// Loads the partial download of `url` at `path`, discarding the one of another URL or
+    /// without a validator.
+    async fn load(path: &std::path::Path, url: &str) -> Option<Self> {
+        let offset = match tokio::fs::metadata(TemporaryFile::path_for(path)).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => return None,
+        };
+        let resume = tokio::fs::read(TemporaryFile::resume_path_for(path))
+            .await
+            .ok()
+            .and_then(|json| serde_json::from_slice::<Self>(&json).ok());
+        match resume {
+            Some(resume) if resume.url == url && offset > 0 => Some(Self { offset, ..resume }),
+            _ => {
+                tracing::debug!("Discard the partial download of {} not validated", url);
+                let _ = TemporaryFile::remove(path).await;
+                None
+            }
+        }
+    }
+
+    /// Saves the source of `<path>.part` being downloaded, or removes the stale one when the
+    /// response has no validator.
+    async fn save(
+        path: &std::path::Path,
+        url: &str,
+        validator: Option<&str>,
+    ) -> Result<(), std::io::Error> {
+        let resume_path = TemporaryFile::resume_path_for(path);
+        match validator {
+            Some(validator) => {
+                let resume = Self {
+                    url: url.to_owned(),
+                    validator: validator.to_owned(),
+                    offset: 0,
+                };
+                let json = serde_json::to_vec(&resume).map_err(std::io::Error::other)?;
+                tokio::fs::write(&resume_path, json).await
+            }
+            None => match tokio::fs::remove_file(&resume_path).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            },
+        }
+    }
+}
+
+/// Returns the validator of the response usable in If-Range, i.e. a strong ETag or Last-Modified.
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+fn response_validator(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    let header = |name| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned)
+    };
+    header(reqwest::header::ETAG)
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| header(reqwest::header::LAST_MODIFIED))
+}
+
+/// Parses `Content-Range: bytes <start>-<end>/<total>` into the start and the total, which is
+/// `None` when unknown.
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+fn parse_content_range(headers: &reqwest::header::HeaderMap) -> Option<(u64, Option<u64>)> {
+    let value = headers.get(reqwest::header::CONTENT_RANGE)?.to_str().ok()?;
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _) = range.split_once('-')?;
+    Some((start.trim().parse().ok()?, total.trim().parse().ok()))
+}
+
+/// Flushes the directory entry of `path` to disk. Directories can't be synced on Windows.
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+fn sync_parent_dir(path: &std::path::Path) -> Result<(), std::io::Error> {
+    #[cfg(unix)]
+    {
+        let dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or_else(|| std::path::Path::new("."));
+        std::fs::File::open(dir)?.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Returns whether the request may succeed when retried, i.e. on 429 and 5xx responses,
+/// timeouts, connection errors and responses cut off.
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+fn is_transient(error: &Error) -> bool {
+    match error {
+        Error::HttpStatusError { status, .. } => *status == 429 || *status >= 500,
+        Error::HttpRequestError(e) => e.is_timeout() || e.is_connect(),
+        Error::HttpReadError(_) | Error::IdleTimeout { .. } | Error::IncompleteDownload { .. } => {
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Returns true if `path` is an HTML attachment, which is legitimately served as HTML.
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+fn expects_html(path: &std::path::Path) -> bool {
+    path.extension().is_some_and(|extension| {
+        extension.eq_ignore_ascii_case("html") || extension.eq_ignore_ascii_case("htm")
+    })
+}
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+fn is_html_type(content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or("").trim();
+    essence.eq_ignore_ascii_case("text/html")
+        || essence.eq_ignore_ascii_case("application/xhtml+xml")
+}
+
+/// Returns whether `url` is on fanbox.cc or its subdomains, which are sent the session cookie.
+#[cfg(not(target_arch = "wasm32"))]
+fn is_fanbox_url(url: &reqwest::Url) -> bool {
+    url.scheme() == "https"
+        && url
+            .host_str()
+            .is_some_and(|host| host == "fanbox.cc" || host.ends_with(".fanbox.cc"))
+}
+
+/// Redirect policy stopping at redirects to another Fanbox host, where reqwest would drop the
+/// session cookie, to be followed by `PostClient::send_download` with the cookie.
+///
+/// Other redirects are followed as usual, without the cookie when crossing hosts.
+#[cfg(not(target_arch = "wasm32"))]
+fn redirect_policy() -> reqwest::redirect::Policy {
+    reqwest::redirect::Policy::custom(|attempt| {
+        if attempt.previous().len() > MAX_REDIRECTS {
+            return attempt.error("too many redirects");
+        }
+        let crosses_hosts =
+            attempt.previous().last().and_then(|url| url.host_str()) != attempt.url().host_str();
+        if crosses_hosts && is_fanbox_url(attempt.url()) {
+            attempt.stop()
+        } else {
+            attempt.follow()
+        }
+    })
+}
+
+/// Returns the location of the redirect to a Fanbox host stopped by `redirect_policy`.
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+fn fanbox_redirect(response: &reqwest::Response) -> Option<reqwest::Url> {
+    if !response.status().is_redirection() {
+        return None;
+    }
+    let location = response
+        .headers()
+        .get(reqwest::header::LOCATION)?
+        .to_str()
+        .ok()?;
+    let location = response.url().join(location).ok()?;
+    is_fanbox_url(&location).then_some(location)
+}
+
+/// Returns whether the response is a block or challenge page of Cloudflare.
+///
+/// API errors are returned as JSON, so only HTML responses with Cloudflare's status codes are
+/// considered as blocked unless Cloudflare marks the response explicitly.
+fn is_blocked(response: &reqwest::Response) -> bool {
+    let headers = response.headers();
+    if headers.contains_key("cf-mitigated") {
+        return true;
+    }
+    let status = response.status().as_u16();
+    let from_cloudflare = headers
+        .get(reqwest::header::SERVER)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|server| server.eq_ignore_ascii_case("cloudflare"));
+    let is_html = headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| {
+            content_type
+                .split(';')
+                .next()
+                .unwrap_or("")
+                .trim()
+                .eq_ignore_ascii_case("text/html")
+        });
+    from_cloudflare && is_html && matches!(status, 403 | 429 | 503)
+}
+
+/// Sniffs the beginning of the HTML body for interstitial pages of Cloudflare.
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+fn looks_like_challenge(bytes: &[u8]) -> bool {
+    const MARKERS: &[&[u8]] = &[
+        b"Just a moment...",
+        b"/cdn-cgi/challenge-platform/",
+        b"cf-browser-verification",
+        b"Attention Required! | Cloudflare",
+    ];
+    MARKERS
+        .iter()
+        .any(|marker| bytes.windows(marker.len()).any(|window| window == *marker))
+}
+
+/// Sniffs the beginning of the response body for HTML documents.
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+fn looks_like_html(bytes: &[u8]) -> bool {
+    let bytes = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes);
+    let start = bytes
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    let head = &bytes[start..bytes.len().min(start + 14)];
+    [b"<!doctype html".as_slice(), b"<html"]
+        .iter()
+        .any(|prefix| {
+            head.len() >= prefix.len() && head[..prefix.len()].eq_ignore_ascii_case(prefix)
+        })
+}
+
+fn post_info_url(id: &str) -> reqwest::Url {
+    reqwest::Url::parse_with_params("https://api.fanbox.cc/post.info", &[("postId", id)])
+        .expect("URL of post.info is valid")
+}
+
+/// Returns paths of fields in `raw` which `T` doesn't know about, i.e. which are dropped by
+/// deserializing it into `T` and serializing it again, e.g. `body.blocks[].style`.
+///
+/// Raw JSON of unsupported types kept by `T` is not reported, which `Post::unknowns` returns.
+pub fn unknown_fields<T>(raw: &serde_json::Value) -> Result<Vec<String>, serde_json::Error>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    fn collect(
+        raw: &serde_json::Value,
+        known: &serde_json::Value,
+        path: &str,
+        fields: &mut std::collections::BTreeSet<String>,
+    ) {
+        match (raw, known) {
+            (serde_json::Value::Object(raw), serde_json::Value::Object(known)) => {
+                for (key, value) in raw {
+                    let path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{}.{}", path, key)
+                    };
+                    match known.get(key) {
+                        Some(known) => collect(value, known, &path, fields),
+                        // Nulls carry nothing the models miss
+                        None if value.is_null() => {}
+                        None => {
+                            fields.insert(path);
+                        }
+                    }
+                }
+            }
+            (serde_json::Value::Array(raw), serde_json::Value::Array(known)) => {
+                let path = format!("{}[]", path);
+                for (value, known) in raw.iter().zip(known) {
+                    collect(value, known, &path, fields);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let known = serde_json::to_value(serde_json::from_value::<T>(raw.clone())?)?;
+    let mut fields = std::collections::BTreeSet::new();
+    collect(raw, &known, "", &mut fields);
+    Ok(fields.into_iter().collect())
+}
+
+/// Fields already logged by `log_unknown_fields`, keyed by the endpoint
+static LOGGED_UNKNOWN_FIELDS: std::sync::Mutex<std::collections::BTreeSet<(&str, String)>> =
+    std::sync::Mutex::new(std::collections::BTreeSet::new());
+
+/// Logs fields of the response of `endpoint` unknown to `T` once per process, which tell new
+/// features of FANBOX the models don't support yet.
+fn log_unknown_fields<T>(endpoint: &'static str, raw: &serde_json::Value)
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    // Failures are reported when the whole response is deserialized
+    let fields = match unknown_fields::<T>(raw) {
+        Ok(fields) => fields,
+        Err(_) => return,
+    };
+    let mut logged = LOGGED_UNKNOWN_FIELDS.lock().unwrap();
+    for field in fields {
+        if logged.insert((endpoint, field.clone())) {
+            tracing::debug!(endpoint, field, "Unknown field in API response");
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct PaginateCreatorResponse {
+    body: Vec<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ListCreatorResponse {
+    body: ListCreatorResponseBody,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ListCreatorResponseBody {
+    items: Vec<ListCreatorItem>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ListFeedResponse {
+    body: ListFeedResponseBody,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListFeedResponseBody {
+    items: Vec<FeedItem>,
+    next_url: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ListBellResponse {
+    body: ListBellResponseBody,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListBellResponseBody {
+    items: Vec<BellItem>,
+    next_url: Option<String>,
+}
+
+/// Notification of the session user, which has the post only if it tells a new post.
+#[derive(Debug, serde::Deserialize)]
+struct BellItem {
+    #[serde(default)]
+    post: Option<FeedItem>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ListSupportingResponse {
+    body: Vec<SupportingPlan>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ListFollowingResponse {
+    body: Vec<FollowingCreator>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct GetCreatorResponse {
+    body: Creator,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct User {
+    pub user_id: String,
+    pub name: String,
+    pub icon_url: Option<String>,
+}
+
+/// Creator's profile. `creator_id` may be renamed by the creator while `user.user_id` is stable.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct Creator {
+    pub creator_id: String,
+    pub user: User,
+    #[serde(default)]
+    pub description: String,
+    pub cover_image_url: Option<String>,
+    #[serde(default)]
+    pub profile_links: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SupportingPlan {
+    pub id: String,
+    pub title: String,
+    pub fee: u32,
+    pub creator_id: String,
+    pub user: User,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct FollowingCreator {
+    pub creator_id: String,
+    pub user: User,
+}
+
+/// Position of a page in a creator's post listing, used to resume pagination.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PageCursor {
+    pub index: usize,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Page {
+    pub cursor: PageCursor,
+    pub items: Vec<ListCreatorItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ListCreatorItem {
+    pub id: String,
+    pub title: String,
+    #[serde(rename = "type")]
+    pub post_type: String,
+    pub published_datetime: chrono::DateTime<chrono::Utc>,
+    pub updated_datetime: chrono::DateTime<chrono::Utc>,
+    pub fee_required: u32,
+    pub is_restricted: bool,
+    /// Cover image shown in the post list, which is the first image of posts without covers
+    #[serde(default)]
+    pub cover: Option<ListItemCover>,
+    /// Beginning of the text of the post, which is empty for restricted posts
+    #[serde(default)]
+    pub excerpt: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub like_count: u64,
+    #[serde(default)]
+    pub comment_count: u64,
+    #[serde(default)]
+    pub has_adult_content: bool,
+}
+
+#[cfg(feature = "time")]
+impl ListCreatorItem {
+    /// Returns `published_datetime` as `time::OffsetDateTime`.
+    pub fn published_offset_datetime(&self) -> time::OffsetDateTime {
+        to_offset_datetime(&self.published_datetime)
+    }
+
+    /// Returns `updated_datetime` as `time::OffsetDateTime`.
+    pub fn updated_offset_datetime(&self) -> time::OffsetDateTime {
+        to_offset_datetime(&self.updated_datetime)
+    }
+}
+
+#[cfg(feature = "time")]
+fn to_offset_datetime(datetime: &chrono::DateTime<chrono::Utc>) -> time::OffsetDateTime {
+    time::OffsetDateTime::from_unix_timestamp(datetime.timestamp())
+        .and_then(|t| t.replace_nanosecond(datetime.timestamp_subsec_nanos()))
+        .expect("timestamps in RFC 3339 have 4-digit years within the range of time")
+}
+
+/// Timestamp set as the mtime of downloaded files.
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+pub trait Mtime {
+    /// Returns seconds and nanoseconds since the Unix epoch.
+    fn unix_time(&self) -> (i64, u32);
+}
+
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+impl<Tz: chrono::TimeZone> Mtime for chrono::DateTime<Tz> {
+    fn unix_time(&self) -> (i64, u32) {
+        (self.timestamp(), self.timestamp_subsec_nanos())
+    }
+}
+
+#[cfg(all(feature = "time", feature = "tokio", not(target_arch = "wasm32")))]
+impl Mtime for time::OffsetDateTime {
+    fn unix_time(&self) -> (i64, u32) {
+        (self.unix_timestamp(), self.nanosecond())
+    }
+}
+
+/// Post listed across creators, in the home feed, tagged posts or notifications.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct FeedItem {
+    pub creator_id: String,
+    #[serde(flatten)]
+    pub item: ListCreatorItem,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ListItemCover {
+    #[serde(rename = "type")]
+    pub cover_type: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub struct Post {
+    #[serde(flatten)]
+    pub info: PostInfo,
+    #[serde(flatten)]
+    pub body: Option<PostBody>,
+}
+
+/// Returns the JSON Schema of `Post`, which describes the supported post types.
+#[cfg(feature = "schemars")]
+pub fn post_schema() -> schemars::schema::RootSchema {
+    schemars::schema_for!(Post)
+}
+
+impl Post {
+    /// Returns the raw JSON of unsupported post bodies, blocks and embeds, with their kinds.
+    pub fn unknowns(&self) -> Vec<(&'static str, &serde_json::Value)> {
+        let mut unknowns = Vec::new();
+        match &self.body {
+            Some(PostBody::Unknown(raw)) => unknowns.push(("post body", raw)),
+            Some(PostBody::Video(video)) => {
+                if let Video::Unknown(raw) = &video.body.video {
+                    unknowns.push(("video", raw));
+                }
+            }
+            Some(PostBody::Article(article)) => {
+                for block in &article.body.blocks {
+                    if let ArticleBlock::Unknown(raw) = block {
+                        unknowns.push(("article block", raw));
+                    }
+                }
+                for embed in article.body.embed_map.values() {
+                    if let Embed::Unknown(raw) = embed {
+                        unknowns.push(("embed", raw));
+                    }
+                }
+                for url_embed in article.body.url_embed_map.values() {
+                    if let UrlEmbed::Unknown(raw) = url_embed {
+                        unknowns.push(("url embed", raw));
+                    }
+                }
+            }
+            _ => {}
+        }
+        unknowns
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct PostInfo {
+    pub id: String,
+    pub title: String,
+    pub cover_image_url: Option<String>,
+    pub published_datetime: chrono::DateTime<chrono::Utc>,
+    pub updated_datetime: chrono::DateTime<chrono::Utc>,
+    pub creator_id: String,
+    /// Pixiv user of the creator, missing in metadata archived by older versions
+    pub user: Option<User>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Minimum fee in JPY of plans which can see the post, missing in metadata archived by older
+    /// versions
+    pub fee_required: Option<u32>,
+    #[serde(default)]
+    pub like_count: u64,
+    #[serde(default)]
+    pub comment_count: u64,
+    #[serde(default)]
+    pub has_adult_content: bool,
+}
+
+#[cfg(feature = "time")]
+impl PostInfo {
+    /// Returns `published_datetime` as `time::OffsetDateTime`.
+    pub fn published_offset_datetime(&self) -> time::OffsetDateTime {
+        to_offset_datetime(&self.published_datetime)
+    }
+
+    /// Returns `updated_datetime` as `time::OffsetDateTime`.
+    pub fn updated_offset_datetime(&self) -> time::OffsetDateTime {
+        to_offset_datetime(&self.updated_datetime)
+    }
+}
+
+/// Implements `Serialize` and `Deserialize` for an internally tagged enum deriving them with
+/// `#[serde(remote = "Self")]`, keeping the raw JSON in `Unknown` for unsupported tags.
+macro_rules! serde_with_unknown {
+    ($ty:ident, $tag:literal, [$($known:literal),* $(,)?]) => {
+        impl serde::Serialize for $ty {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                match self {
+                    $ty::Unknown(raw) => raw.serialize(serializer),
+                    _ => $ty::serialize(self, serializer),
+                }
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $ty {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                let value = serde_json::Value::deserialize(deserializer)?;
+                match value.get($tag).and_then(|v| v.as_str()) {
+                    Some($($known)|*) => $ty::deserialize(value).map_err(serde::de::Error::custom),
+                    _ => Ok($ty::Unknown(value)),
+                }
+            }
+        }
+    };
+}
+
+serde_with_unknown!(
+    PostBody,
+    "type",
+    ["image", "article", "file", "text", "video"]
+);
+serde_with_unknown!(
+    ArticleBlock,
+    "type",
+    ["p", "header", "image", "file", "embed", "url_embed"]
+);
+serde_with_unknown!(
+    Embed,
+    "serviceProvider",
+    ["twitter", "fanbox", "youtube", "vimeo", "soundcloud"]
+);
+serde_with_unknown!(UrlEmbed, "type", ["default", "html", "html.card"]);
+serde_with_unknown!(Video, "serviceProvider", ["youtube", "vimeo", "soundcloud"]);
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[non_exhaustive]
+#[serde(remote = "Self", rename_all = "snake_case", tag = "type")]
+pub enum PostBody {
+    Image(PostBodyImage),
+    Article(PostBodyArticle),
+    File(PostBodyFile),
+    Text(PostBodyText),
+    Video(PostBodyVideo),
+    /// Raw JSON of a type which is not supported yet
+    #[serde(skip)]
+    Unknown(serde_json::Value),
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PostBodyImage {
+    pub body: PostBodyImageBody,
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PostBodyImageBody {
+    pub text: String,
+    pub images: Vec<Image>,
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct Image {
+    pub id: String,
+    pub extension: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub original_url: String,
+    /// URL of the image resized to 1200px wide
+    pub thumbnail_url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct PostBodyArticle {
+    pub body: PostBodyArticleBody,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct PostBodyArticleBody {
+    pub blocks: Vec<ArticleBlock>,
+    pub image_map: std::collections::HashMap<String, Image>,
+    pub file_map: std::collections::HashMap<String, File>,
+    pub embed_map: std::collections::HashMap<String, Embed>,
+    pub url_embed_map: std::collections::HashMap<String, UrlEmbed>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[non_exhaustive]
+#[serde(remote = "Self", rename_all = "snake_case", tag = "type")]
+pub enum ArticleBlock {
+    P(ArticleBlockP),
+    Header(ArticleBlockHeader),
+    Image(ArticleBlockImage),
+    File(ArticleBlockFile),
+    Embed(ArticleBlockEmbed),
+    UrlEmbed(ArticleBlockUrlEmbed),
+    /// Raw JSON of a type which is not supported yet
+    #[serde(skip)]
+    Unknown(serde_json::Value),
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ArticleBlockP {
+    pub text: String,
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ArticleBlockHeader {
+    pub text: String,
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ArticleBlockImage {
+    pub image_id: String,
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ArticleBlockFile {
+    pub file_id: String,
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ArticleBlockEmbed {
+    pub embed_id: String,
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ArticleBlockUrlEmbed {
+    pub url_embed_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PostBodyFile {
+    pub body: PostBodyFileBody,
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PostBodyFileBody {
+    pub text: String,
+    pub files: Vec<File>,
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct File {
+    pub id: String,
+    pub extension: String,
+    pub name: String,
+    pub size: Option<u64>,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[non_exhaustive]
+#[serde(remote = "Self", tag = "serviceProvider", rename_all = "lowercase")]
+pub enum Embed {
+    Twitter(EmbedTwitter),
+    Fanbox(EmbedFanbox),
+    Youtube(EmbedYoutube),
+    Vimeo(EmbedVimeo),
+    Soundcloud(EmbedSoundcloud),
+    /// Raw JSON of a type which is not supported yet
+    #[serde(skip)]
+    Unknown(serde_json::Value),
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct EmbedTwitter {
+    pub content_id: String,
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct EmbedFanbox {
+    pub content_id: String,
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct EmbedYoutube {
+    pub content_id: String,
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct EmbedVimeo {
+    pub content_id: String,
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct EmbedSoundcloud {
+    /// Track id
+    pub content_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[non_exhaustive]
+#[serde(remote = "Self", tag = "type", rename_all = "snake_case")]
+pub enum UrlEmbed {
+    Default(UrlEmbedDefault),
+    Html(UrlEmbedHtml),
+    #[serde(rename = "html.card")]
+    HtmlCard(UrlEmbedHtml),
+    /// Raw JSON of a type which is not supported yet
+    #[serde(skip)]
+    Unknown(serde_json::Value),
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct UrlEmbedDefault {
+    pub url: String,
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct UrlEmbedHtml {
+    pub html: String,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PostBodyText {
+    pub body: PostBodyTextBody,
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PostBodyTextBody {
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PostBodyVideo {
+    pub body: PostBodyVideoBody,
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PostBodyVideoBody {
+    pub text: String,
+    pub video: Video,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[non_exhaustive]
+#[serde(remote = "Self", tag = "serviceProvider", rename_all = "lowercase")]
+pub enum Video {
+    Youtube(VideoYoutube),
+    Vimeo(VideoVimeo),
+    Soundcloud(VideoSoundcloud),
+    /// Raw JSON of a provider which is not supported yet
+    #[serde(skip)]
+    Unknown(serde_json::Value),
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct VideoYoutube {
+    pub video_id: String,
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct VideoVimeo {
+    pub video_id: String,
+}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct VideoSoundcloud {
+    /// Track id
+    pub video_id: String,
+}
@@ -0,0 +1,348 @@
+/// Source of the FANBOXSESSID cookie which `PostClient::from_session_store` sends.
+pub trait SessionStore {
+    /// Returns the value of FANBOXSESSID.
+    fn session_id(&self) -> Result<String, super::Error>;
+}
+
+/// Value of FANBOXSESSID given as is, e.g. by the command-line.
+impl SessionStore for str {
+    fn session_id(&self) -> Result<String, super::Error> {
+        Ok(self.to_owned())
+    }
+}
+
+impl SessionStore for String {
+    fn session_id(&self) -> Result<String, super::Error> {
+        Ok(self.clone())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const SESSION_COOKIE_NAME: &str = "FANBOXSESSID";
+
+/// Returns whether the cookie of `domain` is sent to fanbox.cc.
+#[cfg(not(target_arch = "wasm32"))]
+fn is_fanbox_domain(domain: &str) -> bool {
+    let domain = domain.trim_start_matches('.');
+    domain == "fanbox.cc" || domain.ends_with(".fanbox.cc")
+}
+
+/// Cookie file exported from a browser, in the Netscape format of cookies.txt or JSON.
+///
+/// JSON files are arrays of cookies with `name`, `value` and `domain`, as exported by browser
+/// extensions, or objects with such an array in `cookies`, as saved by Playwright.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct CookieFile {
+    path: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl CookieFile {
+    pub fn new<P>(path: P) -> Self
+    where
+        P: Into<std::path::PathBuf>,
+    {
+        Self { path: path.into() }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SessionStore for CookieFile {
+    fn session_id(&self) -> Result<String, super::Error> {
+        let load_error = |message: String| super::Error::LoadSession {
+            store: self.path.display().to_string(),
+            message,
+        };
+        let content = std::fs::read_to_string(&self.path).map_err(|e| load_error(e.to_string()))?;
+        let session_id = if content.trim_start().starts_with(['[', '{']) {
+            find_json_cookie(&content).map_err(|e| load_error(e.to_string()))?
+        } else {
+            find_netscape_cookie(&content)
+        };
+        session_id
+            .ok_or_else(|| load_error(format!("{} of fanbox.cc is missing", SESSION_COOKIE_NAME)))
+    }
+}
+
+/// Finds the session in lines of `<domain> <subdomains> <path> <secure> <expiry> <name> <value>`
+/// separated by tabs, where HttpOnly cookies are prefixed with `#HttpOnly_`.
+#[cfg(not(target_arch = "wasm32"))]
+fn find_netscape_cookie(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+        if line.starts_with('#') {
+            return None;
+        }
+        let fields: Vec<_> = line.trim_end_matches('\r').split('\t').collect();
+        match fields.as_slice() {
+            [domain, _, _, _, _, name, value]
+                if *name == SESSION_COOKIE_NAME && is_fanbox_domain(domain) =>
+            {
+                Some((*value).to_owned())
+            }
+            _ => None,
+        }
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn find_json_cookie(content: &str) -> Result<Option<String>, serde_json::Error> {
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum CookieJson {
+        Cookies(Vec<Cookie>),
+        StorageState { cookies: Vec<Cookie> },
+    }
+    #[derive(serde::Deserialize)]
+    struct Cookie {
+        name: String,
+        value: String,
+        #[serde(alias = "host")]
+        domain: String,
+    }
+
+    let cookies = match serde_json::from_str(content)? {
+        CookieJson::Cookies(cookies) | CookieJson::StorageState { cookies } => cookies,
+    };
+    Ok(cookies
+        .into_iter()
+        .find(|cookie| cookie.name == SESSION_COOKIE_NAME && is_fanbox_domain(&cookie.domain))
+        .map(|cookie| cookie.value))
+}
+
+/// Browser whose cookies are imported by `BrowserProfile`.
+#[cfg(all(feature = "browser-cookies", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Browser {
+    Firefox,
+    /// Chromium and browsers based on it, e.g. Google Chrome. Encrypted cookies are decrypted
+    /// on Linux and macOS, where the key may be read from the keyring
+    Chromium,
+}
+
+/// Profile directory of a browser logged in to FANBOX, e.g. `~/.mozilla/firefox/xxxxxxxx.default`
+/// or `~/.config/chromium/Default`.
+///
+/// The cookie database is copied before read, since the browser may keep it locked.
+#[cfg(all(feature = "browser-cookies", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone)]
+pub struct BrowserProfile {
+    browser: Browser,
+    dir: std::path::PathBuf,
+}
+
+#[cfg(all(feature = "browser-cookies", not(target_arch = "wasm32")))]
+impl BrowserProfile {
+    pub fn new<P>(browser: Browser, dir: P) -> Self
+    where
+        P: Into<std::path::PathBuf>,
+    {
+        Self {
+            browser,
+            dir: dir.into(),
+        }
+    }
+
+    fn load(&self) -> Result<Option<String>, String> {
+        let database = match self.browser {
+            Browser::Firefox => self.dir.join("cookies.sqlite"),
+            // Moved into Network/ since Chromium 96
+            Browser::Chromium => [
+                self.dir.join("Network").join("Cookies"),
+                self.dir.join("Cookies"),
+            ]
+            .into_iter()
+            .find(|path| path.is_file())
+            .unwrap_or_else(|| self.dir.join("Cookies")),
+        };
+        let copy_dir =
+            std::env::temp_dir().join(format!("fanbox-dl-cookies-{}", std::process::id()));
+        std::fs::create_dir_all(&copy_dir).map_err(|e| e.to_string())?;
+        let result = copy_database(&database, &copy_dir).and_then(|copy| {
+            // Opened writable to apply the write-ahead log to the copy
+            let conn = rusqlite::Connection::open(copy).map_err(|e| e.to_string())?;
+            match self.browser {
+                Browser::Firefox => load_firefox_cookie(&conn),
+                Browser::Chromium => load_chromium_cookie(&conn, &self.dir),
+            }
+        });
+        let _ = std::fs::remove_dir_all(&copy_dir);
+        result
+    }
+}
+
+#[cfg(all(feature = "browser-cookies", not(target_arch = "wasm32")))]
+impl SessionStore for BrowserProfile {
+    fn session_id(&self) -> Result<String, super::Error> {
+        let load_error = |message: String| super::Error::LoadSession {
+            store: self.dir.display().to_string(),
+            message,
+        };
+        self.load().map_err(load_error)?.ok_or_else(|| {
+            load_error(format!(
+                "{} of fanbox.cc is missing, which requires logging in to FANBOX",
+                SESSION_COOKIE_NAME
+            ))
+        })
+    }
+}
+
+/// Copies the database with its write-ahead log into `dir`, returning the copy.
+#[cfg(all(feature = "browser-cookies", not(target_arch = "wasm32")))]
+fn copy_database(
+    database: &std::path::Path,
+    dir: &std::path::Path,
+) -> Result<std::path::PathBuf, String> {
+    let copy = dir.join("cookies.sqlite");
+    std::fs::copy(database, &copy).map_err(|e| format!("{}: {}", database.display(), e))?;
+    let mut wal = database.as_os_str().to_owned();
+    wal.push("-wal");
+    let wal = std::path::PathBuf::from(wal);
+    if wal.is_file() {
+        std::fs::copy(&wal, dir.join("cookies.sqlite-wal"))
+            .map_err(|e| format!("{}: {}", wal.display(), e))?;
+    }
+    Ok(copy)
+}
+
+#[cfg(all(feature = "browser-cookies", not(target_arch = "wasm32")))]
+fn load_firefox_cookie(conn: &rusqlite::Connection) -> Result<Option<String>, String> {
+    use rusqlite::OptionalExtension as _;
+
+    conn.query_row(
+        "SELECT value FROM moz_cookies WHERE name = ?1 AND (host = 'fanbox.cc' OR host LIKE '%.fanbox.cc') ORDER BY lastAccessed DESC LIMIT 1",
+        [SESSION_COOKIE_NAME],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(all(feature = "browser-cookies", not(target_arch = "wasm32")))]
+fn load_chromium_cookie(
+    conn: &rusqlite::Connection,
+    profile_dir: &std::path::Path,
+) -> Result<Option<String>, String> {
+    use rusqlite::OptionalExtension as _;
+
+    let cookie: Option<(String, Vec<u8>)> = conn
+        .query_row(
+            "SELECT value, encrypted_value FROM cookies WHERE name = ?1 AND (host_key = 'fanbox.cc' OR host_key LIKE '%.fanbox.cc') ORDER BY last_access_utc DESC LIMIT 1",
+            [SESSION_COOKIE_NAME],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let (value, encrypted_value) = match cookie {
+        Some(cookie) => cookie,
+        None => return Ok(None),
+    };
+    if !value.is_empty() || encrypted_value.is_empty() {
+        return Ok(Some(value));
+    }
+    let version: Option<String> = conn
+        .query_row("SELECT value FROM meta WHERE key = 'version'", [], |row| {
+            row.get(0)
+        })
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let mut decrypted = decrypt_chromium_value(&encrypted_value, profile_dir)?;
+    // Values are prefixed with the SHA-256 digest of the domain since version 24
+    if version.and_then(|version| version.parse::<u32>().ok()) >= Some(24) {
+        decrypted.drain(..32.min(decrypted.len()));
+    }
+    String::from_utf8(decrypted)
+        .map(Some)
+        .map_err(|_| "failed to decrypt the cookie".to_owned())
+}
+
+/// Decrypts a cookie encrypted by AES-128-CBC with the key derived from the password of the
+/// browser, which is `peanuts` for `v10` on Linux and kept in the keyring for `v11` on Linux and
+/// on macOS.
+#[cfg(all(feature = "browser-cookies", not(target_arch = "wasm32")))]
+fn decrypt_chromium_value(
+    encrypted_value: &[u8],
+    profile_dir: &std::path::Path,
+) -> Result<Vec<u8>, String> {
+    use aes::cipher::{BlockDecryptMut as _, KeyIvInit as _};
+
+    let (prefix, ciphertext) = encrypted_value.split_at(3.min(encrypted_value.len()));
+    let (password, iterations) = match prefix {
+        b"v10" if cfg!(target_os = "linux") => (b"peanuts".to_vec(), 1),
+        b"v11" if cfg!(target_os = "linux") => (
+            keyring_password(profile_dir, |application| {
+                vec![
+                    "secret-tool".to_owned(),
+                    "lookup".to_owned(),
+                    "application".to_owned(),
+                    application.to_lowercase(),
+                ]
+            })?,
+            1,
+        ),
+        b"v10" if cfg!(target_os = "macos") => (
+            keyring_password(profile_dir, |application| {
+                vec![
+                    "security".to_owned(),
+                    "find-generic-password".to_owned(),
+                    "-w".to_owned(),
+                    "-s".to_owned(),
+                    format!("{} Safe Storage", application),
+                ]
+            })?,
+            1003,
+        ),
+        _ => {
+            return Err(
+                "decrypting cookies of Chromium isn't supported on this platform; export them into a cookie file instead"
+                    .to_owned(),
+            )
+        }
+    };
+    let mut key = [0; 16];
+    pbkdf2::pbkdf2_hmac::<sha1::Sha1>(&password, b"saltysalt", iterations, &mut key);
+    let mut buffer = ciphertext.to_vec();
+    let decrypted = cbc::Decryptor::<aes::Aes128>::new(&key.into(), &[b' '; 16].into())
+        .decrypt_padded_mut::<aes::cipher::block_padding::Pkcs7>(&mut buffer)
+        .map_err(|_| "failed to decrypt the cookie".to_owned())?
+        .len();
+    buffer.truncate(decrypted);
+    Ok(buffer)
+}
+
+/// Reads the password of the browser from the keyring by the command, trying the application
+/// named after the profile directory first.
+#[cfg(all(feature = "browser-cookies", not(target_arch = "wasm32")))]
+fn keyring_password<F>(profile_dir: &std::path::Path, command: F) -> Result<Vec<u8>, String>
+where
+    F: Fn(&str) -> Vec<String>,
+{
+    let mut applications = vec!["Chrome", "Chromium"];
+    if profile_dir
+        .to_string_lossy()
+        .to_lowercase()
+        .contains("chromium")
+    {
+        applications.reverse();
+    }
+    for application in applications {
+        let args = command(application);
+        let output = std::process::Command::new(&args[0])
+            .args(&args[1..])
+            .stderr(std::process::Stdio::null())
+            .output();
+        match output {
+            Ok(output) if output.status.success() => {
+                let mut password = output.stdout;
+                while password.last().is_some_and(u8::is_ascii_whitespace) {
+                    password.pop();
+                }
+                return Ok(password);
+            }
+            Ok(_) => continue,
+            Err(e) => return Err(format!("failed to run {}: {}", args[0], e)),
+        }
+    }
+    Err("the password of the browser is missing in the keyring".to_owned())
+}
@@ -0,0 +1,696 @@
+//! Fake FANBOX answering `PostClient` without credentials, for end-to-end tests of applications
+//! built on this crate.
+//!
+//! The fake listens on a port of the loopback interface as an HTTP proxy. Clients built by
+//! `FakeFanbox::client` send every request through it, and it answers the tunneled HTTPS
+//! requests itself with a self-signed certificate of fanbox.cc, its subdomains and example.com.
+//! Requests therefore go through the same HTTP stack, redirect policy and cookies as against
+//! FANBOX.
+
+/// Creator of the posts in `FakeFanbox::with_fixtures`
+pub const FIXTURE_CREATOR_ID: &str = "fixture";
+
+/// Pixiv user id of `FIXTURE_CREATOR_ID`
+pub const FIXTURE_USER_ID: &str = "1000";
+
+/// Session id sent by clients of `FakeFanbox::client`
+pub const FAKE_SESSION_ID: &str = "fake";
+
+/// Number of posts in each page of `post.listCreator`
+const PAGE_SIZE: usize = 10;
+
+/// Hosts in the certificate of the fake, where example.com stands for hosts outside FANBOX
+const HOSTS: &[&str] = &["fanbox.cc", "*.fanbox.cc", "example.com", "*.example.com"];
+
+/// 1x1 transparent PNG returned for downloads of images
+const PNG: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1f, 0x15, 0xc4,
+    0x89, 0x00, 0x00, 0x00, 0x0a, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0x00, 0x01, 0x00, 0x00,
+    0x05, 0x00, 0x01, 0x0d, 0x0a, 0x2d, 0xb4, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae,
+    0x42, 0x60, 0x82,
+];
+
+/// In-memory FANBOX serving creators, posts and files added to it.
+///
+/// Clones share the same state, so posts can be added after the client is built. Downloads which
+/// aren't added with `add_file` or `add_download` return a PNG image for image URLs and their URL
+/// otherwise.
+#[derive(Debug, Clone, Default)]
+pub struct FakeFanbox {
+    state: std::sync::Arc<std::sync::Mutex<State>>,
+}
+
+/// Download served by `FakeFanbox`, see `FakeFanbox::add_download`.
+#[derive(Debug, Clone, Default)]
+pub struct FakeDownload {
+    pub content: Vec<u8>,
+    /// ETag of the content. Range requests are answered with 206 only when it's set and matches
+    /// their If-Range
+    pub etag: Option<String>,
+    /// Drops the connection of the next response after this number of bytes of the body
+    pub cut_off_after: Option<usize>,
+    /// Start of the range answered to range requests instead of the requested one
+    pub range_start: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    creators: Vec<super::Creator>,
+    /// Raw JSON of posts, which may have unsupported types
+    posts: Vec<serde_json::Value>,
+    downloads: std::collections::HashMap<String, FakeDownload>,
+    redirects: std::collections::HashMap<String, String>,
+    requests: Vec<(String, reqwest::header::HeaderMap)>,
+    server: Option<Server>,
+}
+
+/// Proxy started by the first `FakeFanbox::client`
+#[derive(Debug, Clone)]
+struct Server {
+    addr: std::net::SocketAddr,
+    /// DER of the certificate served for `HOSTS`
+    certificate: Vec<u8>,
+}
+
+impl FakeFanbox {
+    /// Returns a fake without creators and posts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a fake with the creator `FIXTURE_CREATOR_ID` and `fixture_posts`.
+    pub fn with_fixtures() -> Self {
+        let fake = Self::new();
+        fake.add_creator(fixture_creator());
+        for post in fixture_posts() {
+            fake.add_post(post);
+        }
+        fake
+    }
+
+    pub fn add_creator(&self, creator: super::Creator) {
+        self.state.lock().unwrap().creators.push(creator);
+    }
+
+    /// Adds the raw JSON of `post.info`, replacing the post with the same id.
+    pub fn add_post(&self, post: serde_json::Value) {
+        let mut state = self.state.lock().unwrap();
+        state.posts.retain(|p| p["id"] != post["id"]);
+        state.posts.push(post);
+    }
+
+    /// Serves `content` at `url` instead of the generated content.
+    pub fn add_file(&self, url: &str, content: Vec<u8>) {
+        self.add_download(
+            url,
+            FakeDownload {
+                content,
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Serves `download` at `url`, replacing the previous one.
+    pub fn add_download(&self, url: &str, download: FakeDownload) {
+        self.state
+            .lock()
+            .unwrap()
+            .downloads
+            .insert(url.to_owned(), download);
+    }
+
+    /// Redirects requests of `url` to `location` with 302.
+    pub fn add_redirect(&self, url: &str, location: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .redirects
+            .insert(url.to_owned(), location.to_owned());
+    }
+
+    /// Returns URLs requested so far, oldest first.
+    pub fn requests(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        state.requests.iter().map(|(url, _)| url.clone()).collect()
+    }
+
+    /// Returns headers of requests of `url` so far, oldest first.
+    pub fn request_headers(&self, url: &str) -> Vec<reqwest::header::HeaderMap> {
+        let state = self.state.lock().unwrap();
+        state
+            .requests
+            .iter()
+            .filter(|(requested, _)| requested == url)
+            .map(|(_, headers)| headers.clone())
+            .collect()
+    }
+
+    /// Returns a client with the session `FAKE_SESSION_ID` answered by this fake.
+    ///
+    /// # Panics
+    ///
+    /// Panics outside a tokio runtime, on which the fake is served until it shuts down.
+    pub fn client(&self) -> super::PostClient {
+        let server = self.server();
+        let builder = reqwest::ClientBuilder::new()
+            .proxy(
+                reqwest::Proxy::all(format!("http://{}", server.addr))
+                    .expect("address of the fake is a valid proxy URL"),
+            )
+            .tls_built_in_root_certs(false)
+            .add_root_certificate(
+                reqwest::Certificate::from_der(&server.certificate)
+                    .expect("certificate of the fake is valid"),
+            );
+        super::PostClient::with_client_builder(FAKE_SESSION_ID, builder)
+            .expect("client of the fake is built")
+    }
+
+    /// Returns the proxy, starting it on the first call.
+    fn server(&self) -> Server {
+        let mut state = self.state.lock().unwrap();
+        if let Some(server) = &state.server {
+            return server.clone();
+        }
+        let certificate = rcgen::generate_simple_self_signed(
+            HOSTS
+                .iter()
+                .map(|host| host.to_string())
+                .collect::<Vec<_>>(),
+        )
+        .expect("certificate of the fake is generated");
+        let der = certificate
+            .serialize_der()
+            .expect("certificate of the fake is serialized");
+        let tls_config = tokio_rustls::rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![tokio_rustls::rustls::Certificate(der.clone())],
+                tokio_rustls::rustls::PrivateKey(certificate.serialize_private_key_der()),
+            )
+            .expect("certificate of the fake is usable");
+        let listener = std::net::TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, 0))
+            .expect("loopback port is bound");
+        listener
+            .set_nonblocking(true)
+            .expect("listener is made non-blocking");
+        let server = Server {
+            addr: listener.local_addr().expect("listener has an address"),
+            certificate: der,
+        };
+        let listener =
+            tokio::net::TcpListener::from_std(listener).expect("listener is registered to tokio");
+        tokio::spawn(self.clone().accept(
+            listener,
+            tokio_rustls::TlsAcceptor::from(std::sync::Arc::new(tls_config)),
+        ));
+        state.server = Some(server.clone());
+        server
+    }
+
+    /// Accepts connections to the proxy, which tunnels them to `respond` over TLS.
+    async fn accept(self, listener: tokio::net::TcpListener, acceptor: tokio_rustls::TlsAcceptor) {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    tracing::debug!("Failed to accept a connection to the fake: {}", e);
+                    continue;
+                }
+            };
+            let fake = self.clone();
+            let acceptor = acceptor.clone();
+            let service = hyper::service::service_fn(move |request| {
+                std::future::ready(Ok::<_, std::convert::Infallible>(
+                    fake.clone().tunnel(acceptor.clone(), request),
+                ))
+            });
+            tokio::spawn(
+                hyper::server::conn::Http::new()
+                    .http1_only(true)
+                    .serve_connection(stream, service)
+                    .with_upgrades(),
+            );
+        }
+    }
+
+    /// Answers CONNECT to the proxy and serves the tunneled connection.
+    fn tunnel(
+        self,
+        acceptor: tokio_rustls::TlsAcceptor,
+        request: hyper::Request<hyper::Body>,
+    ) -> hyper::Response<hyper::Body> {
+        if request.method() != hyper::Method::CONNECT {
+            return status_response(405);
+        }
+        tokio::spawn(async move {
+            let upgraded = match hyper::upgrade::on(request).await {
+                Ok(upgraded) => upgraded,
+                Err(e) => return tracing::debug!("Failed to open a tunnel to the fake: {}", e),
+            };
+            let stream = match acceptor.accept(upgraded).await {
+                Ok(stream) => stream,
+                Err(e) => return tracing::debug!("Failed to accept TLS of the fake: {}", e),
+            };
+            let service = hyper::service::service_fn(move |request| {
+                std::future::ready(Ok::<_, std::convert::Infallible>(self.respond(&request)))
+            });
+            if let Err(e) = hyper::server::conn::Http::new()
+                .http1_only(true)
+                .serve_connection(stream, service)
+                .await
+            {
+                tracing::debug!("Connection to the fake failed: {}", e);
+            }
+        });
+        status_response(200)
+    }
+
+    fn respond(&self, request: &hyper::Request<hyper::Body>) -> hyper::Response<hyper::Body> {
+        let host = request
+            .headers()
+            .get(hyper::header::HOST)
+            .and_then(|host| host.to_str().ok())
+            .unwrap_or_default();
+        let path = request
+            .uri()
+            .path_and_query()
+            .map_or("/", |path| path.as_str());
+        let url = match reqwest::Url::parse(&format!("https://{}{}", host, path)) {
+            Ok(url) => url,
+            Err(_) => return status_response(400),
+        };
+        let mut state = self.state.lock().unwrap();
+        state
+            .requests
+            .push((url.to_string(), request.headers().clone()));
+        if let Some(location) = state.redirects.get(url.as_str()) {
+            return hyper::Response::builder()
+                .status(302)
+                .header(hyper::header::LOCATION, location.as_str())
+                .body(hyper::Body::empty())
+                .expect("redirect response is built");
+        }
+        if url.host_str() == Some("api.fanbox.cc") {
+            let (status, body) = match state.api(&url) {
+                Some(body) => (200, serde_json::json!({ "body": body })),
+                None => (404, serde_json::json!({ "error": "not found" })),
+            };
+            return hyper::Response::builder()
+                .status(status)
+                .header(hyper::header::CONTENT_TYPE, "application/json")
+                .body(hyper::Body::from(body.to_string()))
+                .expect("API response is built");
+        }
+        let (content_type, download) = match state.downloads.get_mut(url.as_str()) {
+            Some(download) => {
+                let served = download.clone();
+                download.cut_off_after = None;
+                ("application/octet-stream", served)
+            }
+            None if is_image_url(&url) => (
+                "image/png",
+                FakeDownload {
+                    content: PNG.to_vec(),
+                    ..Default::default()
+                },
+            ),
+            None => (
+                "application/octet-stream",
+                FakeDownload {
+                    content: url.as_str().as_bytes().to_vec(),
+                    ..Default::default()
+                },
+            ),
+        };
+        drop(state);
+        download.respond(request.headers(), content_type)
+    }
+}
+
+impl FakeDownload {
+    /// Returns the response to the request with `headers`, answering range requests with 206
+    /// when `etag` matches.
+    fn respond(
+        self,
+        headers: &hyper::HeaderMap,
+        content_type: &str,
+    ) -> hyper::Response<hyper::Body> {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value: &hyper::header::HeaderValue| value.to_str().ok())
+        };
+        let range_start = header(hyper::header::RANGE)
+            .and_then(|range| range.strip_prefix("bytes="))
+            .and_then(|range| range.strip_suffix('-'))
+            .and_then(|start| start.parse::<usize>().ok());
+        let matches = self.etag.is_some()
+            && header(hyper::header::IF_RANGE)
+                .is_none_or(|etag| Some(etag) == self.etag.as_deref());
+        let mut response = hyper::Response::builder()
+            .header(hyper::header::CONTENT_TYPE, content_type)
+            .header(hyper::header::ACCEPT_RANGES, "bytes");
+        if let Some(etag) = &self.etag {
+            response = response.header(hyper::header::ETAG, etag);
+        }
+        let total = self.content.len();
+        let mut body = match range_start {
+            Some(start) if matches && start < total => {
+                let start = self.range_start.unwrap_or(start).min(total);
+                response = response.status(206).header(
+                    hyper::header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", start, total - 1, total),
+                );
+                self.content[start..].to_vec()
+            }
+            _ => {
+                response = response.status(200);
+                self.content
+            }
+        };
+        let response = response.header(hyper::header::CONTENT_LENGTH, body.len());
+        let body = match self.cut_off_after {
+            Some(bytes) if bytes < body.len() => {
+                use futures::stream::StreamExt as _;
+
+                body.truncate(bytes);
+                // Yields once so that the sent bytes are flushed before the connection is dropped
+                let cut_off = futures::stream::once(async {
+                    tokio::task::yield_now().await;
+                    Err(std::io::Error::from(std::io::ErrorKind::ConnectionReset))
+                });
+                hyper::Body::wrap_stream(futures::stream::once(async { Ok(body) }).chain(cut_off))
+            }
+            _ => hyper::Body::from(body),
+        };
+        response.body(body).expect("download response is built")
+    }
+}
+
+/// Returns an empty response with `status`.
+fn status_response(status: u16) -> hyper::Response<hyper::Body> {
+    hyper::Response::builder()
+        .status(status)
+        .body(hyper::Body::empty())
+        .expect("status response is built")
+}
+
+impl State {
+    /// Returns the body of the API response, or `None` for 404.
+    fn api(&self, url: &reqwest::Url) -> Option<serde_json::Value> {
+        let query: std::collections::HashMap<_, _> = url.query_pairs().collect();
+        let param = |name: &str| query.get(name).map(|value| value.as_ref());
+        match url.path() {
+            "/post.paginateCreator" => {
+                let creator_id = param("creatorId")?;
+                let pages = self.creator_posts(creator_id).len().div_ceil(PAGE_SIZE);
+                Some(
+                    (0..pages)
+                        .map(|page| {
+                            format!(
+                                "https://api.fanbox.cc/post.listCreator?creatorId={}&page={}",
+                                creator_id, page
+                            )
+                        })
+                        .collect(),
+                )
+            }
+            "/post.listCreator" => {
+                let page: usize = param("page")?.parse().ok()?;
+                let items: Vec<_> = self
+                    .creator_posts(param("creatorId")?)
+                    .into_iter()
+                    .skip(page * PAGE_SIZE)
+                    .take(PAGE_SIZE)
+                    .map(list_item)
+                    .collect();
+                Some(serde_json::json!({ "items": items }))
+            }
+            "/post.listHome" | "/post.listTagged" => {
+                let tag = param("tag");
+                let items: Vec<_> = self
+                    .sorted_posts()
+                    .into_iter()
+                    .filter(|post| {
+                        tag.is_none_or(|tag| {
+                            post["tags"]
+                                .as_array()
+                                .is_some_and(|tags| tags.iter().any(|t| t == tag))
+                        })
+                    })
+                    .map(|post| {
+                        let mut item = list_item(post);
+                        item["creatorId"] = post["creatorId"].clone();
+                        item
+                    })
+                    .collect();
+                Some(serde_json::json!({ "items": items, "nextUrl": null }))
+            }
+            "/bell.list" => Some(serde_json::json!({ "items": [], "nextUrl": null })),
+            "/post.info" => {
+                let id = param("postId")?;
+                self.posts.iter().find(|post| post["id"] == id).cloned()
+            }
+            "/creator.get" => {
+                let creator = match (param("creatorId"), param("userId")) {
+                    (Some(creator_id), _) => self
+                        .creators
+                        .iter()
+                        .find(|creator| creator.creator_id == creator_id),
+                    (None, Some(user_id)) => self
+                        .creators
+                        .iter()
+                        .find(|creator| creator.user.user_id == user_id),
+                    (None, None) => None,
+                }?;
+                serde_json::to_value(creator).ok()
+            }
+            "/plan.listSupporting" => Some(
+                self.creators
+                    .iter()
+                    .map(|creator| {
+                        serde_json::json!({
+                            "id": format!("plan-{}", creator.user.user_id),
+                            "title": "Fixture plan",
+                            "fee": 500,
+                            "creatorId": creator.creator_id,
+                            "user": creator.user,
+                        })
+                    })
+                    .collect(),
+            ),
+            "/creator.listFollowing" => Some(
+                self.creators
+                    .iter()
+                    .map(|creator| {
+                        serde_json::json!({
+                            "creatorId": creator.creator_id,
+                            "user": creator.user,
+                        })
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Returns posts newest first.
+    fn sorted_posts(&self) -> Vec<&serde_json::Value> {
+        let mut posts: Vec<_> = self.posts.iter().collect();
+        posts.sort_by(|a, b| {
+            b["publishedDatetime"]
+                .as_str()
+                .cmp(&a["publishedDatetime"].as_str())
+        });
+        posts
+    }
+
+    fn creator_posts(&self, creator_id: &str) -> Vec<&serde_json::Value> {
+        self.sorted_posts()
+            .into_iter()
+            .filter(|post| post["creatorId"] == creator_id)
+            .collect()
+    }
+}
+
+/// Returns the item of `post.listCreator` listing the post.
+fn list_item(post: &serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "id": post["id"],
+        "title": post["title"],
+        "type": post["type"],
+        "publishedDatetime": post["publishedDatetime"],
+        "updatedDatetime": post["updatedDatetime"],
+        "feeRequired": post["feeRequired"],
+        "isRestricted": post["body"].is_null(),
+        "tags": post["tags"].as_array().cloned().unwrap_or_default(),
+        "excerpt": post["body"]["text"].as_str().unwrap_or_default(),
+        "likeCount": 0,
+        "commentCount": 0,
+        "hasAdultContent": false,
+        "cover": post["coverImageUrl"].as_str().map(|url| serde_json::json!({
+            "type": "cover_image",
+            "url": url,
+        })),
+    })
+}
+
+fn is_image_url(url: &reqwest::Url) -> bool {
+    let path = url.path().to_ascii_lowercase();
+    [".png", ".jpg", ".jpeg", ".gif", ".webp"]
+        .iter()
+        .any(|extension| path.ends_with(extension))
+}
+
+/// Returns the creator `FIXTURE_CREATOR_ID`.
+pub fn fixture_creator() -> super::Creator {
+    super::Creator {
+        creator_id: FIXTURE_CREATOR_ID.to_owned(),
+        user: super::User {
+            user_id: FIXTURE_USER_ID.to_owned(),
+            name: "Fixture".to_owned(),
+            icon_url: Some(download_url("user/icon.png")),
+        },
+        description: "Creator of fixture posts".to_owned(),
+        cover_image_url: Some(download_url("creator/cover.jpeg")),
+        profile_links: vec!["https://example.com/".to_owned()],
+    }
+}
+
+/// Returns raw JSON of `post.info` of posts of every body, block and embed type, including
+/// unsupported ones and a post restricted to higher plans.
+pub fn fixture_posts() -> Vec<serde_json::Value> {
+    let image = |id: &str| {
+        serde_json::json!({
+            "id": id,
+            "extension": "png",
+            "width": 1,
+            "height": 1,
+            "originalUrl": download_url(&format!("images/{}.png", id)),
+            "thumbnailUrl": download_url(&format!("w/1200/images/{}.jpeg", id)),
+        })
+    };
+    let file = |id: &str| {
+        serde_json::json!({
+            "id": id,
+            "extension": "zip",
+            "name": format!("file-{}", id),
+            "size": 1024,
+            "url": download_url(&format!("files/{}.zip", id)),
+        })
+    };
+    vec![
+        fixture_post(
+            "1",
+            "image",
+            Some(serde_json::json!({
+                "text": "Image post",
+                "images": [image("i1"), image("i2")],
+            })),
+        ),
+        fixture_post(
+            "2",
+            "file",
+            Some(serde_json::json!({
+                "text": "File post",
+                "files": [file("f1")],
+            })),
+        ),
+        fixture_post(
+            "3",
+            "text",
+            Some(serde_json::json!({ "text": "Text post" })),
+        ),
+        fixture_post(
+            "4",
+            "video",
+            Some(serde_json::json!({
+                "text": "YouTube post",
+                "video": { "serviceProvider": "youtube", "videoId": "dQw4w9WgXcQ" },
+            })),
+        ),
+        fixture_post(
+            "5",
+            "video",
+            Some(serde_json::json!({
+                "text": "Vimeo post",
+                "video": { "serviceProvider": "vimeo", "videoId": "76979871" },
+            })),
+        ),
+        fixture_post(
+            "6",
+            "article",
+            Some(serde_json::json!({
+                "blocks": [
+                    { "type": "header", "text": "Article post" },
+                    { "type": "p", "text": "Paragraph" },
+                    { "type": "image", "imageId": "a1" },
+                    { "type": "file", "fileId": "af1" },
+                    { "type": "embed", "embedId": "twitter" },
+                    { "type": "embed", "embedId": "fanbox" },
+                    { "type": "embed", "embedId": "youtube" },
+                    { "type": "embed", "embedId": "vimeo" },
+                    { "type": "embed", "embedId": "soundcloud" },
+                    { "type": "embed", "embedId": "unknown" },
+                    { "type": "url_embed", "urlEmbedId": "default" },
+                    { "type": "url_embed", "urlEmbedId": "html" },
+                    { "type": "url_embed", "urlEmbedId": "card" },
+                    { "type": "url_embed", "urlEmbedId": "unknown" },
+                    { "type": "unknown_block", "text": "Unsupported block" },
+                ],
+                "imageMap": { "a1": image("a1") },
+                "fileMap": { "af1": file("af1") },
+                "embedMap": {
+                    "twitter": { "serviceProvider": "twitter", "contentId": "20" },
+                    "fanbox": { "serviceProvider": "fanbox", "contentId": "creator/fixture/post/1" },
+                    "youtube": { "serviceProvider": "youtube", "contentId": "dQw4w9WgXcQ" },
+                    "vimeo": { "serviceProvider": "vimeo", "contentId": "76979871" },
+                    "soundcloud": { "serviceProvider": "soundcloud", "contentId": "293" },
+                    "unknown": { "serviceProvider": "unknown", "contentId": "1" },
+                },
+                "urlEmbedMap": {
+                    "default": { "type": "default", "url": "https://example.com/" },
+                    "html": { "type": "html", "html": "<iframe src='https://example.com/'></iframe>" },
+                    "card": { "type": "html.card", "html": "<iframe src='https://example.com/card'></iframe>" },
+                    "unknown": { "type": "unknown", "url": "https://example.com/unknown" },
+                },
+            })),
+        ),
+        fixture_post(
+            "7",
+            "unknown",
+            Some(serde_json::json!({ "text": "Unsupported post" })),
+        ),
+        fixture_post("8", "image", None),
+    ]
+}
+
+/// Returns the post published on the `id`th day of 2024, restricted to higher plans without
+/// `body`.
+fn fixture_post(id: &str, post_type: &str, body: Option<serde_json::Value>) -> serde_json::Value {
+    let day: u32 = id.parse().unwrap_or(1);
+    let datetime = format!("2024-01-{:02}T00:00:00+09:00", day);
+    serde_json::json!({
+        "id": id,
+        "title": format!("Fixture {} post {}", post_type, id),
+        "coverImageUrl": download_url(&format!("covers/{}.jpeg", id)),
+        "publishedDatetime": datetime,
+        "updatedDatetime": datetime,
+        "creatorId": FIXTURE_CREATOR_ID,
+        "user": fixture_creator().user,
+        "tags": ["fixture", post_type],
+        "feeRequired": if body.is_some() { 500 } else { 1000 },
+        "likeCount": day,
+        "commentCount": 0,
+        "hasAdultContent": false,
+        "type": post_type,
+        "body": body,
+    })
+}
+
+fn download_url(path: &str) -> String {
+    format!("https://downloads.fanbox.cc/{}", path)
+}
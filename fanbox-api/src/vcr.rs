@@ -0,0 +1,208 @@
+//! Recording and replaying HTTP interactions of `PostClient` as cassette files, which reproduce
+//! responses of specific creators without their sessions.
+//!
+//! A cassette is a JSON lines file of interactions, from which cookies and credentials are
+//! removed. Both layers are added with `PostClient::layer`.
+
+/// Headers never written to cassettes
+const SENSITIVE_HEADERS: &[reqwest::header::HeaderName] = &[
+    reqwest::header::COOKIE,
+    reqwest::header::SET_COOKIE,
+    reqwest::header::AUTHORIZATION,
+    reqwest::header::PROXY_AUTHORIZATION,
+];
+
+/// HTTP request and its response recorded in a cassette.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Interaction {
+    pub method: String,
+    pub url: String,
+    pub request_headers: Vec<(String, String)>,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// Base64-encoded body of the response
+    pub body: String,
+}
+
+impl Interaction {
+    fn response(&self) -> Result<reqwest::Response, tower::BoxError> {
+        use base64::Engine as _;
+        use reqwest::ResponseBuilderExt as _;
+
+        let body = base64::engine::general_purpose::STANDARD.decode(&self.body)?;
+        let mut builder = http::Response::builder()
+            .status(self.status)
+            .url(reqwest::Url::parse(&self.url)?);
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        Ok(reqwest::Response::from(builder.body(body)?))
+    }
+}
+
+fn sanitized_headers(headers: &reqwest::header::HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter(|(name, _)| !SENSITIVE_HEADERS.contains(name))
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_owned())))
+        .collect()
+}
+
+/// Layer appending interactions sent through it to a cassette file.
+///
+/// Responses are read into memory to be recorded, so downloads aren't streamed.
+#[derive(Debug, Clone)]
+pub struct RecordLayer {
+    cassette: std::sync::Arc<std::sync::Mutex<std::fs::File>>,
+}
+
+impl RecordLayer {
+    /// Opens the cassette at `path` to append interactions.
+    pub fn create(path: &std::path::Path) -> Result<Self, std::io::Error> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Self {
+            cassette: std::sync::Arc::new(std::sync::Mutex::new(file)),
+        })
+    }
+}
+
+impl<S> tower::Layer<S> for RecordLayer {
+    type Service = Record<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Record {
+            inner,
+            cassette: self.cassette.clone(),
+        }
+    }
+}
+
+/// Service of `RecordLayer`.
+#[derive(Debug, Clone)]
+pub struct Record<S> {
+    inner: S,
+    cassette: std::sync::Arc<std::sync::Mutex<std::fs::File>>,
+}
+
+impl<S> tower::Service<reqwest::Request> for Record<S>
+where
+    S: tower::Service<reqwest::Request, Response = reqwest::Response, Error = tower::BoxError>,
+    S::Future: Send + 'static,
+{
+    type Response = reqwest::Response;
+    type Error = tower::BoxError;
+    type Future = std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>,
+    >;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: reqwest::Request) -> Self::Future {
+        use base64::Engine as _;
+
+        let method = request.method().to_string();
+        let request_headers = sanitized_headers(request.headers());
+        let cassette = self.cassette.clone();
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let response = response.await?;
+            let interaction = Interaction {
+                method,
+                url: response.url().to_string(),
+                request_headers,
+                status: response.status().as_u16(),
+                headers: sanitized_headers(response.headers()),
+                body: base64::engine::general_purpose::STANDARD.encode(response.bytes().await?),
+            };
+            {
+                use std::io::Write as _;
+
+                let mut line = serde_json::to_vec(&interaction)?;
+                line.push(b'\n');
+                cassette.lock().unwrap().write_all(&line)?;
+            }
+            interaction.response()
+        })
+    }
+}
+
+/// Layer answering requests with interactions of a cassette instead of sending them.
+///
+/// Interactions with the same method and URL are replayed in the recorded order, repeating the
+/// last one. Requests which aren't recorded fail.
+#[derive(Debug, Clone)]
+pub struct ReplayLayer {
+    interactions: std::sync::Arc<std::sync::Mutex<Interactions>>,
+}
+
+type Interactions =
+    std::collections::HashMap<(String, String), std::collections::VecDeque<Interaction>>;
+
+impl ReplayLayer {
+    /// Loads the cassette at `path`.
+    pub fn open(path: &std::path::Path) -> Result<Self, std::io::Error> {
+        let mut interactions = Interactions::new();
+        for line in std::fs::read_to_string(path)?.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let interaction: Interaction = serde_json::from_str(line)?;
+            interactions
+                .entry((interaction.method.clone(), interaction.url.clone()))
+                .or_default()
+                .push_back(interaction);
+        }
+        Ok(Self {
+            interactions: std::sync::Arc::new(std::sync::Mutex::new(interactions)),
+        })
+    }
+}
+
+impl<S> tower::Layer<S> for ReplayLayer {
+    type Service = Replay;
+
+    fn layer(&self, _inner: S) -> Self::Service {
+        Replay {
+            interactions: self.interactions.clone(),
+        }
+    }
+}
+
+/// Service of `ReplayLayer`.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    interactions: std::sync::Arc<std::sync::Mutex<Interactions>>,
+}
+
+impl tower::Service<reqwest::Request> for Replay {
+    type Response = reqwest::Response;
+    type Error = tower::BoxError;
+    type Future = std::future::Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: reqwest::Request) -> Self::Future {
+        let key = (request.method().to_string(), request.url().to_string());
+        let mut interactions = self.interactions.lock().unwrap();
+        let result = match interactions.get_mut(&key) {
+            Some(queue) if queue.len() > 1 => queue.pop_front().unwrap().response(),
+            Some(queue) if !queue.is_empty() => queue[0].response(),
+            _ => Err(format!("no interaction of {} {} is recorded", key.0, key.1).into()),
+        };
+        std::future::ready(result)
+    }
+}
@@ -0,0 +1,618 @@
+use fanbox_api::test_util::{
+    FakeDownload, FakeFanbox, FAKE_SESSION_ID, FIXTURE_CREATOR_ID, FIXTURE_USER_ID,
+};
+use fanbox_api::{ArticleBlock, Embed, PostBody, UrlEmbed, Video};
+use futures::stream::TryStreamExt as _;
+
+/// Returns an empty directory for the test, removing the one left by a previous run.
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("fanbox-api-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Returns names of files in `dir`, sorted.
+fn file_names(dir: &std::path::Path) -> Vec<String> {
+    let mut names: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn paginate_creator_lists_posts_newest_first() {
+    let client = FakeFanbox::with_fixtures().client();
+    let items: Vec<_> = client
+        .paginate_creator(FIXTURE_CREATOR_ID)
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    let ids: Vec<_> = items.iter().map(|item| item.id.as_str()).collect();
+    assert_eq!(ids, ["8", "7", "6", "5", "4", "3", "2", "1"]);
+    assert!(items[0].is_restricted);
+    assert!(!items[1].is_restricted);
+}
+
+#[tokio::test]
+async fn paginate_creator_follows_pages() {
+    let fake = FakeFanbox::with_fixtures();
+    let text_post = fanbox_api::test_util::fixture_posts()
+        .into_iter()
+        .find(|post| post["id"] == "3")
+        .unwrap();
+    for day in 10..=20 {
+        let mut post = text_post.clone();
+        post["id"] = day.to_string().into();
+        post["publishedDatetime"] = format!("2024-01-{}T00:00:00+09:00", day).into();
+        fake.add_post(post);
+    }
+    let client = fake.client();
+
+    let urls = client.list_page_urls(FIXTURE_CREATOR_ID).await.unwrap();
+    assert_eq!(urls.len(), 2);
+    let items: Vec<_> = client
+        .paginate_creator(FIXTURE_CREATOR_ID)
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(items.len(), 19);
+    assert_eq!(items[0].id, "20");
+    assert_eq!(items[18].id, "1");
+}
+
+#[tokio::test]
+async fn get_post_parses_image_file_text_and_video_bodies() {
+    let client = FakeFanbox::with_fixtures().client();
+
+    let post = client.get_post("1").await.unwrap();
+    assert_eq!(post.info.creator_id, FIXTURE_CREATOR_ID);
+    assert_eq!(post.info.fee_required, Some(500));
+    assert_eq!(post.info.like_count, 1);
+    match post.body {
+        Some(PostBody::Image(image)) => {
+            assert_eq!(image.body.text, "Image post");
+            let ids: Vec<_> = image.body.images.iter().map(|i| i.id.as_str()).collect();
+            assert_eq!(ids, ["i1", "i2"]);
+        }
+        body => panic!("unexpected body {:?}", body),
+    }
+
+    match client.get_post("2").await.unwrap().body {
+        Some(PostBody::File(file)) => {
+            assert_eq!(file.body.files.len(), 1);
+            assert_eq!(file.body.files[0].name, "file-f1");
+            assert_eq!(file.body.files[0].extension, "zip");
+        }
+        body => panic!("unexpected body {:?}", body),
+    }
+
+    match client.get_post("3").await.unwrap().body {
+        Some(PostBody::Text(text)) => assert_eq!(text.body.text, "Text post"),
+        body => panic!("unexpected body {:?}", body),
+    }
+
+    match client.get_post("4").await.unwrap().body {
+        Some(PostBody::Video(video)) => match video.body.video {
+            Video::Youtube(youtube) => assert_eq!(youtube.video_id, "dQw4w9WgXcQ"),
+            video => panic!("unexpected video {:?}", video),
+        },
+        body => panic!("unexpected body {:?}", body),
+    }
+
+    match client.get_post("5").await.unwrap().body {
+        Some(PostBody::Video(video)) => match video.body.video {
+            Video::Vimeo(vimeo) => assert_eq!(vimeo.video_id, "76979871"),
+            video => panic!("unexpected video {:?}", video),
+        },
+        body => panic!("unexpected body {:?}", body),
+    }
+}
+
+#[tokio::test]
+async fn get_post_parses_article_blocks_and_embeds() {
+    let client = FakeFanbox::with_fixtures().client();
+    let article = match client.get_post("6").await.unwrap().body {
+        Some(PostBody::Article(article)) => article.body,
+        body => panic!("unexpected body {:?}", body),
+    };
+
+    let kinds: Vec<_> = article
+        .blocks
+        .iter()
+        .map(|block| match block {
+            ArticleBlock::P(_) => "p",
+            ArticleBlock::Header(_) => "header",
+            ArticleBlock::Image(_) => "image",
+            ArticleBlock::File(_) => "file",
+            ArticleBlock::Embed(_) => "embed",
+            ArticleBlock::UrlEmbed(_) => "url_embed",
+            ArticleBlock::Unknown(raw) => {
+                assert_eq!(raw["type"], "unknown_block");
+                "unknown"
+            }
+            _ => "unsupported",
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            "header",
+            "p",
+            "image",
+            "file",
+            "embed",
+            "embed",
+            "embed",
+            "embed",
+            "embed",
+            "embed",
+            "url_embed",
+            "url_embed",
+            "url_embed",
+            "url_embed",
+            "unknown",
+        ]
+    );
+    assert_eq!(article.image_map["a1"].extension, "png");
+    assert_eq!(article.file_map["af1"].name, "file-af1");
+
+    assert!(matches!(&article.embed_map["twitter"], Embed::Twitter(e) if e.content_id == "20"));
+    assert!(
+        matches!(&article.embed_map["fanbox"], Embed::Fanbox(e) if e.content_id == "creator/fixture/post/1")
+    );
+    assert!(
+        matches!(&article.embed_map["youtube"], Embed::Youtube(e) if e.content_id == "dQw4w9WgXcQ")
+    );
+    assert!(matches!(&article.embed_map["vimeo"], Embed::Vimeo(e) if e.content_id == "76979871"));
+    assert!(
+        matches!(&article.embed_map["soundcloud"], Embed::Soundcloud(e) if e.content_id == "293")
+    );
+    assert!(
+        matches!(&article.embed_map["unknown"], Embed::Unknown(raw) if raw["serviceProvider"] == "unknown")
+    );
+
+    assert!(
+        matches!(&article.url_embed_map["default"], UrlEmbed::Default(e) if e.url == "https://example.com/")
+    );
+    assert!(
+        matches!(&article.url_embed_map["html"], UrlEmbed::Html(e) if e.html.contains("iframe"))
+    );
+    assert!(
+        matches!(&article.url_embed_map["card"], UrlEmbed::HtmlCard(e) if e.html.contains("/card"))
+    );
+    assert!(
+        matches!(&article.url_embed_map["unknown"], UrlEmbed::Unknown(raw) if raw["type"] == "unknown")
+    );
+}
+
+#[tokio::test]
+async fn get_post_keeps_unknown_and_restricted_posts() {
+    let client = FakeFanbox::with_fixtures().client();
+
+    let post = client.get_post("7").await.unwrap();
+    match &post.body {
+        Some(PostBody::Unknown(raw)) => assert_eq!(raw["type"], "unknown"),
+        body => panic!("unexpected body {:?}", body),
+    }
+    assert_eq!(post.unknowns().len(), 1);
+
+    let post = client.get_post("8").await.unwrap();
+    assert_eq!(post.body, None);
+    assert_eq!(post.info.fee_required, Some(1000));
+}
+
+#[tokio::test]
+async fn strict_client_rejects_unknown_types() {
+    let client = FakeFanbox::with_fixtures().client().strict(true);
+    for id in ["6", "7"] {
+        match client.get_post(id).await {
+            Err(fanbox_api::Error::UnknownType { post_id, .. }) => assert_eq!(post_id, id),
+            result => panic!("unexpected result of post {}: {:?}", id, result),
+        }
+    }
+    assert!(client.get_post("1").await.is_ok());
+}
+
+#[tokio::test]
+async fn get_post_of_missing_post_fails_with_not_found() {
+    let client = FakeFanbox::with_fixtures().client();
+    match client.get_post("404").await {
+        Err(fanbox_api::Error::NotFound { url, code }) => {
+            assert!(url.contains("postId=404"), "{}", url);
+            assert_eq!(code, "not found");
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[tokio::test]
+async fn get_creator_by_creator_id_and_user_id() {
+    let client = FakeFanbox::with_fixtures().client();
+
+    let creator = client.get_creator(FIXTURE_CREATOR_ID).await.unwrap();
+    assert_eq!(creator.user.user_id, FIXTURE_USER_ID);
+    let creator = client
+        .get_creator_by_user_id(FIXTURE_USER_ID)
+        .await
+        .unwrap();
+    assert_eq!(creator.creator_id, FIXTURE_CREATOR_ID);
+    assert!(matches!(
+        client.get_creator("missing").await,
+        Err(fanbox_api::Error::NotFound { .. })
+    ));
+}
+
+#[tokio::test]
+async fn session_user_lists() {
+    let client = FakeFanbox::with_fixtures().client();
+
+    client.check_session().await.unwrap();
+    assert_eq!(
+        client.list_supporting_creators().await.unwrap(),
+        [FIXTURE_CREATOR_ID]
+    );
+    let following = client.list_following_creators().await.unwrap();
+    assert_eq!(following.len(), 1);
+    assert_eq!(following[0].creator_id, FIXTURE_CREATOR_ID);
+
+    let home: Vec<_> = client.home_items().try_collect().await.unwrap();
+    assert_eq!(home.len(), 8);
+    let tagged: Vec<_> = client
+        .tagged_items("video", None)
+        .try_collect()
+        .await
+        .unwrap();
+    let ids: Vec<_> = tagged.iter().map(|feed| feed.item.id.as_str()).collect();
+    assert_eq!(ids, ["5", "4"]);
+}
+
+#[tokio::test]
+async fn download_to_writes_content_and_mtime() {
+    let fake = FakeFanbox::with_fixtures();
+    let custom_url = "https://downloads.fanbox.cc/files/custom.txt";
+    fake.add_file(custom_url, b"custom content".to_vec());
+    let client = fake.client();
+    let dir = temp_dir("download-to");
+    let mtime = chrono::DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+
+    let image_path = dir.join("i1.png");
+    client
+        .download_to(
+            "https://downloads.fanbox.cc/images/i1.png",
+            &image_path,
+            &mtime,
+        )
+        .await
+        .unwrap();
+    let image = std::fs::read(&image_path).unwrap();
+    assert!(image.starts_with(b"\x89PNG\r\n\x1a\n"));
+    let modified = std::fs::metadata(&image_path).unwrap().modified().unwrap();
+    assert_eq!(
+        modified,
+        std::time::UNIX_EPOCH + std::time::Duration::from_secs(mtime.timestamp() as u64)
+    );
+
+    let file_url = "https://downloads.fanbox.cc/files/f1.zip";
+    let file_path = dir.join("f1.zip");
+    client
+        .download_to(file_url, &file_path, &mtime)
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read(&file_path).unwrap(), file_url.as_bytes());
+
+    let custom_path = dir.join("custom.txt");
+    client
+        .download_to(custom_url, &custom_path, &mtime)
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read(&custom_path).unwrap(), b"custom content");
+
+    assert_eq!(file_names(&dir), ["custom.txt", "f1.zip", "i1.png"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn download_to_rejects_html_pages() {
+    let fake = FakeFanbox::with_fixtures();
+    let url = "https://downloads.fanbox.cc/images/login.png";
+    fake.add_file(
+        url,
+        b"<!DOCTYPE html><html><body>Log in</body></html>".to_vec(),
+    );
+    let client = fake.client();
+    let dir = temp_dir("download-html");
+    let path = dir.join("login.png");
+
+    match client.download_to(url, &path, &chrono::Utc::now()).await {
+        Err(fanbox_api::Error::UnexpectedHtml { url: error_url, .. }) => assert_eq!(error_url, url),
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert!(!path.exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn download_to_sends_the_session_only_to_fanbox_hosts() {
+    let fake = FakeFanbox::with_fixtures();
+    let fanbox_url = "https://www.fanbox.cc/files/moved.zip";
+    let external_url = "https://cdn.example.com/files/external.zip";
+    fake.add_redirect("https://downloads.fanbox.cc/files/moved.zip", fanbox_url);
+    fake.add_redirect(
+        "https://downloads.fanbox.cc/files/external.zip",
+        external_url,
+    );
+    let client = fake.client();
+    let dir = temp_dir("download-redirect");
+    let mtime = chrono::Utc::now();
+    let session = format!("FANBOXSESSID={};", FAKE_SESSION_ID);
+    let cookie = |url: &str| {
+        fake.request_headers(url)[0]
+            .get(reqwest::header::COOKIE)
+            .map(|cookie| cookie.to_str().unwrap().to_owned())
+    };
+
+    // reqwest drops the cookie on redirects to other hosts, so the client sends it again
+    let path = dir.join("moved.zip");
+    client
+        .download_to("https://downloads.fanbox.cc/files/moved.zip", &path, &mtime)
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), fanbox_url.as_bytes());
+    assert_eq!(
+        cookie("https://downloads.fanbox.cc/files/moved.zip").as_ref(),
+        Some(&session)
+    );
+    assert_eq!(cookie(fanbox_url).as_ref(), Some(&session));
+
+    let path = dir.join("external.zip");
+    client
+        .download_to(
+            "https://downloads.fanbox.cc/files/external.zip",
+            &path,
+            &mtime,
+        )
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), external_url.as_bytes());
+    assert_eq!(cookie(external_url), None);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Content of downloads interrupted after `INTERRUPTED_BYTES`
+fn resumed_content(seed: u8) -> Vec<u8> {
+    (0..1000u32).map(|i| (i as u8).wrapping_add(seed)).collect()
+}
+
+const INTERRUPTED_BYTES: usize = 400;
+
+/// Downloads `url` into `path` with the connection dropped after `INTERRUPTED_BYTES`, leaving the
+/// partial download.
+async fn interrupt_download(
+    fake: &FakeFanbox,
+    client: &fanbox_api::PostClient,
+    url: &str,
+    path: &std::path::Path,
+) {
+    fake.add_download(
+        url,
+        FakeDownload {
+            content: resumed_content(0),
+            etag: Some("\"v1\"".to_owned()),
+            cut_off_after: Some(INTERRUPTED_BYTES),
+            ..Default::default()
+        },
+    );
+    assert!(client
+        .download_to(url, path, &chrono::Utc::now())
+        .await
+        .is_err());
+    assert!(!path.exists());
+    let mut part_path = path.as_os_str().to_owned();
+    part_path.push(".part");
+    assert_eq!(
+        std::fs::metadata(part_path).unwrap().len(),
+        INTERRUPTED_BYTES as u64
+    );
+}
+
+/// Returns the header of the `index`th request of `url`.
+fn request_header(fake: &FakeFanbox, url: &str, index: usize, name: &str) -> Option<String> {
+    fake.request_headers(url)[index]
+        .get(name)
+        .map(|value| value.to_str().unwrap().to_owned())
+}
+
+#[tokio::test]
+async fn download_to_resumes_with_matching_validator() {
+    let fake = FakeFanbox::new();
+    let client = fake.client().retries(0);
+    let dir = temp_dir("resume-matching");
+    let url = "https://downloads.fanbox.cc/files/resume.zip";
+    let path = dir.join("resume.zip");
+
+    interrupt_download(&fake, &client, url, &path).await;
+    client
+        .download_to(url, &path, &chrono::Utc::now())
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), resumed_content(0));
+    assert_eq!(fake.request_headers(url).len(), 2);
+    assert_eq!(
+        request_header(&fake, url, 1, "range").as_deref(),
+        Some("bytes=400-")
+    );
+    assert_eq!(
+        request_header(&fake, url, 1, "if-range").as_deref(),
+        Some("\"v1\"")
+    );
+    assert_eq!(file_names(&dir), ["resume.zip"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn download_to_restarts_when_content_has_changed() {
+    let fake = FakeFanbox::new();
+    let client = fake.client().retries(0);
+    let dir = temp_dir("resume-changed");
+    let url = "https://downloads.fanbox.cc/files/resume.zip";
+    let path = dir.join("resume.zip");
+
+    interrupt_download(&fake, &client, url, &path).await;
+    fake.add_download(
+        url,
+        FakeDownload {
+            content: resumed_content(1),
+            etag: Some("\"v2\"".to_owned()),
+            ..Default::default()
+        },
+    );
+    client
+        .download_to(url, &path, &chrono::Utc::now())
+        .await
+        .unwrap();
+    // If-Range doesn't match, so the whole content is returned with 200
+    assert_eq!(std::fs::read(&path).unwrap(), resumed_content(1));
+    assert_eq!(
+        request_header(&fake, url, 1, "if-range").as_deref(),
+        Some("\"v1\"")
+    );
+    assert_eq!(file_names(&dir), ["resume.zip"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn download_to_restarts_on_mismatched_content_range() {
+    let fake = FakeFanbox::new();
+    let client = fake.client().retries(0);
+    let dir = temp_dir("resume-content-range");
+    let url = "https://downloads.fanbox.cc/files/resume.zip";
+    let path = dir.join("resume.zip");
+
+    interrupt_download(&fake, &client, url, &path).await;
+    fake.add_download(
+        url,
+        FakeDownload {
+            content: resumed_content(0),
+            etag: Some("\"v1\"".to_owned()),
+            range_start: Some(100),
+            ..Default::default()
+        },
+    );
+    client
+        .download_to(url, &path, &chrono::Utc::now())
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), resumed_content(0));
+    // The range starting at 100 is discarded and the content is downloaded again from the start
+    assert_eq!(fake.request_headers(url).len(), 3);
+    assert_eq!(
+        request_header(&fake, url, 1, "range").as_deref(),
+        Some("bytes=400-")
+    );
+    assert_eq!(request_header(&fake, url, 2, "range"), None);
+    assert_eq!(file_names(&dir), ["resume.zip"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn download_to_restarts_when_url_has_changed() {
+    let fake = FakeFanbox::new();
+    let client = fake.client().retries(0);
+    let dir = temp_dir("resume-url");
+    let url = "https://downloads.fanbox.cc/files/resume.zip";
+    let new_url = "https://downloads.fanbox.cc/files/resume-v2.zip";
+    let path = dir.join("resume.zip");
+
+    interrupt_download(&fake, &client, url, &path).await;
+    fake.add_download(
+        new_url,
+        FakeDownload {
+            content: resumed_content(2),
+            etag: Some("\"v1\"".to_owned()),
+            ..Default::default()
+        },
+    );
+    client
+        .download_to(new_url, &path, &chrono::Utc::now())
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), resumed_content(2));
+    assert_eq!(request_header(&fake, new_url, 0, "range"), None);
+    assert_eq!(file_names(&dir), ["resume.zip"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn dedup_requests_shares_only_posts_creators_and_downloads() {
+    let fake = FakeFanbox::with_fixtures();
+    let client = fake.client().dedup_requests(true);
+    let dir = temp_dir("dedup");
+    let count = |path: &str| {
+        fake.requests()
+            .iter()
+            .filter(|url| url.contains(path))
+            .count()
+    };
+
+    for _ in 0..2 {
+        client.get_post("1").await.unwrap();
+        client.get_creator(FIXTURE_CREATOR_ID).await.unwrap();
+        client.list_page_urls(FIXTURE_CREATOR_ID).await.unwrap();
+        let _: Vec<_> = client.home_items().try_collect().await.unwrap();
+    }
+    assert_eq!(count("/post.info"), 1);
+    assert_eq!(count("/creator.get"), 1);
+    assert_eq!(count("/post.paginateCreator"), 2);
+    assert_eq!(count("/post.listHome"), 2);
+
+    let url = "https://downloads.fanbox.cc/files/f1.zip";
+    let mtime = chrono::Utc::now();
+    client
+        .download_to(url, dir.join("a.zip"), &mtime)
+        .await
+        .unwrap();
+    client
+        .download_to(url, dir.join("b.zip"), &mtime)
+        .await
+        .unwrap();
+    assert_eq!(fake.request_headers(url).len(), 1);
+    assert_eq!(std::fs::read(dir.join("b.zip")).unwrap(), url.as_bytes());
+
+    client.clear_recent_requests();
+    client.get_post("1").await.unwrap();
+    client
+        .download_to(url, dir.join("c.zip"), &mtime)
+        .await
+        .unwrap();
+    assert_eq!(count("/post.info"), 2);
+    assert_eq!(fake.request_headers(url).len(), 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn shared_rate_limiter_takes_precedence_over_interval() {
+    let client = FakeFanbox::with_fixtures()
+        .client()
+        .rate_limiter(std::sync::Arc::new(fanbox_api::RateLimiter::new(
+            std::time::Duration::ZERO,
+        )))
+        .interval(std::time::Duration::from_secs(60))
+        .jitter(0.5);
+
+    let requests = async {
+        client.get_post("1").await.unwrap();
+        client.get_post("2").await.unwrap();
+    };
+    tokio::time::timeout(std::time::Duration::from_secs(10), requests)
+        .await
+        .expect("requests wait for the interval ignored after rate_limiter");
+}
@@ -0,0 +1,142 @@
+/// Progress of an `Archiver` run, e.g. to be rendered by GUI frontends.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Event {
+    /// The post is listed and will be archived
+    #[serde(rename_all = "camelCase")]
+    PostDiscovered { post_id: String, title: String },
+    /// Archiving the post has started
+    #[serde(rename_all = "camelCase")]
+    PostStarted { post_id: String },
+    /// A media file of the post has been downloaded
+    #[serde(rename_all = "camelCase")]
+    FileDownloaded {
+        post_id: String,
+        path: std::path::PathBuf,
+        bytes: u64,
+    },
+    /// The post has been archived
+    #[serde(rename_all = "camelCase")]
+    PostFinished { post_id: String },
+    /// Archiving the post failed, which doesn't stop the run
+    #[serde(rename_all = "camelCase")]
+    Error { post_id: String, message: String },
+}
+
+/// Sender of `Event`s, which is dropped silently when the receiver is closed.
+#[derive(Debug, Clone)]
+pub struct EventSender(tokio::sync::mpsc::UnboundedSender<Event>);
+
+impl EventSender {
+    pub(crate) fn send(&self, event: Event) {
+        let _ = self.0.send(event);
+    }
+}
+
+/// Archives all posts of creators, reporting progress as `Event`s.
+#[derive(Debug)]
+pub struct Archiver<'a> {
+    client: &'a fanbox_api::PostClient,
+    dest_dir: std::path::PathBuf,
+    options: super::ArchiveOptions,
+}
+
+impl<'a> Archiver<'a> {
+    pub fn new(
+        client: &'a fanbox_api::PostClient,
+        dest_dir: &std::path::Path,
+        options: super::ArchiveOptions,
+    ) -> Self {
+        Self {
+            client,
+            dest_dir: dest_dir.to_path_buf(),
+            options,
+        }
+    }
+
+    /// Returns the receiver of events, replacing the previous one.
+    pub fn events(&mut self) -> tokio::sync::mpsc::UnboundedReceiver<Event> {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        self.options.events = Some(EventSender(tx));
+        rx
+    }
+
+    /// Adds the closure deciding whether each image and file is downloaded, skipped, renamed or
+    /// taken from an existing file, after hooks in `ArchiveOptions::hooks`.
+    pub fn filter_files<F>(&mut self, filter: F)
+    where
+        F: Fn(&super::FileCandidate<'_>) -> super::Decision + Send + Sync + 'static,
+    {
+        self.options
+            .hooks
+            .push(std::sync::Arc::new(super::hook::FileFilter(filter)));
+    }
+
+    /// Archives all posts of the creator, newest first.
+    ///
+    /// Posts recorded in `ArchiveOptions::sync_manifest` are skipped unless they have been updated
+    /// since then. Posts which fail to be archived are reported as `Event::Error` and skipped. Returns the
+    /// number of such posts.
+    pub async fn archive_creator(&self, creator_id: &str) -> Result<usize, super::Error> {
+        use futures::stream::TryStreamExt as _;
+
+        let map_err = |source| super::Error::ListPosts {
+            creator_id: creator_id.to_owned(),
+            source,
+        };
+        let items = self
+            .client
+            .paginate_creator(creator_id)
+            .await
+            .map_err(map_err)?;
+        futures::pin_mut!(items);
+        let mut failures = 0;
+        while let Some(item) = items.try_next().await.map_err(map_err)? {
+            if let Some(sync_manifest) = &self.options.sync_manifest {
+                if sync_manifest.is_up_to_date(&item.id, item.updated_datetime) {
+                    tracing::debug!("Skip post {} unchanged since archived", item.id);
+                    continue;
+                }
+            }
+            self.send(Event::PostDiscovered {
+                post_id: item.id.clone(),
+                title: item.title.clone(),
+            });
+            if let Err(e) = self.archive_post(&item.id).await {
+                tracing::error!("Failed to archive post {}: {}", item.id, e);
+                self.send(Event::Error {
+                    post_id: item.id,
+                    message: e.to_string(),
+                });
+                failures += 1;
+            }
+        }
+        Ok(failures)
+    }
+
+    /// Archives the post.
+    pub async fn archive_post(&self, post_id: &str) -> Result<(), super::Error> {
+        self.send(Event::PostStarted {
+            post_id: post_id.to_owned(),
+        });
+        let post = self
+            .client
+            .get_post(post_id)
+            .await
+            .map_err(|source| super::Error::GetPost {
+                post_id: post_id.to_owned(),
+                source,
+            })?;
+        super::archive_post(self.client, &self.dest_dir, post, &self.options).await?;
+        self.send(Event::PostFinished {
+            post_id: post_id.to_owned(),
+        });
+        Ok(())
+    }
+
+    fn send(&self, event: Event) {
+        if let Some(events) = &self.options.events {
+            events.send(event);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+/// Inconsistency of a file in the archive found by `audit_archive`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditIssue {
+    pub post_id: String,
+    pub path: std::path::PathBuf,
+    pub kind: AuditIssueKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditIssueKind {
+    /// The file referenced by the post metadata doesn't exist
+    Missing,
+    /// The size differs from the one recorded in the download log
+    SizeMismatch { expected: u64, actual: u64 },
+    /// The mtime differs from the updated datetime of the post
+    MtimeMismatch {
+        expected: chrono::DateTime<chrono::Utc>,
+        actual: chrono::DateTime<chrono::Utc>,
+    },
+    /// The SHA-256 digest differs from the one recorded in the download log
+    HashMismatch { expected: String, actual: String },
+}
+
+impl std::fmt::Display for AuditIssueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing => write!(f, "missing"),
+            Self::SizeMismatch { expected, actual } => {
+                write!(f, "size {} differs from recorded {}", actual, expected)
+            }
+            Self::MtimeMismatch { expected, actual } => write!(
+                f,
+                "mtime {} differs from post updated at {}",
+                actual.to_rfc3339(),
+                expected.to_rfc3339()
+            ),
+            Self::HashMismatch { expected, actual } => {
+                write!(f, "sha256 {} differs from recorded {}", actual, expected)
+            }
+        }
+    }
+}
+
+/// Options of `audit_archive`.
+#[derive(Debug, Clone)]
+pub struct AuditOptions {
+    /// Hash files to detect bit rot or tampering which keeps the size
+    pub deep: bool,
+    /// Number of threads checking files concurrently
+    pub jobs: usize,
+}
+
+impl Default for AuditOptions {
+    fn default() -> Self {
+        Self {
+            deep: false,
+            jobs: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+/// Checks the internal consistency of the archive under `dest_dir` without network access.
+///
+/// The metadata, cover image and media files of each archived post must exist and have the
+/// updated datetime of the post as their mtime, and their sizes, and hashes with
+/// `options.deep`, must match the last ones recorded in the download log.
+///
+/// Files are checked by `options.jobs` threads and each issue is passed to `report` as soon as
+/// it is found. Returns the number of issues.
+pub fn audit_archive<F>(
+    dest_dir: &std::path::Path,
+    options: &AuditOptions,
+    report: F,
+) -> Result<usize, super::Error>
+where
+    F: Fn(AuditIssue) + Sync,
+{
+    let mut recorded = std::collections::HashMap::new();
+    for entry in super::DownloadLog::read(dest_dir)? {
+        recorded.insert(entry.path.clone(), entry);
+    }
+
+    let mut files = Vec::new();
+    for archived in super::load_archived_posts(dest_dir)? {
+        let info = &archived.post.info;
+        let mut paths = vec![archived.dir.join(super::METADATA_FILE_NAME)];
+        if info.cover_image_url.is_some() {
+            paths.push(archived.dir.join("cover_image.jpeg"));
+        }
+        paths.extend(
+            super::catalog::media_files(&archived.post)
+                .into_iter()
+                .map(|media_file| archived.dir.join(&media_file.file_name)),
+        );
+        files.extend(
+            paths
+                .into_iter()
+                .map(|path| (info.id.clone(), info.updated_datetime, path)),
+        );
+    }
+
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let count = std::sync::atomic::AtomicUsize::new(0);
+    let error = std::sync::Mutex::new(None);
+    std::thread::scope(|scope| {
+        for _ in 0..options.jobs.max(1) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                let Some((post_id, updated_datetime, path)) = files.get(i) else {
+                    break;
+                };
+                if error.lock().unwrap().is_some() {
+                    break;
+                }
+                match check_file(path, updated_datetime, recorded.get(path), options.deep) {
+                    Ok(kinds) => {
+                        for kind in kinds {
+                            count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                            report(AuditIssue {
+                                post_id: post_id.clone(),
+                                path: path.clone(),
+                                kind,
+                            });
+                        }
+                    }
+                    Err(e) => {
+                        error.lock().unwrap().get_or_insert(e);
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    match error.into_inner().unwrap() {
+        Some(e) => Err(e),
+        None => Ok(count.into_inner()),
+    }
+}
+
+fn check_file(
+    path: &std::path::Path,
+    updated_datetime: &chrono::DateTime<chrono::Utc>,
+    recorded: Option<&super::DownloadLogEntry>,
+    deep: bool,
+) -> Result<Vec<AuditIssueKind>, super::Error> {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(vec![AuditIssueKind::Missing])
+        }
+        Err(source) => {
+            return Err(super::Error::Read {
+                path: path.to_path_buf(),
+                source,
+            })
+        }
+    };
+    let mut kinds = Vec::new();
+    let mtime = filetime::FileTime::from_last_modification_time(&metadata);
+    // Sub-second precision is lost on some file systems
+    if mtime.unix_seconds() != updated_datetime.timestamp() {
+        if let Some(actual) =
+            chrono::TimeZone::timestamp_opt(&chrono::Utc, mtime.unix_seconds(), 0).single()
+        {
+            kinds.push(AuditIssueKind::MtimeMismatch {
+                expected: *updated_datetime,
+                actual,
+            });
+        }
+    }
+    if let Some(entry) = recorded {
+        if metadata.len() != entry.size {
+            kinds.push(AuditIssueKind::SizeMismatch {
+                expected: entry.size,
+                actual: metadata.len(),
+            });
+        } else if deep {
+            let actual = super::sha256_file(path)?;
+            if actual != entry.sha256 {
+                kinds.push(AuditIssueKind::HashMismatch {
+                    expected: entry.sha256.clone(),
+                    actual,
+                });
+            }
+        }
+    }
+    Ok(kinds)
+}
@@ -0,0 +1,202 @@
+/// Tarball of files added or changed since the previous differential backup.
+#[derive(Debug, Clone)]
+pub struct DifferentialBackup {
+    pub path: std::path::PathBuf,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Options of `write_differential_backup`.
+#[derive(Debug, Clone, Default)]
+pub struct BackupOptions {
+    /// Public keys which the tarball is encrypted to as `fanbox-dl-<timestamp>.tar.age`
+    #[cfg(feature = "encrypt")]
+    pub recipients: Option<std::sync::Arc<super::Recipients>>,
+}
+
+/// Writes `backup_dir/fanbox-dl-<timestamp>.tar` containing files under `dest_dir` which are
+/// added or changed in size or mtime since the previous call, as recorded in the state database.
+///
+/// The first call includes all files. Nothing is written when no file has changed. The state
+/// database itself, temporary files of downloads and `backup_dir` are excluded.
+///
+/// When `options.recipients` are given, the tarball is encrypted to them in the age format
+/// without writing the plain tarball to disk.
+pub fn write_differential_backup(
+    dest_dir: &std::path::Path,
+    backup_dir: &std::path::Path,
+    options: &BackupOptions,
+) -> Result<Option<DifferentialBackup>, super::Error> {
+    #[cfg(feature = "encrypt")]
+    let recipients = options.recipients.as_deref();
+    #[cfg(not(feature = "encrypt"))]
+    let recipients: Option<&std::convert::Infallible> = {
+        let _ = options;
+        None
+    };
+    let db = super::StateDb::open(dest_dir)?;
+    let previous = db.backed_up_files()?;
+    let mut current = std::collections::HashMap::new();
+    let mut changed = Vec::new();
+    for (path, metadata) in super::gc::list_files(dest_dir)? {
+        if super::gc::is_temporary(&path) || path.starts_with(backup_dir) {
+            continue;
+        }
+        let name = match path.strip_prefix(dest_dir) {
+            Ok(relative) => relative
+                .iter()
+                .map(|component| component.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            Err(_) => continue,
+        };
+        if name.starts_with(super::STATE_DB_FILE_NAME) {
+            continue;
+        }
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |duration| duration.as_nanos() as i64);
+        let state = (metadata.len(), mtime);
+        if previous.get(&name) != Some(&state) {
+            changed.push((name.clone(), path, state));
+        }
+        current.insert(name, state);
+    }
+    if changed.is_empty() {
+        db.record_backed_up_files(&current)?;
+        return Ok(None);
+    }
+
+    std::fs::create_dir_all(backup_dir).map_err(|source| super::Error::CreateDir {
+        path: backup_dir.to_path_buf(),
+        source,
+    })?;
+    let extension = if recipients.is_some() {
+        "tar.age"
+    } else {
+        "tar"
+    };
+    let path = backup_dir.join(format!(
+        "fanbox-dl-{}.{}",
+        chrono::Utc::now().format("%Y%m%dT%H%M%SZ"),
+        extension
+    ));
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".");
+    tmp_path.push(super::gc::TEMPORARY_EXTENSION);
+    let tmp_path = std::path::PathBuf::from(tmp_path);
+    let result = match recipients {
+        #[cfg(feature = "encrypt")]
+        Some(recipients) => recipients.encrypt_to(&tmp_path, |writer| {
+            write_tar(writer, &tmp_path, &changed).map(|(bytes, _)| bytes)
+        }),
+        #[cfg(not(feature = "encrypt"))]
+        Some(never) => match *never {},
+        None => {
+            let map_err = |source| super::Error::Write {
+                path: tmp_path.clone(),
+                source,
+            };
+            std::fs::File::create(&tmp_path)
+                .map_err(map_err)
+                .and_then(|file| {
+                    let (bytes, writer) =
+                        write_tar(std::io::BufWriter::new(file), &tmp_path, &changed)?;
+                    let file = writer.into_inner().map_err(|e| map_err(e.into_error()))?;
+                    file.sync_all().map_err(map_err)?;
+                    Ok(bytes)
+                })
+        }
+    };
+    let bytes = match result {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            let _ = std::fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+    };
+    std::fs::rename(&tmp_path, &path).map_err(|source| super::Error::Write {
+        path: path.clone(),
+        source,
+    })?;
+    db.record_backed_up_files(&current)?;
+    Ok(Some(DifferentialBackup {
+        path,
+        files: changed.len(),
+        bytes,
+    }))
+}
+
+/// Writes the tar archive of the files to `writer` for `path`, returning bytes of the files and
+/// the writer.
+fn write_tar<W>(
+    writer: W,
+    path: &std::path::Path,
+    files: &[(String, std::path::PathBuf, (u64, i64))],
+) -> Result<(u64, W), super::Error>
+where
+    W: std::io::Write,
+{
+    let map_err = |source| super::Error::Write {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut tar = tar::Builder::new(writer);
+    let mut bytes = 0;
+    for (name, file_path, (size, mtime)) in files {
+        let file = std::fs::File::open(file_path).map_err(|source| super::Error::Read {
+            path: file_path.clone(),
+            source,
+        })?;
+        // GNU headers store names and sizes which don't fit in the ustar header
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_size(*size);
+        header.set_mode(0o644);
+        header.set_mtime(mtime.div_euclid(1_000_000_000).max(0) as u64);
+        let data = ExactReader {
+            name,
+            inner: file,
+            remaining: *size,
+        };
+        tar.append_data(&mut header, name, data).map_err(map_err)?;
+        bytes += size;
+    }
+    let writer = tar.into_inner().map_err(map_err)?;
+    Ok((bytes, writer))
+}
+
+/// Reader of the first `remaining` bytes of a file, failing when the file ends before them.
+///
+/// The file may be truncated after listed, which would break the archive since the header
+/// already has the size.
+struct ExactReader<'a, R> {
+    name: &'a str,
+    inner: R,
+    remaining: u64,
+}
+
+impl<R> std::io::Read for ExactReader<'_, R>
+where
+    R: std::io::Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.remaining == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+        let len = buf
+            .len()
+            .min(self.remaining.try_into().unwrap_or(usize::MAX));
+        let n = self.inner.read(&mut buf[..len])?;
+        if n == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("{} was truncated while archived", self.name),
+            ));
+        }
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
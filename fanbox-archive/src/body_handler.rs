@@ -0,0 +1,147 @@
+/// Handler of post bodies of a type which the API client doesn't model yet, registered to
+/// `ArchiveOptions::body_handlers` by the `type` of the body.
+pub trait BodyHandler: std::fmt::Debug + Send + Sync {
+    /// Converts the document of the post from the raw JSON of its body, which has `type` and
+    /// `body` as returned by the API.
+    ///
+    /// The document given has `PostKind::Unknown` with a `Block::Unknown` of the body, whose raw
+    /// JSON is dumped into the post directory as long as it's kept. Images and files of the
+    /// returned document are downloaded and rendered like those of supported types unless its
+    /// kind is still `PostKind::Unknown`.
+    fn convert_document(
+        &self,
+        raw: &serde_json::Value,
+        document: super::PostDocument,
+    ) -> Result<super::PostDocument, super::Error>;
+}
+
+/// Registry of `BodyHandler`s keyed by the `type` of post bodies.
+///
+/// Bodies of types without a registered handler are handled by `DefaultBodyHandler`.
+#[derive(Debug, Clone, Default)]
+pub struct BodyHandlers {
+    handlers: std::collections::HashMap<String, std::sync::Arc<dyn BodyHandler>>,
+}
+
+impl BodyHandlers {
+    /// Registers the handler for bodies of `body_type`, replacing the previous one.
+    pub fn register<H>(&mut self, body_type: &str, handler: H)
+    where
+        H: BodyHandler + 'static,
+    {
+        self.handlers
+            .insert(body_type.to_owned(), std::sync::Arc::new(handler));
+    }
+
+    /// Returns the handler for bodies of `body_type`.
+    pub fn get(&self, body_type: &str) -> &dyn BodyHandler {
+        match self.handlers.get(body_type) {
+            Some(handler) => handler.as_ref(),
+            None => &DefaultBodyHandler,
+        }
+    }
+
+    /// Converts the document by the handler for the `type` of the raw body.
+    pub(crate) fn convert_document(
+        &self,
+        raw: &serde_json::Value,
+        document: super::PostDocument,
+    ) -> Result<super::PostDocument, super::Error> {
+        let body_type = raw
+            .get("type")
+            .and_then(|body_type| body_type.as_str())
+            .unwrap_or_default();
+        self.get(body_type).convert_document(raw, document)
+    }
+}
+
+/// Handler keeping the raw JSON of the body, which is dumped into the post directory, and
+/// downloading media found in it.
+///
+/// Media are strings of the JSON which look like URLs of images and files hosted by FANBOX.
+/// Images among them are added as `Block::Image` and the others as `Block::File`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultBodyHandler;
+
+impl BodyHandler for DefaultBodyHandler {
+    fn convert_document(
+        &self,
+        raw: &serde_json::Value,
+        mut document: super::PostDocument,
+    ) -> Result<super::PostDocument, super::Error> {
+        let mut urls = Vec::new();
+        find_media_urls(raw, &mut urls);
+        if urls.is_empty() {
+            return Ok(document);
+        }
+        let mut paths = std::collections::HashSet::new();
+        for url in urls {
+            let name = url
+                .split(['?', '#'])
+                .next()
+                .and_then(|url| url.rsplit('/').next())
+                .unwrap_or_default();
+            let (stem, extension) = name.rsplit_once('.').unwrap_or((name, ""));
+            let path = super::normalize_name(name);
+            if stem.is_empty() || !paths.insert(path.to_lowercase()) {
+                continue;
+            }
+            let asset = super::Asset {
+                url: url.to_owned(),
+                path,
+                width: None,
+                height: None,
+                size: None,
+            };
+            document.blocks.push(
+                if IMAGE_EXTENSIONS.contains(&extension.to_lowercase().as_str()) {
+                    super::Block::Image {
+                        id: stem.to_owned(),
+                        asset,
+                    }
+                } else {
+                    super::Block::File {
+                        id: stem.to_owned(),
+                        name: stem.to_owned(),
+                        asset,
+                    }
+                },
+            );
+        }
+        document.kind = super::PostKind::File;
+        Ok(document)
+    }
+}
+
+const IMAGE_EXTENSIONS: &[&str] = &["jpeg", "jpg", "png", "gif", "webp"];
+
+/// Hosts serving images and files of posts
+const MEDIA_HOSTS: &[&str] = &["downloads.fanbox.cc", "pixiv.pximg.net"];
+
+/// Collects strings of the JSON which are URLs on `MEDIA_HOSTS`, in the order of appearance
+/// without duplicates.
+fn find_media_urls<'a>(value: &'a serde_json::Value, urls: &mut Vec<&'a str>) {
+    match value {
+        serde_json::Value::String(s) => {
+            let host = s
+                .strip_prefix("https://")
+                .and_then(|rest| rest.split('/').next());
+            if matches!(host, Some(host) if MEDIA_HOSTS.contains(&host))
+                && !urls.contains(&s.as_str())
+            {
+                urls.push(s);
+            }
+        }
+        serde_json::Value::Array(values) => {
+            for value in values {
+                find_media_urls(value, urls);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for value in map.values() {
+                find_media_urls(value, urls);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,132 @@
+/// Directory in `dest_dir` storing books written by `write_book`
+pub const BOOKS_DIR_NAME: &str = "books";
+
+/// Writes the archived posts of the creator into one HTML document
+/// `dest_dir/books/<creator id>.html`, oldest first with a table of contents, for reading long
+/// series of posts end to end.
+///
+/// Images are shown from the post directories and other files are linked. Returns the path of the
+/// book.
+pub fn write_book(
+    dest_dir: &std::path::Path,
+    creator_id: &str,
+    lang: super::Lang,
+) -> Result<std::path::PathBuf, super::Error> {
+    use super::feed::escape_xml;
+
+    let mut posts: Vec<_> = super::load_archived_posts(dest_dir)?
+        .into_iter()
+        .filter(|archived| archived.post.info.creator_id == creator_id)
+        .collect();
+    posts.reverse();
+    let creator = posts
+        .iter()
+        .find_map(|archived| archived.post.info.user.as_ref())
+        .map_or(creator_id, |user| user.name.as_str());
+
+    let mut toc = Vec::new();
+    let mut articles = Vec::new();
+    for archived in &posts {
+        let info = &archived.post.info;
+        // The book is in a sibling directory of creators' ones
+        let relative_dir = archived
+            .dir
+            .strip_prefix(dest_dir)
+            .unwrap_or(&archived.dir)
+            .iter()
+            .map(|component| component.to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("/");
+        toc.push(format!(
+            "<li><a href='#post-{}'>{}</a> <time datetime='{}'>{}</time></li>",
+            info.id,
+            escape_xml(&info.title),
+            info.published_datetime.to_rfc3339(),
+            info.published_datetime.format("%Y-%m-%d")
+        ));
+        articles.push(format!("<article id='post-{}'>", info.id));
+        articles.push(format!(
+            "<h2><a href='../{}/{}'>{}</a></h2>",
+            relative_dir,
+            escape_xml(&archived.index_file_name()),
+            escape_xml(&info.title)
+        ));
+        articles.push(format!(
+            "<p>{} <time datetime='{}'>{}</time></p>",
+            lang.label(super::Label::Published),
+            info.published_datetime.to_rfc3339(),
+            info.published_datetime.format("%Y-%m-%d %H:%M")
+        ));
+        articles.extend(
+            super::post_text(&archived.post)
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(|line| format!("<p>{}</p>", escape_xml(line))),
+        );
+        for media_file in super::catalog::media_files(&archived.post) {
+            let href = format!("../{}/{}", relative_dir, media_file.file_name);
+            if is_image(media_file.extension) {
+                articles.push(format!(
+                    "<p><img loading='lazy' alt='' src='{}'></p>",
+                    escape_xml(&href)
+                ));
+            } else {
+                articles.push(format!(
+                    "<p><a href='{}'>{}.{}</a></p>",
+                    escape_xml(&href),
+                    escape_xml(media_file.name),
+                    escape_xml(media_file.extension)
+                ));
+            }
+        }
+        articles.push("</article>".to_owned());
+    }
+
+    let html = [
+        "<!DOCTYPE html>".to_owned(),
+        format!("<html lang='{}'>", lang.tag()),
+        "<head>".to_owned(),
+        "<meta charset='utf-8'>".to_owned(),
+        format!("<title>{}</title>", escape_xml(creator)),
+        "<style>".to_owned(),
+        "body { max-width: 48em; margin: auto; }".to_owned(),
+        "img { max-width: 100%; }".to_owned(),
+        "article { border-top: 1px solid #ccc; }".to_owned(),
+        "</style>".to_owned(),
+        "</head>".to_owned(),
+        "<body>".to_owned(),
+        format!(
+            "<h1><a href='https://{}.fanbox.cc/'>{}</a> ({})</h1>",
+            creator_id,
+            escape_xml(creator),
+            lang.post_count(posts.len())
+        ),
+        format!("<nav><h2>{}</h2>", lang.label(super::Label::Contents)),
+        "<ol>".to_owned(),
+        toc.join("\n"),
+        "</ol>".to_owned(),
+        "</nav>".to_owned(),
+        articles.join("\n"),
+        "</body>".to_owned(),
+        "</html>".to_owned(),
+    ]
+    .join("\n");
+    let books_dir = dest_dir.join(BOOKS_DIR_NAME);
+    std::fs::create_dir_all(&books_dir).map_err(|source| super::Error::CreateDir {
+        path: books_dir.clone(),
+        source,
+    })?;
+    let path = books_dir.join(format!("{}.html", creator_id));
+    std::fs::write(&path, html).map_err(|source| super::Error::Write {
+        path: path.clone(),
+        source,
+    })?;
+    Ok(path)
+}
+
+fn is_image(extension: &str) -> bool {
+    matches!(
+        extension.to_ascii_lowercase().as_str(),
+        "jpg" | "jpeg" | "png" | "gif" | "webp"
+    )
+}
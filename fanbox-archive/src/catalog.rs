@@ -0,0 +1,216 @@
+/// Post stored in the archive.
+#[derive(Debug, Clone)]
+pub struct ArchivedPost {
+    /// Directory containing the post's index.html and media
+    pub dir: std::path::PathBuf,
+    pub post: fanbox_api::Post,
+}
+
+impl ArchivedPost {
+    /// Returns the file name of the post's HTML page, which is index.html unless another name is
+    /// given by `ArchiveOptions::index_name`.
+    pub fn index_file_name(&self) -> String {
+        if self.dir.join(super::DEFAULT_INDEX_NAME).is_file() {
+            return super::DEFAULT_INDEX_NAME.to_owned();
+        }
+        let named_by_id = format!("{}.html", self.post.info.id);
+        if self.dir.join(&named_by_id).is_file() {
+            return named_by_id;
+        }
+        let mut html_files: Vec<_> = std::fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(".html"))
+            .collect();
+        html_files.sort();
+        html_files
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| super::DEFAULT_INDEX_NAME.to_owned())
+    }
+}
+
+/// Loads metadata of all archived posts under `dest_dir`.
+///
+/// Directories are searched recursively until a directory containing metadata.json is found.
+/// Unreadable metadata is reported and skipped.
+pub fn load_archived_posts(dest_dir: &std::path::Path) -> Result<Vec<ArchivedPost>, super::Error> {
+    let mut posts = Vec::new();
+    let mut dirs = vec![dest_dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let metadata_path = dir.join(super::METADATA_FILE_NAME);
+        if metadata_path.is_file() {
+            match read_metadata(&metadata_path) {
+                Ok(post) => posts.push(ArchivedPost { dir, post }),
+                Err(e) => tracing::warn!("Skip {}: {}", metadata_path.display(), e),
+            }
+            continue;
+        }
+        let entries = std::fs::read_dir(&dir).map_err(|source| super::Error::Read {
+            path: dir.clone(),
+            source,
+        })?;
+        for entry in entries {
+            let entry = entry.map_err(|source| super::Error::Read {
+                path: dir.clone(),
+                source,
+            })?;
+            if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+                dirs.push(entry.path());
+            }
+        }
+    }
+    posts.sort_by(|a, b| {
+        b.post
+            .info
+            .updated_datetime
+            .cmp(&a.post.info.updated_datetime)
+    });
+    Ok(posts)
+}
+
+/// Returns the updated datetime of the post archived in `dest_dir` in any `Layout`, if any.
+pub fn archived_updated_datetime(
+    dest_dir: &std::path::Path,
+    post_id: &str,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    read_archived_post(dest_dir, post_id).map(|post| post.info.updated_datetime)
+}
+
+/// Returns the metadata of the post archived in `dest_dir` in any `Layout`, if any.
+pub(crate) fn read_archived_post(
+    dest_dir: &std::path::Path,
+    post_id: &str,
+) -> Option<fanbox_api::Post> {
+    let path = super::layout::find_post_dir(dest_dir, post_id)?.join(super::METADATA_FILE_NAME);
+    match read_metadata(&path) {
+        Ok(post) => Some(post),
+        Err(e) => {
+            tracing::warn!("Ignore {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+fn read_metadata(path: &std::path::Path) -> Result<fanbox_api::Post, std::io::Error> {
+    let json = std::fs::read(path)?;
+    Ok(serde_json::from_slice(&json)?)
+}
+
+/// Returns the plain text of the post body: descriptions, paragraphs and headers.
+pub fn post_text(post: &fanbox_api::Post) -> String {
+    match &post.body {
+        Some(fanbox_api::PostBody::Image(image)) => image.body.text.clone(),
+        Some(fanbox_api::PostBody::File(file)) => file.body.text.clone(),
+        Some(fanbox_api::PostBody::Text(text)) => text.body.text.clone(),
+        Some(fanbox_api::PostBody::Video(video)) => video.body.text.clone(),
+        Some(fanbox_api::PostBody::Article(article)) => {
+            let mut lines = Vec::new();
+            for block in &article.body.blocks {
+                match block {
+                    fanbox_api::ArticleBlock::P(p) => lines.push(p.text.as_str()),
+                    fanbox_api::ArticleBlock::Header(header) => lines.push(header.text.as_str()),
+                    _ => {}
+                }
+            }
+            lines.join("\n")
+        }
+        _ => String::new(),
+    }
+}
+
+/// Image or file attached to a post.
+pub(crate) struct MediaFile<'a> {
+    pub id: &'a str,
+    /// Original file name of files, or the id of images
+    pub name: &'a str,
+    pub extension: &'a str,
+    pub url: &'a str,
+    /// Name in the post directory, `<id>.<extension>` unless disambiguated by
+    /// `disambiguate_case`
+    pub file_name: String,
+}
+
+/// Returns images and files of the post body in the post order.
+pub(crate) fn media_files(post: &fanbox_api::Post) -> Vec<MediaFile<'_>> {
+    fn image(image: &fanbox_api::Image) -> MediaFile<'_> {
+        MediaFile {
+            id: &image.id,
+            name: &image.id,
+            extension: &image.extension,
+            url: &image.original_url,
+            file_name: format!("{}.{}", image.id, image.extension),
+        }
+    }
+    fn file(file: &fanbox_api::File) -> MediaFile<'_> {
+        MediaFile {
+            id: &file.id,
+            name: &file.name,
+            extension: &file.extension,
+            url: &file.url,
+            file_name: format!("{}.{}", file.id, file.extension),
+        }
+    }
+
+    let mut media_files: Vec<_> = match &post.body {
+        Some(fanbox_api::PostBody::Image(body)) => body.body.images.iter().map(image).collect(),
+        Some(fanbox_api::PostBody::File(body)) => body.body.files.iter().map(file).collect(),
+        Some(fanbox_api::PostBody::Article(article)) => {
+            let body = &article.body;
+            body.blocks
+                .iter()
+                .filter_map(|block| match block {
+                    fanbox_api::ArticleBlock::Image(image_block) => {
+                        body.image_map.get(&image_block.image_id).map(image)
+                    }
+                    fanbox_api::ArticleBlock::File(file_block) => {
+                        body.file_map.get(&file_block.file_id).map(file)
+                    }
+                    _ => None,
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+    disambiguate_case(
+        media_files
+            .iter_mut()
+            .map(|media_file| &mut media_file.file_name),
+    );
+    media_files
+}
+
+/// Renames file names differing from earlier ones only in case to `<stem> (<n>).<extension>`,
+/// since they would overwrite each other on the case-insensitive file systems of Windows and
+/// macOS. Files given in the same order are always renamed the same way, and the same file
+/// referenced twice keeps one name.
+pub(crate) fn disambiguate_case<'a, I>(names: I)
+where
+    I: IntoIterator<Item = &'a mut String>,
+{
+    let mut used = std::collections::HashMap::new();
+    for name in names {
+        let original = name.clone();
+        let (stem, extension) = match original.rsplit_once('.') {
+            Some((stem, extension)) => (stem, Some(extension)),
+            None => (original.as_str(), None),
+        };
+        let mut n = 0;
+        while used
+            .get(&name.to_lowercase())
+            .is_some_and(|existing: &String| existing != name)
+        {
+            n += 1;
+            *name = match extension {
+                Some(extension) => format!("{} ({}).{}", stem, n, extension),
+                None => format!("{} ({})", stem, n),
+            };
+        }
+        if n > 0 {
+            tracing::debug!("Rename {} to {} to avoid case collision", original, name);
+        }
+        used.insert(name.to_lowercase(), name.clone());
+    }
+}
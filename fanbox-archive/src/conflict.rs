@@ -0,0 +1,87 @@
+/// What happens when a media file, cover image or page to be written already exists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Replace the existing file
+    #[default]
+    Overwrite,
+    /// Keep the existing file and don't write the new one
+    Skip,
+    /// Keep the existing file and write the new one as `<stem> (<n>).<extension>`, while pages
+    /// keep linking to the existing one
+    Rename,
+    /// Move the existing file to `<name>.~<n>~` before writing the new one
+    Backup,
+    /// Move the existing file to the trash of the OS before writing the new one, so that it can
+    /// be restored from there
+    #[cfg(feature = "trash")]
+    Trash,
+}
+
+/// Returns the path which the file about to be written to `path` should be written to, or `None`
+/// if it should be skipped. The existing file is moved aside with `ConflictPolicy::Backup`.
+pub(crate) fn resolve(
+    path: &std::path::Path,
+    policy: ConflictPolicy,
+) -> Result<Option<std::path::PathBuf>, super::Error> {
+    if policy == ConflictPolicy::Overwrite || !path.exists() {
+        return Ok(Some(path.to_path_buf()));
+    }
+    match policy {
+        ConflictPolicy::Overwrite => unreachable!(),
+        ConflictPolicy::Skip => {
+            tracing::info!("Skip existing {}", path.display());
+            Ok(None)
+        }
+        ConflictPolicy::Rename => {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let extension = path
+                .extension()
+                .map(|extension| extension.to_string_lossy());
+            let renamed = unused_path(|n| {
+                path.with_file_name(match &extension {
+                    Some(extension) => format!("{} ({}).{}", stem, n, extension),
+                    None => format!("{} ({})", stem, n),
+                })
+            });
+            tracing::info!(
+                "Write {} instead of existing {}",
+                renamed.display(),
+                path.display()
+            );
+            Ok(Some(renamed))
+        }
+        ConflictPolicy::Backup => {
+            let backup = unused_path(|n| {
+                let mut backup = path.as_os_str().to_owned();
+                backup.push(format!(".~{}~", n));
+                backup.into()
+            });
+            tracing::info!("Back up {} to {}", path.display(), backup.display());
+            std::fs::rename(path, &backup).map_err(|source| super::Error::Write {
+                path: backup.clone(),
+                source,
+            })?;
+            Ok(Some(path.to_path_buf()))
+        }
+        #[cfg(feature = "trash")]
+        ConflictPolicy::Trash => {
+            tracing::info!("Move {} to the trash", path.display());
+            trash::delete(path).map_err(|source| super::Error::Trash {
+                path: path.to_path_buf(),
+                source,
+            })?;
+            Ok(Some(path.to_path_buf()))
+        }
+    }
+}
+
+/// Returns the first path built by `candidate` from 1 which doesn't exist.
+fn unused_path<F>(candidate: F) -> std::path::PathBuf
+where
+    F: Fn(usize) -> std::path::PathBuf,
+{
+    (1..)
+        .map(candidate)
+        .find(|path| !path.exists())
+        .expect("some path is unused")
+}
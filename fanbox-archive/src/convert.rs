@@ -0,0 +1,121 @@
+/// Format which images are converted into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvertFormat {
+    Png,
+    Jpeg,
+}
+
+impl ConvertFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpeg",
+        }
+    }
+}
+
+/// Extensions which are displayed by virtually every viewer and never converted
+const COMPATIBLE_EXTENSIONS: &[&str] = &["gif", "jpeg", "jpg", "png"];
+
+/// Replaces extensions of the post's images in formats unsupported by older viewers, so that the
+/// rendered pages reference the converted files.
+pub(crate) fn convert_images(post: &mut fanbox_api::Post, to: ConvertFormat) {
+    let images: Vec<&mut fanbox_api::Image> = match &mut post.body {
+        Some(fanbox_api::PostBody::Image(image)) => image.body.images.iter_mut().collect(),
+        Some(fanbox_api::PostBody::Article(article)) => {
+            article.body.image_map.values_mut().collect()
+        }
+        _ => Vec::new(),
+    };
+    for image in images {
+        let extension = image.extension.to_ascii_lowercase();
+        if COMPATIBLE_EXTENSIONS.contains(&extension.as_str()) {
+            continue;
+        }
+        if image::ImageFormat::from_extension(&extension).is_some_and(|f| f.reading_enabled()) {
+            image.extension = to.extension().to_owned();
+        }
+    }
+}
+
+/// Re-encodes the downloaded image at `path` when its content doesn't match its extension.
+///
+/// Files which aren't images or can't be decoded are left as is. Converted files and their
+/// directory entries are flushed to disk with `fsync`.
+pub(crate) fn convert_file(
+    path: &std::path::Path,
+    mtime: &chrono::DateTime<chrono::Utc>,
+    fsync: bool,
+) -> Result<(), super::Error> {
+    let format = match path
+        .extension()
+        .and_then(image::ImageFormat::from_extension)
+    {
+        Some(format) if format.writing_enabled() => format,
+        _ => return Ok(()),
+    };
+    let reader = image::ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|source| super::Error::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    match reader.format() {
+        Some(actual) if actual != format && actual.reading_enabled() => {}
+        _ => return Ok(()),
+    }
+    let mut image = match reader.decode() {
+        Ok(image) => image,
+        Err(e) => {
+            tracing::warn!("Failed to decode {}: {}", path.display(), e);
+            return Ok(());
+        }
+    };
+    if format == image::ImageFormat::Jpeg {
+        image = image::DynamicImage::ImageRgb8(image.to_rgb8());
+    }
+    tracing::info!("Convert {} into {:?}", path.display(), format);
+    let mut temporary_path = path.as_os_str().to_owned();
+    temporary_path.push(".");
+    temporary_path.push(super::TEMPORARY_EXTENSION);
+    let temporary_path = std::path::PathBuf::from(temporary_path);
+    image
+        .save_with_format(&temporary_path, format)
+        .map_err(|source| super::Error::ConvertImage {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    std::fs::rename(&temporary_path, path).map_err(|source| super::Error::Write {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    filetime::set_file_mtime(
+        path,
+        filetime::FileTime::from_unix_time(mtime.timestamp(), mtime.timestamp_subsec_nanos()),
+    )
+    .map_err(|source| super::Error::SetMtime {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    if fsync {
+        sync_file(path).map_err(|source| super::Error::Write {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    }
+    Ok(())
+}
+
+/// Flushes the file at `path` and, on Unix, the entry of its directory to disk.
+fn sync_file(path: &std::path::Path) -> Result<(), std::io::Error> {
+    std::fs::File::open(path)?.sync_all()?;
+    #[cfg(unix)]
+    {
+        let dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or_else(|| std::path::Path::new("."));
+        std::fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,110 @@
+/// Directory in `dest_dir/<creator id>/` storing cover images written by `archive_covers`
+pub const COVERS_DIR_NAME: &str = "covers";
+
+/// Downloads cover images of the listed posts of the creator into
+/// `dest_dir/<creator id>/covers/<post id>.<extension>` and writes `index.html` there showing
+/// them as a gallery linking to the posts on FANBOX, without post directories.
+///
+/// Covers whose mtime matches the updated datetime of the post are kept. Returns the path of the
+/// gallery page.
+pub async fn archive_covers(
+    client: &fanbox_api::PostClient,
+    dest_dir: &std::path::Path,
+    creator_id: &str,
+    items: &[fanbox_api::ListCreatorItem],
+    lang: super::Lang,
+) -> Result<std::path::PathBuf, super::Error> {
+    let covers_dir = dest_dir.join(creator_id).join(COVERS_DIR_NAME);
+    std::fs::create_dir_all(&covers_dir).map_err(|source| super::Error::CreateDir {
+        path: covers_dir.clone(),
+        source,
+    })?;
+
+    let mut figures = Vec::new();
+    for item in items {
+        let post_url = format!("https://{}.fanbox.cc/posts/{}", creator_id, item.id);
+        let image = match &item.cover {
+            Some(cover) => {
+                let extension = cover
+                    .url
+                    .rsplit('/')
+                    .next()
+                    .and_then(|name| name.rsplit_once('.'))
+                    .map_or("jpeg", |(_, extension)| extension);
+                let name = format!("{}.{}", item.id, extension);
+                let path = covers_dir.join(&name);
+                if is_up_to_date(&path, &item.updated_datetime) {
+                    tracing::debug!("Skip up-to-date {}", path.display());
+                } else {
+                    tracing::info!("Download cover image {}", cover.url);
+                    client
+                        .download_media_to(
+                            &cover.url,
+                            &path,
+                            &item.updated_datetime,
+                            Some(&post_url),
+                        )
+                        .await
+                        .map_err(|source| super::Error::Download {
+                            url: cover.url.clone(),
+                            source,
+                        })?;
+                }
+                format!(
+                    "<img loading='lazy' alt='' src='./{}'>",
+                    super::feed::escape_xml(&name)
+                )
+            }
+            None => "<div class='no-cover'></div>".to_owned(),
+        };
+        figures.push(format!(
+            "<figure><a href='{}'>{}</a><figcaption>{}<br><time datetime='{}'>{}</time></figcaption></figure>",
+            post_url,
+            image,
+            super::feed::escape_xml(&item.title),
+            item.published_datetime.to_rfc3339(),
+            item.published_datetime.format("%Y-%m-%d")
+        ));
+    }
+
+    let html = [
+        "<!DOCTYPE html>".to_owned(),
+        format!("<html lang='{}'>", lang.tag()),
+        "<head>".to_owned(),
+        "<meta charset='utf-8'>".to_owned(),
+        format!("<title>{}</title>", super::feed::escape_xml(creator_id)),
+        "<style>".to_owned(),
+        "main { display: grid; grid-template-columns: repeat(auto-fill, minmax(200px, 1fr)); gap: 16px; }".to_owned(),
+        "figure { margin: 0; }".to_owned(),
+        "figure img, .no-cover { width: 100%; aspect-ratio: 16 / 9; object-fit: cover; background: #ddd; }".to_owned(),
+        "figcaption { font-size: small; }".to_owned(),
+        "</style>".to_owned(),
+        "</head>".to_owned(),
+        "<body>".to_owned(),
+        format!(
+            "<h1><a href='https://{}.fanbox.cc/'>{}</a> ({})</h1>",
+            creator_id,
+            super::feed::escape_xml(creator_id),
+            lang.post_count(items.len())
+        ),
+        "<main>".to_owned(),
+        figures.join("\n"),
+        "</main>".to_owned(),
+        "</body>".to_owned(),
+        "</html>".to_owned(),
+    ]
+    .join("\n");
+    let path = covers_dir.join(super::DEFAULT_INDEX_NAME);
+    std::fs::write(&path, html).map_err(|source| super::Error::Write {
+        path: path.clone(),
+        source,
+    })?;
+    Ok(path)
+}
+
+fn is_up_to_date(path: &std::path::Path, updated_datetime: &chrono::DateTime<chrono::Utc>) -> bool {
+    std::fs::metadata(path).is_ok_and(|metadata| {
+        filetime::FileTime::from_last_modification_time(&metadata).unix_seconds()
+            == updated_datetime.timestamp()
+    })
+}
@@ -0,0 +1,152 @@
+/// Downloads the banner and icon of the creator into `dest_dir/<creator id>/` and writes
+/// `index.html` there with a header resembling the creator page on FANBOX, followed by the
+/// creator's archived posts, newest first.
+///
+/// The images are downloaded again on every call since their URLs change when the creator
+/// replaces them. Returns the path of the page.
+pub async fn write_creator_page(
+    client: &fanbox_api::PostClient,
+    dest_dir: &std::path::Path,
+    creator: &fanbox_api::Creator,
+    lang: super::Lang,
+) -> Result<std::path::PathBuf, super::Error> {
+    use super::feed::escape_xml;
+
+    let creator_id = creator.creator_id.as_str();
+    let creator_url = format!("https://{}.fanbox.cc/", creator_id);
+    let creator_dir = dest_dir.join(creator_id);
+    std::fs::create_dir_all(&creator_dir).map_err(|source| super::Error::CreateDir {
+        path: creator_dir.clone(),
+        source,
+    })?;
+    let mut images = Vec::new();
+    for (class, url) in [
+        ("banner", &creator.cover_image_url),
+        ("icon", &creator.user.icon_url),
+    ] {
+        if let Some(url) = url {
+            let name = download_image(client, &creator_dir, class, url, &creator_url).await?;
+            images.push(format!(
+                "<img class='{}' alt='' src='./{}'>",
+                class,
+                escape_xml(&name)
+            ));
+        }
+    }
+
+    let mut posts: Vec<_> = super::load_archived_posts(dest_dir)?
+        .into_iter()
+        .filter(|archived| archived.post.info.creator_id == creator_id)
+        .collect();
+    posts.sort_by(|a, b| {
+        b.post
+            .info
+            .published_datetime
+            .cmp(&a.post.info.published_datetime)
+    });
+    let mut items = Vec::new();
+    for archived in &posts {
+        let info = &archived.post.info;
+        // The page is in a sibling directory of post directories
+        let relative_dir = archived
+            .dir
+            .strip_prefix(dest_dir)
+            .unwrap_or(&archived.dir)
+            .iter()
+            .map(|component| component.to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("/");
+        items.push(format!(
+            "<li><time datetime='{}'>{}</time> <a href='../{}/{}'>{}</a></li>",
+            info.published_datetime.to_rfc3339(),
+            info.published_datetime.format("%Y-%m-%d"),
+            escape_xml(&relative_dir),
+            escape_xml(&archived.index_file_name()),
+            escape_xml(&info.title)
+        ));
+    }
+
+    let mut header = images;
+    header.push(format!(
+        "<h1><a href='{}'>{}</a></h1>",
+        creator_url,
+        escape_xml(&creator.user.name)
+    ));
+    header.extend(
+        creator
+            .description
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| format!("<p>{}</p>", escape_xml(line))),
+    );
+    if !creator.profile_links.is_empty() {
+        header.push("<ul class='links'>".to_owned());
+        for link in &creator.profile_links {
+            header.push(format!(
+                "<li><a href='{}'>{}</a></li>",
+                escape_xml(link),
+                escape_xml(link)
+            ));
+        }
+        header.push("</ul>".to_owned());
+    }
+
+    let html = [
+        "<!DOCTYPE html>".to_owned(),
+        format!("<html lang='{}'>", lang.tag()),
+        "<head>".to_owned(),
+        "<meta charset='utf-8'>".to_owned(),
+        format!("<title>{}</title>", escape_xml(&creator.user.name)),
+        "<style>".to_owned(),
+        "body { max-width: 60em; margin: auto; }".to_owned(),
+        "header { text-align: center; }".to_owned(),
+        ".banner { display: block; width: 100%; aspect-ratio: 16 / 5; object-fit: cover; }".to_owned(),
+        ".icon { width: 96px; height: 96px; margin-top: -48px; border: 4px solid #fff; border-radius: 50%; object-fit: cover; }".to_owned(),
+        ".links { list-style: none; padding: 0; }".to_owned(),
+        "</style>".to_owned(),
+        "</head>".to_owned(),
+        "<body>".to_owned(),
+        "<header>".to_owned(),
+        header.join("\n"),
+        "</header>".to_owned(),
+        format!("<h2>{}</h2>", lang.post_count(posts.len())),
+        "<ul>".to_owned(),
+        items.join("\n"),
+        "</ul>".to_owned(),
+        "</body>".to_owned(),
+        "</html>".to_owned(),
+    ]
+    .join("\n");
+    let path = creator_dir.join(super::DEFAULT_INDEX_NAME);
+    std::fs::write(&path, html).map_err(|source| super::Error::Write {
+        path: path.clone(),
+        source,
+    })?;
+    Ok(path)
+}
+
+/// Downloads the image at `url` into `dir` as `<stem>.<extension of the URL>`, returning the file
+/// name.
+async fn download_image(
+    client: &fanbox_api::PostClient,
+    dir: &std::path::Path,
+    stem: &str,
+    url: &str,
+    creator_url: &str,
+) -> Result<String, super::Error> {
+    let extension = url
+        .rsplit('/')
+        .next()
+        .and_then(|name| name.rsplit_once('.'))
+        .map_or("jpeg", |(_, extension)| extension);
+    let name = format!("{}.{}", stem, extension);
+    tracing::info!("Download {} image {}", stem, url);
+    client
+        .download_media_to(url, dir.join(&name), &chrono::Utc::now(), Some(creator_url))
+        .await
+        .map_err(|source| super::Error::Download {
+            url: url.to_owned(),
+            source,
+        })?;
+    Ok(name)
+}
@@ -0,0 +1,377 @@
+//! Render-agnostic documents of posts.
+//!
+//! Bodies of all post types are normalized into blocks in the display order with their images,
+//! files and embeds resolved, so that renderers don't have to know the block model of FANBOX.
+
+/// Post normalized into blocks in the display order.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostDocument {
+    pub id: String,
+    pub creator_id: String,
+    /// Name of the creator, which is the creator id in metadata archived by older versions
+    pub creator_name: String,
+    /// Pixiv user id of the creator, missing in metadata archived by older versions
+    pub user_id: Option<String>,
+    pub title: String,
+    pub url: String,
+    pub kind: PostKind,
+    pub published_datetime: chrono::DateTime<chrono::Utc>,
+    pub updated_datetime: chrono::DateTime<chrono::Utc>,
+    pub fee_required: Option<u32>,
+    pub tags: Vec<String>,
+    pub cover: Option<Asset>,
+    pub blocks: Vec<Block>,
+}
+
+/// Type of the post body which the document is normalized from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostKind {
+    Image,
+    Article,
+    File,
+    Text,
+    Video,
+    /// Post without body or with a body of a type which is not supported yet
+    Unknown,
+}
+
+impl PostKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Image => "image",
+            Self::Article => "article",
+            Self::File => "file",
+            Self::Text => "text",
+            Self::Video => "video",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// Downloaded image or file.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Asset {
+    /// URL which the asset is downloaded from
+    pub url: String,
+    /// Path relative to the post directory
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Block {
+    Paragraph {
+        text: String,
+    },
+    Header {
+        text: String,
+    },
+    Image {
+        id: String,
+        asset: Asset,
+    },
+    File {
+        id: String,
+        /// Original file name without the extension
+        name: String,
+        asset: Asset,
+    },
+    /// Video hosted on YouTube or Vimeo, or audio hosted on SoundCloud
+    Video {
+        provider: String,
+        video_id: String,
+        url: String,
+    },
+    /// Link to another post on FANBOX
+    Post {
+        creator_id: String,
+        post_id: String,
+        url: String,
+    },
+    /// Link to a tweet or another web page
+    Link {
+        url: String,
+        text: String,
+    },
+    /// Card of a web page rendered by FANBOX
+    Html {
+        html: String,
+    },
+    /// Body, block, embed or video of a type which is not supported yet
+    Unknown {
+        /// What is unknown, i.e. `body`, `block`, `embed`, `url_embed` or `video`
+        kind: String,
+        /// Index of the block or id of the embed
+        id: Option<String>,
+        /// Raw JSON, which is available for types the API client doesn't know
+        raw: Option<serde_json::Value>,
+    },
+}
+
+impl Block {
+    fn unknown(kind: &str, id: Option<&str>, raw: Option<&serde_json::Value>) -> Self {
+        Self::Unknown {
+            kind: kind.to_owned(),
+            id: id.map(ToOwned::to_owned),
+            raw: raw.cloned(),
+        }
+    }
+}
+
+impl PostDocument {
+    /// Normalizes the post, whose images and files are named `<id>.<extension>` in the post
+    /// directory, disambiguated when they differ only in case. Images, files and embeds missing in the maps of articles are dropped.
+    pub fn new(post: &fanbox_api::Post) -> Self {
+        let info = &post.info;
+        let mut blocks = Vec::new();
+        let kind = match &post.body {
+            Some(fanbox_api::PostBody::Image(image)) => {
+                blocks.extend(image.body.images.iter().map(image_block));
+                push_text(&mut blocks, &image.body.text);
+                PostKind::Image
+            }
+            Some(fanbox_api::PostBody::File(file)) => {
+                blocks.extend(file.body.files.iter().map(file_block));
+                push_text(&mut blocks, &file.body.text);
+                PostKind::File
+            }
+            Some(fanbox_api::PostBody::Text(text)) => {
+                push_text(&mut blocks, &text.body.text);
+                PostKind::Text
+            }
+            Some(fanbox_api::PostBody::Video(video)) => {
+                blocks.push(match &video.body.video {
+                    fanbox_api::Video::Youtube(youtube) => youtube_block(&youtube.video_id),
+                    fanbox_api::Video::Vimeo(vimeo) => vimeo_block(&vimeo.video_id),
+                    fanbox_api::Video::Soundcloud(soundcloud) => {
+                        soundcloud_block(&soundcloud.video_id)
+                    }
+                    fanbox_api::Video::Unknown(raw) => Block::unknown("video", None, Some(raw)),
+                    _ => Block::unknown("video", None, None),
+                });
+                push_text(&mut blocks, &video.body.text);
+                PostKind::Video
+            }
+            Some(fanbox_api::PostBody::Article(article)) => {
+                let body = &article.body;
+                blocks.extend(
+                    body.blocks
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, block)| article_block(body, i, block)),
+                );
+                PostKind::Article
+            }
+            Some(fanbox_api::PostBody::Unknown(raw)) => {
+                blocks.push(Block::unknown("body", None, Some(raw)));
+                PostKind::Unknown
+            }
+            Some(_) => {
+                blocks.push(Block::unknown("body", None, None));
+                PostKind::Unknown
+            }
+            None => PostKind::Unknown,
+        };
+        super::catalog::disambiguate_case(blocks.iter_mut().filter_map(|block| match block {
+            Block::Image { asset, .. } | Block::File { asset, .. } => Some(&mut asset.path),
+            _ => None,
+        }));
+        Self {
+            id: info.id.clone(),
+            creator_id: info.creator_id.clone(),
+            creator_name: info
+                .user
+                .as_ref()
+                .map_or_else(|| info.creator_id.clone(), |user| user.name.clone()),
+            user_id: info.user.as_ref().map(|user| user.user_id.clone()),
+            title: info.title.clone(),
+            url: super::post_url(info),
+            kind,
+            published_datetime: info.published_datetime,
+            updated_datetime: info.updated_datetime,
+            fee_required: info.fee_required,
+            tags: info.tags.clone(),
+            cover: info.cover_image_url.as_ref().map(|url| Asset {
+                url: url.clone(),
+                path: "cover_image.jpeg".to_owned(),
+                width: None,
+                height: None,
+                size: None,
+            }),
+            blocks,
+        }
+    }
+
+    /// Returns whether the document has images other than the cover image.
+    pub fn has_images(&self) -> bool {
+        self.blocks
+            .iter()
+            .any(|block| matches!(block, Block::Image { .. }))
+    }
+}
+
+fn push_text(blocks: &mut Vec<Block>, text: &str) {
+    if !text.is_empty() {
+        blocks.push(Block::Paragraph {
+            text: text.to_owned(),
+        });
+    }
+}
+
+fn image_block(image: &fanbox_api::Image) -> Block {
+    Block::Image {
+        id: image.id.clone(),
+        asset: Asset {
+            url: image.original_url.clone(),
+            path: format!("{}.{}", image.id, image.extension),
+            width: image.width,
+            height: image.height,
+            size: None,
+        },
+    }
+}
+
+fn file_block(file: &fanbox_api::File) -> Block {
+    Block::File {
+        id: file.id.clone(),
+        name: file.name.clone(),
+        asset: Asset {
+            url: file.url.clone(),
+            path: format!("{}.{}", file.id, file.extension),
+            width: None,
+            height: None,
+            size: file.size,
+        },
+    }
+}
+
+fn youtube_block(video_id: &str) -> Block {
+    Block::Video {
+        provider: "youtube".to_owned(),
+        video_id: video_id.to_owned(),
+        url: format!("https://www.youtube.com/watch?v={}", video_id),
+    }
+}
+
+fn vimeo_block(video_id: &str) -> Block {
+    Block::Video {
+        provider: "vimeo".to_owned(),
+        video_id: video_id.to_owned(),
+        url: format!("https://vimeo.com/{}", video_id),
+    }
+}
+
+fn soundcloud_block(track_id: &str) -> Block {
+    Block::Video {
+        provider: "soundcloud".to_owned(),
+        video_id: track_id.to_owned(),
+        url: format!(
+            "https://w.soundcloud.com/player/?url=https%3A//api.soundcloud.com/tracks/{}",
+            track_id
+        ),
+    }
+}
+
+/// Resolves the `i`-th block of the article.
+fn article_block(
+    body: &fanbox_api::PostBodyArticleBody,
+    i: usize,
+    block: &fanbox_api::ArticleBlock,
+) -> Option<Block> {
+    Some(match block {
+        fanbox_api::ArticleBlock::P(p) => Block::Paragraph {
+            text: p.text.clone(),
+        },
+        fanbox_api::ArticleBlock::Header(header) => Block::Header {
+            text: header.text.clone(),
+        },
+        fanbox_api::ArticleBlock::Image(image_block_ref) => {
+            let Some(image) = body.image_map.get(&image_block_ref.image_id) else {
+                tracing::warn!(
+                    "image {} is not available in imageMap",
+                    image_block_ref.image_id
+                );
+                return None;
+            };
+            image_block(image)
+        }
+        fanbox_api::ArticleBlock::File(file_block_ref) => {
+            let Some(file) = body.file_map.get(&file_block_ref.file_id) else {
+                tracing::warn!(
+                    "file {} is not available in fileMap",
+                    file_block_ref.file_id
+                );
+                return None;
+            };
+            file_block(file)
+        }
+        fanbox_api::ArticleBlock::Embed(embed_block) => {
+            let id = embed_block.embed_id.as_str();
+            let Some(embed) = body.embed_map.get(id) else {
+                tracing::warn!("embed {} is not available in embedMap", id);
+                return None;
+            };
+            match embed {
+                // embedMap doesn't have screen name but /*/status/:id is redirected to the
+                // correct URL
+                fanbox_api::Embed::Twitter(twitter) => Block::Link {
+                    url: format!("https://twitter.com/unknown/status/{}", twitter.content_id),
+                    text: format!("twitter:{}", twitter.content_id),
+                },
+                fanbox_api::Embed::Fanbox(fanbox) => {
+                    match fanbox.content_id.split('/').collect::<Vec<_>>()[..] {
+                        ["creator", creator_id, "post", post_id] => Block::Post {
+                            creator_id: creator_id.to_owned(),
+                            post_id: post_id.to_owned(),
+                            url: format!("https://{}.fanbox.cc/posts/{}", creator_id, post_id),
+                        },
+                        _ => Block::unknown("embed", Some(id), None),
+                    }
+                }
+                fanbox_api::Embed::Youtube(youtube) => youtube_block(&youtube.content_id),
+                fanbox_api::Embed::Vimeo(vimeo) => vimeo_block(&vimeo.content_id),
+                fanbox_api::Embed::Soundcloud(soundcloud) => {
+                    soundcloud_block(&soundcloud.content_id)
+                }
+                fanbox_api::Embed::Unknown(raw) => Block::unknown("embed", Some(id), Some(raw)),
+                _ => Block::unknown("embed", Some(id), None),
+            }
+        }
+        fanbox_api::ArticleBlock::UrlEmbed(url_embed_block) => {
+            let id = url_embed_block.url_embed_id.as_str();
+            let Some(url_embed) = body.url_embed_map.get(id) else {
+                tracing::warn!("url_embed {} is not available in urlEmbedMap", id);
+                return None;
+            };
+            match url_embed {
+                fanbox_api::UrlEmbed::Default(default) => Block::Link {
+                    url: default.url.clone(),
+                    text: default.url.clone(),
+                },
+                fanbox_api::UrlEmbed::Html(html) | fanbox_api::UrlEmbed::HtmlCard(html) => {
+                    Block::Html {
+                        html: html.html.clone(),
+                    }
+                }
+                fanbox_api::UrlEmbed::Unknown(raw) => {
+                    Block::unknown("url_embed", Some(id), Some(raw))
+                }
+                _ => Block::unknown("url_embed", Some(id), None),
+            }
+        }
+        fanbox_api::ArticleBlock::Unknown(raw) => {
+            Block::unknown("block", Some(&i.to_string()), Some(raw))
+        }
+        _ => Block::unknown("block", Some(&i.to_string()), None),
+    })
+}
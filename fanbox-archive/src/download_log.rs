@@ -0,0 +1,106 @@
+/// Name of the log of downloaded files in the destination directory
+pub const DOWNLOAD_LOG_FILE_NAME: &str = "downloads.log.jsonl";
+
+/// Entry of the download log.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadLogEntry {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub post_id: String,
+    pub url: String,
+    pub path: std::path::PathBuf,
+    pub size: u64,
+    /// Hex-encoded SHA-256 digest of the file
+    pub sha256: String,
+    /// HTTP status of the response
+    pub status: u16,
+    pub duration_ms: u64,
+}
+
+/// Append-only JSON lines log of every downloaded file, kept across runs as an audit trail.
+#[derive(Debug)]
+pub struct DownloadLog {
+    path: std::path::PathBuf,
+    file: std::sync::Mutex<std::fs::File>,
+}
+
+impl DownloadLog {
+    /// Opens the log in `dest_dir` to append entries.
+    pub fn open(dest_dir: &std::path::Path) -> Result<Self, super::Error> {
+        std::fs::create_dir_all(dest_dir).map_err(|source| super::Error::CreateDir {
+            path: dest_dir.to_path_buf(),
+            source,
+        })?;
+        let path = dest_dir.join(DOWNLOAD_LOG_FILE_NAME);
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|source| super::Error::Write {
+                path: path.clone(),
+                source,
+            })?;
+        Ok(Self {
+            path,
+            file: std::sync::Mutex::new(file),
+        })
+    }
+
+    /// Reads entries of the log in `dest_dir`, oldest first. Broken lines are skipped.
+    pub fn read(dest_dir: &std::path::Path) -> Result<Vec<DownloadLogEntry>, super::Error> {
+        let path = dest_dir.join(DOWNLOAD_LOG_FILE_NAME);
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(source) => return Err(super::Error::Read { path, source }),
+        };
+        Ok(content
+            .lines()
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    tracing::warn!("Ignore broken download log entry {}: {}", line, e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    pub fn append(&self, entry: &DownloadLogEntry) -> Result<(), super::Error> {
+        use std::io::Write as _;
+
+        let mut line = serde_json::to_vec(entry).map_err(|e| super::Error::Write {
+            path: self.path.clone(),
+            source: e.into(),
+        })?;
+        line.push(b'\n');
+        self.file
+            .lock()
+            .unwrap()
+            .write_all(&line)
+            .map_err(|source| super::Error::Write {
+                path: self.path.clone(),
+                source,
+            })
+    }
+}
+
+/// Returns the hex-encoded SHA-256 digest of the file.
+pub fn sha256_file(path: &std::path::Path) -> Result<String, super::Error> {
+    use sha2::Digest as _;
+
+    let mut file = std::fs::File::open(path).map_err(|source| super::Error::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let mut hasher = sha2::Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|source| super::Error::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
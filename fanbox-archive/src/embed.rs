@@ -0,0 +1,75 @@
+/// Name of the sidecar file listing videos, links and HTML embedded in the post
+pub const EMBEDS_FILE_NAME: &str = "embeds.json";
+
+/// Returns blocks of the document referring to content hosted outside FANBOX or in other posts.
+fn embedded_blocks(document: &super::PostDocument) -> Vec<&super::Block> {
+    document
+        .blocks
+        .iter()
+        .filter(|block| {
+            matches!(
+                block,
+                super::Block::Video { .. }
+                    | super::Block::Link { .. }
+                    | super::Block::Post { .. }
+                    | super::Block::Html { .. }
+            )
+        })
+        .collect()
+}
+
+/// Writes the embedded blocks of the document into `embeds.json` in the post directory, so that
+/// what they refer to can be fetched later. Nothing is written when the post has none.
+pub(crate) async fn write_embeds_json(
+    dest_dir: &std::path::Path,
+    document: &super::PostDocument,
+    options: &super::ArchiveOptions,
+) -> Result<(), super::Error> {
+    let blocks = embedded_blocks(document);
+    if blocks.is_empty() {
+        return Ok(());
+    }
+    let path = dest_dir.join(EMBEDS_FILE_NAME);
+    let path = match super::conflict::resolve(&path, options.on_conflict)? {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+    let json = serde_json::to_vec_pretty(&blocks).map_err(|e| super::Error::Write {
+        path: path.clone(),
+        source: e.into(),
+    })?;
+    super::write_file(&path, &json, &document.updated_datetime).await
+}
+
+/// Runs `command` in the post directory with the URL of each embedded video appended.
+///
+/// Failures are only warned, since the providers may have removed the videos.
+pub(crate) async fn download_embedded_videos(
+    dest_dir: &std::path::Path,
+    document: &super::PostDocument,
+    command: &[String],
+) {
+    let (program, args) = match command.split_first() {
+        Some(command) => command,
+        None => return,
+    };
+    for block in &document.blocks {
+        let url = match block {
+            super::Block::Video { url, .. } => url,
+            _ => continue,
+        };
+        tracing::info!("Run {} for {} in {}", program, url, dest_dir.display());
+        let status = tokio::process::Command::new(program)
+            .args(args)
+            .arg(url)
+            .current_dir(dest_dir)
+            .stdin(std::process::Stdio::null())
+            .status()
+            .await;
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => tracing::warn!("{} exited with {} for {}", program, status, url),
+            Err(e) => tracing::warn!("Failed to run {} for {}: {}", program, url, e),
+        }
+    }
+}
@@ -0,0 +1,112 @@
+/// age or SSH public keys which backups and exports are encrypted to.
+pub struct Recipients(Vec<Box<dyn age::Recipient + Send + Sync>>);
+
+impl std::fmt::Debug for Recipients {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Recipients").field(&self.0.len()).finish()
+    }
+}
+
+impl Recipients {
+    /// Parses age or SSH public keys, or files of them one per line as `age --recipients-file`
+    /// reads, where empty lines and lines starting with `#` are ignored.
+    pub fn parse(recipients: &[String]) -> Result<Self, super::Error> {
+        let mut keys = Vec::new();
+        for recipient in recipients {
+            let path = std::path::Path::new(recipient);
+            if path.is_file() {
+                let content =
+                    std::fs::read_to_string(path).map_err(|source| super::Error::Read {
+                        path: path.to_path_buf(),
+                        source,
+                    })?;
+                for (i, line) in content.lines().enumerate() {
+                    let line = line.trim();
+                    if line.is_empty() || line.starts_with('#') {
+                        continue;
+                    }
+                    let key =
+                        parse_key(line).map_err(|message| super::Error::InvalidRecipient {
+                            recipient: format!("{}:{}", recipient, i + 1),
+                            message,
+                        })?;
+                    keys.push(key);
+                }
+            } else {
+                let key =
+                    parse_key(recipient).map_err(|message| super::Error::InvalidRecipient {
+                        recipient: recipient.clone(),
+                        message,
+                    })?;
+                keys.push(key);
+            }
+        }
+        if keys.is_empty() {
+            return Err(super::Error::InvalidRecipient {
+                recipient: recipients.join(","),
+                message: "no public keys are given".to_owned(),
+            });
+        }
+        Ok(Self(keys))
+    }
+
+    /// Creates `path` and writes what `write` writes into it encrypted in the age format, without
+    /// writing the plain content to disk.
+    pub fn encrypt_to<F, T>(&self, path: &std::path::Path, write: F) -> Result<T, super::Error>
+    where
+        F: FnOnce(&mut dyn std::io::Write) -> Result<T, super::Error>,
+    {
+        use std::io::Write as _;
+
+        let map_err = |source| super::Error::Write {
+            path: path.to_path_buf(),
+            source,
+        };
+        let encryptor = age::Encryptor::with_recipients(
+            self.0.iter().map(|key| key.as_ref() as &dyn age::Recipient),
+        )
+        .map_err(|e| super::Error::Encrypt {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+        let file = std::fs::File::create(path).map_err(map_err)?;
+        let mut writer = encryptor
+            .wrap_output(std::io::BufWriter::new(file))
+            .map_err(map_err)?;
+        let value = write(&mut writer)?;
+        let mut file = writer.finish().map_err(map_err)?;
+        file.flush().map_err(map_err)?;
+        file.into_inner()
+            .map_err(|e| map_err(e.into_error()))?
+            .sync_all()
+            .map_err(map_err)?;
+        Ok(value)
+    }
+}
+
+/// Parses an age X25519 or SSH public key.
+fn parse_key(key: &str) -> Result<Box<dyn age::Recipient + Send + Sync>, String> {
+    if key.starts_with("age1") {
+        key.parse::<age::x25519::Recipient>()
+            .map(|key| Box::new(key) as _)
+            .map_err(|e| e.to_owned())
+    } else if key.starts_with("ssh-") {
+        key.parse::<age::ssh::Recipient>()
+            .map(|key| Box::new(key) as _)
+            .map_err(|e| match e {
+                age::ssh::ParseRecipientKeyError::Ignore => "ignored SSH key type".to_owned(),
+                age::ssh::ParseRecipientKeyError::Invalid(message) => message.to_owned(),
+                age::ssh::ParseRecipientKeyError::RsaModulusTooLarge => {
+                    "RSA key is too large".to_owned()
+                }
+                age::ssh::ParseRecipientKeyError::RsaModulusTooSmall => {
+                    "RSA key is smaller than 2048 bits".to_owned()
+                }
+                age::ssh::ParseRecipientKeyError::Unsupported(key_type) => {
+                    format!("unsupported SSH key type {}", key_type)
+                }
+            })
+    } else {
+        Err("not an age or SSH public key, nor a file of them".to_owned())
+    }
+}
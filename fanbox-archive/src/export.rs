@@ -0,0 +1,628 @@
+use std::io::Write as _;
+
+/// Format which archived posts are exported into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// `<creator id>/<post id>/index.md` with media, usable as page bundles of Hugo
+    Markdown,
+    /// `<creator id>.epub` containing posts of the creator as chapters, oldest first
+    Epub,
+    /// `<creator id>/<post id>.cbz` containing images of the post in order
+    Cbz,
+    /// `<creator id>/<post id>.zip` containing all files of the post directory
+    Zip,
+    /// `<creator id>/<post id>.html`, the HTML page of the post with its images inlined as data
+    /// URIs so that it can be mailed or put into note apps alone
+    SingleFile,
+    /// `<creator id>/<post id>.mhtml` packaging the HTML page of the post with its images, which
+    /// browsers open as a web archive
+    Mhtml,
+    /// `<creator id>/<post id>.json` with blocks of the post in the display order and their
+    /// images, files and embeds resolved, for renderers written in other languages
+    Json,
+}
+
+/// Options of `export`.
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// Exports posts of this creator only
+    pub creator_id: Option<String>,
+    /// Public keys which every exported file is encrypted to, written as `<file name>.age`.
+    /// Archives of `Epub`, `Cbz` and `Zip` are built in memory before encrypted, and `Markdown`
+    /// can't be encrypted since it is exported into directories
+    #[cfg(feature = "encrypt")]
+    pub recipients: Option<std::sync::Arc<super::Recipients>>,
+}
+
+/// Exports archived posts under `dest_dir` into `out_dir` in `format` using their stored
+/// metadata and media, without network access.
+///
+/// Returns the number of written files.
+pub fn export(
+    dest_dir: &std::path::Path,
+    out_dir: &std::path::Path,
+    format: ExportFormat,
+    options: &ExportOptions,
+) -> Result<usize, super::Error> {
+    #[cfg(feature = "encrypt")]
+    if format == ExportFormat::Markdown && options.recipients.is_some() {
+        return Err(super::Error::Encrypt {
+            path: out_dir.to_path_buf(),
+            message: "Markdown is exported into directories, which can't be encrypted".to_owned(),
+        });
+    }
+    let creator_id = options.creator_id.as_deref();
+    let posts: Vec<_> = super::load_archived_posts(dest_dir)?
+        .into_iter()
+        .filter(|archived| creator_id.is_none_or(|id| archived.post.info.creator_id == id))
+        .collect();
+    let mut written = 0;
+    if format == ExportFormat::Epub {
+        let mut creators: Vec<(&str, Vec<&super::ArchivedPost>)> = Vec::new();
+        // Posts are loaded newest first
+        for archived in posts.iter().rev() {
+            let creator_id = archived.post.info.creator_id.as_str();
+            match creators.iter_mut().find(|(id, _)| *id == creator_id) {
+                Some((_, posts)) => posts.push(archived),
+                None => creators.push((creator_id, vec![archived])),
+            }
+        }
+        for (creator_id, posts) in creators {
+            create_dir_all(out_dir)?;
+            write_epub(
+                &out_dir.join(format!("{}.epub", creator_id)),
+                creator_id,
+                &posts,
+                options,
+            )?;
+            written += 1;
+        }
+        return Ok(written);
+    }
+
+    for archived in &posts {
+        let info = &archived.post.info;
+        let creator_dir = out_dir.join(&info.creator_id);
+        match format {
+            ExportFormat::Markdown => {
+                let post_dir = creator_dir.join(&info.id);
+                create_dir_all(&post_dir)?;
+                let path = post_dir.join("index.md");
+                let document = super::PostDocument::new(&archived.post);
+                let markdown = super::markdown::render(&document, &super::markdown::Links::Hugo);
+                std::fs::write(&path, markdown).map_err(|source| super::Error::Write {
+                    path: path.clone(),
+                    source,
+                })?;
+                super::obsidian::copy_attachments(
+                    &archived.dir,
+                    &archived.index_file_name(),
+                    &post_dir,
+                )?;
+            }
+            ExportFormat::Cbz => {
+                let images = post_images(archived);
+                if images.is_empty() {
+                    tracing::info!("Skip post {} without images", info.id);
+                    continue;
+                }
+                create_dir_all(&creator_dir)?;
+                let path = creator_dir.join(format!("{}.cbz", info.id));
+                let mut files: Vec<_> = images
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, (path, extension))| {
+                        (format!("{:03}.{}", i + 1, extension), Source::File(path))
+                    })
+                    .collect();
+                files.push((
+                    "ComicInfo.xml".to_owned(),
+                    Source::Bytes(render_comic_info(info).into_bytes()),
+                ));
+                write_zip(&path, files, options)?;
+            }
+            ExportFormat::Zip => {
+                create_dir_all(&creator_dir)?;
+                let path = creator_dir.join(format!("{}.zip", info.id));
+                let mut names = Vec::new();
+                let entries =
+                    std::fs::read_dir(&archived.dir).map_err(|source| super::Error::Read {
+                        path: archived.dir.clone(),
+                        source,
+                    })?;
+                for entry in entries {
+                    let entry = entry.map_err(|source| super::Error::Read {
+                        path: archived.dir.clone(),
+                        source,
+                    })?;
+                    if entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
+                        names.push(entry.file_name().to_string_lossy().into_owned());
+                    }
+                }
+                names.sort();
+                let files = names
+                    .iter()
+                    .map(|name| (name.clone(), Source::File(archived.dir.join(name))))
+                    .collect();
+                write_zip(&path, files, options)?;
+            }
+            ExportFormat::SingleFile | ExportFormat::Mhtml => {
+                let Some(html) = read_page(archived)? else {
+                    tracing::info!("Skip post {} without HTML page", info.id);
+                    continue;
+                };
+                create_dir_all(&creator_dir)?;
+                let (path, content) = if format == ExportFormat::SingleFile {
+                    let content =
+                        rewrite_image_sources(&html, &archived.dir, |_, path, media_type| {
+                            use base64::Engine as _;
+
+                            let bytes = read_file(path)?;
+                            Ok(format!(
+                                "data:{};base64,{}",
+                                media_type,
+                                base64::engine::general_purpose::STANDARD.encode(bytes)
+                            ))
+                        })?;
+                    (
+                        creator_dir.join(format!("{}.html", info.id)),
+                        content.into_bytes(),
+                    )
+                } else {
+                    (
+                        creator_dir.join(format!("{}.mhtml", info.id)),
+                        render_mhtml(archived, &html)?,
+                    )
+                };
+                write_file(&path, &content, options)?;
+            }
+            ExportFormat::Json => {
+                create_dir_all(&creator_dir)?;
+                let path = creator_dir.join(format!("{}.json", info.id));
+                let document = JsonDocument {
+                    dir: &archived.dir,
+                    document: super::PostDocument::new(&archived.post),
+                };
+                let json =
+                    serde_json::to_vec_pretty(&document).map_err(|e| super::Error::Write {
+                        path: path.clone(),
+                        source: e.into(),
+                    })?;
+                write_file(&path, &json, options)?;
+            }
+            ExportFormat::Epub => unreachable!(),
+        }
+        written += 1;
+    }
+    Ok(written)
+}
+
+/// Document of `ExportFormat::Json` with the post directory which paths of assets are relative to.
+#[derive(serde::Serialize)]
+struct JsonDocument<'a> {
+    dir: &'a std::path::Path,
+    #[serde(flatten)]
+    document: super::PostDocument,
+}
+
+/// Content of a file in a zip archive.
+enum Source {
+    File(std::path::PathBuf),
+    Bytes(Vec<u8>),
+}
+
+/// Writes `content` into `path`, or encrypted into `<path>.age` with `options.recipients`.
+fn write_file(
+    path: &std::path::Path,
+    content: &[u8],
+    options: &ExportOptions,
+) -> Result<(), super::Error> {
+    #[cfg(feature = "encrypt")]
+    if let Some(recipients) = &options.recipients {
+        let path = encrypted_path(path);
+        return recipients.encrypt_to(&path, |file| {
+            file.write_all(content)
+                .map_err(|source| super::Error::Write {
+                    path: path.clone(),
+                    source,
+                })
+        });
+    }
+    #[cfg(not(feature = "encrypt"))]
+    let _ = options;
+    std::fs::write(path, content).map_err(|source| super::Error::Write {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(feature = "encrypt")]
+fn encrypted_path(path: &std::path::Path) -> std::path::PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".age");
+    path.into()
+}
+
+/// Writes `files` into the zip archive at `path`, storing them without compression since media
+/// are already compressed.
+fn write_zip(
+    path: &std::path::Path,
+    files: Vec<(String, Source)>,
+    options: &ExportOptions,
+) -> Result<(), super::Error> {
+    #[cfg(feature = "encrypt")]
+    if options.recipients.is_some() {
+        // The zip writer seeks back to headers, which the encrypted stream can't
+        let zip = build_zip(path, std::io::Cursor::new(Vec::new()), files)?;
+        return write_file(path, zip.get_ref(), options);
+    }
+    #[cfg(not(feature = "encrypt"))]
+    let _ = options;
+    let file = std::fs::File::create(path).map_err(|source| super::Error::Write {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    build_zip(path, std::io::BufWriter::new(file), files)?
+        .flush()
+        .map_err(|source| super::Error::Write {
+            path: path.to_path_buf(),
+            source,
+        })
+}
+
+/// Writes `files` into `writer` as the zip archive for `path`, returning the writer.
+fn build_zip<W>(
+    path: &std::path::Path,
+    writer: W,
+    files: Vec<(String, Source)>,
+) -> Result<W, super::Error>
+where
+    W: std::io::Write + std::io::Seek,
+{
+    let map_err = |source| super::Error::Zip {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut zip = zip::ZipWriter::new(writer);
+    let options =
+        zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    for (name, source) in files {
+        zip.start_file(name, options).map_err(map_err)?;
+        match source {
+            Source::File(source_path) => {
+                let mut source_file =
+                    std::fs::File::open(&source_path).map_err(|source| super::Error::Read {
+                        path: source_path.clone(),
+                        source,
+                    })?;
+                std::io::copy(&mut source_file, &mut zip).map(|_| ())
+            }
+            Source::Bytes(bytes) => zip.write_all(&bytes),
+        }
+        .map_err(|source| super::Error::Write {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    }
+    zip.finish().map_err(map_err)
+}
+
+/// Reads the HTML page of the archived post, which doesn't exist in formats other than HTML.
+fn read_page(archived: &super::ArchivedPost) -> Result<Option<String>, super::Error> {
+    let path = archived.dir.join(archived.index_file_name());
+    match std::fs::read_to_string(&path) {
+        Ok(html) => Ok(Some(html)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(source) => Err(super::Error::Read { path, source }),
+    }
+}
+
+fn read_file(path: &std::path::Path) -> Result<Vec<u8>, super::Error> {
+    std::fs::read(path).map_err(|source| super::Error::Read {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Replaces `./<name>` of `src='./<name>'` referring to images in the post directory `dir` with
+/// the one returned by `rewrite` given the name, path and media type of the image. Links to other
+/// files are kept as is.
+fn rewrite_image_sources<F>(
+    html: &str,
+    dir: &std::path::Path,
+    mut rewrite: F,
+) -> Result<String, super::Error>
+where
+    F: FnMut(&str, &std::path::Path, &'static str) -> Result<String, super::Error>,
+{
+    let mut rewritten = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find("src='./") {
+        let (before, after) = rest.split_at(start + "src='".len());
+        rewritten.push_str(before);
+        let Some(end) = after.find('\'') else {
+            rest = after;
+            break;
+        };
+        let (reference, after) = after.split_at(end);
+        rest = after;
+        let name = &reference["./".len()..];
+        let extension = name.rsplit_once('.').map_or("", |(_, extension)| extension);
+        let path = dir.join(name);
+        match image_media_type(extension) {
+            Some(media_type) if !name.contains('/') && path.is_file() => {
+                rewritten.push_str(&rewrite(name, &path, media_type)?);
+            }
+            _ => rewritten.push_str(reference),
+        }
+    }
+    rewritten.push_str(rest);
+    Ok(rewritten)
+}
+
+/// Renders the page of the post and its images as a MHTML document, which is opened by browsers
+/// with all images from the one file.
+fn render_mhtml(archived: &super::ArchivedPost, html: &str) -> Result<Vec<u8>, super::Error> {
+    use base64::Engine as _;
+
+    let info = &archived.post.info;
+    // Relative references in the page are resolved against its location
+    let base_url = format!("{}/", super::post_url(info));
+    let boundary = format!("----=_fanbox-dl_{}", info.id);
+    let mut images = Vec::new();
+    rewrite_image_sources(html, &archived.dir, |name, path, media_type| {
+        if !images.iter().any(|(n, _, _)| n == name) {
+            images.push((name.to_owned(), path.to_path_buf(), media_type));
+        }
+        Ok(format!("./{}", name))
+    })?;
+
+    let mut lines = vec![
+        "From: <Saved by fanbox-dl>".to_owned(),
+        format!("Snapshot-Content-Location: {}", super::post_url(info)),
+        format!("Subject: {}", encode_header(&info.title)),
+        format!("Date: {}", info.updated_datetime.to_rfc2822()),
+        "MIME-Version: 1.0".to_owned(),
+        format!(
+            "Content-Type: multipart/related; type=\"text/html\"; boundary=\"{}\"",
+            boundary
+        ),
+        String::new(),
+    ];
+    let mut parts = vec![(
+        "text/html; charset=utf-8".to_owned(),
+        format!("{}index.html", base_url),
+        html.as_bytes().to_vec(),
+    )];
+    for (name, path, media_type) in images {
+        parts.push((
+            media_type.to_owned(),
+            format!("{}{}", base_url, name),
+            read_file(&path)?,
+        ));
+    }
+    for (content_type, location, bytes) in parts {
+        lines.push(format!("--{}", boundary));
+        lines.push(format!("Content-Type: {}", content_type));
+        lines.push("Content-Transfer-Encoding: base64".to_owned());
+        lines.push(format!("Content-Location: {}", location));
+        lines.push(String::new());
+        let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+        // Lines of MIME bodies are limited to 76 characters
+        lines.extend(
+            encoded
+                .as_bytes()
+                .chunks(76)
+                .map(|chunk| String::from_utf8_lossy(chunk).into_owned()),
+        );
+        lines.push(String::new());
+    }
+    lines.push(format!("--{}--", boundary));
+    lines.push(String::new());
+    Ok(lines.join("\r\n").into_bytes())
+}
+
+/// Encodes a header value as a MIME encoded-word unless it's printable ASCII.
+fn encode_header(value: &str) -> String {
+    use base64::Engine as _;
+
+    if value.bytes().all(|b| (b' '..=b'~').contains(&b)) {
+        value.to_owned()
+    } else {
+        format!(
+            "=?utf-8?B?{}?=",
+            base64::engine::general_purpose::STANDARD.encode(value)
+        )
+    }
+}
+
+/// Returns downloaded images of the post in the post order with their extensions.
+fn post_images(archived: &super::ArchivedPost) -> Vec<(std::path::PathBuf, String)> {
+    super::catalog::media_files(&archived.post)
+        .into_iter()
+        .filter(|media_file| image_media_type(media_file.extension).is_some())
+        .map(|media_file| {
+            (
+                archived.dir.join(&media_file.file_name),
+                media_file.extension.to_ascii_lowercase(),
+            )
+        })
+        .filter(|(path, _)| path.is_file())
+        .collect()
+}
+
+fn image_media_type(extension: &str) -> Option<&'static str> {
+    match extension.to_ascii_lowercase().as_str() {
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "png" => Some("image/png"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Renders ComicInfo.xml read by comic readers for metadata of the CBZ.
+fn render_comic_info(info: &fanbox_api::PostInfo) -> String {
+    let creator = info
+        .user
+        .as_ref()
+        .map_or(info.creator_id.as_str(), |user| user.name.as_str());
+    let date = info.published_datetime;
+    [
+        "<?xml version='1.0' encoding='utf-8'?>".to_owned(),
+        "<ComicInfo>".to_owned(),
+        format!("<Title>{}</Title>", super::feed::escape_xml(&info.title)),
+        format!("<Writer>{}</Writer>", super::feed::escape_xml(creator)),
+        format!("<Year>{}</Year>", date.format("%Y")),
+        format!("<Month>{}</Month>", date.format("%-m")),
+        format!("<Day>{}</Day>", date.format("%-d")),
+        format!(
+            "<Web>{}</Web>",
+            super::feed::escape_xml(&super::post_url(info))
+        ),
+        format!(
+            "<Tags>{}</Tags>",
+            super::feed::escape_xml(&info.tags.join(","))
+        ),
+        "</ComicInfo>".to_owned(),
+    ]
+    .join("\n")
+}
+
+const CONTAINER_XML: &str = "<?xml version='1.0' encoding='utf-8'?>
+<container version='1.0' xmlns='urn:oasis:names:tc:opendocument:xmlns:container'>
+<rootfiles>
+<rootfile full-path='OEBPS/content.opf' media-type='application/oebps-package+xml'/>
+</rootfiles>
+</container>";
+
+/// Writes an EPUB 3 book containing `posts` of the creator as chapters in the given order.
+fn write_epub(
+    path: &std::path::Path,
+    creator_id: &str,
+    posts: &[&super::ArchivedPost],
+    options: &ExportOptions,
+) -> Result<(), super::Error> {
+    let creator = posts
+        .iter()
+        .find_map(|archived| archived.post.info.user.as_ref())
+        .map_or(creator_id, |user| user.name.as_str());
+    let modified = posts
+        .iter()
+        .map(|archived| archived.post.info.updated_datetime)
+        .max()
+        .unwrap_or_else(chrono::Utc::now);
+
+    // The mimetype must be the first entry
+    let mut files = vec![
+        (
+            "mimetype".to_owned(),
+            Source::Bytes(b"application/epub+zip".to_vec()),
+        ),
+        (
+            "META-INF/container.xml".to_owned(),
+            Source::Bytes(CONTAINER_XML.as_bytes().to_vec()),
+        ),
+    ];
+    let mut manifest = vec![
+        "<item id='nav' href='nav.xhtml' media-type='application/xhtml+xml' properties='nav'/>"
+            .to_owned(),
+    ];
+    let mut spine = Vec::new();
+    let mut toc = Vec::new();
+    for archived in posts {
+        let info = &archived.post.info;
+        let chapter = format!("posts/{}.xhtml", info.id);
+        let mut body = vec![format!("<h1>{}</h1>", super::feed::escape_xml(&info.title))];
+        body.extend(
+            super::post_text(&archived.post)
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(|line| format!("<p>{}</p>", super::feed::escape_xml(line))),
+        );
+        for (i, (image_path, extension)) in post_images(archived).into_iter().enumerate() {
+            let href = format!("images/{}/{:03}.{}", info.id, i + 1, extension);
+            manifest.push(format!(
+                "<item id='image-{}-{}' href='{}' media-type='{}'/>",
+                info.id,
+                i + 1,
+                href,
+                image_media_type(&extension).unwrap_or("image/jpeg")
+            ));
+            body.push(format!("<p><img alt='' src='../{}'/></p>", href));
+            files.push((format!("OEBPS/{}", href), Source::File(image_path)));
+        }
+        files.push((
+            format!("OEBPS/{}", chapter),
+            Source::Bytes(render_xhtml(&info.title, &body).into_bytes()),
+        ));
+        manifest.push(format!(
+            "<item id='post-{}' href='{}' media-type='application/xhtml+xml'/>",
+            info.id, chapter
+        ));
+        spine.push(format!("<itemref idref='post-{}'/>", info.id));
+        toc.push(format!(
+            "<li><a href='{}'>{}</a></li>",
+            chapter,
+            super::feed::escape_xml(&info.title)
+        ));
+    }
+
+    let mut nav = vec!["<nav epub:type='toc'>".to_owned(), "<ol>".to_owned()];
+    nav.extend(toc);
+    nav.push("</ol>".to_owned());
+    nav.push("</nav>".to_owned());
+    files.push((
+        "OEBPS/nav.xhtml".to_owned(),
+        Source::Bytes(render_xhtml(creator, &nav).into_bytes()),
+    ));
+    let opf = [
+        "<?xml version='1.0' encoding='utf-8'?>".to_owned(),
+        "<package xmlns='http://www.idpf.org/2007/opf' version='3.0' unique-identifier='book-id'>"
+            .to_owned(),
+        "<metadata xmlns:dc='http://purl.org/dc/elements/1.1/'>".to_owned(),
+        format!(
+            "<dc:identifier id='book-id'>https://{}.fanbox.cc/</dc:identifier>",
+            super::feed::escape_xml(creator_id)
+        ),
+        format!("<dc:title>{}</dc:title>", super::feed::escape_xml(creator)),
+        format!(
+            "<dc:creator>{}</dc:creator>",
+            super::feed::escape_xml(creator)
+        ),
+        "<dc:language>ja</dc:language>".to_owned(),
+        format!(
+            "<meta property='dcterms:modified'>{}</meta>",
+            modified.format("%Y-%m-%dT%H:%M:%SZ")
+        ),
+        "</metadata>".to_owned(),
+        "<manifest>".to_owned(),
+        manifest.join("\n"),
+        "</manifest>".to_owned(),
+        "<spine>".to_owned(),
+        spine.join("\n"),
+        "</spine>".to_owned(),
+        "</package>".to_owned(),
+    ]
+    .join("\n");
+    files.push((
+        "OEBPS/content.opf".to_owned(),
+        Source::Bytes(opf.into_bytes()),
+    ));
+    write_zip(path, files, options)
+}
+
+fn render_xhtml(title: &str, body: &[String]) -> String {
+    format!(
+        "<?xml version='1.0' encoding='utf-8'?>\n<!DOCTYPE html>\n<html xmlns='http://www.w3.org/1999/xhtml' xmlns:epub='http://www.idpf.org/2007/ops'>\n<head>\n<title>{}</title>\n</head>\n<body>\n{}\n</body>\n</html>",
+        super::feed::escape_xml(title),
+        body.join("\n")
+    )
+}
+
+fn create_dir_all(path: &std::path::Path) -> Result<(), super::Error> {
+    std::fs::create_dir_all(path).map_err(|source| super::Error::CreateDir {
+        path: path.to_path_buf(),
+        source,
+    })
+}
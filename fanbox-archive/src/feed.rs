@@ -0,0 +1,99 @@
+/// Maximum number of entries in a feed
+const MAX_ENTRIES: usize = 50;
+
+/// Writes an Atom feed of the creator's archived posts to `dest_dir/feeds/<creator id>.atom`.
+///
+/// Entries link to the local HTML pages under `base_url`, which defaults to the `file://` URL of
+/// `dest_dir`.
+pub fn write_atom_feed(
+    dest_dir: &std::path::Path,
+    creator_id: &str,
+    base_url: Option<&str>,
+) -> Result<std::path::PathBuf, super::Error> {
+    let base_url = match base_url {
+        Some(base_url) => base_url.trim_end_matches('/').to_owned(),
+        None => {
+            let dest_dir = dest_dir
+                .canonicalize()
+                .map_err(|source| super::Error::Read {
+                    path: dest_dir.to_path_buf(),
+                    source,
+                })?;
+            format!("file://{}", dest_dir.display())
+        }
+    };
+    let posts: Vec<_> = super::load_archived_posts(dest_dir)?
+        .into_iter()
+        .filter(|archived| archived.post.info.creator_id == creator_id)
+        .take(MAX_ENTRIES)
+        .collect();
+
+    let mut lines = vec![
+        "<?xml version='1.0' encoding='utf-8'?>".to_owned(),
+        "<feed xmlns='http://www.w3.org/2005/Atom'>".to_owned(),
+        format!("<title>{}</title>", escape_xml(creator_id)),
+        format!("<id>https://{}.fanbox.cc/</id>", escape_xml(creator_id)),
+        format!(
+            "<link href='https://{}.fanbox.cc/'/>",
+            escape_xml(creator_id)
+        ),
+    ];
+    if let Some(latest) = posts.first() {
+        lines.push(format!(
+            "<updated>{}</updated>",
+            latest.post.info.updated_datetime.to_rfc3339()
+        ));
+    }
+    for archived in &posts {
+        let info = &archived.post.info;
+        let relative_dir = archived
+            .dir
+            .strip_prefix(dest_dir)
+            .unwrap_or(&archived.dir)
+            .iter()
+            .map(|component| component.to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("/");
+        let summary: String = super::post_text(&archived.post).chars().take(200).collect();
+        lines.push("<entry>".to_owned());
+        lines.push(format!("<title>{}</title>", escape_xml(&info.title)));
+        lines.push(format!(
+            "<id>https://{}.fanbox.cc/posts/{}</id>",
+            escape_xml(&info.creator_id),
+            escape_xml(&info.id)
+        ));
+        lines.push(format!(
+            "<link href='{}/{}/{}'/>",
+            escape_xml(&base_url),
+            escape_xml(&relative_dir),
+            escape_xml(&archived.index_file_name())
+        ));
+        lines.push(format!(
+            "<updated>{}</updated>",
+            info.updated_datetime.to_rfc3339()
+        ));
+        lines.push(format!("<summary>{}</summary>", escape_xml(&summary)));
+        lines.push("</entry>".to_owned());
+    }
+    lines.push("</feed>".to_owned());
+
+    let feeds_dir = dest_dir.join("feeds");
+    std::fs::create_dir_all(&feeds_dir).map_err(|source| super::Error::CreateDir {
+        path: feeds_dir.clone(),
+        source,
+    })?;
+    let path = feeds_dir.join(format!("{}.atom", creator_id));
+    std::fs::write(&path, lines.join("\n")).map_err(|source| super::Error::Write {
+        path: path.clone(),
+        source,
+    })?;
+    Ok(path)
+}
+
+pub(crate) fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\'', "&apos;")
+        .replace('"', "&quot;")
+}
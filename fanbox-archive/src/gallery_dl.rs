@@ -0,0 +1,100 @@
+/// Downloads the post's media into `dest_dir/fanbox/<creator id>/` with the file names and JSON
+/// metadata sidecars of gallery-dl's fanbox extractor.
+///
+/// The cover image is numbered 0 and the images and files in the post body are numbered from 1,
+/// i.e. `<post id>_<num>.<extension>` with `<post id>_<num>.<extension>.json`.
+#[tracing::instrument(name = "gallery-dl", skip_all, fields(id = %post.info.id))]
+pub async fn archive_post(
+    client: &fanbox_api::PostClient,
+    dest_dir: &std::path::Path,
+    post: &fanbox_api::Post,
+    options: &super::ArchiveOptions,
+) -> Result<(), super::Error> {
+    let info = &post.info;
+    let dest_dir = dest_dir.join("fanbox").join(&info.creator_id);
+    std::fs::create_dir_all(&dest_dir).map_err(|source| super::Error::CreateDir {
+        path: dest_dir.clone(),
+        source,
+    })?;
+
+    let mut files = Vec::new();
+    if let Some(cover_image_url) = &info.cover_image_url {
+        let extension = cover_image_url
+            .rsplit('/')
+            .next()
+            .and_then(|name| name.rsplit_once('.'))
+            .map_or("jpeg", |(_, extension)| extension);
+        files.push((0, cover_image_url.as_str(), extension, true));
+    }
+    for (i, media_file) in super::catalog::media_files(post).into_iter().enumerate() {
+        files.push((i + 1, media_file.url, media_file.extension, false));
+    }
+
+    let mut base = match serde_json::to_value(info) {
+        Ok(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    base.insert("category".to_owned(), "fanbox".into());
+    base.insert("subcategory".to_owned(), "post".into());
+    base.insert("text".to_owned(), super::post_text(post).into());
+    base.insert(
+        "date".to_owned(),
+        info.published_datetime
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+            .into(),
+    );
+
+    for (num, url, extension, is_cover_image) in files {
+        let filename = format!("{}_{}", info.id, num);
+        let path = match super::conflict::resolve(
+            &dest_dir.join(format!("{}.{}", filename, extension)),
+            options.on_conflict,
+        )? {
+            Some(path) => path,
+            None => continue,
+        };
+        tracing::info!("Download {}", url);
+        let started = std::time::Instant::now();
+        let downloaded = client
+            .download_media_to(
+                url,
+                &path,
+                &info.updated_datetime,
+                Some(&super::post_url(info)),
+            )
+            .await
+            .map_err(|source| super::Error::Download {
+                url: url.to_owned(),
+                source,
+            })?;
+        #[cfg(feature = "convert-images")]
+        if options.convert_images.is_some() {
+            super::convert::convert_file(&path, &info.updated_datetime, options.fsync)?;
+        }
+        super::log_download(options, info, url, &path, downloaded, started)?;
+        if let Some(events) = &options.events {
+            events.send(super::Event::FileDownloaded {
+                post_id: info.id.clone(),
+                path: path.clone(),
+                bytes: std::fs::metadata(&path).map_or(0, |metadata| metadata.len()),
+            });
+        }
+
+        let mut metadata = base.clone();
+        metadata.insert("num".to_owned(), num.into());
+        metadata.insert("fileUrl".to_owned(), url.into());
+        metadata.insert("filename".to_owned(), filename.clone().into());
+        metadata.insert("extension".to_owned(), extension.into());
+        metadata.insert("isCoverImage".to_owned(), is_cover_image.into());
+        let mut metadata_path = path.clone().into_os_string();
+        metadata_path.push(".json");
+        let metadata_path = std::path::PathBuf::from(metadata_path);
+        let json = serde_json::to_vec_pretty(&metadata).map_err(|e| super::Error::Write {
+            path: metadata_path.clone(),
+            source: e.into(),
+        })?;
+        super::write_file(&metadata_path, &json, &info.updated_datetime).await?;
+    }
+    Ok(())
+}
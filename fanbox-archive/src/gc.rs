@@ -0,0 +1,308 @@
+/// Extension of temporary files written during downloads
+pub const TEMPORARY_EXTENSION: &str = "part";
+
+/// Files with the same content.
+#[derive(Debug, Clone)]
+pub struct Duplicates {
+    pub size: u64,
+    /// Paths of the files, the first of which is kept when compacting
+    pub paths: Vec<std::path::PathBuf>,
+}
+
+/// Returns temporary files under `dest_dir` which haven't been modified for `min_age`.
+pub fn find_stale_temporaries(
+    dest_dir: &std::path::Path,
+    min_age: std::time::Duration,
+) -> Result<Vec<std::path::PathBuf>, super::Error> {
+    let now = std::time::SystemTime::now();
+    let mut paths = Vec::new();
+    for (path, metadata) in list_files(dest_dir)? {
+        if !is_temporary(&path) {
+            continue;
+        }
+        let modified = metadata.modified().map_err(|source| super::Error::Read {
+            path: path.clone(),
+            source,
+        })?;
+        if now.duration_since(modified).unwrap_or_default() >= min_age {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Returns groups of files under `dest_dir` with the same content, largest first.
+///
+/// Files which are already hard links of each other are counted once.
+pub fn find_duplicates(dest_dir: &std::path::Path) -> Result<Vec<Duplicates>, super::Error> {
+    let mut by_size: std::collections::HashMap<u64, Vec<std::path::PathBuf>> =
+        std::collections::HashMap::new();
+    let mut seen_inodes = std::collections::HashSet::new();
+    for (path, metadata) in list_files(dest_dir)? {
+        if metadata.len() == 0 || is_temporary(&path) {
+            continue;
+        }
+        if let Some(inode) = inode(&metadata) {
+            if !seen_inodes.insert(inode) {
+                continue;
+            }
+        }
+        by_size.entry(metadata.len()).or_default().push(path);
+    }
+
+    let mut groups = Vec::new();
+    for (size, paths) in by_size {
+        if paths.len() < 2 {
+            continue;
+        }
+        let mut by_hash: std::collections::HashMap<u64, Vec<std::path::PathBuf>> =
+            std::collections::HashMap::new();
+        for path in paths {
+            by_hash.entry(hash_file(&path)?).or_default().push(path);
+        }
+        for (_, mut paths) in by_hash {
+            if paths.len() < 2 {
+                continue;
+            }
+            paths.sort();
+            // Hash collisions are unlikely but compare contents before reporting
+            let mut same = vec![paths[0].clone()];
+            for path in &paths[1..] {
+                if same_content(&paths[0], path)? {
+                    same.push(path.clone());
+                }
+            }
+            if same.len() >= 2 {
+                groups.push(Duplicates { size, paths: same });
+            }
+        }
+    }
+    groups.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.paths.cmp(&b.paths)));
+    Ok(groups)
+}
+
+/// Replaces the duplicated files with hard links to the first file.
+pub fn link_duplicates(duplicates: &Duplicates) -> Result<(), super::Error> {
+    let (original, rest) = match duplicates.paths.split_first() {
+        Some(split) => split,
+        None => return Ok(()),
+    };
+    for path in rest {
+        hard_link(original, path)?;
+    }
+    Ok(())
+}
+
+/// Replaces the cover image at `cover_path` with a hard link to an identical file, which is
+/// `candidate` (typically the first image of the post) or another cover image recorded in the
+/// state database of `dest_dir`.
+#[cfg_attr(not(feature = "state-db"), allow(unused_variables))]
+pub(crate) fn link_duplicate_cover(
+    dest_dir: &std::path::Path,
+    cover_path: &std::path::Path,
+    candidate: Option<&std::path::Path>,
+) -> Result<(), super::Error> {
+    let metadata = match std::fs::metadata(cover_path) {
+        Ok(metadata) if metadata.is_file() => metadata,
+        _ => return Ok(()),
+    };
+    let linked = match candidate {
+        Some(candidate) => link_if_same(cover_path, &metadata, candidate)?,
+        None => false,
+    };
+    #[cfg(feature = "state-db")]
+    {
+        let db = super::StateDb::open(dest_dir)?;
+        let hash = hash_file(cover_path)?;
+        if !linked {
+            for path in db.find_cover_images(metadata.len(), hash)? {
+                let path = dest_dir.join(path);
+                if path != cover_path && link_if_same(cover_path, &metadata, &path)? {
+                    break;
+                }
+            }
+        }
+        if let Ok(relative_path) = cover_path.strip_prefix(dest_dir) {
+            db.record_cover_image(relative_path, metadata.len(), hash)?;
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the downloaded media file at `path` with a hard link to an identical file recorded in
+/// the state database of `index_dir`, which indexes media of all creator directories, and records
+/// it there.
+///
+/// Identical files on another file system are kept as is.
+#[cfg(feature = "state-db")]
+pub(crate) fn link_duplicate_media(
+    index_dir: &std::path::Path,
+    path: &std::path::Path,
+) -> Result<(), super::Error> {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() && metadata.len() > 0 => metadata,
+        _ => return Ok(()),
+    };
+    let path = std::path::absolute(path).map_err(|source| super::Error::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let db = super::StateDb::open(index_dir)?;
+    let hash = hash_file(&path)?;
+    for original in db.find_media_files(metadata.len(), hash)? {
+        if original == path {
+            continue;
+        }
+        match link_if_same(&path, &metadata, &original) {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(e) => {
+                tracing::debug!("Keep {}: {}", path.display(), e);
+                break;
+            }
+        }
+    }
+    db.record_media_file(&path, metadata.len(), hash)?;
+    Ok(())
+}
+
+/// Replaces `path` with a hard link to `original` if their contents are the same.
+fn link_if_same(
+    path: &std::path::Path,
+    metadata: &std::fs::Metadata,
+    original: &std::path::Path,
+) -> Result<bool, super::Error> {
+    let original_metadata = match std::fs::metadata(original) {
+        Ok(original_metadata) if original_metadata.is_file() => original_metadata,
+        _ => return Ok(false),
+    };
+    if original_metadata.len() != metadata.len() {
+        return Ok(false);
+    }
+    if inode(metadata).is_some() && inode(metadata) == inode(&original_metadata) {
+        return Ok(true);
+    }
+    if !same_content(original, path)? {
+        return Ok(false);
+    }
+    tracing::info!("Link {} to {}", path.display(), original.display());
+    hard_link(original, path)?;
+    Ok(true)
+}
+
+/// Atomically replaces `path` with a hard link to `original`.
+fn hard_link(original: &std::path::Path, path: &std::path::Path) -> Result<(), super::Error> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".gc");
+    let tmp_path = std::path::PathBuf::from(tmp_path);
+    std::fs::hard_link(original, &tmp_path).map_err(|source| super::Error::Write {
+        path: tmp_path.clone(),
+        source,
+    })?;
+    std::fs::rename(&tmp_path, path).map_err(|source| super::Error::Write {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(())
+}
+
+pub(crate) fn is_temporary(path: &std::path::Path) -> bool {
+    path.extension() == Some(TEMPORARY_EXTENSION.as_ref())
+}
+
+pub(crate) fn list_files(
+    dir: &std::path::Path,
+) -> Result<Vec<(std::path::PathBuf, std::fs::Metadata)>, super::Error> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(current) = dirs.pop() {
+        let entries = std::fs::read_dir(&current).map_err(|source| super::Error::Read {
+            path: current.clone(),
+            source,
+        })?;
+        for entry in entries {
+            let entry = entry.map_err(|source| super::Error::Read {
+                path: current.clone(),
+                source,
+            })?;
+            let path = entry.path();
+            let metadata = entry.metadata().map_err(|source| super::Error::Read {
+                path: path.clone(),
+                source,
+            })?;
+            if metadata.is_dir() {
+                dirs.push(path);
+            } else if metadata.is_file() {
+                files.push((path, metadata));
+            }
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(unix)]
+fn inode(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt as _;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+fn hash_file(path: &std::path::Path) -> Result<u64, super::Error> {
+    use std::hash::Hasher as _;
+    use std::io::Read as _;
+
+    let map_err = |source| super::Error::Read {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut file = std::fs::File::open(path).map_err(map_err)?;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).map_err(map_err)?;
+        if n == 0 {
+            break;
+        }
+        hasher.write(&buf[..n]);
+    }
+    Ok(hasher.finish())
+}
+
+fn same_content(a: &std::path::Path, b: &std::path::Path) -> Result<bool, super::Error> {
+    use std::io::Read as _;
+
+    let open = |path: &std::path::Path| {
+        std::fs::File::open(path)
+            .map(std::io::BufReader::new)
+            .map_err(|source| super::Error::Read {
+                path: path.to_path_buf(),
+                source,
+            })
+    };
+    let mut a_bytes = open(a)?.bytes();
+    let mut b_bytes = open(b)?.bytes();
+    loop {
+        match (a_bytes.next(), b_bytes.next()) {
+            (None, None) => return Ok(true),
+            (Some(Ok(x)), Some(Ok(y))) if x == y => {}
+            (Some(Err(source)), _) => {
+                return Err(super::Error::Read {
+                    path: a.to_path_buf(),
+                    source,
+                })
+            }
+            (_, Some(Err(source))) => {
+                return Err(super::Error::Read {
+                    path: b.to_path_buf(),
+                    source,
+                })
+            }
+            _ => return Ok(false),
+        }
+    }
+}
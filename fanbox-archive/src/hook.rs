@@ -0,0 +1,79 @@
+/// Hook into archiving of posts, added to `ArchiveOptions::hooks`.
+///
+/// Every method is optional and keeps the default behavior unless implemented.
+pub trait Hook: std::fmt::Debug + Send + Sync {
+    /// Returns whether the post should be archived, which is checked before anything of the post
+    /// is written.
+    fn should_archive(&self, document: &super::PostDocument) -> Result<bool, super::Error> {
+        let _ = document;
+        Ok(true)
+    }
+
+    /// Transforms the document of the post before its images and files are downloaded and it's
+    /// rendered.
+    fn transform_document(
+        &self,
+        document: super::PostDocument,
+    ) -> Result<super::PostDocument, super::Error> {
+        Ok(document)
+    }
+
+    /// Returns whether the image or file of the post should be downloaded. Blocks of skipped
+    /// assets are left out of the page.
+    fn should_download(
+        &self,
+        document: &super::PostDocument,
+        asset: &super::Asset,
+    ) -> Result<bool, super::Error> {
+        let _ = (document, asset);
+        Ok(true)
+    }
+
+    /// Decides what to do with the image or file of the post which passed `should_download` of
+    /// every hook. The first decision other than `Decision::Download` among hooks is taken.
+    fn decide_file(&self, candidate: &FileCandidate<'_>) -> Result<Decision, super::Error> {
+        let _ = candidate;
+        Ok(Decision::Download)
+    }
+}
+
+/// Image or file of a post about to be downloaded.
+#[derive(Debug, Clone, Copy)]
+pub struct FileCandidate<'a> {
+    pub document: &'a super::PostDocument,
+    pub asset: &'a super::Asset,
+    /// Path where the file is downloaded to
+    pub path: &'a std::path::Path,
+}
+
+/// What to do with a `FileCandidate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// Download the file to `FileCandidate::path`
+    Download,
+    /// Leave the file and its block out of the post
+    Skip,
+    /// Download the file to this name in the post directory instead
+    Rename(String),
+    /// Hard-link or copy this existing file instead of downloading it, e.g. when a file with the
+    /// same content is already archived elsewhere
+    Redirect(std::path::PathBuf),
+}
+
+/// Hook calling a closure given by `Archiver::filter_files`.
+pub(crate) struct FileFilter<F>(pub(crate) F);
+
+impl<F> std::fmt::Debug for FileFilter<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("FileFilter").finish_non_exhaustive()
+    }
+}
+
+impl<F> Hook for FileFilter<F>
+where
+    F: Fn(&FileCandidate<'_>) -> Decision + Send + Sync,
+{
+    fn decide_file(&self, candidate: &FileCandidate<'_>) -> Result<Decision, super::Error> {
+        Ok((self.0)(candidate))
+    }
+}
@@ -0,0 +1,71 @@
+/// Language of labels in generated pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Lang {
+    #[default]
+    En,
+    Ja,
+}
+
+/// Label in generated pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Label {
+    /// Prefix of the published datetime
+    Published,
+    /// Prefix of the updated datetime
+    Updated,
+    /// Heading of the list of creators
+    Creators,
+    /// Search button and heading of search results
+    Search,
+    /// Link to the previous post
+    Previous,
+    /// Link to the next post
+    Next,
+    /// Heading of the table of contents
+    Contents,
+}
+
+impl Lang {
+    /// Returns the BCP 47 language tag used for the lang attribute.
+    pub fn tag(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Ja => "ja",
+        }
+    }
+
+    pub fn label(self, label: Label) -> &'static str {
+        match (self, label) {
+            (Self::En, Label::Published) => "published",
+            (Self::En, Label::Updated) => "updated",
+            (Self::En, Label::Creators) => "Creators",
+            (Self::En, Label::Search) => "Search",
+            (Self::En, Label::Previous) => "Previous",
+            (Self::En, Label::Next) => "Next",
+            (Self::En, Label::Contents) => "Contents",
+            (Self::Ja, Label::Published) => "公開",
+            (Self::Ja, Label::Updated) => "更新",
+            (Self::Ja, Label::Creators) => "クリエイター",
+            (Self::Ja, Label::Search) => "検索",
+            (Self::Ja, Label::Previous) => "前の投稿",
+            (Self::Ja, Label::Next) => "次の投稿",
+            (Self::Ja, Label::Contents) => "目次",
+        }
+    }
+
+    /// Returns the number of posts with its unit.
+    pub fn post_count(self, count: usize) -> String {
+        match self {
+            Self::En => format!("{} posts", count),
+            Self::Ja => format!("{}件", count),
+        }
+    }
+
+    /// Returns the name of the plan requiring `fee` JPY.
+    pub fn fee_plan(self, fee: u32) -> String {
+        match self {
+            Self::En => format!("{} JPY plan", fee),
+            Self::Ja => format!("{}円プラン", fee),
+        }
+    }
+}
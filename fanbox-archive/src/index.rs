@@ -0,0 +1,125 @@
+/// Post matched by `SearchIndex::search`.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub post_id: String,
+    pub creator_id: String,
+    pub title: String,
+    pub updated_datetime: chrono::DateTime<chrono::Utc>,
+    /// Directory containing the post's index.html and media
+    pub dir: std::path::PathBuf,
+}
+
+/// Full-text search index of archived posts, stored in the state database.
+#[derive(Debug)]
+pub struct SearchIndex {
+    dest_dir: std::path::PathBuf,
+    path: std::path::PathBuf,
+    conn: rusqlite::Connection,
+}
+
+impl SearchIndex {
+    /// Opens the state database in `dest_dir`, creating it if missing.
+    pub fn open(dest_dir: &std::path::Path) -> Result<Self, super::Error> {
+        let (path, conn) = super::state::open_connection(dest_dir)?;
+        // The trigram tokenizer matches substrings, which also works for CJK text without spaces
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS post_texts USING fts5(
+                post_id UNINDEXED,
+                creator_id UNINDEXED,
+                updated_datetime UNINDEXED,
+                dir UNINDEXED,
+                title,
+                body,
+                tokenize = 'trigram'
+            )",
+        )
+        .map_err(|source| super::Error::StateDb {
+            path: path.clone(),
+            source,
+        })?;
+        Ok(Self {
+            dest_dir: dest_dir.to_path_buf(),
+            path,
+            conn,
+        })
+    }
+
+    /// Replaces the indexed text of the post stored in `post_dir`.
+    pub fn index_post(
+        &self,
+        post_dir: &std::path::Path,
+        post: &fanbox_api::Post,
+    ) -> Result<(), super::Error> {
+        let info = &post.info;
+        let dir = post_dir
+            .strip_prefix(&self.dest_dir)
+            .unwrap_or(post_dir)
+            .to_string_lossy();
+        self.conn
+            .execute("DELETE FROM post_texts WHERE post_id = ?1", [&info.id])
+            .and_then(|_| {
+                self.conn.execute(
+                    "INSERT INTO post_texts (post_id, creator_id, updated_datetime, dir, title, body) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    [
+                        info.id.as_str(),
+                        info.creator_id.as_str(),
+                        &info.updated_datetime.to_rfc3339(),
+                        &dir,
+                        &info.title,
+                        &super::post_text(post),
+                    ],
+                )
+            })
+            .map_err(|source| super::Error::StateDb {
+                path: self.path.clone(),
+                source,
+            })?;
+        Ok(())
+    }
+
+    /// Returns posts whose title or text contains `query`, newest first.
+    pub fn search(&self, query: &str) -> Result<Vec<SearchHit>, super::Error> {
+        let pattern = format!(
+            "%{}%",
+            query
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        let map_err = |source| super::Error::StateDb {
+            path: self.path.clone(),
+            source,
+        };
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT post_id, creator_id, title, updated_datetime, dir FROM post_texts
+                WHERE title LIKE ?1 ESCAPE '\\' OR body LIKE ?1 ESCAPE '\\'
+                ORDER BY updated_datetime DESC",
+            )
+            .map_err(map_err)?;
+        let hits = stmt
+            .query_map([&pattern], |row| {
+                let updated_datetime: String = row.get(3)?;
+                let updated_datetime = chrono::DateTime::parse_from_rfc3339(&updated_datetime)
+                    .map_err(|e| {
+                        rusqlite::Error::FromSqlConversionFailure(
+                            3,
+                            rusqlite::types::Type::Text,
+                            Box::new(e),
+                        )
+                    })?;
+                let dir: String = row.get(4)?;
+                Ok(SearchHit {
+                    post_id: row.get(0)?,
+                    creator_id: row.get(1)?,
+                    title: row.get(2)?,
+                    updated_datetime: updated_datetime.with_timezone(&chrono::Utc),
+                    dir: self.dest_dir.join(dir),
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(map_err)?;
+        Ok(hits)
+    }
+}
@@ -0,0 +1,183 @@
+/// Name of the run journal in the destination directory
+pub const JOURNAL_FILE_NAME: &str = ".fanbox-dl-journal.jsonl";
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum Entry {
+    /// The post of the creator was listed and is waiting to be archived
+    #[serde(rename_all = "camelCase")]
+    Queued { creator_id: String, post_id: String },
+    /// All posts of the creator to be archived were queued
+    #[serde(rename_all = "camelCase")]
+    Listed { creator_id: String },
+    /// A media file of the post was downloaded
+    #[serde(rename_all = "camelCase")]
+    File {
+        post_id: String,
+        path: std::path::PathBuf,
+    },
+    /// The post was archived completely
+    #[serde(rename_all = "camelCase")]
+    Post { post_id: String },
+}
+
+/// JSON lines journal of posts queued and posts and files completed in the current run, used to
+/// resume an interrupted run without listing posts again.
+#[derive(Debug)]
+pub struct Journal {
+    path: std::path::PathBuf,
+    file: std::sync::Mutex<std::fs::File>,
+    completed_posts: std::collections::HashSet<String>,
+    completed_files: std::collections::HashSet<std::path::PathBuf>,
+    /// Post ids queued by creator id, in the listed order
+    queued_posts: std::collections::HashMap<String, Vec<String>>,
+    listed_creators: std::collections::HashSet<String>,
+}
+
+impl Journal {
+    /// Opens the journal in `dest_dir`.
+    ///
+    /// When `resume` is true, entries of the previous run are loaded and appended to. Otherwise
+    /// the journal is started over.
+    pub fn open(dest_dir: &std::path::Path, resume: bool) -> Result<Self, super::Error> {
+        let path = dest_dir.join(JOURNAL_FILE_NAME);
+        let mut completed_posts = std::collections::HashSet::new();
+        let mut completed_files = std::collections::HashSet::new();
+        let mut queued_posts: std::collections::HashMap<String, Vec<String>> =
+            std::collections::HashMap::new();
+        let mut listed_creators = std::collections::HashSet::new();
+        if resume {
+            match std::fs::read_to_string(&path) {
+                Ok(content) => {
+                    for line in content.lines() {
+                        // The last line may be truncated when the previous run was killed
+                        match serde_json::from_str(line) {
+                            Ok(Entry::File { path, .. }) => {
+                                completed_files.insert(path);
+                            }
+                            Ok(Entry::Post { post_id }) => {
+                                completed_posts.insert(post_id);
+                            }
+                            Ok(Entry::Queued {
+                                creator_id,
+                                post_id,
+                            }) => {
+                                queued_posts.entry(creator_id).or_default().push(post_id);
+                            }
+                            Ok(Entry::Listed { creator_id }) => {
+                                listed_creators.insert(creator_id);
+                            }
+                            Err(e) => tracing::warn!("Ignore broken journal entry {}: {}", line, e),
+                        }
+                    }
+                    tracing::info!(
+                        "Resume from {}: {} posts and {} files are completed",
+                        path.display(),
+                        completed_posts.len(),
+                        completed_files.len()
+                    );
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(source) => return Err(super::Error::Read { path, source }),
+            }
+        }
+        std::fs::create_dir_all(dest_dir).map_err(|source| super::Error::CreateDir {
+            path: dest_dir.to_path_buf(),
+            source,
+        })?;
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(resume)
+            .write(true)
+            .truncate(!resume)
+            .open(&path)
+            .map_err(|source| super::Error::Write {
+                path: path.clone(),
+                source,
+            })?;
+        Ok(Self {
+            path,
+            file: std::sync::Mutex::new(file),
+            completed_posts,
+            completed_files,
+            queued_posts,
+            listed_creators,
+        })
+    }
+
+    pub fn is_post_completed(&self, post_id: &str) -> bool {
+        self.completed_posts.contains(post_id)
+    }
+
+    pub fn is_file_completed(&self, path: &std::path::Path) -> bool {
+        self.completed_files.contains(path)
+    }
+
+    /// Returns post ids of the creator queued by the previous run when all of them were queued,
+    /// so that listing can be skipped.
+    pub fn queued_posts(&self, creator_id: &str) -> Option<Vec<String>> {
+        if !self.listed_creators.contains(creator_id) {
+            return None;
+        }
+        Some(
+            self.queued_posts
+                .get(creator_id)
+                .cloned()
+                .unwrap_or_default(),
+        )
+    }
+
+    pub fn enqueue_post(&self, creator_id: &str, post_id: &str) -> Result<(), super::Error> {
+        self.append(&Entry::Queued {
+            creator_id: creator_id.to_owned(),
+            post_id: post_id.to_owned(),
+        })
+    }
+
+    /// Records that all posts of the creator to be archived were queued.
+    pub fn complete_listing(&self, creator_id: &str) -> Result<(), super::Error> {
+        self.append(&Entry::Listed {
+            creator_id: creator_id.to_owned(),
+        })
+    }
+
+    pub fn complete_file(&self, post_id: &str, path: &std::path::Path) -> Result<(), super::Error> {
+        self.append(&Entry::File {
+            post_id: post_id.to_owned(),
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn complete_post(&self, post_id: &str) -> Result<(), super::Error> {
+        self.append(&Entry::Post {
+            post_id: post_id.to_owned(),
+        })
+    }
+
+    /// Removes the journal after the run is completed.
+    pub fn finish(self) -> Result<(), super::Error> {
+        drop(self.file);
+        std::fs::remove_file(&self.path).map_err(|source| super::Error::Write {
+            path: self.path,
+            source,
+        })
+    }
+
+    fn append(&self, entry: &Entry) -> Result<(), super::Error> {
+        use std::io::Write as _;
+
+        let mut line = serde_json::to_vec(entry).map_err(|e| super::Error::Write {
+            path: self.path.clone(),
+            source: e.into(),
+        })?;
+        line.push(b'\n');
+        self.file
+            .lock()
+            .unwrap()
+            .write_all(&line)
+            .map_err(|source| super::Error::Write {
+                path: self.path.clone(),
+                source,
+            })
+    }
+}
@@ -0,0 +1,58 @@
+/// Where post directories are placed in the archive. Posts are found in any layout, so the
+/// layout can be changed for an existing archive, moving each post when it's archived again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// `dest_dir/<post id>/`
+    #[default]
+    Flat,
+    /// `dest_dir/<creator id>/<fee>yen/<post id>/`, grouping posts by the fee of the plan required
+    /// to see them, where free posts are in `0yen`
+    ByFee,
+    /// `dest_dir/<creator id>/<post id>/`, e.g. for archives of many creators
+    ByCreator,
+}
+
+impl Layout {
+    /// Returns the directory of the post in `dest_dir`.
+    pub fn post_dir(
+        self,
+        dest_dir: &std::path::Path,
+        info: &fanbox_api::PostInfo,
+    ) -> std::path::PathBuf {
+        match self {
+            Self::Flat => dest_dir.join(&info.id),
+            Self::ByFee => dest_dir
+                .join(&info.creator_id)
+                .join(format!("{}yen", info.fee_required.unwrap_or_default()))
+                .join(&info.id),
+            Self::ByCreator => dest_dir.join(&info.creator_id).join(&info.id),
+        }
+    }
+}
+
+/// Returns the directory of the post archived in `dest_dir` in any layout, if any.
+pub(crate) fn find_post_dir(
+    dest_dir: &std::path::Path,
+    post_id: &str,
+) -> Option<std::path::PathBuf> {
+    let flat = dest_dir.join(post_id);
+    if flat.join(super::METADATA_FILE_NAME).is_file() {
+        return Some(flat);
+    }
+    std::fs::read_dir(dest_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|creator_dir| creator_dir.ok())
+        .flat_map(|creator_dir| {
+            let tier_dirs = std::fs::read_dir(creator_dir.path())
+                .into_iter()
+                .flatten()
+                .filter_map(|tier_dir| tier_dir.ok())
+                .filter(|tier_dir| tier_dir.file_name().to_string_lossy().ends_with("yen"))
+                .map(|tier_dir| tier_dir.path());
+            std::iter::once(creator_dir.path())
+                .chain(tier_dirs)
+                .map(|dir| dir.join(post_id))
+        })
+        .find(|dir| dir.join(super::METADATA_FILE_NAME).is_file())
+}
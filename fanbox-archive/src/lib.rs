@@ -0,0 +1,962 @@
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("failed to download {url}: {source}")]
+    Download {
+        url: String,
+        source: fanbox_api::Error,
+    },
+    #[error("failed to list posts of {creator_id}: {source}")]
+    ListPosts {
+        creator_id: String,
+        source: fanbox_api::Error,
+    },
+    #[error("failed to get post {post_id}: {source}")]
+    GetPost {
+        post_id: String,
+        source: fanbox_api::Error,
+    },
+    #[error("failed to get embeded fanbox post {post_id}: {source}")]
+    GetEmbedPost {
+        post_id: String,
+        source: fanbox_api::Error,
+    },
+    #[error("failed to create directory {}: {source}", path.display())]
+    CreateDir {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to write {}: {source}", path.display())]
+    Write {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to read {}: {source}", path.display())]
+    Read {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to update mtime {}: {source}", path.display())]
+    SetMtime {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to update permissions of {}: {source}", path.display())]
+    SetPermissions {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{} was written by a newer version of fanbox-dl (format version {version})", path.display())]
+    NewerFormat {
+        path: std::path::PathBuf,
+        version: u32,
+    },
+    #[error("invalid file name {name:?} given by a hook")]
+    InvalidFileName { name: String },
+    #[error("invalid path template {template:?}: {message}")]
+    InvalidTemplate { template: String, message: String },
+    #[cfg(feature = "convert-images")]
+    #[error("failed to convert image {}: {source}", path.display())]
+    ConvertImage {
+        path: std::path::PathBuf,
+        source: image::ImageError,
+    },
+    #[cfg(feature = "trash")]
+    #[error("failed to move {} to the trash: {source}", path.display())]
+    Trash {
+        path: std::path::PathBuf,
+        source: trash::Error,
+    },
+    #[cfg(feature = "export")]
+    #[error("failed to write zip archive {}: {source}", path.display())]
+    Zip {
+        path: std::path::PathBuf,
+        source: zip::result::ZipError,
+    },
+    #[cfg(feature = "plugins")]
+    #[error("plugin {} failed: {message}", path.display())]
+    Plugin {
+        path: std::path::PathBuf,
+        message: String,
+    },
+    #[cfg(feature = "scripting")]
+    #[error("script {} failed: {message}", path.display())]
+    Script {
+        path: std::path::PathBuf,
+        message: String,
+    },
+    #[cfg(feature = "state-db")]
+    #[error("failed to access state database {}: {source}", path.display())]
+    StateDb {
+        path: std::path::PathBuf,
+        source: rusqlite::Error,
+    },
+    #[cfg(feature = "encrypt")]
+    #[error("failed to encrypt {}: {message}", path.display())]
+    Encrypt {
+        path: std::path::PathBuf,
+        message: String,
+    },
+    #[cfg(feature = "encrypt")]
+    #[error("invalid recipient {recipient:?}: {message}")]
+    InvalidRecipient { recipient: String, message: String },
+}
+
+mod archiver;
+mod audit;
+#[cfg(feature = "state-db")]
+mod backup;
+mod body_handler;
+mod book;
+mod catalog;
+mod conflict;
+#[cfg(feature = "convert-images")]
+mod convert;
+mod covers;
+mod creator_page;
+mod document;
+mod download_log;
+mod embed;
+#[cfg(feature = "encrypt")]
+mod encrypt;
+#[cfg(feature = "export")]
+mod export;
+mod feed;
+mod gallery_dl;
+mod gc;
+mod hook;
+mod i18n;
+#[cfg(feature = "search-index")]
+mod index;
+mod journal;
+mod layout;
+mod lightbox;
+mod manifest;
+mod markdown;
+mod migrate;
+mod navigation;
+mod nfo;
+mod obsidian;
+mod page;
+mod path_template;
+#[cfg(feature = "plugins")]
+mod plugin;
+mod protect;
+mod rename;
+mod render;
+#[cfg(feature = "scripting")]
+mod script;
+mod sitemap;
+#[cfg(feature = "state-db")]
+mod state;
+mod stats;
+mod sync_manifest;
+mod text;
+#[cfg(feature = "verify")]
+mod verify;
+mod version;
+
+pub use archiver::{Archiver, Event, EventSender};
+pub use audit::{audit_archive, AuditIssue, AuditIssueKind, AuditOptions};
+#[cfg(feature = "state-db")]
+pub use backup::{write_differential_backup, BackupOptions, DifferentialBackup};
+pub use body_handler::{BodyHandler, BodyHandlers, DefaultBodyHandler};
+pub use book::{write_book, BOOKS_DIR_NAME};
+pub use catalog::{archived_updated_datetime, load_archived_posts, post_text, ArchivedPost};
+pub use conflict::ConflictPolicy;
+#[cfg(feature = "convert-images")]
+pub use convert::ConvertFormat;
+pub use covers::{archive_covers, COVERS_DIR_NAME};
+pub use creator_page::write_creator_page;
+pub use document::{Asset, Block, PostDocument, PostKind};
+pub use download_log::{sha256_file, DownloadLog, DownloadLogEntry, DOWNLOAD_LOG_FILE_NAME};
+pub use embed::EMBEDS_FILE_NAME;
+#[cfg(feature = "encrypt")]
+pub use encrypt::Recipients;
+#[cfg(feature = "export")]
+pub use export::{export, ExportFormat, ExportOptions};
+pub use feed::write_atom_feed;
+pub use gc::{
+    find_duplicates, find_stale_temporaries, link_duplicates, Duplicates, TEMPORARY_EXTENSION,
+};
+pub use hook::{Decision, FileCandidate, Hook};
+pub use i18n::{Label, Lang};
+#[cfg(feature = "search-index")]
+pub use index::{SearchHit, SearchIndex};
+pub use journal::{Journal, JOURNAL_FILE_NAME};
+pub use layout::Layout;
+pub use manifest::{write_posts_manifest, ManifestFile, ManifestPost, POSTS_MANIFEST_FILE_NAME};
+pub use migrate::GoLayout;
+pub use navigation::write_post_navigation;
+pub use obsidian::export_obsidian;
+pub use path_template::PathTemplate;
+#[cfg(feature = "plugins")]
+pub use plugin::WasmPlugin;
+pub use rename::relink_creator;
+pub use render::{HtmlRenderer, MarkdownRenderer, RenderedFile, Renderer};
+#[cfg(feature = "scripting")]
+pub use script::ScriptHook;
+pub use sitemap::write_sitemap;
+#[cfg(feature = "state-db")]
+pub use state::{PostState, RunState, StateDb, STATE_DB_FILE_NAME};
+pub use stats::{archive_stats, ArchiveStats, CreatorStats, FileStats};
+pub use sync_manifest::{SyncManifest, SYNC_MANIFEST_FILE_NAME};
+#[cfg(feature = "verify")]
+pub use verify::{check_file, find_broken_files, BrokenFile};
+pub use version::{
+    check_format_version, read_format_version, upgrade_format, FORMAT_VERSION,
+    FORMAT_VERSION_FILE_NAME,
+};
+
+/// Name of the file storing the post as JSON in each post directory
+pub const METADATA_FILE_NAME: &str = "metadata.json";
+
+/// Name of the HTML page in each post directory unless `ArchiveOptions::index_name` is given
+pub const DEFAULT_INDEX_NAME: &str = "index.html";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// index.html
+    #[default]
+    Html,
+    /// index.md with front matter, usable as a page bundle of Hugo
+    Hugo,
+    /// Media and JSON sidecars named like gallery-dl's fanbox extractor, without index pages
+    GalleryDl,
+    /// index.txt with the title, dates, text and names of downloaded files of the post
+    Text,
+}
+
+/// Size of images to download.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageSize {
+    /// Full-resolution originalUrl
+    #[default]
+    Original,
+    /// thumbnailUrl, 1200px wide
+    W1200,
+    /// 300px wide variant of thumbnailUrl
+    Thumb,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveOptions {
+    /// Save raw JSON of unsupported blocks and embeds into post directories. Raw JSON of post
+    /// bodies of unsupported types is saved unless their `BodyHandler` converts it
+    pub dump_unknown: bool,
+    /// Output format of archived posts
+    pub format: Format,
+    /// Download media and metadata only, without rendering index pages
+    pub no_index: bool,
+    /// File name of HTML pages where `{id}` is replaced by the post id, e.g. `{id}.html`
+    /// [default: index.html]
+    pub index_name: Option<String>,
+    /// Language of labels in generated pages
+    pub lang: Lang,
+    /// Where post directories are placed. Ignored with `Format::GalleryDl`, which doesn't have
+    /// post directories
+    pub layout: Layout,
+    /// Template of post directories and names of their images and files, used instead of
+    /// `layout`. Posts archived by a template aren't found by `archived_updated_datetime`, so
+    /// use `sync_manifest` to skip them. Ignored with `Format::GalleryDl`
+    pub path_template: Option<PathTemplate>,
+    /// Embed a lightbox viewer of images into HTML pages of image and article posts
+    pub lightbox: bool,
+    /// Write the post text to description.txt for plain-text tooling
+    pub description: bool,
+    /// Write videos, links and HTML embedded in the post to embeds.json
+    pub embeds_json: bool,
+    /// Command run in the post directory with the URL of each embedded video appended, e.g.
+    /// `["yt-dlp"]`. Its failures are only warned. Ignored with `render_only`
+    pub embed_downloader: Option<Vec<String>>,
+    /// Write Kodi/Jellyfin NFO files and folder art next to the media
+    pub nfo: bool,
+    /// What to do with media, cover images and pages which already exist in the post directory
+    pub on_conflict: ConflictPolicy,
+    /// Render pages from the post and media already in the post directory without network
+    /// access, linking embedded posts to their archived copies or to FANBOX
+    pub render_only: bool,
+    /// Size of images to download
+    pub image_size: ImageSize,
+    /// Download cover images in the original resolution instead of the resized ones returned by
+    /// the API
+    pub original_covers: bool,
+    /// Convert images in formats unsupported by older viewers, e.g. WebP, into this format
+    #[cfg(feature = "convert-images")]
+    pub convert_images: Option<ConvertFormat>,
+    /// Replace cover images identical to the first image of the post or to other cover images
+    /// with hard links
+    pub link_duplicate_covers: bool,
+    /// Directory whose state database indexes downloaded media across creator directories, used
+    /// to replace media identical to ones of other creators or posts with hard links
+    #[cfg(feature = "state-db")]
+    pub dedup_index_dir: Option<std::path::PathBuf>,
+    /// Number of images and files of a post downloaded at the same time, where 0 is taken as 1
+    pub concurrency: usize,
+    /// Flush converted images and their directory entries to disk before recording them, along
+    /// with `PostClient::fsync` for downloaded files
+    pub fsync: bool,
+    /// Make post directories and their files read-only after archived. Protected directories are
+    /// made writable again while their posts are archived again. Ignored with
+    /// `Format::GalleryDl`, which doesn't have post directories
+    pub protect: bool,
+    /// Journal recording completed posts and files, which are skipped when already recorded
+    pub journal: Option<std::sync::Arc<Journal>>,
+    /// Manifest recording archived posts, whose posts are skipped by `Archiver` unless they have
+    /// been updated since archived
+    pub sync_manifest: Option<std::sync::Arc<SyncManifest>>,
+    /// Log appended with every downloaded file
+    pub download_log: Option<std::sync::Arc<DownloadLog>>,
+    /// Sender of progress events, set by `Archiver::events`
+    pub events: Option<EventSender>,
+    /// Renderers writing files into post directories in addition to the page of `format`.
+    /// Ignored with `Format::GalleryDl`, which doesn't have post directories
+    pub renderers: Vec<std::sync::Arc<dyn Renderer>>,
+    /// Hooks transforming documents of posts and filtering their images and files, in order.
+    /// Ignored with `Format::GalleryDl`
+    pub hooks: Vec<std::sync::Arc<dyn Hook>>,
+    /// Handlers of post bodies of types the API client doesn't know, applied before `hooks`.
+    /// Ignored with `Format::GalleryDl`
+    pub body_handlers: BodyHandlers,
+}
+
+/// Downloads the post's media into `dest_dir/<post id>/` and renders its index.html.
+pub async fn archive_post(
+    client: &fanbox_api::PostClient,
+    dest_dir: &std::path::Path,
+    post: fanbox_api::Post,
+    options: &ArchiveOptions,
+) -> Result<(), Error> {
+    if post.body.is_none() {
+        tracing::warn!(
+            "You don't have permission to see post https://{}.fanbox.cc/posts/{}",
+            post.info.creator_id,
+            post.info.id
+        );
+        return Ok(());
+    }
+    if let Some(journal) = &options.journal {
+        if journal.is_post_completed(&post.info.id) {
+            tracing::info!("Skip post {} completed in the previous run", post.info.id);
+            return Ok(());
+        }
+    }
+    if !options.hooks.is_empty() {
+        let document = PostDocument::new(&post);
+        for hook in &options.hooks {
+            if !hook.should_archive(&document)? {
+                tracing::info!("Skip post {} by hook", post.info.id);
+                return Ok(());
+            }
+        }
+    }
+    let info = post.info.clone();
+    if options.format == Format::GalleryDl {
+        let mut post = post;
+        if options.original_covers {
+            use_original_cover(&mut post);
+        }
+        resize_images(&mut post, options.image_size);
+        #[cfg(feature = "convert-images")]
+        if let Some(to) = options.convert_images {
+            convert::convert_images(&mut post, to);
+        }
+        gallery_dl::archive_post(client, dest_dir, &post, options).await?;
+    } else {
+        let post_dir = match &options.path_template {
+            Some(template) => template.post_dir(dest_dir, &post),
+            None => options.layout.post_dir(dest_dir, &info),
+        };
+        move_post_dir(dest_dir, &info.id, &post_dir)?;
+        protect::unprotect_dir(&post_dir)?;
+        archive_post_dir(client, dest_dir, &post_dir, post, options).await?;
+        if options.protect {
+            protect::protect_dir(&post_dir)?;
+        }
+    }
+    #[cfg(feature = "state-db")]
+    StateDb::open(dest_dir)?.record_post(&info)?;
+    if let Some(journal) = &options.journal {
+        journal.complete_post(&info.id)?;
+    }
+    if let Some(sync_manifest) = &options.sync_manifest {
+        sync_manifest.record(&info)?;
+    }
+    Ok(())
+}
+
+/// Moves the post archived in another layout to `post_dir`, so that its media aren't downloaded
+/// again.
+fn move_post_dir(
+    dest_dir: &std::path::Path,
+    post_id: &str,
+    post_dir: &std::path::Path,
+) -> Result<(), Error> {
+    let old_dir = match layout::find_post_dir(dest_dir, post_id) {
+        Some(old_dir) if old_dir != post_dir => old_dir,
+        _ => return Ok(()),
+    };
+    tracing::info!("Move {} to {}", old_dir.display(), post_dir.display());
+    if let Some(parent) = post_dir.parent() {
+        std::fs::create_dir_all(parent).map_err(|source| Error::CreateDir {
+            path: parent.to_path_buf(),
+            source,
+        })?;
+    }
+    std::fs::rename(&old_dir, post_dir).map_err(|source| Error::Write {
+        path: post_dir.to_path_buf(),
+        source,
+    })
+}
+
+async fn archive_post_dir(
+    client: &fanbox_api::PostClient,
+    archive_dir: &std::path::Path,
+    post_dir: &std::path::Path,
+    post: fanbox_api::Post,
+    options: &ArchiveOptions,
+) -> Result<(), Error> {
+    #[cfg(feature = "search-index")]
+    let index = SearchIndex::open(archive_dir)?;
+    let dest_dir = post_dir.to_path_buf();
+    std::fs::create_dir_all(&dest_dir).map_err(|source| Error::CreateDir {
+        path: dest_dir.clone(),
+        source,
+    })?;
+    write_metadata(&dest_dir, &post).await?;
+    let mut post = post;
+    if options.original_covers {
+        use_original_cover(&mut post);
+    }
+    resize_images(&mut post, options.image_size);
+    #[cfg(feature = "convert-images")]
+    if let Some(to) = options.convert_images {
+        convert::convert_images(&mut post, to);
+    }
+    #[cfg(feature = "search-index")]
+    index.index_post(&dest_dir, &post)?;
+    let mut document = PostDocument::new(&post);
+    if let Some(fanbox_api::PostBody::Unknown(raw)) = &post.body {
+        document = options.body_handlers.convert_document(raw, document)?;
+    }
+    for hook in &options.hooks {
+        document = hook.transform_document(document)?;
+    }
+    if let Some(template) = &options.path_template {
+        template.rename_assets(&post.info, &mut document);
+    }
+    if options.format == Format::Hugo && !options.no_index {
+        write_rendered(&dest_dir, &MarkdownRenderer, &document, options).await?;
+    }
+    if options.format == Format::Text && !options.no_index {
+        let path = dest_dir.join(text::TEXT_FILE_NAME);
+        if let Some(path) = conflict::resolve(&path, options.on_conflict)? {
+            write_file(
+                &path,
+                text::render_text(&post).as_bytes(),
+                &post.info.updated_datetime,
+            )
+            .await?;
+        }
+    }
+    if options.description {
+        let text = post_text(&post);
+        if !text.is_empty() {
+            write_file(
+                &dest_dir.join("description.txt"),
+                format!("{}\n", text).as_bytes(),
+                &post.info.updated_datetime,
+            )
+            .await?;
+        }
+    }
+    if options.nfo {
+        nfo::write_nfo_files(&dest_dir, &post).await?;
+    }
+    let has_cover = post.info.cover_image_url.is_some();
+    let first_media_path = match &options.path_template {
+        Some(_) => document.blocks.iter().find_map(|block| match block {
+            Block::Image { asset, .. } | Block::File { asset, .. } => {
+                Some(dest_dir.join(&asset.path))
+            }
+            _ => None,
+        }),
+        None => catalog::media_files(&post)
+            .first()
+            .map(|media_file| dest_dir.join(&media_file.file_name)),
+    };
+    download_document(
+        client,
+        archive_dir,
+        &dest_dir,
+        &post.info,
+        &document,
+        options,
+    )
+    .await?;
+    for renderer in &options.renderers {
+        write_rendered(&dest_dir, renderer.as_ref(), &document, options).await?;
+    }
+    if options.embeds_json {
+        embed::write_embeds_json(&dest_dir, &document, options).await?;
+    }
+    if let Some(command) = &options.embed_downloader {
+        if !options.render_only {
+            embed::download_embedded_videos(&dest_dir, &document, command).await;
+        }
+    }
+    if options.link_duplicate_covers && has_cover {
+        gc::link_duplicate_cover(
+            archive_dir,
+            &dest_dir.join("cover_image.jpeg"),
+            first_media_path.as_deref(),
+        )?;
+    }
+    if options.nfo && has_cover {
+        nfo::write_folder_art(&dest_dir).await?;
+    }
+    Ok(())
+}
+
+/// Downloads images and files of the document into `dest_dir` in `archive_dir`, up to
+/// `options.concurrency` at the same time, and writes its page.
+///
+/// The span is attached to the future rather than entered, since documents are downloaded
+/// concurrently on the same task.
+#[tracing::instrument(
+    name = "post",
+    skip_all,
+    fields(kind = document.kind.name(), id = %info.id)
+)]
+async fn download_document(
+    client: &fanbox_api::PostClient,
+    archive_dir: &std::path::Path,
+    dest_dir: &std::path::Path,
+    info: &fanbox_api::PostInfo,
+    document: &PostDocument,
+    options: &ArchiveOptions,
+) -> Result<(), Error> {
+    use futures::stream::{StreamExt as _, TryStreamExt as _};
+
+    if document.kind == PostKind::Unknown {
+        for block in &document.blocks {
+            unsupported_block(dest_dir, info, block, options).await?;
+        }
+        return Ok(());
+    }
+
+    // Images and files are resolved by hooks and downloaded before the page is written
+    let mut blocks = Vec::new();
+    for block in &document.blocks {
+        let mut block = std::borrow::Cow::Borrowed(block);
+        if let Block::Image { asset, .. } | Block::File { asset, .. } = block.as_ref() {
+            if should_skip(document, asset, options)? {
+                tracing::info!("Skip {} by hook", asset.url);
+                continue;
+            }
+            let candidate = FileCandidate {
+                document,
+                asset,
+                path: &dest_dir.join(&asset.path),
+            };
+            match decide_file(&candidate, options)? {
+                Decision::Download => {}
+                Decision::Skip => {
+                    tracing::info!("Skip {} by hook", asset.url);
+                    continue;
+                }
+                Decision::Rename(name) => {
+                    if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".."
+                    {
+                        return Err(Error::InvalidFileName { name });
+                    }
+                    if let Block::Image { asset, .. } | Block::File { asset, .. } = block.to_mut() {
+                        asset.path = normalize_name(&name);
+                    }
+                }
+                Decision::Redirect(source) => {
+                    redirect(&source, candidate.path, options)?;
+                    blocks.push((block, false));
+                    continue;
+                }
+            }
+            blocks.push((block, true));
+        } else {
+            blocks.push((block, false));
+        }
+    }
+
+    let mut page = page::PageWriter::start(client, dest_dir, info, document, options).await?;
+    let mut downloads = Vec::new();
+    for (block, _) in blocks.iter().filter(|(_, download)| *download) {
+        let (label, asset) = match block.as_ref() {
+            Block::Image { asset, .. } => ("image", asset),
+            Block::File { asset, .. } => ("file", asset),
+            _ => continue,
+        };
+        downloads.push(async move {
+            tracing::info!("Download {} {}", label, asset.url);
+            download(
+                client,
+                &asset.url,
+                &dest_dir.join(&asset.path),
+                info,
+                options,
+            )
+            .await
+        });
+    }
+    futures::stream::iter(downloads)
+        .buffer_unordered(options.concurrency.max(1))
+        .try_collect::<()>()
+        .await?;
+    for (block, _) in &blocks {
+        let block = block.as_ref();
+        let content = match block {
+            Block::Post {
+                creator_id,
+                post_id,
+                ..
+            } => Some(if options.render_only {
+                let archived = layout::find_post_dir(archive_dir, post_id).and_then(|dir| {
+                    let post = catalog::read_archived_post(archive_dir, post_id)?;
+                    Some((navigation::relative_href(dest_dir, &dir), post))
+                });
+                match archived {
+                    Some((href, post)) => format!("<a href='{}/'>{}</a>", href, post.info.title),
+                    None => format!(
+                        "<a href='https://{}.fanbox.cc/posts/{}'>{}</a>",
+                        creator_id, post_id, post_id
+                    ),
+                }
+            } else {
+                let post =
+                    client
+                        .get_post(post_id)
+                        .await
+                        .map_err(|source| Error::GetEmbedPost {
+                            post_id: post_id.clone(),
+                            source,
+                        })?;
+                format!("<a href='{}'>{}</a>", post.info.creator_id, post.info.title)
+            }),
+            Block::Unknown { .. } => {
+                unsupported_block(dest_dir, info, block, options).await?;
+                None
+            }
+            _ => page::render_block_content(block),
+        };
+        for line in page::render_block(document.kind, block, content) {
+            page.write_line(&line).await?;
+        }
+    }
+    page.finish().await
+}
+
+/// Returns whether any of the hooks skips downloading the asset.
+fn should_skip(
+    document: &PostDocument,
+    asset: &Asset,
+    options: &ArchiveOptions,
+) -> Result<bool, Error> {
+    for hook in &options.hooks {
+        if !hook.should_download(document, asset)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Returns the first decision of the hooks other than `Decision::Download`.
+fn decide_file(candidate: &FileCandidate<'_>, options: &ArchiveOptions) -> Result<Decision, Error> {
+    for hook in &options.hooks {
+        let decision = hook.decide_file(candidate)?;
+        if decision != Decision::Download {
+            return Ok(decision);
+        }
+    }
+    Ok(Decision::Download)
+}
+
+/// Hard-links `source` to `path`, or copies it when they're on different file systems.
+fn redirect(
+    source: &std::path::Path,
+    path: &std::path::Path,
+    options: &ArchiveOptions,
+) -> Result<(), Error> {
+    if options.render_only {
+        return Ok(());
+    }
+    let target = match conflict::resolve(path, options.on_conflict)? {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+    tracing::info!("Link {} to {}", target.display(), source.display());
+    if target.exists() {
+        std::fs::remove_file(&target).map_err(|source| Error::Write {
+            path: target.clone(),
+            source,
+        })?;
+    }
+    if std::fs::hard_link(source, &target).is_err() {
+        std::fs::copy(source, &target).map_err(|source| Error::Write {
+            path: target.clone(),
+            source,
+        })?;
+    }
+    Ok(())
+}
+
+/// Writes files rendered from the document by `renderer` into the post directory `dest_dir`.
+async fn write_rendered(
+    dest_dir: &std::path::Path,
+    renderer: &dyn Renderer,
+    document: &PostDocument,
+    options: &ArchiveOptions,
+) -> Result<(), Error> {
+    for file in renderer.render_document(document)? {
+        let path = dest_dir.join(normalize_name(&file.name));
+        if let Some(path) = conflict::resolve(&path, options.on_conflict)? {
+            write_file(&path, &file.content, &document.updated_datetime).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Warns about the unknown block and dumps its raw JSON, which is done only with
+/// `options.dump_unknown` except for post bodies.
+async fn unsupported_block(
+    dest_dir: &std::path::Path,
+    info: &fanbox_api::PostInfo,
+    block: &Block,
+    options: &ArchiveOptions,
+) -> Result<(), Error> {
+    let Block::Unknown { kind, id, raw } = block else {
+        return Ok(());
+    };
+    match raw {
+        Some(raw) => {
+            tracing::warn!("Unknown {} was found in {}: {}", kind, post_url(info), raw);
+            if options.dump_unknown || kind == "body" {
+                let name = match id {
+                    Some(id) => format!("unknown_{}_{}.json", kind, id),
+                    None => format!("unknown_{}.json", kind),
+                };
+                dump_unknown(dest_dir, &name, raw).await?;
+            }
+        }
+        None => tracing::warn!("Unsupported {} was found in {}", kind, post_url(info)),
+    }
+    Ok(())
+}
+
+async fn dump_unknown(
+    dest_dir: &std::path::Path,
+    name: &str,
+    raw: &serde_json::Value,
+) -> Result<(), Error> {
+    let path = dest_dir.join(name);
+    tracing::info!("Dump unknown JSON to {}", path.display());
+    let json = serde_json::to_vec_pretty(raw).map_err(|e| Error::Write {
+        path: path.clone(),
+        source: e.into(),
+    })?;
+    tokio::fs::write(&path, json)
+        .await
+        .map_err(|source| Error::Write { path, source })?;
+    Ok(())
+}
+
+async fn write_metadata(dest_dir: &std::path::Path, post: &fanbox_api::Post) -> Result<(), Error> {
+    let path = dest_dir.join(METADATA_FILE_NAME);
+    let json = serde_json::to_vec_pretty(post).map_err(|e| Error::Write {
+        path: path.clone(),
+        source: e.into(),
+    })?;
+    write_file(&path, &json, &post.info.updated_datetime).await
+}
+
+/// Replaces URLs and extensions of the post's images with the resized ones, which are kept as is
+/// when the API doesn't provide them.
+fn resize_images(post: &mut fanbox_api::Post, size: ImageSize) {
+    if size == ImageSize::Original {
+        return;
+    }
+    let images: Vec<&mut fanbox_api::Image> = match &mut post.body {
+        Some(fanbox_api::PostBody::Image(image)) => image.body.images.iter_mut().collect(),
+        Some(fanbox_api::PostBody::Article(article)) => {
+            article.body.image_map.values_mut().collect()
+        }
+        _ => Vec::new(),
+    };
+    for image in images {
+        let url = match (&image.thumbnail_url, size) {
+            (Some(url), ImageSize::W1200) => url.clone(),
+            (Some(url), ImageSize::Thumb) => url.replace("/w/1200/", "/w/300/"),
+            _ => continue,
+        };
+        if let Some((_, extension)) = url
+            .rsplit('/')
+            .next()
+            .and_then(|name| name.rsplit_once('.'))
+        {
+            image.extension = extension.to_owned();
+        }
+        image.original_url = url;
+    }
+}
+
+/// Replaces the URL of the post's cover image with the original-resolution one, which is the
+/// original of the image in the post body with the same thumbnail, or the URL without its size
+/// segment, e.g. `/w/1200/` or `/c/1200x630_90_a2_g5/`.
+fn use_original_cover(post: &mut fanbox_api::Post) {
+    let url = match &post.info.cover_image_url {
+        Some(url) => url,
+        None => return,
+    };
+    let images: Vec<&fanbox_api::Image> = match &post.body {
+        Some(fanbox_api::PostBody::Image(image)) => image.body.images.iter().collect(),
+        Some(fanbox_api::PostBody::Article(article)) => article.body.image_map.values().collect(),
+        _ => Vec::new(),
+    };
+    let original = images
+        .into_iter()
+        .find(|image| image.thumbnail_url.as_ref() == Some(url))
+        .map(|image| image.original_url.clone())
+        .or_else(|| strip_image_size(url));
+    if let Some(original) = original {
+        tracing::debug!("Use original cover image {} instead of {}", original, url);
+        post.info.cover_image_url = Some(original);
+    }
+}
+
+/// Removes the size segment from the URL of a resized image.
+fn strip_image_size(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once("://")?;
+    let (host, path) = rest.split_once('/')?;
+    let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let mut segments: Vec<_> = path.split('/').collect();
+    let pos = segments.windows(2).position(|pair| match pair[0] {
+        "w" => is_number(pair[1]),
+        "c" => pair[1].split('x').next().is_some_and(is_number),
+        _ => false,
+    })?;
+    segments.drain(pos..pos + 2);
+    Some(format!("{}://{}/{}", scheme, host, segments.join("/")))
+}
+
+/// Downloads media of the post from `url` to `path`, or to another path chosen by
+/// `options.on_conflict` if `path` exists.
+async fn download(
+    client: &fanbox_api::PostClient,
+    url: &str,
+    path: &std::path::Path,
+    info: &fanbox_api::PostInfo,
+    options: &ArchiveOptions,
+) -> Result<(), Error> {
+    if options.render_only {
+        if !path.is_file() {
+            tracing::warn!("{} is missing, which is not downloaded", path.display());
+        }
+        return Ok(());
+    }
+    if let Some(journal) = &options.journal {
+        if journal.is_file_completed(path) && path.is_file() {
+            tracing::info!("Skip {} completed in the previous run", path.display());
+            return Ok(());
+        }
+    }
+    let target = match conflict::resolve(path, options.on_conflict)? {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+    let started = std::time::Instant::now();
+    let downloaded = client
+        .download_media_to(url, &target, &info.updated_datetime, Some(&post_url(info)))
+        .await
+        .map_err(|source| Error::Download {
+            url: url.to_owned(),
+            source,
+        })?;
+    #[cfg(feature = "convert-images")]
+    if options.convert_images.is_some() {
+        convert::convert_file(&target, &info.updated_datetime, options.fsync)?;
+    }
+    log_download(options, info, url, &target, downloaded, started)?;
+    #[cfg(feature = "state-db")]
+    if let Some(index_dir) = &options.dedup_index_dir {
+        gc::link_duplicate_media(index_dir, &target)?;
+    }
+    if let Some(events) = &options.events {
+        events.send(Event::FileDownloaded {
+            post_id: info.id.clone(),
+            path: target.clone(),
+            bytes: std::fs::metadata(&target).map_or(0, |metadata| metadata.len()),
+        });
+    }
+    if let Some(journal) = &options.journal {
+        journal.complete_file(&info.id, path)?;
+    }
+    Ok(())
+}
+
+/// Appends the downloaded file, after converted if requested, to `options.download_log`.
+fn log_download(
+    options: &ArchiveOptions,
+    info: &fanbox_api::PostInfo,
+    url: &str,
+    path: &std::path::Path,
+    downloaded: fanbox_api::DownloadedMedia,
+    started: std::time::Instant,
+) -> Result<(), Error> {
+    let download_log = match &options.download_log {
+        Some(download_log) => download_log,
+        None => return Ok(()),
+    };
+    let size = std::fs::metadata(path).map_or(downloaded.bytes, |metadata| metadata.len());
+    download_log.append(&DownloadLogEntry {
+        timestamp: chrono::Utc::now(),
+        post_id: info.id.clone(),
+        url: url.to_owned(),
+        path: path.to_path_buf(),
+        size,
+        sha256: sha256_file(path)?,
+        status: downloaded.status,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// Normalizes a path component generated from strings of the API or hooks to NFC, since the same
+/// title may arrive decomposed (e.g. after passing through macOS) and would otherwise make a
+/// different-looking file on other platforms.
+pub(crate) fn normalize_name(name: &str) -> String {
+    use unicode_normalization::UnicodeNormalization as _;
+
+    name.nfc().collect()
+}
+
+fn post_url(info: &fanbox_api::PostInfo) -> String {
+    format!("https://{}.fanbox.cc/posts/{}", info.creator_id, info.id)
+}
+
+/// Writes `content` to `path` and sets its mtime.
+async fn write_file(
+    path: &std::path::Path,
+    content: &[u8],
+    mtime: &chrono::DateTime<chrono::Utc>,
+) -> Result<(), Error> {
+    tokio::fs::write(path, content)
+        .await
+        .map_err(|source| Error::Write {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    filetime::set_file_mtime(
+        path,
+        filetime::FileTime::from_unix_time(mtime.timestamp(), mtime.timestamp_subsec_nanos()),
+    )
+    .map_err(|source| Error::SetMtime {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(())
+}
@@ -0,0 +1,75 @@
+//! Lightbox viewer embedded into HTML pages without external assets.
+//!
+//! Clicking an image of the post opens it fitted to the screen. Arrow keys move to the
+//! previous/next image, which are preloaded, Escape closes the viewer and clicking the opened
+//! image toggles between fitted and actual size.
+
+pub(crate) const STYLE: &str = "<style>
+#lightbox { display: none; position: fixed; inset: 0; z-index: 1000; overflow: auto; background: rgba(0, 0, 0, 0.9); }
+#lightbox.open { display: flex; }
+#lightbox img { margin: auto; max-width: 100vw; max-height: 100vh; object-fit: contain; cursor: zoom-in; }
+#lightbox.actual-size img { max-width: none; max-height: none; cursor: zoom-out; }
+#lightbox-counter { position: fixed; top: 8px; right: 12px; color: #fff; font: 14px sans-serif; }
+img.lightbox-item { cursor: zoom-in; }
+</style>";
+
+pub(crate) const SCRIPT: &str = "<script>
+(function () {
+  var items = Array.prototype.slice.call(document.querySelectorAll(\"img[src^='./'], img[src^='data:']\"));
+  if (items.length === 0) {
+    return;
+  }
+  var box = document.createElement('div');
+  box.id = 'lightbox';
+  var image = document.createElement('img');
+  var counter = document.createElement('div');
+  counter.id = 'lightbox-counter';
+  box.appendChild(image);
+  box.appendChild(counter);
+  document.body.appendChild(box);
+  var current = -1;
+  function preload(i) {
+    if (i >= 0 && i < items.length) {
+      new Image().src = items[i].src;
+    }
+  }
+  function show(i) {
+    current = (i + items.length) % items.length;
+    image.src = items[current].src;
+    image.alt = items[current].alt;
+    counter.textContent = (current + 1) + ' / ' + items.length;
+    box.classList.remove('actual-size');
+    box.classList.add('open');
+    preload(current + 1);
+    preload(current - 1);
+  }
+  function close() {
+    box.classList.remove('open');
+    current = -1;
+  }
+  items.forEach(function (item, i) {
+    item.classList.add('lightbox-item');
+    item.addEventListener('click', function () { show(i); });
+  });
+  image.addEventListener('click', function (e) {
+    e.stopPropagation();
+    box.classList.toggle('actual-size');
+  });
+  box.addEventListener('click', close);
+  document.addEventListener('keydown', function (e) {
+    if (current < 0) {
+      return;
+    }
+    if (e.key === 'Escape') {
+      close();
+    } else if (e.key === 'ArrowRight' || e.key === ' ') {
+      show(current + 1);
+    } else if (e.key === 'ArrowLeft') {
+      show(current - 1);
+    } else {
+      return;
+    }
+    e.preventDefault();
+  });
+})();
+</script>";
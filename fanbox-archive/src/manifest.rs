@@ -0,0 +1,88 @@
+/// Name of the manifest of archived posts in `dest_dir/<creator id>/`
+pub const POSTS_MANIFEST_FILE_NAME: &str = "posts.json";
+
+/// Entry of posts.json.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestPost {
+    pub id: String,
+    pub title: String,
+    pub published_datetime: chrono::DateTime<chrono::Utc>,
+    pub updated_datetime: chrono::DateTime<chrono::Utc>,
+    /// Post directory relative to the destination directory
+    pub dir: std::path::PathBuf,
+    pub files: Vec<ManifestFile>,
+}
+
+/// Media file of a post in posts.json.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestFile {
+    /// Original file name of files, or the id of images
+    pub name: String,
+    pub url: String,
+    /// Path relative to the destination directory
+    pub path: std::path::PathBuf,
+    /// Whether the file has been downloaded
+    pub downloaded: bool,
+}
+
+/// Writes `dest_dir/<creator id>/posts.json` listing the creator's archived posts, newest first,
+/// with their media files, for external scripts.
+pub fn write_posts_manifest(
+    dest_dir: &std::path::Path,
+    creator_id: &str,
+) -> Result<std::path::PathBuf, super::Error> {
+    let posts: Vec<_> = super::load_archived_posts(dest_dir)?
+        .into_iter()
+        .filter(|archived| archived.post.info.creator_id == creator_id)
+        .map(|archived| {
+            let relative =
+                |path: &std::path::Path| path.strip_prefix(dest_dir).unwrap_or(path).to_path_buf();
+            let info = &archived.post.info;
+            let mut files = Vec::new();
+            if let Some(cover_image_url) = &info.cover_image_url {
+                let path = archived.dir.join("cover_image.jpeg");
+                files.push(ManifestFile {
+                    name: "cover_image".to_owned(),
+                    url: cover_image_url.clone(),
+                    path: relative(&path),
+                    downloaded: path.is_file(),
+                });
+            }
+            for media_file in super::catalog::media_files(&archived.post) {
+                let path = archived.dir.join(&media_file.file_name);
+                files.push(ManifestFile {
+                    name: media_file.name.to_owned(),
+                    url: media_file.url.to_owned(),
+                    path: relative(&path),
+                    downloaded: path.is_file(),
+                });
+            }
+            ManifestPost {
+                id: info.id.clone(),
+                title: info.title.clone(),
+                published_datetime: info.published_datetime,
+                updated_datetime: info.updated_datetime,
+                dir: relative(&archived.dir),
+                files,
+            }
+        })
+        .collect();
+
+    let creator_dir = dest_dir.join(creator_id);
+    std::fs::create_dir_all(&creator_dir).map_err(|source| super::Error::CreateDir {
+        path: creator_dir.clone(),
+        source,
+    })?;
+    let path = creator_dir.join(POSTS_MANIFEST_FILE_NAME);
+    let json = serde_json::to_vec_pretty(&posts).map_err(|e| super::Error::Write {
+        path: path.clone(),
+        source: e.into(),
+    })?;
+    std::fs::write(&path, json).map_err(|source| super::Error::Write {
+        path: path.clone(),
+        source,
+    })?;
+    Ok(path)
+}
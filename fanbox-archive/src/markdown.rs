@@ -0,0 +1,122 @@
+/// How media and other posts are referenced from the Markdown.
+pub(crate) enum Links<'a> {
+    /// Media live next to index.md in the page bundle
+    Hugo,
+    /// Media live in `attachments/<post id>/` of the vault and archived posts are wiki-linked
+    /// by their note names keyed by post id
+    Obsidian {
+        notes: &'a std::collections::HashMap<String, String>,
+    },
+}
+
+impl Links<'_> {
+    fn image(&self, post_id: &str, file_name: &str, alt: &str) -> String {
+        match self {
+            Self::Hugo => format!("![{}]({})", alt, file_name),
+            Self::Obsidian { .. } => format!("![[{}]]", attachment_path(post_id, file_name)),
+        }
+    }
+
+    fn file(&self, post_id: &str, file_name: &str, label: &str) -> String {
+        match self {
+            Self::Hugo => format!("[{}]({})", label, file_name),
+            Self::Obsidian { .. } => {
+                format!("[[{}|{}]]", attachment_path(post_id, file_name), label)
+            }
+        }
+    }
+
+    fn post(&self, post_id: &str) -> String {
+        match self {
+            Self::Obsidian { notes } if notes.contains_key(post_id) => {
+                format!("[[{}]]", notes[post_id])
+            }
+            _ => format!("<https://www.fanbox.cc/posts/{}>", post_id),
+        }
+    }
+}
+
+/// Returns the vault-relative path of the post's attachment.
+pub(crate) fn attachment_path(post_id: &str, file_name: &str) -> String {
+    format!("attachments/{}/{}", post_id, file_name)
+}
+
+/// Renders the document of the post as Markdown with YAML front matter using `links`.
+pub(crate) fn render(document: &super::PostDocument, links: &Links) -> String {
+    let mut lines = vec![
+        "---".to_owned(),
+        format!("title: {}", yaml_string(&document.title)),
+        format!("date: {}", document.published_datetime.to_rfc3339()),
+        format!("lastmod: {}", document.updated_datetime.to_rfc3339()),
+        format!(
+            "tags: [{}]",
+            document
+                .tags
+                .iter()
+                .map(|tag| yaml_string(tag))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        format!("creator: {}", yaml_string(&document.creator_id)),
+        format!(
+            "creator_user_id: {}",
+            document
+                .user_id
+                .as_ref()
+                .map_or_else(|| "null".to_owned(), |user_id| yaml_string(user_id))
+        ),
+        format!("fanbox_url: {}", yaml_string(&document.url)),
+        "---".to_owned(),
+        String::new(),
+    ];
+    if let Some(cover) = &document.cover {
+        lines.push(links.image(&document.id, &cover.path, "cover"));
+        lines.push(String::new());
+    }
+    lines.extend(render_body(document, links));
+    lines.join("\n")
+}
+
+fn render_body(document: &super::PostDocument, links: &Links) -> Vec<String> {
+    let post_id = document.id.as_str();
+    let article = document.kind == super::PostKind::Article;
+    let mut lines = Vec::new();
+    for block in &document.blocks {
+        let line = match block {
+            super::Block::Paragraph { text } => Some(self::text(text)),
+            super::Block::Header { text } => Some(format!("## {}", text)),
+            super::Block::Image { id, asset } => Some(links.image(post_id, &asset.path, id)),
+            super::Block::File { name, asset, .. } => {
+                let extension = asset.path.rsplit_once('.').map_or("", |(_, e)| e);
+                Some(links.file(post_id, &asset.path, &format!("{}.{}", name, extension)))
+            }
+            super::Block::Video { url, .. } | super::Block::Link { url, .. } => {
+                Some(format!("<{}>", url))
+            }
+            super::Block::Post { post_id, .. } => Some(links.post(post_id)),
+            super::Block::Html { html } => Some(html.clone()),
+            super::Block::Unknown { .. } => None,
+        };
+        // Text of posts other than articles comes last without a following blank line
+        let trailing_text = !article && matches!(block, super::Block::Paragraph { .. });
+        match line {
+            Some(line) => lines.push(line),
+            None if article => {}
+            None => continue,
+        }
+        if !trailing_text {
+            lines.push(String::new());
+        }
+    }
+    lines
+}
+
+/// Keeps line breaks of the plain text in Markdown.
+fn text(s: &str) -> String {
+    s.lines().collect::<Vec<_>>().join("  \n")
+}
+
+/// Quotes the string as YAML. JSON strings are valid YAML double-quoted scalars.
+fn yaml_string(s: &str) -> String {
+    serde_json::to_string(s).unwrap()
+}
@@ -0,0 +1,130 @@
+/// Files of a creator downloaded by the Go version of fanbox-dl.
+///
+/// The Go version saves the n-th (0-origin) image or file of a post to either
+/// `<creator id>/<published date>-<title>-<n>-<name>.<extension>` or, with `--dir-by-post`,
+/// `<creator id>/<published date>-<title>/<n>-<name>.<extension>` under its save directory, where
+/// the name is the image id or the file name.
+#[derive(Debug, Clone)]
+pub struct GoLayout {
+    files: Vec<(String, std::path::PathBuf)>,
+}
+
+impl GoLayout {
+    /// Lists files of the creator under `go_dir`, the save directory of the Go version.
+    pub fn scan(go_dir: &std::path::Path, creator_id: &str) -> Result<Self, super::Error> {
+        let creator_dir = go_dir.join(creator_id);
+        let files = if creator_dir.is_dir() {
+            list_files(&creator_dir)?
+        } else {
+            Vec::new()
+        };
+        Ok(Self { files })
+    }
+
+    /// Imports media of the post into `dest_dir/<post id>/`, so that `archive_post` with
+    /// `ConflictPolicy::Skip` doesn't download them again.
+    ///
+    /// Files are hard-linked when possible and copied otherwise. Returns the number of imported
+    /// files.
+    pub fn import(
+        &self,
+        dest_dir: &std::path::Path,
+        post: &fanbox_api::Post,
+    ) -> Result<usize, super::Error> {
+        let info = &post.info;
+        // FANBOX returns datetimes in JST, which the Go version keeps when formatting dates
+        let jst = chrono::FixedOffset::east_opt(9 * 60 * 60).unwrap();
+        let date_prefixes = [
+            format!(
+                "{}-",
+                info.published_datetime
+                    .with_timezone(&jst)
+                    .format("%Y-%m-%d")
+            ),
+            format!("{}-", info.published_datetime.format("%Y-%m-%d")),
+        ];
+        let post_dir = dest_dir.join(&info.id);
+
+        let mut imported = 0;
+        for (order, media_file) in super::catalog::media_files(post).iter().enumerate() {
+            let suffix = escape(&format!(
+                "-{}-{}.{}",
+                order, media_file.name, media_file.extension
+            ));
+            let found = self.files.iter().find(|(relative, _)| {
+                date_prefixes
+                    .iter()
+                    .any(|prefix| relative.starts_with(prefix))
+                    && escape(relative).ends_with(&suffix)
+            });
+            let (_, source) = match found {
+                Some(found) => found,
+                None => continue,
+            };
+            let dest_path = post_dir.join(&media_file.file_name);
+            if dest_path.exists() {
+                continue;
+            }
+            std::fs::create_dir_all(&post_dir).map_err(|source| super::Error::CreateDir {
+                path: post_dir.clone(),
+                source,
+            })?;
+            tracing::info!("Import {} to {}", source.display(), dest_path.display());
+            if std::fs::hard_link(source, &dest_path).is_err() {
+                std::fs::copy(source, &dest_path).map_err(|source| super::Error::Write {
+                    path: dest_path.clone(),
+                    source,
+                })?;
+            }
+            imported += 1;
+        }
+        Ok(imported)
+    }
+}
+
+/// Lists files under `dir` as pairs of the path relative to `dir`, joined with `-` instead of the
+/// path separator, and the full path.
+fn list_files(dir: &std::path::Path) -> Result<Vec<(String, std::path::PathBuf)>, super::Error> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(current) = dirs.pop() {
+        let entries = std::fs::read_dir(&current).map_err(|source| super::Error::Read {
+            path: current.clone(),
+            source,
+        })?;
+        for entry in entries {
+            let entry = entry.map_err(|source| super::Error::Read {
+                path: current.clone(),
+                source,
+            })?;
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(t) if t.is_dir() => dirs.push(path),
+                Ok(t) if t.is_file() => {
+                    let relative = path
+                        .strip_prefix(dir)
+                        .unwrap_or(&path)
+                        .iter()
+                        .map(|component| component.to_string_lossy().into_owned())
+                        .collect::<Vec<_>>()
+                        .join("-");
+                    files.push((relative, path));
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Replaces characters which the Go version doesn't keep in file names, comparing names in NFC
+/// since they may be decomposed on macOS.
+fn escape(s: &str) -> String {
+    super::normalize_name(s)
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect()
+}
@@ -0,0 +1,117 @@
+/// Prefix of the navigation line, replaced when the links are written again
+const NAVIGATION_PREFIX: &str = "<nav class='post-navigation'>";
+
+/// Adds links to the previous and next posts in published order to the HTML pages of the
+/// creator's archived posts under `dest_dir`, so that the archive can be read sequentially.
+///
+/// Links written by a previous run are replaced, and the modification time of pages is kept.
+/// Returns the number of updated pages.
+pub fn write_post_navigation(
+    dest_dir: &std::path::Path,
+    creator_id: &str,
+    lang: super::Lang,
+) -> Result<usize, super::Error> {
+    let mut posts: Vec<_> = super::load_archived_posts(dest_dir)?
+        .into_iter()
+        .filter(|archived| archived.post.info.creator_id == creator_id)
+        .map(|archived| {
+            let page = archived.dir.join(archived.index_file_name());
+            (archived, page)
+        })
+        .filter(|(_, page)| page.is_file())
+        .collect();
+    posts.sort_by(|(a, _), (b, _)| {
+        a.post
+            .info
+            .published_datetime
+            .cmp(&b.post.info.published_datetime)
+    });
+
+    let mut updated = 0;
+    for (i, (archived, page)) in posts.iter().enumerate() {
+        let mut links = Vec::new();
+        if let Some((previous, previous_page)) = i.checked_sub(1).map(|j| &posts[j]) {
+            links.push(format!(
+                "<a rel='prev' href='{}'>← {}: {}</a>",
+                super::feed::escape_xml(&relative_href(&archived.dir, previous_page)),
+                lang.label(super::Label::Previous),
+                super::feed::escape_xml(&previous.post.info.title)
+            ));
+        }
+        if let Some((next, next_page)) = posts.get(i + 1) {
+            links.push(format!(
+                "<a rel='next' href='{}'>{}: {} →</a>",
+                super::feed::escape_xml(&relative_href(&archived.dir, next_page)),
+                lang.label(super::Label::Next),
+                super::feed::escape_xml(&next.post.info.title)
+            ));
+        }
+        if update_page(page, &links)? {
+            updated += 1;
+        }
+    }
+    Ok(updated)
+}
+
+/// Replaces the navigation line of the page with `links`, returning whether the page changed.
+fn update_page(page: &std::path::Path, links: &[String]) -> Result<bool, super::Error> {
+    let html = std::fs::read_to_string(page).map_err(|source| super::Error::Read {
+        path: page.to_path_buf(),
+        source,
+    })?;
+    let mut lines: Vec<_> = html
+        .lines()
+        .filter(|line| !line.starts_with(NAVIGATION_PREFIX))
+        .map(ToOwned::to_owned)
+        .collect();
+    if !links.is_empty() {
+        let navigation = format!("{}{}</nav>", NAVIGATION_PREFIX, links.join(" | "));
+        match lines.iter().rposition(|line| line == "</body>") {
+            Some(pos) => lines.insert(pos, navigation),
+            None => lines.push(navigation),
+        }
+    }
+    let new_html = lines.join("\n");
+    if new_html == html {
+        return Ok(false);
+    }
+
+    let mtime = std::fs::metadata(page)
+        .map(|metadata| filetime::FileTime::from_last_modification_time(&metadata))
+        .map_err(|source| super::Error::Read {
+            path: page.to_path_buf(),
+            source,
+        })?;
+    // Pages of protected posts are made writable only while updated
+    let protected = super::protect::is_readonly(page)?;
+    if protected {
+        super::protect::set_writable(page, true)?;
+    }
+    std::fs::write(page, new_html).map_err(|source| super::Error::Write {
+        path: page.to_path_buf(),
+        source,
+    })?;
+    filetime::set_file_mtime(page, mtime).map_err(|source| super::Error::SetMtime {
+        path: page.to_path_buf(),
+        source,
+    })?;
+    if protected {
+        super::protect::set_writable(page, false)?;
+    }
+    Ok(true)
+}
+
+/// Returns the relative URL of `target` from a page in `from_dir`.
+pub(crate) fn relative_href(from_dir: &std::path::Path, target: &std::path::Path) -> String {
+    let from: Vec<_> = from_dir.components().collect();
+    let to: Vec<_> = target.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    std::iter::repeat_n("..".to_owned(), from.len() - common)
+        .chain(
+            to[common..]
+                .iter()
+                .map(|component| component.as_os_str().to_string_lossy().into_owned()),
+        )
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,62 @@
+/// Name of the NFO file describing the whole post
+const POST_NFO_FILE_NAME: &str = "post.nfo";
+/// Name of the folder art recognized by Kodi and Jellyfin
+const FOLDER_ART_FILE_NAME: &str = "folder.jpg";
+
+/// Writes NFO files of the post and of each image or file into `dest_dir`.
+pub async fn write_nfo_files(
+    dest_dir: &std::path::Path,
+    post: &fanbox_api::Post,
+) -> Result<(), super::Error> {
+    let info = &post.info;
+    let plot = super::post_text(post);
+    super::write_file(
+        &dest_dir.join(POST_NFO_FILE_NAME),
+        render(info, &info.title, &plot).as_bytes(),
+        &info.updated_datetime,
+    )
+    .await?;
+    for (i, media_file) in super::catalog::media_files(post).iter().enumerate() {
+        let title = format!("{} ({})", info.title, i + 1);
+        super::write_file(
+            &dest_dir.join(&media_file.file_name).with_extension("nfo"),
+            render(info, &title, &plot).as_bytes(),
+            &info.updated_datetime,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Copies the downloaded cover image to the folder art.
+pub async fn write_folder_art(dest_dir: &std::path::Path) -> Result<(), super::Error> {
+    let path = dest_dir.join(FOLDER_ART_FILE_NAME);
+    tokio::fs::copy(dest_dir.join("cover_image.jpeg"), &path)
+        .await
+        .map_err(|source| super::Error::Write { path, source })?;
+    Ok(())
+}
+
+fn render(info: &fanbox_api::PostInfo, title: &str, plot: &str) -> String {
+    let esc = super::feed::escape_xml;
+    let mut lines = vec![
+        "<?xml version='1.0' encoding='utf-8' standalone='yes'?>".to_owned(),
+        "<musicvideo>".to_owned(),
+        format!("  <title>{}</title>", esc(title)),
+        format!("  <plot>{}</plot>", esc(plot)),
+        format!(
+            "  <premiered>{}</premiered>",
+            info.published_datetime.format("%Y-%m-%d")
+        ),
+        format!("  <artist>{}</artist>", esc(&info.creator_id)),
+    ];
+    for tag in &info.tags {
+        lines.push(format!("  <tag>{}</tag>", esc(tag)));
+    }
+    lines.push(format!(
+        "  <uniqueid type='fanbox' default='true'>{}</uniqueid>",
+        esc(&info.id)
+    ));
+    lines.push("</musicvideo>".to_owned());
+    lines.join("\n")
+}
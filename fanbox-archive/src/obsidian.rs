@@ -0,0 +1,130 @@
+/// Files in post directories which are not copied into the vault
+const SKIPPED_FILES: &[&str] = &[
+    super::METADATA_FILE_NAME,
+    "index.md",
+    super::text::TEXT_FILE_NAME,
+];
+
+/// Exports archived posts under `dest_dir` into `vault_dir` as Obsidian notes.
+///
+/// Each post becomes `<creator id>/<title> (<post id>).md` with YAML front matter, and its media
+/// are copied into `attachments/<post id>/`. Embedded FANBOX posts which are also archived are
+/// wiki-linked, and `<creator id>/<creator id>.md` links to all notes of the creator.
+/// Returns the number of exported posts.
+pub fn export_obsidian(
+    dest_dir: &std::path::Path,
+    vault_dir: &std::path::Path,
+) -> Result<usize, super::Error> {
+    let posts = super::load_archived_posts(dest_dir)?;
+    let notes: std::collections::HashMap<_, _> = posts
+        .iter()
+        .map(|archived| {
+            let info = &archived.post.info;
+            (
+                info.id.clone(),
+                format!("{} ({})", sanitize_note_name(&info.title), info.id),
+            )
+        })
+        .collect();
+    let links = super::markdown::Links::Obsidian { notes: &notes };
+
+    let mut creators: Vec<(&str, Vec<&str>)> = Vec::new();
+    for archived in &posts {
+        let info = &archived.post.info;
+        let note_name = &notes[&info.id];
+        let creator_dir = vault_dir.join(&info.creator_id);
+        create_dir_all(&creator_dir)?;
+        let note_path = creator_dir.join(format!("{}.md", note_name));
+        let document = super::PostDocument::new(&archived.post);
+        std::fs::write(&note_path, super::markdown::render(&document, &links)).map_err(
+            |source| super::Error::Write {
+                path: note_path.clone(),
+                source,
+            },
+        )?;
+        copy_attachments(
+            &archived.dir,
+            &archived.index_file_name(),
+            &vault_dir.join("attachments").join(&info.id),
+        )?;
+
+        match creators
+            .iter_mut()
+            .find(|(creator_id, _)| *creator_id == info.creator_id)
+        {
+            Some((_, note_names)) => note_names.push(note_name),
+            None => creators.push((&info.creator_id, vec![note_name])),
+        }
+    }
+
+    for (creator_id, note_names) in creators {
+        let mut lines = vec![format!("# {}", creator_id), String::new()];
+        lines.extend(note_names.iter().map(|name| format!("- [[{}]]", name)));
+        let index_path = vault_dir
+            .join(creator_id)
+            .join(format!("{}.md", creator_id));
+        std::fs::write(&index_path, lines.join("\n")).map_err(|source| super::Error::Write {
+            path: index_path.clone(),
+            source,
+        })?;
+    }
+    Ok(posts.len())
+}
+
+/// Copies media of the post directory except its HTML page, skipping files which are already
+/// copied.
+pub(crate) fn copy_attachments(
+    post_dir: &std::path::Path,
+    index_file_name: &str,
+    attachments_dir: &std::path::Path,
+) -> Result<(), super::Error> {
+    let entries = std::fs::read_dir(post_dir).map_err(|source| super::Error::Read {
+        path: post_dir.to_path_buf(),
+        source,
+    })?;
+    for entry in entries {
+        let entry = entry.map_err(|source| super::Error::Read {
+            path: post_dir.to_path_buf(),
+            source,
+        })?;
+        let file_name = entry.file_name();
+        let file_name_str = file_name.to_string_lossy();
+        if SKIPPED_FILES.contains(&file_name_str.as_ref())
+            || file_name_str == index_file_name
+            || file_name_str.starts_with("unknown_")
+            || !entry.file_type().map(|t| t.is_file()).unwrap_or(false)
+        {
+            continue;
+        }
+        create_dir_all(attachments_dir)?;
+        let dest_path = attachments_dir.join(&file_name);
+        if dest_path.is_file() {
+            continue;
+        }
+        std::fs::copy(entry.path(), &dest_path).map_err(|source| super::Error::Write {
+            path: dest_path.clone(),
+            source,
+        })?;
+    }
+    Ok(())
+}
+
+fn create_dir_all(path: &std::path::Path) -> Result<(), super::Error> {
+    std::fs::create_dir_all(path).map_err(|source| super::Error::CreateDir {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Replaces characters which cannot be used in Obsidian note names.
+fn sanitize_note_name(title: &str) -> String {
+    let name: String = super::normalize_name(title)
+        .chars()
+        .map(|c| match c {
+            '*' | '"' | '\\' | '/' | '<' | '>' | ':' | '|' | '?' | '#' | '^' | '[' | ']' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    name.trim().trim_start_matches('.').to_owned()
+}
@@ -0,0 +1,337 @@
+//! HTML pages of posts, streamed into files while media of the posts are downloaded.
+
+use super::i18n::{Label, Lang};
+
+/// Writer of the HTML page of a post.
+///
+/// Lines are written into a temporary file next to the page, which replaces the page on
+/// `finish` so that failed downloads don't leave truncated pages behind. Nothing is written
+/// when another format is chosen or `options.no_index` is set.
+pub(crate) struct PageWriter {
+    file: Option<PageFile>,
+    lightbox: bool,
+    on_conflict: super::ConflictPolicy,
+}
+
+struct PageFile {
+    path: std::path::PathBuf,
+    temporary_path: std::path::PathBuf,
+    writer: tokio::io::BufWriter<tokio::fs::File>,
+    mtime: chrono::DateTime<chrono::Utc>,
+}
+
+impl PageWriter {
+    /// Downloads the cover image of the post and starts its page with the head, heading, byline
+    /// and the cover image.
+    ///
+    /// The lightbox is embedded with `options.lightbox` if the document has images other than
+    /// the cover image.
+    pub(crate) async fn start(
+        client: &fanbox_api::PostClient,
+        dest_dir: &std::path::Path,
+        info: &fanbox_api::PostInfo,
+        document: &super::PostDocument,
+        options: &super::ArchiveOptions,
+    ) -> Result<Self, super::Error> {
+        let file = if options.format == super::Format::Html && !options.no_index {
+            let index_name = options
+                .index_name
+                .as_deref()
+                .unwrap_or(super::DEFAULT_INDEX_NAME)
+                .replace("{id}", &info.id);
+            let temporary_path = dest_dir.join(format!(".{}.part", index_name));
+            let file = tokio::fs::File::create(&temporary_path)
+                .await
+                .map_err(|source| super::Error::Write {
+                    path: temporary_path.clone(),
+                    source,
+                })?;
+            Some(PageFile {
+                path: dest_dir.join(index_name),
+                temporary_path,
+                writer: tokio::io::BufWriter::new(file),
+                mtime: info.updated_datetime,
+            })
+        } else {
+            None
+        };
+        let mut page = Self {
+            file,
+            lightbox: options.lightbox && document.has_images(),
+            on_conflict: options.on_conflict,
+        };
+
+        if let Some(cover) = &document.cover {
+            tracing::info!("Download cover image {}", cover.url);
+            super::download(
+                client,
+                &cover.url,
+                &dest_dir.join(&cover.path),
+                info,
+                options,
+            )
+            .await?;
+        }
+        for line in render_top(document, options.lang, page.lightbox) {
+            page.write_line(&line).await?;
+        }
+        Ok(page)
+    }
+
+    /// Appends a line to the body of the page.
+    pub(crate) async fn write_line(&mut self, line: &str) -> Result<(), super::Error> {
+        use tokio::io::AsyncWriteExt as _;
+
+        if let Some(file) = &mut self.file {
+            let result = match file.writer.write_all(line.as_bytes()).await {
+                Ok(()) => file.writer.write_all(b"\n").await,
+                Err(e) => Err(e),
+            };
+            result.map_err(|source| super::Error::Write {
+                path: file.temporary_path.clone(),
+                source,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Closes the body and replaces the page with the written one, or writes it to another path
+    /// chosen by `options.on_conflict` if the page exists.
+    pub(crate) async fn finish(mut self) -> Result<(), super::Error> {
+        use tokio::io::AsyncWriteExt as _;
+
+        for line in render_bottom(self.lightbox) {
+            self.write_line(line).await?;
+        }
+        let mut file = match self.file.take() {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+        let result = match file.writer.write_all(b"</html>").await {
+            Ok(()) => file.writer.shutdown().await,
+            Err(e) => Err(e),
+        };
+        let path = match result {
+            Ok(()) => super::conflict::resolve(&file.path, self.on_conflict),
+            Err(source) => Err(super::Error::Write {
+                path: file.temporary_path.clone(),
+                source,
+            }),
+        };
+        let path = match path {
+            Ok(Some(path)) => path,
+            Ok(None) => {
+                let _ = std::fs::remove_file(&file.temporary_path);
+                return Ok(());
+            }
+            Err(e) => {
+                let _ = std::fs::remove_file(&file.temporary_path);
+                return Err(e);
+            }
+        };
+        if let Err(source) = std::fs::rename(&file.temporary_path, &path) {
+            let _ = std::fs::remove_file(&file.temporary_path);
+            return Err(super::Error::Write { path, source });
+        }
+        filetime::set_file_mtime(
+            &path,
+            filetime::FileTime::from_unix_time(
+                file.mtime.timestamp(),
+                file.mtime.timestamp_subsec_nanos(),
+            ),
+        )
+        .map_err(|source| super::Error::SetMtime { path, source })
+    }
+}
+
+impl Drop for PageWriter {
+    fn drop(&mut self) {
+        if let Some(file) = &self.file {
+            let _ = std::fs::remove_file(&file.temporary_path);
+        }
+    }
+}
+
+/// Renders the lines of the page up to the cover image, which is expected to be downloaded.
+pub(crate) fn render_top(
+    document: &super::PostDocument,
+    lang: Lang,
+    lightbox: bool,
+) -> Vec<String> {
+    let mut lines = render_head(document, lang, lightbox);
+    lines.push(format!(
+        "<h1><a href='{}'>{}</a></h1>",
+        document.url, document.title
+    ));
+    lines.push(render_byline(document, lang));
+    if let Some(cover) = &document.cover {
+        lines.push("<p>".to_owned());
+        lines.push(format!("<img alt='{}' src='./{}'>", cover.url, cover.path));
+        lines.push("</p>".to_owned());
+    }
+    lines
+}
+
+/// Renders the lines of the page after the blocks except the last `</html>`.
+pub(crate) fn render_bottom(lightbox: bool) -> Vec<&'static str> {
+    let mut lines = Vec::new();
+    if lightbox {
+        lines.push(super::lightbox::SCRIPT);
+    }
+    lines.push("</body>");
+    lines
+}
+
+/// Renders the content of the block whose assets are expected to be downloaded, linking
+/// embedded posts to FANBOX.
+pub(crate) fn render_block_content(block: &super::Block) -> Option<String> {
+    match block {
+        super::Block::Paragraph { text } => Some(text.clone()),
+        super::Block::Header { text } => Some(format!("<h2>{}</h2>", text)),
+        super::Block::Image { asset, .. } => Some(format!(
+            "<img alt='{}' src='./{}' style='width: 100%;'>",
+            asset.url, asset.path
+        )),
+        super::Block::File { name, asset, .. } => {
+            Some(format!("<a href='./{}'>{}</a>", asset.path, name))
+        }
+        super::Block::Video { url, .. } => Some(format!("<a href='{}'>{}</a>", url, url)),
+        super::Block::Post { post_id, url, .. } => {
+            Some(format!("<a href='{}'>{}</a>", url, post_id))
+        }
+        super::Block::Link { url, text } => Some(format!("<a href='{}'>{}</a>", url, text)),
+        super::Block::Html { html } => Some(html.clone()),
+        super::Block::Unknown { .. } => None,
+    }
+}
+
+/// Renders the lines of the block with its content rendered.
+///
+/// Blocks of articles are wrapped in paragraphs, while images and text of other posts are
+/// paragraphs by themselves.
+pub(crate) fn render_block(
+    kind: super::PostKind,
+    block: &super::Block,
+    content: Option<String>,
+) -> Vec<String> {
+    match (kind, block, content) {
+        (
+            super::PostKind::Image
+            | super::PostKind::File
+            | super::PostKind::Text
+            | super::PostKind::Video,
+            super::Block::Paragraph { .. } | super::Block::Image { .. },
+            Some(content),
+        ) => vec![format!("<p>{}</p>", content)],
+        (_, _, content) => {
+            let mut lines = vec!["<p>".to_owned()];
+            lines.extend(content);
+            lines.push("</p>".to_owned());
+            lines
+        }
+    }
+}
+
+/// Renders the lines up to `<body>` with OpenGraph and metadata tags of the post.
+fn render_head(document: &super::PostDocument, lang: Lang, lightbox: bool) -> Vec<String> {
+    let mut lines = vec![
+        "<!DOCTYPE html>".to_owned(),
+        format!("<html lang='{}'>", lang.tag()),
+        "<head>".to_owned(),
+        "<meta charset='utf-8'>".to_owned(),
+        format!(
+            "<title>{}</title>",
+            super::feed::escape_xml(&document.title)
+        ),
+        format!(
+            "<meta property='og:title' content='{}'>",
+            super::feed::escape_xml(&document.title)
+        ),
+        "<meta property='og:type' content='article'>".to_owned(),
+        format!(
+            "<meta property='og:url' content='{}'>",
+            super::feed::escape_xml(&document.url)
+        ),
+        "<meta property='og:site_name' content='pixivFANBOX'>".to_owned(),
+    ];
+    if let Some(cover) = &document.cover {
+        lines.push(format!(
+            "<meta property='og:image' content='./{}'>",
+            cover.path
+        ));
+    }
+    let mut meta = vec![
+        ("author", document.creator_name.clone()),
+        ("fanbox:creator-id", document.creator_id.clone()),
+        (
+            "article:published_time",
+            document.published_datetime.to_rfc3339(),
+        ),
+        (
+            "article:modified_time",
+            document.updated_datetime.to_rfc3339(),
+        ),
+    ];
+    if let Some(fee_required) = document.fee_required {
+        meta.push(("fanbox:fee-required", fee_required.to_string()));
+    }
+    meta.extend(document.tags.iter().map(|tag| ("article:tag", tag.clone())));
+    for (name, content) in meta {
+        let attribute = if name.starts_with("article:") {
+            "property"
+        } else {
+            "name"
+        };
+        lines.push(format!(
+            "<meta {}='{}' content='{}'>",
+            attribute,
+            name,
+            super::feed::escape_xml(&content)
+        ));
+    }
+    if lightbox {
+        lines.push(super::lightbox::STYLE.to_owned());
+    }
+    lines.push("</head>".to_owned());
+    lines.push("<body>".to_owned());
+    lines
+}
+
+/// Renders the creator, dates, fee and tags of the post shown below the heading.
+fn render_byline(document: &super::PostDocument, lang: Lang) -> String {
+    let mut parts = vec![
+        format!(
+            "<a href='https://{}.fanbox.cc/'>{}</a>",
+            super::feed::escape_xml(&document.creator_id),
+            super::feed::escape_xml(&document.creator_name)
+        ),
+        format!(
+            "{} <time datetime='{}'>{}</time>",
+            lang.label(Label::Published),
+            document.published_datetime.to_rfc3339(),
+            document.published_datetime.format("%Y-%m-%d %H:%M")
+        ),
+    ];
+    if document.updated_datetime != document.published_datetime {
+        parts.push(format!(
+            "{} <time datetime='{}'>{}</time>",
+            lang.label(Label::Updated),
+            document.updated_datetime.to_rfc3339(),
+            document.updated_datetime.format("%Y-%m-%d %H:%M")
+        ));
+    }
+    if let Some(fee_required) = document.fee_required {
+        parts.push(lang.fee_plan(fee_required));
+    }
+    if !document.tags.is_empty() {
+        parts.push(
+            document
+                .tags
+                .iter()
+                .map(|tag| format!("#{}", super::feed::escape_xml(tag)))
+                .collect::<Vec<_>>()
+                .join(" "),
+        );
+    }
+    format!("<p class='byline'>{}</p>", parts.join(" · "))
+}
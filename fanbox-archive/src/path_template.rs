@@ -0,0 +1,381 @@
+/// Template of paths of posts and their media relative to the destination directory, e.g.
+/// `{creator_id}/{published:%Y-%m}/{post_id}_{title}/{index}_{filename}`.
+///
+/// Components but the last one give the post directory and the last one gives names of images
+/// and files in it. Placeholders are:
+///
+/// - `{creator_id}`, `{post_id}` (or `{id}`), `{title}`, `{fee}` and `{type}` of the post
+/// - `{published}` and `{updated}` datetimes in UTC, formatted by strftime as `{published:%Y-%m}`
+///   [default: `%Y-%m-%d`]
+/// - `{index}` of the image or file in the post from 1, zero-padded as `{index:03}`, `{file_id}`,
+///   `{name}` (original name of files or the id of images), `{ext}` and `{filename}`
+///   (`{name}.{ext}`), only in the last component
+///
+/// `{{` and `}}` are literal braces. Characters which cannot be used in file names on Windows are
+/// replaced with `_` in rendered components.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathTemplate {
+    template: String,
+    dirs: Vec<Vec<Segment>>,
+    file: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Post(PostField, Option<String>),
+    File(FileField, Option<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PostField {
+    CreatorId,
+    PostId,
+    Title,
+    Fee,
+    Type,
+    Published,
+    Updated,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileField {
+    Index,
+    FileId,
+    Name,
+    Ext,
+    FileName,
+}
+
+/// Longest component in bytes, leaving room for disambiguation within the usual limit of 255
+const MAX_COMPONENT_LEN: usize = 200;
+
+impl std::str::FromStr for PathTemplate {
+    type Err = super::Error;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let invalid = |message: String| super::Error::InvalidTemplate {
+            template: template.to_owned(),
+            message,
+        };
+        let mut components = Vec::new();
+        for component in template.split(['/', '\\']) {
+            if component.is_empty() || component == "." || component == ".." {
+                return Err(invalid(format!("invalid component {:?}", component)));
+            }
+            components.push(parse_component(component).map_err(invalid)?);
+        }
+        let file = components.pop().unwrap_or_default();
+        if components.is_empty() {
+            return Err(invalid("the post directory is missing".to_owned()));
+        }
+        if components
+            .iter()
+            .flatten()
+            .any(|segment| matches!(segment, Segment::File(..)))
+        {
+            return Err(invalid(
+                "placeholders of files are allowed only in the last component".to_owned(),
+            ));
+        }
+        if !file
+            .iter()
+            .any(|segment| matches!(segment, Segment::File(..)))
+        {
+            return Err(invalid(
+                "the last component needs a placeholder of files, e.g. {filename}".to_owned(),
+            ));
+        }
+        Ok(Self {
+            template: template.to_owned(),
+            dirs: components,
+            file,
+        })
+    }
+}
+
+impl std::fmt::Display for PathTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+impl PathTemplate {
+    /// Returns the directory of the post in `dest_dir`.
+    ///
+    /// When the directory is taken by another post, e.g. of the same title, the post id is
+    /// appended to its last component.
+    pub fn post_dir(
+        &self,
+        dest_dir: &std::path::Path,
+        post: &fanbox_api::Post,
+    ) -> std::path::PathBuf {
+        let info = &post.info;
+        let kind = super::PostDocument::new(post).kind;
+        let mut dir = dest_dir.to_path_buf();
+        let mut last = String::new();
+        for (i, segments) in self.dirs.iter().enumerate() {
+            let component = sanitize_component(&render(segments, info, kind, None));
+            if i + 1 == self.dirs.len() {
+                last = component;
+            } else {
+                dir.push(component);
+            }
+        }
+        let post_dir = dir.join(&last);
+        match archived_post_id(&post_dir) {
+            Some(post_id) if post_id != info.id => {
+                dir.join(sanitize_component(&format!("{}_{}", last, info.id)))
+            }
+            _ => post_dir,
+        }
+    }
+
+    /// Renames images and files of the document by the last component, disambiguating names
+    /// taken by other media of the post. The same media referenced twice keeps one name.
+    pub fn rename_assets(&self, info: &fanbox_api::PostInfo, document: &mut super::PostDocument) {
+        let kind = document.kind;
+        let mut names_by_url = std::collections::HashMap::<String, String>::new();
+        let mut used = std::collections::HashSet::new();
+        let mut index = 0;
+        for block in &mut document.blocks {
+            let (id, name, asset) = match block {
+                super::Block::Image { id, asset } => (id.as_str(), id.as_str(), asset),
+                super::Block::File { id, name, asset } => (id.as_str(), name.as_str(), asset),
+                _ => continue,
+            };
+            if let Some(path) = names_by_url.get(&asset.url) {
+                asset.path = path.clone();
+                continue;
+            }
+            index += 1;
+            let ext = asset
+                .path
+                .rsplit_once('.')
+                .map_or("", |(_, extension)| extension);
+            let media = Media {
+                index,
+                id,
+                name,
+                ext,
+            };
+            let file_name = sanitize_component(&render(&self.file, info, kind, Some(&media)));
+            let path = disambiguate(&file_name, &mut used);
+            names_by_url.insert(asset.url.clone(), path.clone());
+            asset.path = path;
+        }
+    }
+}
+
+/// Image or file rendered into the last component
+struct Media<'a> {
+    index: usize,
+    id: &'a str,
+    name: &'a str,
+    ext: &'a str,
+}
+
+fn parse_component(component: &str) -> Result<Vec<Segment>, String> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut chars = component.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err(format!("unclosed placeholder {{{}", placeholder)),
+                    }
+                }
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                }
+                segments.push(parse_placeholder(&placeholder)?);
+            }
+            '}' => return Err("unmatched }".to_owned()),
+            c => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+    Ok(segments)
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<Segment, String> {
+    let (name, spec) = match placeholder.split_once(':') {
+        Some((name, spec)) => (name, Some(spec.to_owned())),
+        None => (placeholder, None),
+    };
+    let segment = match name {
+        "creator_id" => Segment::Post(PostField::CreatorId, None),
+        "post_id" | "id" => Segment::Post(PostField::PostId, None),
+        "title" => Segment::Post(PostField::Title, None),
+        "fee" => Segment::Post(PostField::Fee, None),
+        "type" => Segment::Post(PostField::Type, None),
+        "published" | "updated" => {
+            if let Some(spec) = &spec {
+                let invalid = chrono::format::StrftimeItems::new(spec)
+                    .any(|item| matches!(item, chrono::format::Item::Error));
+                if invalid {
+                    return Err(format!("invalid datetime format {:?}", spec));
+                }
+            }
+            let field = if name == "published" {
+                PostField::Published
+            } else {
+                PostField::Updated
+            };
+            return Ok(Segment::Post(field, spec));
+        }
+        "index" => {
+            if let Some(spec) = &spec {
+                if spec.parse::<usize>().is_err() {
+                    return Err(format!("invalid width {:?} of index", spec));
+                }
+            }
+            return Ok(Segment::File(FileField::Index, spec));
+        }
+        "file_id" => Segment::File(FileField::FileId, None),
+        "name" => Segment::File(FileField::Name, None),
+        "ext" => Segment::File(FileField::Ext, None),
+        "filename" => Segment::File(FileField::FileName, None),
+        _ => return Err(format!("unknown placeholder {{{}}}", placeholder)),
+    };
+    match spec {
+        Some(_) => Err(format!("{{{}}} doesn't take a format", name)),
+        None => Ok(segment),
+    }
+}
+
+fn render(
+    segments: &[Segment],
+    info: &fanbox_api::PostInfo,
+    kind: super::PostKind,
+    media: Option<&Media<'_>>,
+) -> String {
+    let mut rendered = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Literal(literal) => rendered.push_str(literal),
+            Segment::Post(field, spec) => {
+                let format = spec.as_deref().unwrap_or("%Y-%m-%d");
+                match field {
+                    PostField::CreatorId => rendered.push_str(&info.creator_id),
+                    PostField::PostId => rendered.push_str(&info.id),
+                    PostField::Title => rendered.push_str(&info.title),
+                    PostField::Fee => {
+                        rendered.push_str(&info.fee_required.unwrap_or_default().to_string())
+                    }
+                    PostField::Type => rendered.push_str(kind.name()),
+                    PostField::Published => {
+                        rendered.push_str(&info.published_datetime.format(format).to_string())
+                    }
+                    PostField::Updated => {
+                        rendered.push_str(&info.updated_datetime.format(format).to_string())
+                    }
+                }
+            }
+            Segment::File(field, spec) => {
+                let media = match media {
+                    Some(media) => media,
+                    None => continue,
+                };
+                match field {
+                    FileField::Index => {
+                        let width = spec.as_deref().map_or(0, |spec| spec.parse().unwrap_or(0));
+                        rendered.push_str(&format!("{:0width$}", media.index, width = width));
+                    }
+                    FileField::FileId => rendered.push_str(media.id),
+                    FileField::Name => rendered.push_str(media.name),
+                    FileField::Ext => rendered.push_str(media.ext),
+                    FileField::FileName => {
+                        rendered.push_str(media.name);
+                        if !media.ext.is_empty() {
+                            rendered.push('.');
+                            rendered.push_str(media.ext);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    rendered
+}
+
+/// Replaces characters which cannot be used in file names on Windows, trims spaces and dots
+/// which Windows drops and truncates long components, keeping their extensions.
+fn sanitize_component(component: &str) -> String {
+    let sanitized: String = super::normalize_name(component)
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let sanitized = sanitized.trim().trim_end_matches('.').trim_end();
+    let sanitized = truncate(sanitized);
+    if sanitized.is_empty() || sanitized.chars().all(|c| c == '.') {
+        "_".to_owned()
+    } else {
+        sanitized
+    }
+}
+
+fn truncate(component: &str) -> String {
+    if component.len() <= MAX_COMPONENT_LEN {
+        return component.to_owned();
+    }
+    let (stem, extension) = match component.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() && extension.len() <= 16 => (stem, extension),
+        _ => (component, ""),
+    };
+    let mut end = MAX_COMPONENT_LEN.saturating_sub(extension.len() + 1);
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    let stem = stem[..end].trim_end();
+    if extension.is_empty() {
+        stem.to_owned()
+    } else {
+        format!("{}.{}", stem, extension)
+    }
+}
+
+/// Returns `name`, or `<stem> (<n>).<extension>` when it's already used, ignoring case for
+/// case-insensitive file systems.
+fn disambiguate(name: &str, used: &mut std::collections::HashSet<String>) -> String {
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+        _ => (name, None),
+    };
+    let mut candidate = name.to_owned();
+    let mut n = 0;
+    while !used.insert(candidate.to_lowercase()) {
+        n += 1;
+        candidate = match extension {
+            Some(extension) => format!("{} ({}).{}", stem, n, extension),
+            None => format!("{} ({})", stem, n),
+        };
+    }
+    candidate
+}
+
+/// Returns the id of the post archived in `post_dir`, if any.
+fn archived_post_id(post_dir: &std::path::Path) -> Option<String> {
+    let json = std::fs::read(post_dir.join(super::METADATA_FILE_NAME)).ok()?;
+    let post: fanbox_api::Post = serde_json::from_slice(&json).ok()?;
+    Some(post.info.id)
+}
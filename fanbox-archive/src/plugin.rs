@@ -0,0 +1,198 @@
+//! WebAssembly plugins hooking into archiving and rendering of posts.
+//!
+//! A plugin is a WebAssembly module without imports which exports `memory` and
+//! `alloc(len: i32) -> i32` returning a buffer of `len` bytes in the memory, and any of the
+//! following hooks. Values are passed as UTF-8 JSON written into buffers allocated by `alloc`,
+//! and returned as `(ptr << 32) | len` of a buffer in the memory.
+//!
+//! - `transform_document(ptr: i32, len: i32) -> i64` takes a `PostDocument` and returns the
+//!   transformed one, or 0 to keep it
+//! - `should_download(ptr: i32, len: i32) -> i32` takes `{"document": PostDocument, "asset":
+//!   Asset}` and returns 0 to skip downloading the asset
+//! - `render_document(ptr: i32, len: i32) -> i64` takes a `PostDocument` and returns an array
+//!   of `{"name": string, "content": string}` written into the post directory, or 0 for none
+//!
+//! Plugins run on the wasmi interpreter rather than wasmtime, which compiles modules into native
+//! code. The hooks are small compared to downloads, and wasmi avoids the JIT, its build time and
+//! its platform support matrix. Each call is given a bounded amount of fuel, so a plugin running
+//! into an infinite loop fails the call instead of hanging the archiver.
+
+/// Fuel given to each call of a plugin by default, which is roughly the number of executed
+/// instructions.
+const DEFAULT_FUEL_PER_CALL: u64 = 1_000_000_000;
+
+/// WebAssembly plugin loaded from a file, run on the wasmi interpreter with a bounded number of
+/// instructions per call.
+pub struct WasmPlugin {
+    path: std::path::PathBuf,
+    store: std::sync::Mutex<wasmi::Store<()>>,
+    instance: wasmi::Instance,
+    memory: wasmi::Memory,
+    alloc: wasmi::TypedFunc<i32, i32>,
+    fuel_per_call: u64,
+}
+
+impl std::fmt::Debug for WasmPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmPlugin")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WasmPlugin {
+    /// Loads and instantiates the plugin at `path`.
+    pub fn load<P>(path: P) -> Result<Self, super::Error>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let path = path.as_ref();
+        let wasm = std::fs::read(path).map_err(|source| super::Error::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let map_err = |e: &dyn std::fmt::Display| super::Error::Plugin {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        };
+        let mut config = wasmi::Config::default();
+        config.consume_fuel(true);
+        let engine = wasmi::Engine::new(&config);
+        let module = wasmi::Module::new(&engine, &wasm).map_err(|e| map_err(&e))?;
+        let mut store = wasmi::Store::new(&engine, ());
+        // The start function is also bounded
+        store
+            .set_fuel(DEFAULT_FUEL_PER_CALL)
+            .map_err(|e| map_err(&e))?;
+        let instance = wasmi::Linker::<()>::new(&engine)
+            .instantiate(&mut store, &module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| map_err(&e))?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| map_err(&"memory is not exported"))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&store, "alloc")
+            .map_err(|e| map_err(&e))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            store: std::sync::Mutex::new(store),
+            instance,
+            memory,
+            alloc,
+            fuel_per_call: DEFAULT_FUEL_PER_CALL,
+        })
+    }
+
+    /// Fails calls of the plugin executing more than about `fuel` instructions, 1,000,000,000 by
+    /// default.
+    pub fn fuel_per_call(mut self, fuel: u64) -> Self {
+        self.fuel_per_call = fuel;
+        self
+    }
+
+    fn error(&self, e: &dyn std::fmt::Display) -> super::Error {
+        super::Error::Plugin {
+            path: self.path.clone(),
+            message: e.to_string(),
+        }
+    }
+
+    /// Calls the exported `name` with `input` serialized as JSON and returns its result, or
+    /// `None` if the plugin doesn't export it.
+    fn call<I, R>(&self, name: &str, input: &I) -> Result<Option<R>, super::Error>
+    where
+        I: serde::Serialize,
+        R: wasmi::WasmResults,
+    {
+        let mut store = self.store.lock().unwrap();
+        let func = match self.instance.get_typed_func::<(i32, i32), R>(&*store, name) {
+            Ok(func) => func,
+            Err(_) => return Ok(None),
+        };
+        let input = serde_json::to_vec(input).map_err(|e| self.error(&e))?;
+        let len = i32::try_from(input.len()).map_err(|e| self.error(&e))?;
+        // Fuel is shared by alloc and the hook, and left fuel isn't carried over to the next call
+        store
+            .set_fuel(self.fuel_per_call)
+            .map_err(|e| self.error(&e))?;
+        let ptr = self
+            .alloc
+            .call(&mut *store, len)
+            .map_err(|e| self.error(&e))?;
+        self.memory
+            .write(&mut *store, ptr as u32 as usize, &input)
+            .map_err(|e| self.error(&e))?;
+        func.call(&mut *store, (ptr, len))
+            .map(Some)
+            .map_err(|e| self.error(&e))
+    }
+
+    /// Reads JSON from the buffer returned as `(ptr << 32) | len`, which is `None` for 0.
+    fn read_json<T>(&self, packed: i64) -> Result<Option<T>, super::Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        if packed == 0 {
+            return Ok(None);
+        }
+        let store = self.store.lock().unwrap();
+        let ptr = (packed as u64 >> 32) as usize;
+        let len = (packed as u64 & 0xffff_ffff) as usize;
+        let mut buffer = vec![0; len];
+        self.memory
+            .read(&*store, ptr, &mut buffer)
+            .map_err(|e| self.error(&e))?;
+        serde_json::from_slice(&buffer)
+            .map(Some)
+            .map_err(|e| self.error(&e))
+    }
+}
+
+impl super::Hook for WasmPlugin {
+    fn transform_document(
+        &self,
+        document: super::PostDocument,
+    ) -> Result<super::PostDocument, super::Error> {
+        match self.call::<_, i64>("transform_document", &document)? {
+            Some(packed) => Ok(self.read_json(packed)?.unwrap_or(document)),
+            None => Ok(document),
+        }
+    }
+
+    fn should_download(
+        &self,
+        document: &super::PostDocument,
+        asset: &super::Asset,
+    ) -> Result<bool, super::Error> {
+        let input = serde_json::json!({ "document": document, "asset": asset });
+        Ok(self.call::<_, i32>("should_download", &input)? != Some(0))
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct PluginFile {
+    name: String,
+    content: String,
+}
+
+impl super::Renderer for WasmPlugin {
+    fn render_document(
+        &self,
+        document: &super::PostDocument,
+    ) -> Result<Vec<super::RenderedFile>, super::Error> {
+        let files: Option<Vec<PluginFile>> =
+            match self.call::<_, i64>("render_document", document)? {
+                Some(packed) => self.read_json(packed)?,
+                None => None,
+            };
+        Ok(files
+            .unwrap_or_default()
+            .into_iter()
+            .map(|file| super::RenderedFile {
+                name: file.name,
+                content: file.content.into_bytes(),
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,76 @@
+//! Read-only post directories guarding the archive against accidental modifications.
+//!
+//! Clearing the write bits, or setting the read-only attribute on Windows, of the files and the
+//! directory of a post keeps other tools from modifying or adding files there. The post directory
+//! is made writable again before the post is archived again.
+
+/// Makes the files in the post directory `dir` and the directory itself read-only.
+pub(crate) fn protect_dir(dir: &std::path::Path) -> Result<(), super::Error> {
+    for path in list_files(dir)? {
+        set_writable(&path, false)?;
+    }
+    set_writable(dir, false)
+}
+
+/// Makes the post directory `dir` and the files in it writable if it's protected.
+pub(crate) fn unprotect_dir(dir: &std::path::Path) -> Result<(), super::Error> {
+    if !is_readonly(dir)? {
+        return Ok(());
+    }
+    set_writable(dir, true)?;
+    for path in list_files(dir)? {
+        set_writable(&path, true)?;
+    }
+    Ok(())
+}
+
+/// Returns whether `path` is read-only, which is false when it doesn't exist.
+pub(crate) fn is_readonly(path: &std::path::Path) -> Result<bool, super::Error> {
+    match std::fs::metadata(path) {
+        Ok(metadata) => Ok(metadata.permissions().readonly()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(source) => Err(super::Error::Read {
+            path: path.to_path_buf(),
+            source,
+        }),
+    }
+}
+
+/// Clears or restores the write permission of the owner of `path`.
+pub(crate) fn set_writable(path: &std::path::Path, writable: bool) -> Result<(), super::Error> {
+    let map_err = |source| super::Error::SetPermissions {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut permissions = std::fs::metadata(path).map_err(map_err)?.permissions();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let mode = permissions.mode();
+        permissions.set_mode(if writable {
+            mode | 0o200
+        } else {
+            mode & !0o222
+        });
+    }
+    #[cfg(not(unix))]
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(!writable);
+    std::fs::set_permissions(path, permissions).map_err(map_err)
+}
+
+fn list_files(dir: &std::path::Path) -> Result<Vec<std::path::PathBuf>, super::Error> {
+    let map_err = |source| super::Error::Read {
+        path: dir.to_path_buf(),
+        source,
+    };
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).map_err(map_err)? {
+        let entry = entry.map_err(map_err)?;
+        if entry.file_type().map_err(map_err)?.is_file() {
+            files.push(entry.path());
+        }
+    }
+    Ok(files)
+}
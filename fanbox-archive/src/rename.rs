@@ -0,0 +1,45 @@
+/// Moves files keyed by the creator id from `old_creator_id` to `new_creator_id` under
+/// `dest_dir`: the gallery-dl directory, the per-creator directory and the Atom feed.
+///
+/// Files are kept when the destination already exists. Returns the moved pairs of paths.
+pub fn relink_creator(
+    dest_dir: &std::path::Path,
+    old_creator_id: &str,
+    new_creator_id: &str,
+) -> Result<Vec<(std::path::PathBuf, std::path::PathBuf)>, super::Error> {
+    let candidates = [
+        (
+            dest_dir.join("fanbox").join(old_creator_id),
+            dest_dir.join("fanbox").join(new_creator_id),
+        ),
+        (dest_dir.join(old_creator_id), dest_dir.join(new_creator_id)),
+        (
+            dest_dir
+                .join("feeds")
+                .join(format!("{}.atom", old_creator_id)),
+            dest_dir
+                .join("feeds")
+                .join(format!("{}.atom", new_creator_id)),
+        ),
+    ];
+    let mut moved = Vec::new();
+    for (from, to) in candidates {
+        if !from.exists() {
+            continue;
+        }
+        if to.exists() {
+            tracing::warn!(
+                "Keep {} since {} already exists",
+                from.display(),
+                to.display()
+            );
+            continue;
+        }
+        std::fs::rename(&from, &to).map_err(|source| super::Error::Write {
+            path: to.clone(),
+            source,
+        })?;
+        moved.push((from, to));
+    }
+    Ok(moved)
+}
@@ -0,0 +1,76 @@
+//! Renderers turning documents of posts into files in their post directories.
+
+/// File written into the post directory by a `Renderer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedFile {
+    /// File name relative to the post directory
+    pub name: String,
+    pub content: Vec<u8>,
+}
+
+/// Renderer of documents of posts, run by `archive_post` after images and files of the post are
+/// downloaded next to the rendered files.
+///
+/// Implement this for custom output formats and add it to `ArchiveOptions::renderers`.
+pub trait Renderer: std::fmt::Debug + Send + Sync {
+    fn render_document(
+        &self,
+        document: &super::PostDocument,
+    ) -> Result<Vec<RenderedFile>, super::Error>;
+}
+
+/// Renders the HTML page of the post like `Format::Html`, linking embedded posts to FANBOX.
+#[derive(Debug, Clone, Default)]
+pub struct HtmlRenderer {
+    /// Language of labels in the page
+    pub lang: super::Lang,
+    /// Embed a lightbox viewer of images into pages of posts with images
+    pub lightbox: bool,
+    /// File name of the page where `{id}` is replaced by the post id [default: index.html]
+    pub index_name: Option<String>,
+}
+
+impl Renderer for HtmlRenderer {
+    fn render_document(
+        &self,
+        document: &super::PostDocument,
+    ) -> Result<Vec<RenderedFile>, super::Error> {
+        let lightbox = self.lightbox && document.has_images();
+        let mut lines = super::page::render_top(document, self.lang, lightbox);
+        for block in &document.blocks {
+            let content = super::page::render_block_content(block);
+            lines.extend(super::page::render_block(document.kind, block, content));
+        }
+        lines.extend(
+            super::page::render_bottom(lightbox)
+                .into_iter()
+                .map(ToOwned::to_owned),
+        );
+        lines.push("</html>".to_owned());
+        let name = self
+            .index_name
+            .as_deref()
+            .unwrap_or(super::DEFAULT_INDEX_NAME)
+            .replace("{id}", &document.id);
+        Ok(vec![RenderedFile {
+            name,
+            content: lines.join("\n").into_bytes(),
+        }])
+    }
+}
+
+/// Renders index.md with YAML front matter like `Format::Hugo`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkdownRenderer;
+
+impl Renderer for MarkdownRenderer {
+    fn render_document(
+        &self,
+        document: &super::PostDocument,
+    ) -> Result<Vec<RenderedFile>, super::Error> {
+        Ok(vec![RenderedFile {
+            name: "index.md".to_owned(),
+            content: super::markdown::render(document, &super::markdown::Links::Hugo).into_bytes(),
+        }])
+    }
+}
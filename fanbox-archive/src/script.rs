@@ -0,0 +1,133 @@
+//! Rhai scripts hooking into archiving of posts.
+//!
+//! A script defines any of the following functions, which take the post as a `PostDocument`
+//! object and the image or file as an `Asset` object.
+//!
+//! - `skip_post(post)` returns true to skip archiving the post
+//! - `skip_asset(post, asset)` returns true to skip downloading the image or file
+//! - `rename_asset(post, asset)` returns the file name in the post directory which the image or
+//!   file is downloaded to, or `()` to keep `<id>.<extension>`
+
+/// Hook running functions defined by a Rhai script.
+pub struct ScriptHook {
+    path: std::path::PathBuf,
+    engine: rhai::Engine,
+    ast: rhai::AST,
+}
+
+impl std::fmt::Debug for ScriptHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScriptHook")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ScriptHook {
+    /// Compiles the script at `path`.
+    pub fn load<P>(path: P) -> Result<Self, super::Error>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let path = path.as_ref();
+        let engine = rhai::Engine::new();
+        let ast = engine
+            .compile_file(path.to_path_buf())
+            .map_err(|e| super::Error::Script {
+                path: path.to_path_buf(),
+                message: e.to_string(),
+            })?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            engine,
+            ast,
+        })
+    }
+
+    fn error(&self, e: &dyn std::fmt::Display) -> super::Error {
+        super::Error::Script {
+            path: self.path.clone(),
+            message: e.to_string(),
+        }
+    }
+
+    /// Calls the function `name` of the script with `args`, or returns `None` if the script
+    /// doesn't define it.
+    fn call(
+        &self,
+        name: &str,
+        args: Vec<rhai::Dynamic>,
+    ) -> Result<Option<rhai::Dynamic>, super::Error> {
+        let defined = self
+            .ast
+            .iter_functions()
+            .any(|f| f.name == name && f.params.len() == args.len());
+        if !defined {
+            return Ok(None);
+        }
+        self.engine
+            .call_fn::<rhai::Dynamic>(&mut rhai::Scope::new(), &self.ast, name, args)
+            .map(Some)
+            .map_err(|e| self.error(&e))
+    }
+
+    fn to_dynamic<T>(&self, value: &T) -> Result<rhai::Dynamic, super::Error>
+    where
+        T: serde::Serialize,
+    {
+        rhai::serde::to_dynamic(value).map_err(|e| self.error(&e))
+    }
+
+    fn call_predicate(&self, name: &str, args: Vec<rhai::Dynamic>) -> Result<bool, super::Error> {
+        match self.call(name, args)? {
+            Some(result) => result
+                .as_bool()
+                .map_err(|type_name| self.error(&format!("{} returned {}", name, type_name))),
+            None => Ok(false),
+        }
+    }
+}
+
+impl super::Hook for ScriptHook {
+    fn should_archive(&self, document: &super::PostDocument) -> Result<bool, super::Error> {
+        let skip = self.call_predicate("skip_post", vec![self.to_dynamic(document)?])?;
+        Ok(!skip)
+    }
+
+    fn transform_document(
+        &self,
+        mut document: super::PostDocument,
+    ) -> Result<super::PostDocument, super::Error> {
+        let post = self.to_dynamic(&document)?;
+        for block in &mut document.blocks {
+            let (super::Block::Image { asset, .. } | super::Block::File { asset, .. }) = block
+            else {
+                continue;
+            };
+            let args = vec![post.clone(), self.to_dynamic(asset)?];
+            let Some(name) = self.call("rename_asset", args)? else {
+                return Ok(document);
+            };
+            if name.is_unit() {
+                continue;
+            }
+            let name = name
+                .into_string()
+                .map_err(|type_name| self.error(&format!("rename_asset returned {}", type_name)))?;
+            if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+                return Err(self.error(&format!("rename_asset returned invalid name {:?}", name)));
+            }
+            asset.path = name;
+        }
+        Ok(document)
+    }
+
+    fn should_download(
+        &self,
+        document: &super::PostDocument,
+        asset: &super::Asset,
+    ) -> Result<bool, super::Error> {
+        let args = vec![self.to_dynamic(document)?, self.to_dynamic(asset)?];
+        Ok(!self.call_predicate("skip_asset", args)?)
+    }
+}
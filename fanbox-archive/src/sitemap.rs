@@ -0,0 +1,91 @@
+/// Writes `dest_dir/sitemap.xml` listing HTML pages of all archived posts and Atom feeds of
+/// creators under `base_url`, for serving the archive as a static site.
+///
+/// `dest_dir/robots.txt` pointing to the sitemap is written too unless it already exists, so
+/// that custom rules are kept.
+pub fn write_sitemap(
+    dest_dir: &std::path::Path,
+    base_url: &str,
+) -> Result<std::path::PathBuf, super::Error> {
+    let base_url = base_url.trim_end_matches('/');
+    let mut lines = vec![
+        "<?xml version='1.0' encoding='utf-8'?>".to_owned(),
+        "<urlset xmlns='http://www.sitemaps.org/schemas/sitemap/0.9'>".to_owned(),
+    ];
+    for archived in super::load_archived_posts(dest_dir)? {
+        let page = archived.dir.join(archived.index_file_name());
+        if !page.is_file() {
+            continue;
+        }
+        lines.push(render_url(
+            &format!("{}/{}", base_url, relative_url(dest_dir, &page)),
+            Some(&archived.post.info.updated_datetime),
+        ));
+    }
+    let feeds_dir = dest_dir.join("feeds");
+    if feeds_dir.is_dir() {
+        let entries = std::fs::read_dir(&feeds_dir).map_err(|source| super::Error::Read {
+            path: feeds_dir.clone(),
+            source,
+        })?;
+        let mut feeds = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|source| super::Error::Read {
+                path: feeds_dir.clone(),
+                source,
+            })?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.ends_with(".atom") {
+                feeds.push(name);
+            }
+        }
+        feeds.sort();
+        for name in feeds {
+            lines.push(render_url(&format!("{}/feeds/{}", base_url, name), None));
+        }
+    }
+    lines.push("</urlset>".to_owned());
+
+    let path = dest_dir.join("sitemap.xml");
+    std::fs::write(&path, lines.join("\n")).map_err(|source| super::Error::Write {
+        path: path.clone(),
+        source,
+    })?;
+
+    let robots_path = dest_dir.join("robots.txt");
+    if !robots_path.exists() {
+        std::fs::write(
+            &robots_path,
+            format!(
+                "User-agent: *\nAllow: /\nSitemap: {}/sitemap.xml\n",
+                base_url
+            ),
+        )
+        .map_err(|source| super::Error::Write {
+            path: robots_path,
+            source,
+        })?;
+    }
+    Ok(path)
+}
+
+fn render_url(loc: &str, lastmod: Option<&chrono::DateTime<chrono::Utc>>) -> String {
+    match lastmod {
+        Some(lastmod) => format!(
+            "<url><loc>{}</loc><lastmod>{}</lastmod></url>",
+            super::feed::escape_xml(loc),
+            lastmod.to_rfc3339()
+        ),
+        None => format!("<url><loc>{}</loc></url>", super::feed::escape_xml(loc)),
+    }
+}
+
+/// Returns the `/`-separated path of `path` relative to `dest_dir`.
+fn relative_url(dest_dir: &std::path::Path, path: &std::path::Path) -> String {
+    path.strip_prefix(dest_dir)
+        .unwrap_or(path)
+        .iter()
+        .map(|component| component.to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,412 @@
+/// Name of the state database in the destination directory
+pub const STATE_DB_FILE_NAME: &str = "fanbox-dl.sqlite3";
+
+/// Opens the state database in `dest_dir`, creating it if missing.
+pub(crate) fn open_connection(
+    dest_dir: &std::path::Path,
+) -> Result<(std::path::PathBuf, rusqlite::Connection), super::Error> {
+    let path = dest_dir.join(STATE_DB_FILE_NAME);
+    let conn = rusqlite::Connection::open(&path).map_err(|source| super::Error::StateDb {
+        path: path.clone(),
+        source,
+    })?;
+    Ok((path, conn))
+}
+
+/// Archived post recorded in the state database.
+#[derive(Debug, Clone)]
+pub struct PostState {
+    pub post_id: String,
+    pub user_id: Option<String>,
+    pub creator_id: String,
+    pub updated_datetime: chrono::DateTime<chrono::Utc>,
+}
+
+/// Run of the download command recorded in the state database.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunState {
+    pub started_datetime: chrono::DateTime<chrono::Utc>,
+    pub finished_datetime: chrono::DateTime<chrono::Utc>,
+    /// Number of new or updated posts
+    pub posts: u64,
+    /// Number of downloaded files
+    pub files: u64,
+    /// Bytes of downloaded files
+    pub bytes: u64,
+}
+
+/// Archive state of creators and posts, keyed by the creators' stable pixiv user ids rather than
+/// their creator ids which can be renamed.
+#[derive(Debug)]
+pub struct StateDb {
+    path: std::path::PathBuf,
+    conn: rusqlite::Connection,
+}
+
+impl StateDb {
+    /// Opens the state database in `dest_dir`, creating it if missing.
+    pub fn open(dest_dir: &std::path::Path) -> Result<Self, super::Error> {
+        let (path, conn) = open_connection(dest_dir)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS creators (
+                user_id TEXT PRIMARY KEY,
+                creator_id TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS creator_aliases (
+                creator_id TEXT PRIMARY KEY,
+                user_id TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS posts (
+                post_id TEXT PRIMARY KEY,
+                user_id TEXT,
+                creator_id TEXT NOT NULL,
+                updated_datetime TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS posts_user_id ON posts (user_id);
+            CREATE TABLE IF NOT EXISTS cover_images (
+                path TEXT PRIMARY KEY,
+                size INTEGER NOT NULL,
+                hash TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS cover_images_size_hash ON cover_images (size, hash);
+            CREATE TABLE IF NOT EXISTS media_files (
+                path TEXT PRIMARY KEY,
+                size INTEGER NOT NULL,
+                hash TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS media_files_size_hash ON media_files (size, hash);
+            CREATE TABLE IF NOT EXISTS backup_files (
+                path TEXT PRIMARY KEY,
+                size INTEGER NOT NULL,
+                mtime INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS runs (
+                started_datetime TEXT PRIMARY KEY,
+                finished_datetime TEXT NOT NULL,
+                posts INTEGER NOT NULL,
+                files INTEGER NOT NULL,
+                bytes INTEGER NOT NULL
+            )",
+        )
+        .map_err(|source| super::Error::StateDb {
+            path: path.clone(),
+            source,
+        })?;
+        Ok(Self { path, conn })
+    }
+
+    /// Records `creator_id` as the current creator id of the user.
+    ///
+    /// When the user was recorded with another creator id, it is kept as an alias and returned.
+    pub fn record_creator(
+        &self,
+        user_id: &str,
+        creator_id: &str,
+    ) -> Result<Option<String>, super::Error> {
+        use rusqlite::OptionalExtension as _;
+
+        let map_err = |source| super::Error::StateDb {
+            path: self.path.clone(),
+            source,
+        };
+        let previous: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT creator_id FROM creators WHERE user_id = ?1",
+                [user_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(map_err)?;
+        let renamed_from = previous.filter(|previous| previous != creator_id);
+        if let Some(old_creator_id) = &renamed_from {
+            self.conn
+                .execute(
+                    "INSERT OR REPLACE INTO creator_aliases (creator_id, user_id) VALUES (?1, ?2)",
+                    [old_creator_id.as_str(), user_id],
+                )
+                .map_err(map_err)?;
+        }
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO creators (user_id, creator_id) VALUES (?1, ?2)",
+                [user_id, creator_id],
+            )
+            .map_err(map_err)?;
+        Ok(renamed_from)
+    }
+
+    /// Returns former creator ids of the user who is currently `creator_id`.
+    pub fn creator_aliases(&self, creator_id: &str) -> Result<Vec<String>, super::Error> {
+        let map_err = |source| super::Error::StateDb {
+            path: self.path.clone(),
+            source,
+        };
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT creator_aliases.creator_id FROM creator_aliases
+                JOIN creators ON creator_aliases.user_id = creators.user_id
+                WHERE creators.creator_id = ?1
+                ORDER BY creator_aliases.creator_id",
+            )
+            .map_err(map_err)?;
+        let aliases = stmt
+            .query_map([creator_id], |row| row.get(0))
+            .and_then(|rows| rows.collect())
+            .map_err(map_err)?;
+        Ok(aliases)
+    }
+
+    /// Records the archived post.
+    pub fn record_post(&self, info: &fanbox_api::PostInfo) -> Result<(), super::Error> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO posts (post_id, user_id, creator_id, updated_datetime)
+                VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![
+                    info.id,
+                    info.user.as_ref().map(|user| &user.user_id),
+                    info.creator_id,
+                    info.updated_datetime.to_rfc3339(),
+                ],
+            )
+            .map_err(|source| super::Error::StateDb {
+                path: self.path.clone(),
+                source,
+            })?;
+        Ok(())
+    }
+
+    /// Records the cover image at `path` relative to the destination directory.
+    pub(crate) fn record_cover_image(
+        &self,
+        path: &std::path::Path,
+        size: u64,
+        hash: u64,
+    ) -> Result<(), super::Error> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO cover_images (path, size, hash) VALUES (?1, ?2, ?3)",
+                rusqlite::params![
+                    path.to_string_lossy(),
+                    size as i64,
+                    format!("{:016x}", hash)
+                ],
+            )
+            .map_err(|source| super::Error::StateDb {
+                path: self.path.clone(),
+                source,
+            })?;
+        Ok(())
+    }
+
+    /// Returns paths of recorded cover images with the size and hash, relative to the destination
+    /// directory.
+    pub(crate) fn find_cover_images(
+        &self,
+        size: u64,
+        hash: u64,
+    ) -> Result<Vec<std::path::PathBuf>, super::Error> {
+        let map_err = |source| super::Error::StateDb {
+            path: self.path.clone(),
+            source,
+        };
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path FROM cover_images WHERE size = ?1 AND hash = ?2 ORDER BY path")
+            .map_err(map_err)?;
+        let paths = stmt
+            .query_map(
+                rusqlite::params![size as i64, format!("{:016x}", hash)],
+                |row| row.get::<_, String>(0).map(std::path::PathBuf::from),
+            )
+            .and_then(|rows| rows.collect())
+            .map_err(map_err)?;
+        Ok(paths)
+    }
+
+    /// Records the downloaded media file at the absolute `path`, which can be in a directory of
+    /// another creator.
+    pub(crate) fn record_media_file(
+        &self,
+        path: &std::path::Path,
+        size: u64,
+        hash: u64,
+    ) -> Result<(), super::Error> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO media_files (path, size, hash) VALUES (?1, ?2, ?3)",
+                rusqlite::params![
+                    path.to_string_lossy(),
+                    size as i64,
+                    format!("{:016x}", hash)
+                ],
+            )
+            .map_err(|source| super::Error::StateDb {
+                path: self.path.clone(),
+                source,
+            })?;
+        Ok(())
+    }
+
+    /// Returns absolute paths of recorded media files with the size and hash.
+    pub(crate) fn find_media_files(
+        &self,
+        size: u64,
+        hash: u64,
+    ) -> Result<Vec<std::path::PathBuf>, super::Error> {
+        let map_err = |source| super::Error::StateDb {
+            path: self.path.clone(),
+            source,
+        };
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path FROM media_files WHERE size = ?1 AND hash = ?2 ORDER BY path")
+            .map_err(map_err)?;
+        let paths = stmt
+            .query_map(
+                rusqlite::params![size as i64, format!("{:016x}", hash)],
+                |row| row.get::<_, String>(0).map(std::path::PathBuf::from),
+            )
+            .and_then(|rows| rows.collect())
+            .map_err(map_err)?;
+        Ok(paths)
+    }
+
+    /// Returns posts archived for the user under any creator id, newest first.
+    pub fn posts_of_user(&self, user_id: &str) -> Result<Vec<PostState>, super::Error> {
+        let map_err = |source| super::Error::StateDb {
+            path: self.path.clone(),
+            source,
+        };
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT post_id, user_id, creator_id, updated_datetime FROM posts
+                WHERE user_id = ?1
+                ORDER BY updated_datetime DESC",
+            )
+            .map_err(map_err)?;
+        let posts = stmt
+            .query_map([user_id], |row| {
+                Ok(PostState {
+                    post_id: row.get(0)?,
+                    user_id: row.get(1)?,
+                    creator_id: row.get(2)?,
+                    updated_datetime: parse_datetime(row, 3)?,
+                })
+            })
+            .and_then(|rows| rows.collect())
+            .map_err(map_err)?;
+        Ok(posts)
+    }
+
+    /// Returns sizes and mtimes in nanoseconds of files in the last differential backup, keyed by
+    /// paths relative to the destination directory.
+    pub(crate) fn backed_up_files(
+        &self,
+    ) -> Result<std::collections::HashMap<String, (u64, i64)>, super::Error> {
+        let map_err = |source| super::Error::StateDb {
+            path: self.path.clone(),
+            source,
+        };
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, size, mtime FROM backup_files")
+            .map_err(map_err)?;
+        let files = stmt
+            .query_map([], |row| {
+                Ok((row.get(0)?, (row.get::<_, i64>(1)? as u64, row.get(2)?)))
+            })
+            .and_then(|rows| rows.collect())
+            .map_err(map_err)?;
+        Ok(files)
+    }
+
+    /// Replaces the files of the last differential backup with `files`.
+    pub(crate) fn record_backed_up_files(
+        &self,
+        files: &std::collections::HashMap<String, (u64, i64)>,
+    ) -> Result<(), super::Error> {
+        let map_err = |source| super::Error::StateDb {
+            path: self.path.clone(),
+            source,
+        };
+        let tx = self.conn.unchecked_transaction().map_err(map_err)?;
+        tx.execute("DELETE FROM backup_files", [])
+            .map_err(map_err)?;
+        {
+            let mut stmt = tx
+                .prepare("INSERT INTO backup_files (path, size, mtime) VALUES (?1, ?2, ?3)")
+                .map_err(map_err)?;
+            for (path, (size, mtime)) in files {
+                stmt.execute(rusqlite::params![path, *size as i64, mtime])
+                    .map_err(map_err)?;
+            }
+        }
+        tx.commit().map_err(map_err)
+    }
+
+    /// Records the run of the download command.
+    pub fn record_run(&self, run: &RunState) -> Result<(), super::Error> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO runs (started_datetime, finished_datetime, posts, files, bytes)
+                VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![
+                    run.started_datetime.to_rfc3339(),
+                    run.finished_datetime.to_rfc3339(),
+                    run.posts as i64,
+                    run.files as i64,
+                    run.bytes as i64,
+                ],
+            )
+            .map_err(|source| super::Error::StateDb {
+                path: self.path.clone(),
+                source,
+            })?;
+        Ok(())
+    }
+
+    /// Returns recorded runs of the download command, oldest first.
+    pub fn runs(&self) -> Result<Vec<RunState>, super::Error> {
+        let map_err = |source| super::Error::StateDb {
+            path: self.path.clone(),
+            source,
+        };
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT started_datetime, finished_datetime, posts, files, bytes FROM runs
+                ORDER BY started_datetime",
+            )
+            .map_err(map_err)?;
+        let runs = stmt
+            .query_map([], |row| {
+                Ok(RunState {
+                    started_datetime: parse_datetime(row, 0)?,
+                    finished_datetime: parse_datetime(row, 1)?,
+                    posts: row.get::<_, i64>(2)? as u64,
+                    files: row.get::<_, i64>(3)? as u64,
+                    bytes: row.get::<_, i64>(4)? as u64,
+                })
+            })
+            .and_then(|rows| rows.collect())
+            .map_err(map_err)?;
+        Ok(runs)
+    }
+}
+
+/// Parses the RFC 3339 datetime in the column of the row.
+fn parse_datetime(
+    row: &rusqlite::Row<'_>,
+    index: usize,
+) -> rusqlite::Result<chrono::DateTime<chrono::Utc>> {
+    let datetime: String = row.get(index)?;
+    let datetime = chrono::DateTime::parse_from_rfc3339(&datetime).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+    Ok(datetime.with_timezone(&chrono::Utc))
+}
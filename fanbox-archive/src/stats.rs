@@ -0,0 +1,114 @@
+/// Summary of the archive in a destination directory.
+#[derive(Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveStats {
+    pub posts: usize,
+    /// Downloaded cover images and media files
+    pub files: usize,
+    pub bytes: u64,
+    /// Keyed by the creator id
+    pub creators: std::collections::BTreeMap<String, CreatorStats>,
+    /// Number of posts keyed by `PostKind::name`
+    pub post_kinds: std::collections::BTreeMap<&'static str, usize>,
+    /// Published datetime of the oldest post
+    pub oldest: Option<chrono::DateTime<chrono::Utc>>,
+    /// Published datetime of the newest post
+    pub newest: Option<chrono::DateTime<chrono::Utc>>,
+    /// Number of posts keyed by the published month in `YYYY-MM` and the creator id
+    pub monthly_posts:
+        std::collections::BTreeMap<String, std::collections::BTreeMap<String, usize>>,
+    /// Biggest files, biggest first
+    pub biggest_files: Vec<FileStats>,
+}
+
+/// Archived posts and files of a creator.
+#[derive(Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatorStats {
+    pub posts: usize,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Downloaded file of a post.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileStats {
+    pub path: std::path::PathBuf,
+    pub size: u64,
+    pub post_id: String,
+}
+
+/// Summarizes the archive in `dest_dir` from metadata.json of posts, keeping `top` biggest files.
+///
+/// Sizes of files are taken from the download log, falling back to the file system metadata for
+/// files downloaded before the log existed, so no file contents are read.
+pub fn archive_stats(dest_dir: &std::path::Path, top: usize) -> Result<ArchiveStats, super::Error> {
+    let logged_sizes: std::collections::HashMap<_, _> = super::DownloadLog::read(dest_dir)?
+        .into_iter()
+        .map(|entry| (entry.path, entry.size))
+        .collect();
+    let size_of = |path: &std::path::Path| {
+        if let Some(size) = logged_sizes.get(path) {
+            if path.is_file() {
+                return Some(*size);
+            }
+        }
+        match std::fs::metadata(path) {
+            Ok(metadata) if metadata.is_file() => Some(metadata.len()),
+            _ => None,
+        }
+    };
+
+    let mut stats = ArchiveStats::default();
+    let mut files = Vec::new();
+    for archived in super::load_archived_posts(dest_dir)? {
+        let info = &archived.post.info;
+        stats.posts += 1;
+        *stats
+            .post_kinds
+            .entry(super::PostDocument::new(&archived.post).kind.name())
+            .or_default() += 1;
+        stats.oldest = Some(stats.oldest.map_or(info.published_datetime, |oldest| {
+            oldest.min(info.published_datetime)
+        }));
+        stats.newest = Some(stats.newest.map_or(info.published_datetime, |newest| {
+            newest.max(info.published_datetime)
+        }));
+        *stats
+            .monthly_posts
+            .entry(info.published_datetime.format("%Y-%m").to_string())
+            .or_default()
+            .entry(info.creator_id.clone())
+            .or_default() += 1;
+
+        let creator = stats.creators.entry(info.creator_id.clone()).or_default();
+        creator.posts += 1;
+        let mut paths = Vec::new();
+        if info.cover_image_url.is_some() {
+            paths.push(archived.dir.join("cover_image.jpeg"));
+        }
+        paths.extend(
+            super::catalog::media_files(&archived.post)
+                .into_iter()
+                .map(|media_file| archived.dir.join(media_file.file_name)),
+        );
+        for path in paths {
+            if let Some(size) = size_of(&path) {
+                creator.files += 1;
+                creator.bytes += size;
+                files.push(FileStats {
+                    path,
+                    size,
+                    post_id: info.id.clone(),
+                });
+            }
+        }
+    }
+    stats.files = files.len();
+    stats.bytes = files.iter().map(|file| file.size).sum();
+    files.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    files.truncate(top);
+    stats.biggest_files = files;
+    Ok(stats)
+}
@@ -0,0 +1,135 @@
+/// Name of the manifest of synced posts in the destination directory
+pub const SYNC_MANIFEST_FILE_NAME: &str = ".fanbox-dl.json";
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    /// Keyed by the post id
+    posts: std::collections::BTreeMap<String, SyncedPost>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncedPost {
+    creator_id: String,
+    updated_datetime: chrono::DateTime<chrono::Utc>,
+}
+
+/// Number of posts recorded between writes of the manifest
+const FLUSH_INTERVAL: usize = 50;
+
+/// Manifest of archived posts with their updated datetimes, used to skip posts which haven't
+/// been updated since archived without reading their metadata, also with `Format::GalleryDl`.
+///
+/// The manifest is written every `FLUSH_INTERVAL` posts rather than rewritten for each of them,
+/// and on `flush` and drop, so that an interrupted run loses at most the latest posts, which are
+/// then archived again.
+#[derive(Debug)]
+pub struct SyncManifest {
+    path: std::path::PathBuf,
+    state: std::sync::Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    manifest: Manifest,
+    /// Number of posts recorded since the manifest was written
+    unsaved: usize,
+}
+
+impl SyncManifest {
+    /// Opens the manifest in `dest_dir`, which is empty until a post is recorded.
+    pub fn open(dest_dir: &std::path::Path) -> Result<Self, super::Error> {
+        let path = dest_dir.join(SYNC_MANIFEST_FILE_NAME);
+        let manifest = match std::fs::read(&path) {
+            Ok(json) => serde_json::from_slice(&json).map_err(|e| super::Error::Read {
+                path: path.clone(),
+                source: e.into(),
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Manifest::default(),
+            Err(source) => return Err(super::Error::Read { path, source }),
+        };
+        Ok(Self {
+            path,
+            state: std::sync::Mutex::new(State {
+                manifest,
+                unsaved: 0,
+            }),
+        })
+    }
+
+    /// Returns whether the post is recorded and hasn't been updated since then.
+    pub fn is_up_to_date(
+        &self,
+        post_id: &str,
+        updated_datetime: chrono::DateTime<chrono::Utc>,
+    ) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .manifest
+            .posts
+            .get(post_id)
+            .is_some_and(|post| post.updated_datetime >= updated_datetime)
+    }
+
+    /// Returns the number of recorded posts.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().manifest.posts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Records the archived post, writing the manifest every `FLUSH_INTERVAL` posts.
+    pub fn record(&self, info: &fanbox_api::PostInfo) -> Result<(), super::Error> {
+        let mut state = self.state.lock().unwrap();
+        state.manifest.posts.insert(
+            info.id.clone(),
+            SyncedPost {
+                creator_id: info.creator_id.clone(),
+                updated_datetime: info.updated_datetime,
+            },
+        );
+        state.unsaved += 1;
+        if state.unsaved >= FLUSH_INTERVAL {
+            self.write(&mut state)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the posts recorded since the last write, e.g. at the end of the run.
+    pub fn flush(&self) -> Result<(), super::Error> {
+        let mut state = self.state.lock().unwrap();
+        if state.unsaved > 0 {
+            self.write(&mut state)?;
+        }
+        Ok(())
+    }
+
+    fn write(&self, state: &mut State) -> Result<(), super::Error> {
+        let map_err = |source| super::Error::Write {
+            path: self.path.clone(),
+            source,
+        };
+        let json = serde_json::to_vec_pretty(&state.manifest).map_err(|e| map_err(e.into()))?;
+        // Replaced atomically so that a killed run doesn't leave a truncated manifest
+        let mut tmp_path = self.path.as_os_str().to_owned();
+        tmp_path.push(".");
+        tmp_path.push(super::TEMPORARY_EXTENSION);
+        let tmp_path = std::path::PathBuf::from(tmp_path);
+        std::fs::write(&tmp_path, json).map_err(map_err)?;
+        std::fs::rename(&tmp_path, &self.path).map_err(map_err)?;
+        state.unsaved = 0;
+        Ok(())
+    }
+}
+
+impl Drop for SyncManifest {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            tracing::warn!("Failed to write the sync manifest: {}", e);
+        }
+    }
+}
@@ -0,0 +1,37 @@
+/// Name of the plain text page in each post directory with `Format::Text`
+pub(crate) const TEXT_FILE_NAME: &str = "index.txt";
+
+/// Renders the title, dates and text of the post followed by the names of its downloaded files.
+pub(crate) fn render_text(post: &fanbox_api::Post) -> String {
+    let info = &post.info;
+    let mut lines = vec![
+        info.title.clone(),
+        super::post_url(info),
+        format!("Published: {}", info.published_datetime.to_rfc3339()),
+    ];
+    if info.updated_datetime != info.published_datetime {
+        lines.push(format!("Updated: {}", info.updated_datetime.to_rfc3339()));
+    }
+    let text = super::post_text(post);
+    if !text.is_empty() {
+        lines.push(String::new());
+        lines.push(text);
+    }
+    let media_files = super::catalog::media_files(post);
+    if !media_files.is_empty() {
+        lines.push(String::new());
+        lines.extend(media_files.into_iter().map(|media_file| {
+            let file_name = media_file.file_name;
+            if media_file.name == media_file.id {
+                file_name
+            } else {
+                format!(
+                    "{} ({}.{})",
+                    file_name, media_file.name, media_file.extension
+                )
+            }
+        }));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
@@ -0,0 +1,127 @@
+use std::io::{Read as _, Seek as _};
+
+/// Media file of an archived post which is corrupt.
+#[derive(Debug, Clone)]
+pub struct BrokenFile {
+    pub post: fanbox_api::PostInfo,
+    pub path: std::path::PathBuf,
+    /// URL the file was downloaded from
+    pub url: String,
+    pub reason: String,
+}
+
+/// Returns the cover image and media files of archived posts under `dest_dir` which exist but
+/// can't be decoded.
+///
+/// Images are decoded entirely and zip archives are checked for their central directory. Files
+/// in other formats are assumed to be valid.
+pub fn find_broken_files(dest_dir: &std::path::Path) -> Result<Vec<BrokenFile>, super::Error> {
+    let mut broken = Vec::new();
+    for archived in super::load_archived_posts(dest_dir)? {
+        let info = &archived.post.info;
+        let mut files = Vec::new();
+        if let Some(cover_image_url) = &info.cover_image_url {
+            files.push((
+                archived.dir.join("cover_image.jpeg"),
+                cover_image_url.as_str(),
+            ));
+        }
+        for media_file in super::catalog::media_files(&archived.post) {
+            files.push((archived.dir.join(&media_file.file_name), media_file.url));
+        }
+        for (path, url) in files {
+            if !path.is_file() {
+                continue;
+            }
+            if let Some(reason) = check_file(&path)? {
+                tracing::warn!("{} is broken: {}", path.display(), reason);
+                broken.push(BrokenFile {
+                    post: info.clone(),
+                    path,
+                    url: url.to_owned(),
+                    reason,
+                });
+            }
+        }
+    }
+    Ok(broken)
+}
+
+/// Returns why the file at `path` is broken, or `None` if it looks valid.
+pub fn check_file(path: &std::path::Path) -> Result<Option<String>, super::Error> {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    if extension == "zip" {
+        return check_zip(path).map_err(|source| super::Error::Read {
+            path: path.to_path_buf(),
+            source,
+        });
+    }
+    match image::ImageFormat::from_extension(&extension) {
+        Some(format) if format.reading_enabled() => {}
+        _ => return Ok(None),
+    }
+    let reader = image::ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|source| super::Error::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    if reader.format().is_none() {
+        return Ok(Some("unknown image format".to_owned()));
+    }
+    match reader.decode() {
+        Ok(_) => Ok(None),
+        Err(image::ImageError::Unsupported(_)) => Ok(None),
+        Err(e) => Ok(Some(e.to_string())),
+    }
+}
+
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: &[u8] = b"PK\x05\x06";
+const CENTRAL_DIRECTORY_SIGNATURE: &[u8] = b"PK\x01\x02";
+/// Size of the end of central directory record without the comment
+const END_OF_CENTRAL_DIRECTORY_SIZE: u64 = 22;
+
+/// Checks that the zip archive ends with a central directory, which is lost when truncated.
+fn check_zip(path: &std::path::Path) -> Result<Option<String>, std::io::Error> {
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    if len < END_OF_CENTRAL_DIRECTORY_SIZE {
+        return Ok(Some("too short for a zip archive".to_owned()));
+    }
+    // The record is followed by a comment up to 65535 bytes
+    let tail_len = len.min(END_OF_CENTRAL_DIRECTORY_SIZE + u16::MAX as u64);
+    file.seek(std::io::SeekFrom::Start(len - tail_len))?;
+    let mut tail = vec![0; tail_len as usize];
+    file.read_exact(&mut tail)?;
+    let pos = match tail
+        .windows(END_OF_CENTRAL_DIRECTORY_SIGNATURE.len())
+        .rposition(|window| window == END_OF_CENTRAL_DIRECTORY_SIGNATURE)
+    {
+        Some(pos) if tail.len() - pos >= END_OF_CENTRAL_DIRECTORY_SIZE as usize => pos,
+        _ => return Ok(Some("end of central directory not found".to_owned())),
+    };
+    let record = &tail[pos..];
+    let entries = u16::from_le_bytes([record[10], record[11]]);
+    let size = u32::from_le_bytes([record[12], record[13], record[14], record[15]]);
+    let offset = u32::from_le_bytes([record[16], record[17], record[18], record[19]]);
+    if size == u32::MAX || offset == u32::MAX {
+        // ZIP64 stores them in another record
+        return Ok(None);
+    }
+    let record_offset = len - tail_len + pos as u64;
+    if offset as u64 + size as u64 > record_offset {
+        return Ok(Some("central directory out of range".to_owned()));
+    }
+    if entries > 0 {
+        file.seek(std::io::SeekFrom::Start(offset as u64))?;
+        let mut signature = [0; 4];
+        file.read_exact(&mut signature)?;
+        if signature != CENTRAL_DIRECTORY_SIGNATURE {
+            return Ok(Some("central directory not found".to_owned()));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,123 @@
+/// Name of the file in the destination directory storing the version of its layout
+pub const FORMAT_VERSION_FILE_NAME: &str = ".fanbox-dl-format";
+
+/// Version of the archive layout written by this version of fanbox-dl.
+///
+/// 1. Post directories without the version file
+/// 2. Archived posts are recorded in the state database and the search index
+pub const FORMAT_VERSION: u32 = 2;
+
+/// Returns the layout version recorded in `dest_dir`, or `None` if it isn't recorded.
+pub fn read_format_version(dest_dir: &std::path::Path) -> Result<Option<u32>, super::Error> {
+    let path = dest_dir.join(FORMAT_VERSION_FILE_NAME);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(source) => return Err(super::Error::Read { path, source }),
+    };
+    content
+        .trim()
+        .parse()
+        .map(Some)
+        .map_err(|e| super::Error::Read {
+            path,
+            source: std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        })
+}
+
+/// Checks the layout version of the archive in `dest_dir` before writing into it.
+///
+/// The current version is recorded into new archives. Returns the version of archives which
+/// need to be upgraded by `upgrade_format`, and fails for archives written by newer versions.
+pub fn check_format_version(dest_dir: &std::path::Path) -> Result<Option<u32>, super::Error> {
+    let version = match read_format_version(dest_dir)? {
+        Some(version) => version,
+        None if has_archived_posts(dest_dir)? => 1,
+        None => {
+            write_format_version(dest_dir)?;
+            return Ok(None);
+        }
+    };
+    if version > FORMAT_VERSION {
+        Err(super::Error::NewerFormat {
+            path: dest_dir.to_path_buf(),
+            version,
+        })
+    } else if version < FORMAT_VERSION {
+        Ok(Some(version))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Upgrades the archive in `dest_dir` from its layout version to the current one, returning the
+/// version before upgraded.
+pub fn upgrade_format(dest_dir: &std::path::Path) -> Result<u32, super::Error> {
+    let from = match check_format_version(dest_dir)? {
+        Some(version) => version,
+        None => return Ok(FORMAT_VERSION),
+    };
+    for version in from..FORMAT_VERSION {
+        tracing::info!(
+            "Upgrade {} from format version {} to {}",
+            dest_dir.display(),
+            version,
+            version + 1
+        );
+        if version == 1 {
+            record_archived_posts(dest_dir)?;
+        }
+    }
+    write_format_version(dest_dir)?;
+    Ok(from)
+}
+
+fn write_format_version(dest_dir: &std::path::Path) -> Result<(), super::Error> {
+    let path = dest_dir.join(FORMAT_VERSION_FILE_NAME);
+    std::fs::write(&path, format!("{}\n", FORMAT_VERSION))
+        .map_err(|source| super::Error::Write { path, source })
+}
+
+/// Returns whether `dest_dir` already contains posts or state written by older versions.
+fn has_archived_posts(dest_dir: &std::path::Path) -> Result<bool, super::Error> {
+    let entries = match std::fs::read_dir(dest_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(source) => {
+            return Err(super::Error::Read {
+                path: dest_dir.to_path_buf(),
+                source,
+            })
+        }
+    };
+    for entry in entries {
+        let entry = entry.map_err(|source| super::Error::Read {
+            path: dest_dir.to_path_buf(),
+            source,
+        })?;
+        let path = entry.path();
+        if path.join(super::METADATA_FILE_NAME).is_file()
+            || entry.file_name() == super::JOURNAL_FILE_NAME
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Records posts archived before the state database and the search index existed.
+#[cfg_attr(not(feature = "state-db"), allow(unused_variables))]
+fn record_archived_posts(dest_dir: &std::path::Path) -> Result<(), super::Error> {
+    #[cfg(feature = "state-db")]
+    {
+        let state_db = super::StateDb::open(dest_dir)?;
+        #[cfg(feature = "search-index")]
+        let index = super::SearchIndex::open(dest_dir)?;
+        for archived in super::load_archived_posts(dest_dir)? {
+            state_db.record_post(&archived.post.info)?;
+            #[cfg(feature = "search-index")]
+            index.index_post(&archived.dir, &archived.post)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use fanbox_api::test_util::{FakeFanbox, FIXTURE_CREATOR_ID};
+
+/// Returns an empty directory for the test, removing the one left by a previous run.
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("fanbox-archive-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Returns names of files in `dir`, sorted.
+fn file_names(dir: &std::path::Path) -> Vec<String> {
+    let mut names: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Archives all fixture posts into `dest_dir`, returning the events.
+async fn archive_fixtures(
+    client: &fanbox_api::PostClient,
+    dest_dir: &std::path::Path,
+    options: fanbox_archive::ArchiveOptions,
+) -> Vec<fanbox_archive::Event> {
+    let mut archiver = fanbox_archive::Archiver::new(client, dest_dir, options);
+    let mut receiver = archiver.events();
+    let failures = archiver.archive_creator(FIXTURE_CREATOR_ID).await.unwrap();
+    assert_eq!(failures, 0);
+    drop(archiver);
+    let mut events = Vec::new();
+    while let Some(event) = receiver.recv().await {
+        events.push(event);
+    }
+    events
+}
+
+#[tokio::test]
+async fn archive_creator_writes_post_directories() {
+    let client = FakeFanbox::with_fixtures().client();
+    let dest_dir = temp_dir("archive-creator");
+
+    let events = archive_fixtures(&client, &dest_dir, Default::default()).await;
+    let finished: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            fanbox_archive::Event::PostFinished { post_id } => Some(post_id.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(finished, ["8", "7", "6", "5", "4", "3", "2", "1"]);
+
+    assert_eq!(
+        file_names(&dest_dir.join("1")),
+        [
+            "cover_image.jpeg",
+            "i1.png",
+            "i2.png",
+            "index.html",
+            "metadata.json"
+        ]
+    );
+    assert_eq!(
+        file_names(&dest_dir.join("2")),
+        ["cover_image.jpeg", "f1.zip", "index.html", "metadata.json"]
+    );
+    assert_eq!(
+        std::fs::read(dest_dir.join("2").join("f1.zip")).unwrap(),
+        b"https://downloads.fanbox.cc/files/f1.zip"
+    );
+    assert!(std::fs::read(dest_dir.join("1").join("i1.png"))
+        .unwrap()
+        .starts_with(b"\x89PNG"));
+
+    let page = std::fs::read_to_string(dest_dir.join("6").join("index.html")).unwrap();
+    assert!(page.contains("<title>Fixture article post 6</title>"));
+    for link in [
+        "a1.png",
+        "af1.zip",
+        "youtube.com/watch?v=dQw4w9WgXcQ",
+        "tracks/293",
+    ] {
+        assert!(page.contains(link), "{} is missing in the page", link);
+    }
+
+    // Unsupported bodies are kept as JSON, and restricted posts have nothing to archive
+    assert_eq!(
+        file_names(&dest_dir.join("7")),
+        ["metadata.json", "unknown_body.json"]
+    );
+    assert!(!dest_dir.join("8").exists());
+
+    let archived = fanbox_archive::load_archived_posts(&dest_dir).unwrap();
+    let mut ids: Vec<_> = archived
+        .iter()
+        .map(|archived| archived.post.info.id.as_str())
+        .collect();
+    ids.sort();
+    assert_eq!(ids, ["1", "2", "3", "4", "5", "6", "7"]);
+    std::fs::remove_dir_all(&dest_dir).unwrap();
+}
+
+#[tokio::test]
+async fn archive_creator_skips_synced_posts() {
+    let client = FakeFanbox::with_fixtures().client();
+    let dest_dir = temp_dir("sync-manifest");
+    let options = || fanbox_archive::ArchiveOptions {
+        sync_manifest: Some(std::sync::Arc::new(
+            fanbox_archive::SyncManifest::open(&dest_dir).unwrap(),
+        )),
+        ..Default::default()
+    };
+
+    let events = archive_fixtures(&client, &dest_dir, options()).await;
+    assert!(!events.is_empty());
+    // The manifest is written when the last reference is dropped
+    assert!(dest_dir
+        .join(fanbox_archive::SYNC_MANIFEST_FILE_NAME)
+        .is_file());
+    // Restricted posts are not recorded to be archived once they are supported
+    assert_eq!(
+        fanbox_archive::SyncManifest::open(&dest_dir).unwrap().len(),
+        7
+    );
+
+    let events = archive_fixtures(&client, &dest_dir, options()).await;
+    let post_ids: Vec<_> = events
+        .iter()
+        .map(|event| match event {
+            fanbox_archive::Event::PostDiscovered { post_id, .. }
+            | fanbox_archive::Event::PostStarted { post_id }
+            | fanbox_archive::Event::FileDownloaded { post_id, .. }
+            | fanbox_archive::Event::PostFinished { post_id }
+            | fanbox_archive::Event::Error { post_id, .. } => post_id.as_str(),
+        })
+        .collect();
+    assert_eq!(post_ids, ["8", "8", "8"]);
+    std::fs::remove_dir_all(&dest_dir).unwrap();
+}
+
+#[tokio::test]
+async fn archive_creator_places_posts_by_path_template() {
+    let client = FakeFanbox::with_fixtures().client();
+    let dest_dir = temp_dir("path-template");
+    let options = fanbox_archive::ArchiveOptions {
+        path_template: Some(
+            "{creator_id}/{published:%Y-%m}/{post_id}_{title}/{index:02}_{filename}"
+                .parse()
+                .unwrap(),
+        ),
+        ..Default::default()
+    };
+
+    archive_fixtures(&client, &dest_dir, options).await;
+    let post_dir = dest_dir
+        .join(FIXTURE_CREATOR_ID)
+        .join("2023-12")
+        .join("1_Fixture image post 1");
+    let names = file_names(&post_dir);
+    assert!(names.contains(&"01_i1.png".to_owned()), "{:?}", names);
+    assert!(names.contains(&"02_i2.png".to_owned()), "{:?}", names);
+    let page = std::fs::read_to_string(post_dir.join("index.html")).unwrap();
+    assert!(page.contains("01_i1.png"));
+    std::fs::remove_dir_all(&dest_dir).unwrap();
+}
+
+#[test]
+fn invalid_path_templates_are_rejected() {
+    for template in [
+        "{filename}",
+        "{post_id}/{title}",
+        "{post_id}/{unknown}",
+        "../{filename}",
+    ] {
+        assert!(
+            matches!(
+                template.parse::<fanbox_archive::PathTemplate>(),
+                Err(fanbox_archive::Error::InvalidTemplate { .. })
+            ),
+            "{} is accepted",
+            template
+        );
+    }
+}
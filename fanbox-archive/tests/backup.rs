@@ -0,0 +1,182 @@
+/// Returns an empty directory for the test, removing the one left by a previous run.
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("fanbox-archive-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Decrypts the age file at `path` with `identity`.
+fn decrypt(path: &std::path::Path, identity: &age::x25519::Identity) -> Vec<u8> {
+    use std::io::Read as _;
+
+    let decryptor = age::Decryptor::new(std::fs::File::open(path).unwrap()).unwrap();
+    let mut reader = decryptor
+        .decrypt(std::iter::once(identity as &dyn age::Identity))
+        .unwrap();
+    let mut content = Vec::new();
+    reader.read_to_end(&mut content).unwrap();
+    content
+}
+
+/// Returns names and contents of entries in the tarball.
+fn read_tar(tarball: &[u8]) -> Vec<(String, Vec<u8>)> {
+    use std::io::Read as _;
+
+    tar::Archive::new(tarball)
+        .entries()
+        .unwrap()
+        .map(|entry| {
+            let mut entry = entry.unwrap();
+            let name = entry.path().unwrap().to_string_lossy().into_owned();
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).unwrap();
+            (name, content)
+        })
+        .collect()
+}
+
+#[test]
+fn write_differential_backup_writes_changed_files() {
+    let dir = temp_dir("backup");
+    let dest_dir = dir.join("dest");
+    let backup_dir = dir.join("backup");
+    let long_dir = "a".repeat(90);
+    let files = [
+        (format!("{}/{}.png", long_dir, "b".repeat(20)), &b"long"[..]),
+        ("1/画像 1.png".to_owned(), &b"non-ascii"[..]),
+        ("1/empty.txt".to_owned(), &b""[..]),
+    ];
+    for (name, content) in &files {
+        let path = dest_dir.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    let options = Default::default();
+
+    let backup = fanbox_archive::write_differential_backup(&dest_dir, &backup_dir, &options)
+        .unwrap()
+        .unwrap();
+    assert_eq!(backup.files, 3);
+    assert_eq!(backup.bytes, 13);
+    let mut entries = read_tar(&std::fs::read(&backup.path).unwrap());
+    entries.sort();
+    let mut expected: Vec<_> = files
+        .iter()
+        .map(|(name, content)| (name.clone(), content.to_vec()))
+        .collect();
+    expected.sort();
+    assert_eq!(entries, expected);
+
+    // Only changed files are written by the next backup
+    std::fs::write(dest_dir.join("1").join("empty.txt"), b"changed").unwrap();
+    let backup = fanbox_archive::write_differential_backup(&dest_dir, &backup_dir, &options)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        read_tar(&std::fs::read(&backup.path).unwrap()),
+        [("1/empty.txt".to_owned(), b"changed".to_vec())]
+    );
+    assert!(
+        fanbox_archive::write_differential_backup(&dest_dir, &backup_dir, &options)
+            .unwrap()
+            .is_none()
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn write_differential_backup_encrypts_tarball() {
+    let dir = temp_dir("encrypted-backup");
+    let dest_dir = dir.join("dest");
+    let backup_dir = dir.join("backup");
+    std::fs::create_dir_all(dest_dir.join("1")).unwrap();
+    std::fs::write(dest_dir.join("1").join("i1.png"), b"image").unwrap();
+    let identity = age::x25519::Identity::generate();
+    let recipients_file = dir.join("recipients.txt");
+    std::fs::write(
+        &recipients_file,
+        format!("# backup key\n\n{}\n", identity.to_public()),
+    )
+    .unwrap();
+    let options = fanbox_archive::BackupOptions {
+        recipients: Some(std::sync::Arc::new(
+            fanbox_archive::Recipients::parse(&[recipients_file.display().to_string()]).unwrap(),
+        )),
+    };
+
+    let backup = fanbox_archive::write_differential_backup(&dest_dir, &backup_dir, &options)
+        .unwrap()
+        .unwrap();
+    assert_eq!(backup.files, 1);
+    assert!(backup.path.to_string_lossy().ends_with(".tar.age"));
+    assert_eq!(
+        read_tar(&decrypt(&backup.path, &identity)),
+        [("1/i1.png".to_owned(), b"image".to_vec())]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn export_encrypts_files() {
+    use fanbox_api::test_util::{FakeFanbox, FIXTURE_CREATOR_ID};
+
+    let dir = temp_dir("encrypted-export");
+    let dest_dir = dir.join("dest");
+    let out_dir = dir.join("out");
+    std::fs::create_dir_all(&dest_dir).unwrap();
+    let client = FakeFanbox::with_fixtures().client();
+    let failures = fanbox_archive::Archiver::new(&client, &dest_dir, Default::default())
+        .archive_creator(FIXTURE_CREATOR_ID)
+        .await
+        .unwrap();
+    assert_eq!(failures, 0);
+    let identity = age::x25519::Identity::generate();
+    let options = fanbox_archive::ExportOptions {
+        recipients: Some(std::sync::Arc::new(
+            fanbox_archive::Recipients::parse(&[identity.to_public().to_string()]).unwrap(),
+        )),
+        ..Default::default()
+    };
+
+    fanbox_archive::export(
+        &dest_dir,
+        &out_dir,
+        fanbox_archive::ExportFormat::Zip,
+        &options,
+    )
+    .unwrap();
+    let creator_dir = out_dir.join(FIXTURE_CREATOR_ID);
+    assert!(!creator_dir.join("1.zip").exists());
+    assert!(decrypt(&creator_dir.join("1.zip.age"), &identity).starts_with(b"PK"));
+    // Directories can't be encrypted as a file
+    assert!(matches!(
+        fanbox_archive::export(
+            &dest_dir,
+            &out_dir,
+            fanbox_archive::ExportFormat::Markdown,
+            &options
+        ),
+        Err(fanbox_archive::Error::Encrypt { .. })
+    ));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn invalid_recipients_are_rejected() {
+    for recipients in [
+        vec![],
+        vec!["age1invalid".to_owned()],
+        vec!["ssh-ed25519 AAAA".to_owned()],
+        vec!["alice@example.com".to_owned()],
+    ] {
+        assert!(
+            matches!(
+                fanbox_archive::Recipients::parse(&recipients),
+                Err(fanbox_archive::Error::InvalidRecipient { .. })
+            ),
+            "{:?} is accepted",
+            recipients
+        );
+    }
+}
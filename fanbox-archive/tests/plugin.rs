@@ -0,0 +1,40 @@
+use fanbox_archive::Hook as _;
+
+/// Loads the plugin written in the WebAssembly text format.
+fn load_plugin(name: &str, wat: &str) -> fanbox_archive::WasmPlugin {
+    let path = std::env::temp_dir().join(format!(
+        "fanbox-archive-{}-{}.wasm",
+        name,
+        std::process::id()
+    ));
+    std::fs::write(&path, wat::parse_str(wat).unwrap()).unwrap();
+    let plugin = fanbox_archive::WasmPlugin::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    plugin
+}
+
+#[tokio::test]
+async fn plugin_running_out_of_fuel_fails_the_call() {
+    let client = fanbox_api::test_util::FakeFanbox::with_fixtures().client();
+    let post = client.get_post("1").await.unwrap();
+    let document = fanbox_archive::PostDocument::new(&post);
+    let plugin = load_plugin(
+        "infinite-loop",
+        r#"(module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) i32.const 0)
+            (func (export "transform_document") (param i32 i32) (result i64)
+                (loop $forever (br $forever))
+                i64.const 0)
+            (func (export "should_download") (param i32 i32) (result i32) i32.const 0))"#,
+    )
+    .fuel_per_call(100_000);
+
+    assert!(matches!(
+        plugin.transform_document(document.clone()),
+        Err(fanbox_archive::Error::Plugin { .. })
+    ));
+    // Fuel is refilled for the next call
+    let asset = document.cover.as_ref().unwrap();
+    assert!(!plugin.should_download(&document, asset).unwrap());
+}
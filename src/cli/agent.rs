@@ -0,0 +1,352 @@
+//! JSON-RPC 2.0 protocol over stdio for GUI frontends running fanbox-dl as a subprocess.
+//!
+//! Each line of stdin is a request and each line of stdout is a response or a notification.
+//! Logs are written to stderr. Methods are:
+//!
+//! - `startJob` with `{"creatorId": ...}` starts archiving all posts of the creator and returns
+//!   `{"jobId": ...}`. Its progress is notified as `event` with `{"jobId": ..., "event": ...}` and
+//!   `jobFinished` with `{"jobId": ..., "progress": ...}`. Posts are archived as by `download -c`
+//!   with the settings of the config file, and the creator id may also be a pixiv user id or a
+//!   URL, which is resolved by the job
+//! - `getProgress` with `{"jobId": ...}` returns the progress of the job
+//! - `cancelJob` with `{"jobId": ...}` cancels the job, whose downloads in progress are discarded
+//! - `listArchive` returns posts archived in the destination directory
+//!
+//! Jobs in progress are cancelled when stdin is closed.
+
+use tokio::io::AsyncBufReadExt as _;
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+#[derive(Debug, serde::Deserialize)]
+struct Request {
+    /// Missing in notifications, which aren't responded
+    id: Option<serde_json::Value>,
+    method: String,
+    #[serde(default)]
+    params: serde_json::Value,
+}
+
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn server<E>(e: E) -> Self
+    where
+        E: std::fmt::Display,
+    {
+        Self {
+            code: SERVER_ERROR,
+            message: format!("{:#}", e),
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StartJobParams {
+    creator_id: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JobParams {
+    job_id: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+enum JobStatus {
+    #[default]
+    Running,
+    Finished,
+    Failed,
+    Cancelled,
+}
+
+/// Progress of a job, updated by its `Event`s.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Progress {
+    status: JobStatus,
+    discovered_posts: usize,
+    finished_posts: usize,
+    failed_posts: usize,
+    downloaded_files: usize,
+    downloaded_bytes: u64,
+    /// Error which stopped the job
+    error: Option<String>,
+}
+
+#[derive(Debug)]
+struct Job {
+    progress: Progress,
+    token: tokio_util::sync::CancellationToken,
+}
+
+type Jobs = std::sync::Arc<std::sync::Mutex<std::collections::HashMap<u64, Job>>>;
+
+/// Context and the run state shared by jobs, which live as long as the agent.
+struct Agent {
+    ctx: super::Context,
+    run: super::download::RunState,
+}
+
+/// Serves requests from stdin until it's closed.
+pub async fn run(ctx: super::Context) -> anyhow::Result<()> {
+    let stats = std::sync::Arc::new(fanbox_dl::TransferStats::new());
+    let run = super::download::RunState::open(&ctx, &Default::default(), &stats)?;
+    let agent = std::sync::Arc::new(Agent { ctx, run });
+    let jobs = Jobs::default();
+    let mut handles = Vec::new();
+    let mut lines = tokio::io::BufReader::new(tokio::io::stdin()).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request: Request = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                write_message(&serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": null,
+                    "error": {"code": PARSE_ERROR, "message": e.to_string()},
+                }));
+                continue;
+            }
+        };
+        let result = handle(&agent, &jobs, &mut handles, &request.method, request.params).await;
+        if let Some(id) = request.id {
+            write_message(&match result {
+                Ok(result) => serde_json::json!({"jsonrpc": "2.0", "id": id, "result": result}),
+                Err(e) => serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": {"code": e.code, "message": e.message},
+                }),
+            });
+        }
+    }
+
+    for job in jobs.lock().unwrap().values() {
+        job.token.cancel();
+    }
+    for handle in handles {
+        handle.await?;
+    }
+    // The journal is kept to resume from the posts which aren't archived yet
+    let completed = jobs
+        .lock()
+        .unwrap()
+        .values()
+        .all(|job| matches!(job.progress.status, JobStatus::Finished));
+    if completed {
+        match std::sync::Arc::try_unwrap(agent) {
+            Ok(agent) => agent.run.finish()?,
+            Err(_) => tracing::warn!("Kept the journal since the run is still shared by a job"),
+        }
+    }
+    Ok(())
+}
+
+async fn handle(
+    agent: &std::sync::Arc<Agent>,
+    jobs: &Jobs,
+    handles: &mut Vec<tokio::task::JoinHandle<()>>,
+    method: &str,
+    params: serde_json::Value,
+) -> Result<serde_json::Value, RpcError> {
+    match method {
+        "startJob" => {
+            let params: StartJobParams = parse_params(params)?;
+            let token = tokio_util::sync::CancellationToken::new();
+            let client = agent
+                .ctx
+                .client()
+                .map_err(RpcError::server)?
+                .dedup_requests(true)
+                .cancellation_token(token.clone());
+
+            let mut jobs_guard = jobs.lock().unwrap();
+            let job_id = jobs_guard.len() as u64 + 1;
+            jobs_guard.insert(
+                job_id,
+                Job {
+                    progress: Progress::default(),
+                    token: token.clone(),
+                },
+            );
+            drop(jobs_guard);
+            handles.push(tokio::spawn(run_job(
+                agent.clone(),
+                client,
+                params.creator_id,
+                job_id,
+                token,
+                jobs.clone(),
+            )));
+            Ok(serde_json::json!({ "jobId": job_id }))
+        }
+        "getProgress" => {
+            let params: JobParams = parse_params(params)?;
+            let jobs = jobs.lock().unwrap();
+            let job = find_job(&jobs, params.job_id)?;
+            serde_json::to_value(&job.progress).map_err(RpcError::server)
+        }
+        "cancelJob" => {
+            let params: JobParams = parse_params(params)?;
+            let jobs = jobs.lock().unwrap();
+            find_job(&jobs, params.job_id)?.token.cancel();
+            Ok(serde_json::Value::Null)
+        }
+        "listArchive" => {
+            let dest_dir = agent.ctx.dest_dir.clone();
+            let posts = tokio::task::spawn_blocking(move || {
+                fanbox_dl::archive::load_archived_posts(&dest_dir)
+            })
+            .await
+            .map_err(RpcError::server)?
+            .map_err(RpcError::server)?;
+            Ok(posts
+                .into_iter()
+                .map(|archived| {
+                    let info = &archived.post.info;
+                    serde_json::json!({
+                        "creatorId": info.creator_id,
+                        "postId": info.id,
+                        "title": info.title,
+                        "publishedDatetime": info.published_datetime,
+                        "updatedDatetime": info.updated_datetime,
+                        "dir": archived.dir,
+                    })
+                })
+                .collect())
+        }
+        _ => Err(RpcError {
+            code: METHOD_NOT_FOUND,
+            message: format!("unknown method {}", method),
+        }),
+    }
+}
+
+fn parse_params<T>(params: serde_json::Value) -> Result<T, RpcError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    serde_json::from_value(params).map_err(|e| RpcError {
+        code: INVALID_PARAMS,
+        message: e.to_string(),
+    })
+}
+
+fn find_job(jobs: &std::collections::HashMap<u64, Job>, job_id: u64) -> Result<&Job, RpcError> {
+    jobs.get(&job_id).ok_or_else(|| RpcError {
+        code: INVALID_PARAMS,
+        message: format!("unknown job {}", job_id),
+    })
+}
+
+/// Archives posts of the creator, notifying its events and the result.
+async fn run_job(
+    agent: std::sync::Arc<Agent>,
+    client: fanbox_dl::PostClient,
+    input: String,
+    job_id: u64,
+    token: tokio_util::sync::CancellationToken,
+    jobs: Jobs,
+) {
+    let result = archive_creator(&agent, &client, &input, job_id, &jobs).await;
+
+    let progress = {
+        let mut jobs = jobs.lock().unwrap();
+        let progress = &mut jobs.get_mut(&job_id).unwrap().progress;
+        progress.status = match result {
+            Ok(_) => JobStatus::Finished,
+            Err(_) if token.is_cancelled() => JobStatus::Cancelled,
+            Err(e) => {
+                progress.error = Some(e.to_string());
+                JobStatus::Failed
+            }
+        };
+        progress.clone()
+    };
+    write_notification(
+        "jobFinished",
+        serde_json::json!({"jobId": job_id, "progress": progress}),
+    );
+}
+
+/// Archives posts of the creator given by `input` with the options of `download -c`.
+async fn archive_creator(
+    agent: &Agent,
+    client: &fanbox_dl::PostClient,
+    input: &str,
+    job_id: u64,
+    jobs: &Jobs,
+) -> anyhow::Result<()> {
+    let ctx = &agent.ctx;
+    let creator_id = super::resolve_creator_id(client, input).await?;
+    // Settings are given by the config file as by `download -c CREATOR_ID` without options
+    let args = &super::download::DownloadArgs::for_creator(&creator_id);
+    let dest_dir = super::download::prepare_creator(ctx, client, args, &creator_id).await?;
+    let options = super::download::archive_options(ctx, args, &creator_id, &dest_dir, &agent.run)?;
+
+    let mut archiver = fanbox_dl::archive::Archiver::new(client, &dest_dir, options.clone());
+    let events = archiver.events();
+    let forward = tokio::spawn(forward_events(events, job_id, jobs.clone()));
+    let result = archiver.archive_creator(&creator_id).await;
+    // The events are closed when the archiver is dropped
+    drop(archiver);
+    let _ = forward.await;
+    result?;
+
+    super::download::finish_creator(ctx, client, args, &dest_dir, &creator_id, &options).await
+}
+
+async fn forward_events(
+    mut events: tokio::sync::mpsc::UnboundedReceiver<fanbox_dl::archive::Event>,
+    job_id: u64,
+    jobs: Jobs,
+) {
+    use fanbox_dl::archive::Event;
+
+    while let Some(event) = events.recv().await {
+        if let Some(job) = jobs.lock().unwrap().get_mut(&job_id) {
+            let progress = &mut job.progress;
+            match &event {
+                Event::PostDiscovered { .. } => progress.discovered_posts += 1,
+                Event::PostStarted { .. } => {}
+                Event::FileDownloaded { bytes, .. } => {
+                    progress.downloaded_files += 1;
+                    progress.downloaded_bytes += bytes;
+                }
+                Event::PostFinished { .. } => progress.finished_posts += 1,
+                Event::Error { .. } => progress.failed_posts += 1,
+            }
+        }
+        write_notification(
+            "event",
+            serde_json::json!({"jobId": job_id, "event": event}),
+        );
+    }
+}
+
+fn write_notification(method: &str, params: serde_json::Value) {
+    write_message(&serde_json::json!({"jsonrpc": "2.0", "method": method, "params": params}));
+}
+
+/// Writes the message as a line of stdout.
+fn write_message(message: &serde_json::Value) {
+    use std::io::Write as _;
+
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+    // Closed stdout means the frontend has gone, which also closes stdin to stop the agent
+    let _ = writeln!(stdout, "{}", message).and_then(|()| stdout.flush());
+}
@@ -0,0 +1,13 @@
+#[derive(Debug, clap::Args)]
+pub struct CompletionsArgs {
+    /// Shell to generate the completion script for
+    #[clap(value_enum)]
+    shell: clap_complete::Shell,
+}
+
+/// Prints the completion script of the shell.
+pub fn run(command: &mut clap::Command, args: CompletionsArgs) -> anyhow::Result<()> {
+    let bin_name = command.get_name().to_owned();
+    clap_complete::generate(args.shell, command, bin_name, &mut std::io::stdout());
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use anyhow::Context as _;
+
+/// Settings loaded from the TOML config file given by `--config`.
+///
+/// Command-line options take precedence over these values.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub session_id: Option<String>,
+    /// Cookie file read when FANBOXSESSID isn't given otherwise
+    pub cookie_file: Option<std::path::PathBuf>,
+    pub dest_dir: Option<std::path::PathBuf>,
+    pub referer: Option<String>,
+    pub accept_language: Option<String>,
+    pub interval: Option<f64>,
+    pub jitter: Option<f64>,
+    pub lang: Option<super::LangArg>,
+    pub copy_buffer_size: Option<usize>,
+    pub memory_limit: Option<usize>,
+    pub listing_concurrency: Option<usize>,
+    pub retries: Option<u32>,
+    /// Seconds for which responses of post listings and post.info are cached
+    pub cache_ttl: Option<u64>,
+    pub cache_dir: Option<std::path::PathBuf>,
+    pub log_file: Option<std::path::PathBuf>,
+    /// Start a new log file every period, suffixed with its date, e.g. fanbox-dl.log.2024-01-02
+    pub log_rotation: Option<LogRotation>,
+    /// Start a new log file when the log file would exceed this size in bytes, renaming older
+    /// ones to fanbox-dl.log.1, fanbox-dl.log.2 and so on
+    pub log_max_size: Option<u64>,
+    /// Number of rotated log files kept, deleting older ones [default: unlimited with
+    /// log_rotation, 5 with log_max_size]
+    pub log_max_files: Option<usize>,
+    /// Creators skipped with --all-supporting
+    #[serde(default)]
+    pub exclude_creators: Vec<String>,
+    /// Rhai scripts hooking into archiving of every creator's posts
+    #[serde(default)]
+    pub scripts: Vec<std::path::PathBuf>,
+    /// age or SSH public keys, or files of them, which tarballs of --backup-dir are encrypted to in
+    /// the age format
+    #[serde(default)]
+    pub backup_recipients: Vec<String>,
+    /// Email sent when a download run fails or the session expires
+    pub email: Option<EmailConfig>,
+    /// ntfy topic notified of new posts archived by each download run
+    pub ntfy: Option<NtfyConfig>,
+    /// Gotify server notified of new posts archived by each download run
+    pub gotify: Option<GotifyConfig>,
+    /// Overrides for each creator keyed by creator id
+    #[serde(default)]
+    pub creators: std::collections::HashMap<String, CreatorConfig>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogRotation {
+    Hourly,
+    Daily,
+    Weekly,
+}
+
+/// Settings of `[email]` to send notifications via SMTP.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EmailConfig {
+    pub smtp_host: String,
+    /// [default: 587 with starttls, 465 with tls, 25 with none]
+    pub smtp_port: Option<u16>,
+    /// [default: starttls]
+    pub security: Option<SmtpSecurity>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Sender, e.g. `fanbox-dl <fanbox-dl@example.com>`
+    pub from: String,
+    /// Recipients
+    pub to: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SmtpSecurity {
+    #[default]
+    Starttls,
+    Tls,
+    None,
+}
+
+/// Settings of `[ntfy]` to publish push notifications.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NtfyConfig {
+    /// URL of the topic, e.g. `https://ntfy.sh/my-fanbox-dl`
+    pub url: String,
+    /// Access token of the topic
+    pub token: Option<String>,
+    /// Priority of messages from 1 to 5 [default: 3]
+    pub priority: Option<u8>,
+}
+
+/// Settings of `[gotify]` to post push notifications.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GotifyConfig {
+    /// URL of the server, e.g. `https://gotify.example.com`
+    pub url: String,
+    /// Token of the application
+    pub token: String,
+    /// Priority of messages [default: the application's default]
+    pub priority: Option<u8>,
+}
+
+/// Settings of `[creators.<creator id>]` overriding the global ones for the creator.
+///
+/// Command-line options take precedence over these values too. Options of the whole run, e.g.
+/// --fsync, --jobs and the session, can't be overridden for a creator.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CreatorConfig {
+    pub dest_dir: Option<std::path::PathBuf>,
+    pub format: Option<super::download::FormatArg>,
+    pub image_size: Option<super::download::ImageSizeArg>,
+    pub layout: Option<super::download::LayoutArg>,
+    pub original_covers: Option<bool>,
+    pub convert_images: Option<super::download::ConvertImagesArg>,
+    pub no_index: Option<bool>,
+    pub index_name: Option<String>,
+    pub lightbox: Option<bool>,
+    pub description: Option<bool>,
+    pub nfo: Option<bool>,
+    pub dump_unknown: Option<bool>,
+    pub embeds_json: Option<bool>,
+    pub on_conflict: Option<super::download::OnConflictArg>,
+    pub concurrency: Option<usize>,
+    pub protect: Option<bool>,
+    pub link_duplicate_covers: Option<bool>,
+    pub dedup_media: Option<bool>,
+    /// Template of post directories as --output-template, ignored with --layout
+    pub output_template: Option<String>,
+    /// Download only posts of the creator tagged with this tag with --creator-id,
+    /// --all-supporting or --all-following
+    pub tag: Option<String>,
+    /// Rhai scripts hooking into archiving of the creator's posts, run after the global ones
+    #[serde(default)]
+    pub scripts: Vec<std::path::PathBuf>,
+}
+
+impl Config {
+    pub fn load(path: &std::path::Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("failed to parse config file {}", path.display()))
+    }
+}
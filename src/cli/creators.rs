@@ -0,0 +1,83 @@
+#[derive(Debug, clap::Args)]
+pub struct CreatorsArgs {
+    /// Print creators as JSON lines
+    #[clap(long)]
+    json: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Creator {
+    creator_id: String,
+    user_id: String,
+    name: String,
+    plan_title: Option<String>,
+    fee: Option<u32>,
+    supporting: bool,
+    following: bool,
+    last_updated_datetime: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+pub async fn run(ctx: &super::Context, args: CreatorsArgs) -> anyhow::Result<()> {
+    let client = ctx.client()?;
+    let mut creators: Vec<Creator> = client
+        .list_supporting_plans()
+        .await?
+        .into_iter()
+        .map(|plan| Creator {
+            creator_id: plan.creator_id,
+            user_id: plan.user.user_id,
+            name: plan.user.name,
+            plan_title: Some(plan.title),
+            fee: Some(plan.fee),
+            supporting: true,
+            following: false,
+            last_updated_datetime: None,
+        })
+        .collect();
+    for following in client.list_following_creators().await? {
+        if let Some(creator) = creators
+            .iter_mut()
+            .find(|c| c.creator_id == following.creator_id)
+        {
+            creator.following = true;
+        } else {
+            creators.push(Creator {
+                creator_id: following.creator_id,
+                user_id: following.user.user_id,
+                name: following.user.name,
+                plan_title: None,
+                fee: None,
+                supporting: false,
+                following: true,
+                last_updated_datetime: None,
+            });
+        }
+    }
+    for creator in &mut creators {
+        tracing::debug!("Getting latest post of {}", creator.creator_id);
+        creator.last_updated_datetime = client
+            .latest_post(&creator.creator_id)
+            .await?
+            .map(|item| item.updated_datetime);
+    }
+
+    for creator in creators {
+        if args.json {
+            println!("{}", serde_json::to_string(&creator)?);
+        } else {
+            println!(
+                "{}\t{}\t{}\t{}",
+                creator.creator_id,
+                creator
+                    .fee
+                    .map_or_else(|| "-".to_owned(), |fee| format!("{}yen", fee)),
+                creator
+                    .last_updated_datetime
+                    .map_or_else(|| "-".to_owned(), |t| t.to_rfc3339()),
+                creator.name
+            );
+        }
+    }
+    Ok(())
+}
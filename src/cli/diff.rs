@@ -0,0 +1,139 @@
+use futures::stream::TryStreamExt as _;
+
+#[derive(Debug, clap::Args)]
+pub struct DiffArgs {
+    /// Creator id, pixiv user id (also as pixiv:<id>), or URL of the creator on pixiv or FANBOX (repeatable)
+    #[clap(short, long, required = true)]
+    creator_id: Vec<String>,
+    /// Print differences as JSON lines
+    #[clap(long)]
+    json: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    /// Posted remotely but not archived
+    Missing,
+    /// Updated remotely since archived
+    Updated,
+    /// Archived but no longer visible remotely
+    Removed,
+}
+
+impl Status {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Missing => "missing",
+            Self::Updated => "updated",
+            Self::Removed => "removed",
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Difference {
+    creator_id: String,
+    status: Status,
+    post_id: String,
+    title: String,
+    remote_updated_datetime: Option<chrono::DateTime<chrono::Utc>>,
+    local_updated_datetime: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Compares archived posts with posts listed remotely without downloading anything.
+pub async fn run(ctx: &super::Context, args: DiffArgs) -> anyhow::Result<()> {
+    let client = ctx.client()?;
+    for input in &args.creator_id {
+        let creator_id = &super::resolve_creator_id(&client, input).await?;
+        let user_id = client.get_creator(creator_id).await?.user.user_id;
+        let dest_dir = ctx.creator_dest_dir(creator_id);
+        let (archived_posts, aliases) = {
+            let creator_id = creator_id.clone();
+            tokio::task::spawn_blocking(move || load_posts(&dest_dir, &creator_id)).await??
+        };
+        let mut local: Vec<_> = archived_posts
+            .iter()
+            .filter(|archived| {
+                let info = &archived.post.info;
+                match &info.user {
+                    Some(user) => user.user_id == user_id,
+                    // Metadata archived by older versions doesn't have the user
+                    None => &info.creator_id == creator_id || aliases.contains(&info.creator_id),
+                }
+            })
+            .map(|archived| &archived.post.info)
+            .collect();
+        let mut differences = Vec::new();
+        let items = client.paginate_creator(creator_id).await?;
+        futures::pin_mut!(items);
+        while let Some(item) = items.try_next().await? {
+            let status = match local.iter().position(|info| info.id == item.id) {
+                Some(i) => {
+                    let info = local.swap_remove(i);
+                    if info.updated_datetime < item.updated_datetime {
+                        Some((Status::Updated, Some(info.updated_datetime)))
+                    } else {
+                        None
+                    }
+                }
+                // Restricted posts cannot be archived
+                None if item.is_restricted => None,
+                None => Some((Status::Missing, None)),
+            };
+            if let Some((status, local_updated_datetime)) = status {
+                differences.push(Difference {
+                    creator_id: creator_id.clone(),
+                    status,
+                    post_id: item.id,
+                    title: item.title,
+                    remote_updated_datetime: Some(item.updated_datetime),
+                    local_updated_datetime,
+                });
+            }
+        }
+        for info in local {
+            differences.push(Difference {
+                creator_id: creator_id.clone(),
+                status: Status::Removed,
+                post_id: info.id.clone(),
+                title: info.title.clone(),
+                remote_updated_datetime: None,
+                local_updated_datetime: Some(info.updated_datetime),
+            });
+        }
+
+        for difference in differences {
+            if args.json {
+                println!("{}", serde_json::to_string(&difference)?);
+            } else {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    difference.creator_id,
+                    difference.status.as_str(),
+                    difference.post_id,
+                    difference.title
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Loads archived posts and former creator ids of the creator recorded in the state database.
+fn load_posts(
+    dest_dir: &std::path::Path,
+    creator_id: &str,
+) -> anyhow::Result<(Vec<fanbox_dl::archive::ArchivedPost>, Vec<String>)> {
+    let posts = fanbox_dl::archive::load_archived_posts(dest_dir)?;
+    let aliases = if dest_dir
+        .join(fanbox_dl::archive::STATE_DB_FILE_NAME)
+        .is_file()
+    {
+        fanbox_dl::archive::StateDb::open(dest_dir)?.creator_aliases(creator_id)?
+    } else {
+        Vec::new()
+    };
+    Ok((posts, aliases))
+}
@@ -0,0 +1,1292 @@
+use futures::stream::{StreamExt as _, TryStreamExt as _};
+
+#[derive(Debug, Default, clap::Args)]
+pub struct DownloadArgs {
+    /// Creator id, pixiv user id (also as pixiv:<id>), or URL of the creator on pixiv or FANBOX. Can be repeated
+    #[clap(short, long, required_unless_present_any = &["all_supporting", "all_following", "home", "tag", "bell", "retry_failed"])]
+    creator_id: Vec<String>,
+    /// Download posts of all creators you support. Use --layout by-creator to place their posts
+    /// in a directory for each creator
+    #[clap(long, conflicts_with = "creator_id")]
+    all_supporting: bool,
+    /// Download posts visible to you of all creators you follow, which can be combined with
+    /// --all-supporting
+    #[clap(long, conflicts_with = "creator_id")]
+    all_following: bool,
+    /// Download new posts in your home feed across creators, stopping at the first post which is
+    /// already archived and up-to-date
+    #[clap(
+        long,
+        conflicts_with_all = &["creator_id", "all_supporting", "all_following", "tag", "start_page_url", "resume", "covers_only"]
+    )]
+    home: bool,
+    /// Download new posts announced by your notifications, stopping at the first post which is
+    /// already archived and up-to-date
+    #[clap(
+        long,
+        conflicts_with_all = &["creator_id", "all_supporting", "all_following", "home", "tag", "start_page_url", "resume", "covers_only"]
+    )]
+    bell: bool,
+    /// Keep running and download again every SECONDS, which polls only notifications with --bell
+    #[clap(long, value_name = "SECONDS", conflicts_with_all = &["start_page_url", "resume"])]
+    watch: Option<u64>,
+    /// Ping this healthchecks.io-style URL at the start of each run, and on its success or with
+    /// /fail appended on its failure, with the run summary as the body
+    #[clap(long, value_name = "URL")]
+    healthcheck_url: Option<String>,
+    /// Log the number of downloaded files, the current speed and the ETA of downloads in progress
+    /// every SECONDS
+    #[clap(long, value_name = "SECONDS")]
+    progress: Option<u64>,
+    /// Download only posts tagged with this tag, of the given creators or of all creators without
+    /// --creator-id, --all-supporting or --all-following
+    #[clap(
+        long,
+        value_name = "NAME",
+        conflicts_with_all = &["start_page_url", "resume", "covers_only"]
+    )]
+    tag: Option<String>,
+    /// Skip the creator with --all-supporting or --all-following (repeatable)
+    #[clap(long, value_name = "CREATOR_ID")]
+    exclude_creator: Vec<String>,
+    /// Move files of a renamed creator to its new creator id
+    #[clap(long)]
+    relink_renamed: bool,
+    /// Resume from the given page URL of a single creator, as logged by a previous run
+    #[clap(long, conflicts_with_all = &["all_supporting", "all_following"])]
+    start_page_url: Option<String>,
+    /// Save raw JSON of unsupported post types, blocks and embeds into post directories
+    #[clap(long)]
+    dump_unknown: bool,
+    /// Size of images to download [default: original]
+    #[clap(long, value_enum)]
+    image_size: Option<ImageSizeArg>,
+    /// Where post directories are placed: flat for DEST_DIR/POST_ID, by-fee for
+    /// DEST_DIR/CREATOR_ID/<fee>yen/POST_ID, by-creator for DEST_DIR/CREATOR_ID/POST_ID. Posts
+    /// archived in another layout are moved [default: flat]
+    #[clap(long, value_enum)]
+    layout: Option<LayoutArg>,
+    /// Template of post directories and names of their media relative to DEST_DIR instead of
+    /// --layout, e.g. "{creator_id}/{published:%Y-%m}/{post_id}_{title}/{index:02}_{filename}".
+    /// Placeholders are {creator_id}, {post_id}, {title}, {fee}, {type}, {published[:FORMAT]}
+    /// and {updated[:FORMAT]}, and {index[:WIDTH]}, {file_id}, {name}, {ext} and {filename} in
+    /// the last component. Implies --sync to skip archived posts
+    #[clap(long, value_name = "TEMPLATE", conflicts_with = "layout")]
+    output_template: Option<fanbox_dl::archive::PathTemplate>,
+    /// Download cover images in the original resolution instead of the resized ones
+    #[clap(long)]
+    original_covers: bool,
+    /// Convert images in formats unsupported by older viewers, e.g. WebP, into this format
+    #[clap(long, value_enum, value_name = "FORMAT")]
+    convert_images: Option<ConvertImagesArg>,
+    /// Replace cover images identical to the first image or other cover images with hard links
+    #[clap(long)]
+    link_duplicate_covers: bool,
+    /// Replace downloaded media identical to already archived ones, including those in other
+    /// creators' directories, with hard links
+    #[clap(long)]
+    dedup_media: bool,
+    /// Download media and metadata only, without rendering index.html, index.md or index.txt
+    #[clap(long)]
+    no_index: bool,
+    /// File name of HTML pages, where {id} is replaced by the post id [default: index.html]
+    #[clap(long, value_name = "NAME")]
+    index_name: Option<String>,
+    /// Embed a lightbox viewer with keyboard navigation into pages of image and article posts
+    #[clap(long)]
+    lightbox: bool,
+    /// Write the post text to description.txt in each post directory
+    #[clap(long)]
+    description: bool,
+    /// Write Kodi/Jellyfin NFO files and folder art for media centers
+    #[clap(long)]
+    nfo: bool,
+    /// Write videos, links and HTML embedded in each post to embeds.json in its directory
+    #[clap(long)]
+    embeds_json: bool,
+    /// Command run in each post directory with the URL of each embedded YouTube, Vimeo or
+    /// SoundCloud content appended, e.g. "yt-dlp --no-progress"
+    #[clap(long, value_name = "COMMAND")]
+    embed_downloader: Option<String>,
+    /// Download only cover images of posts into DEST_DIR/CREATOR_ID/covers/ with a gallery page,
+    /// without post directories
+    #[clap(long, conflicts_with_all = &["resume", "start_page_url"])]
+    covers_only: bool,
+    /// Stop at the first post which is already archived and up-to-date
+    #[clap(long)]
+    stop_at_existing: bool,
+    /// Fetch and archive posts again even if their updated datetimes in the listing are the same
+    /// as archived ones, e.g. to apply other options or download missing media
+    #[clap(long)]
+    refresh: bool,
+    /// Record archived posts in DEST_DIR/.fanbox-dl.json and skip posts recorded there unless
+    /// they have been updated since then, without reading their metadata
+    #[clap(long, alias = "skip-existing", conflicts_with = "refresh")]
+    sync: bool,
+    /// Continue the previous interrupted run from its queue of posts, skipping completed ones
+    #[clap(long)]
+    resume: bool,
+    /// What to do when media, cover images or pages already exist [default: overwrite]
+    #[clap(long, value_enum, value_name = "POLICY")]
+    on_conflict: Option<OnConflictArg>,
+    /// Make post directories and their files read-only after archived, which are made writable
+    /// again only while their posts are updated
+    #[clap(long)]
+    protect: bool,
+    /// Flush downloaded files and their directory entries to disk before recording them as
+    /// downloaded, so that a power loss can't leave empty files behind
+    #[clap(long)]
+    fsync: bool,
+    /// Number of posts whose metadata is fetched ahead of downloading their media [default: 4]
+    #[clap(long)]
+    prefetch: Option<usize>,
+    /// Number of posts whose media are downloaded concurrently across all creators [default: 2]
+    #[clap(short, long)]
+    jobs: Option<usize>,
+    /// Number of images and files of each post downloaded concurrently, throttled together by
+    /// --interval [default: 2]
+    #[clap(long, value_name = "N")]
+    concurrency: Option<usize>,
+    /// Number of creators archived concurrently with multiple creators [default: 1]
+    #[clap(long, value_name = "N")]
+    parallel_creators: Option<usize>,
+    /// Number of posts of each creator downloaded concurrently, so that a creator with huge files
+    /// doesn't take all of --jobs [default: --jobs]
+    #[clap(long, value_name = "N")]
+    jobs_per_creator: Option<usize>,
+    /// Stop starting new posts once this many bytes are downloaded in the run, e.g. 50GiB,
+    /// finishing the ones in progress. Run again with --resume to continue from there
+    #[clap(long, value_name = "SIZE", value_parser = parse_size)]
+    max_total_bytes: Option<u64>,
+    /// Log and skip posts which fail to be archived instead of stopping at the first one, and
+    /// fail the run once more than this ratio of posts fail, e.g. 0.2
+    #[clap(long, value_name = "RATE")]
+    max_failure_rate: Option<f64>,
+    /// Write posts which failed to be archived to this file as JSON lines at the end of the run,
+    /// which is emptied when none fails
+    #[clap(long, value_name = "FILE")]
+    failed_list: Option<std::path::PathBuf>,
+    /// Archive again only the posts listed in FILE written by --failed-list
+    #[clap(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = &["creator_id", "all_supporting", "all_following", "home", "bell", "tag"]
+    )]
+    retry_failed: Option<std::path::PathBuf>,
+    /// Fail when unsupported post types, blocks or embeds are found
+    #[clap(long)]
+    strict: bool,
+    /// Output format of archived posts [default: html]
+    #[clap(long, value_enum)]
+    format: Option<FormatArg>,
+    /// Also render posts with this renderer into post directories, e.g. index.md next to HTML
+    /// pages (repeatable)
+    #[clap(long, value_enum, value_name = "RENDERER")]
+    renderer: Vec<RendererArg>,
+    /// Load the WebAssembly plugin transforming posts, filtering their files or rendering extra
+    /// files into post directories (repeatable). Calls of the plugin executing more than about a
+    /// billion instructions fail
+    #[clap(long, value_name = "PATH")]
+    plugin: Vec<std::path::PathBuf>,
+    /// Load the Rhai script defining skip_post, skip_asset or rename_asset functions, run before
+    /// scripts in the config file (repeatable)
+    #[clap(long, value_name = "PATH")]
+    script: Vec<std::path::PathBuf>,
+    /// Write an Atom feed of archived posts to DEST_DIR/feeds/CREATOR_ID.atom after downloading
+    #[clap(long)]
+    feed: bool,
+    /// Base URL of DEST_DIR used for links in the feed [default: file URL of DEST_DIR]
+    #[clap(long)]
+    feed_base_url: Option<String>,
+    /// Write archived posts of the creator into one HTML document DEST_DIR/books/CREATOR_ID.html,
+    /// oldest first with a table of contents, after downloading
+    #[clap(long)]
+    book: bool,
+    /// Write DEST_DIR/CREATOR_ID/index.html listing archived posts of the creator under the
+    /// creator's banner, icon and profile after downloading
+    #[clap(long)]
+    creator_page: bool,
+    /// Write DEST_DIR/sitemap.xml and robots.txt for DEST_DIR served at this URL after downloading
+    #[clap(long, value_name = "URL")]
+    sitemap_base_url: Option<String>,
+    /// Write a tarball of files added or changed in DEST_DIR since the previous backup into this
+    /// directory after downloading, e.g. to ship only deltas to offsite backups
+    #[clap(long, value_name = "DIR")]
+    backup_dir: Option<std::path::PathBuf>,
+    /// Encrypt tarballs of --backup-dir in the age format to this age or SSH public key, or keys
+    /// in this file one per line. Can be repeated [default: backup_recipients in the config file]
+    #[clap(
+        long,
+        value_name = "RECIPIENT",
+        env = "FANBOX_DL_BACKUP_RECIPIENTS",
+        value_delimiter = ','
+    )]
+    backup_recipient: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FormatArg {
+    Html,
+    Hugo,
+    GalleryDl,
+    Text,
+}
+
+impl From<FormatArg> for fanbox_dl::archive::Format {
+    fn from(format: FormatArg) -> Self {
+        match format {
+            FormatArg::Html => Self::Html,
+            FormatArg::Hugo => Self::Hugo,
+            FormatArg::GalleryDl => Self::GalleryDl,
+            FormatArg::Text => Self::Text,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImageSizeArg {
+    Original,
+    W1200,
+    Thumb,
+}
+
+impl From<ImageSizeArg> for fanbox_dl::archive::ImageSize {
+    fn from(size: ImageSizeArg) -> Self {
+        match size {
+            ImageSizeArg::Original => Self::Original,
+            ImageSizeArg::W1200 => Self::W1200,
+            ImageSizeArg::Thumb => Self::Thumb,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LayoutArg {
+    Flat,
+    ByFee,
+    ByCreator,
+}
+
+impl From<LayoutArg> for fanbox_dl::archive::Layout {
+    fn from(layout: LayoutArg) -> Self {
+        match layout {
+            LayoutArg::Flat => Self::Flat,
+            LayoutArg::ByFee => Self::ByFee,
+            LayoutArg::ByCreator => Self::ByCreator,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConvertImagesArg {
+    Png,
+    Jpeg,
+}
+
+impl From<ConvertImagesArg> for fanbox_dl::archive::ConvertFormat {
+    fn from(format: ConvertImagesArg) -> Self {
+        match format {
+            ConvertImagesArg::Png => Self::Png,
+            ConvertImagesArg::Jpeg => Self::Jpeg,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum RendererArg {
+    Html,
+    Markdown,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OnConflictArg {
+    Skip,
+    Overwrite,
+    Rename,
+    Backup,
+    Trash,
+}
+
+impl From<OnConflictArg> for fanbox_dl::archive::ConflictPolicy {
+    fn from(policy: OnConflictArg) -> Self {
+        match policy {
+            OnConflictArg::Skip => Self::Skip,
+            OnConflictArg::Overwrite => Self::Overwrite,
+            OnConflictArg::Rename => Self::Rename,
+            OnConflictArg::Backup => Self::Backup,
+            OnConflictArg::Trash => Self::Trash,
+        }
+    }
+}
+
+impl DownloadArgs {
+    /// Arguments of `download -c CREATOR_ID` without other options, which leaves the settings to
+    /// the config file as in the command-line.
+    pub(super) fn for_creator(creator_id: &str) -> Self {
+        Self {
+            creator_id: vec![creator_id.to_owned()],
+            ..Default::default()
+        }
+    }
+
+    /// Number of posts whose media are downloaded concurrently across all creators.
+    fn jobs(&self) -> usize {
+        self.jobs.unwrap_or(2).max(1)
+    }
+}
+
+pub async fn run(ctx: &super::Context, args: DownloadArgs) -> anyhow::Result<()> {
+    let stats = std::sync::Arc::new(fanbox_dl::TransferStats::new());
+    let client = ctx
+        .client()?
+        .strict(args.strict)
+        .fsync(args.fsync)
+        .transfer_stats(stats.clone())
+        .dedup_requests(true);
+    // Fails before a long run rather than in the middle of it
+    if !ctx.offline && ctx.replay_http.is_none() {
+        client.check_session().await?;
+    }
+    let _reporter = args.progress.map(|seconds| {
+        AbortOnDrop(super::progress::spawn_reporter(
+            stats.clone(),
+            std::time::Duration::from_secs(seconds.max(1)),
+        ))
+    });
+    let interval = match args.watch {
+        Some(seconds) => std::time::Duration::from_secs(seconds),
+        None => {
+            let (result, report) = download_round(ctx, &client, &stats, &args).await;
+            super::notify::notify(ctx, &report).await;
+            return result;
+        }
+    };
+    let mut failing = false;
+    loop {
+        // Failures such as network errors are retried in the next round
+        let (result, report) = download_round(ctx, &client, &stats, &args).await;
+        if let Err(e) = &result {
+            tracing::error!("{:#}", e);
+        }
+        // Notify only the first of consecutive failures
+        if !(failing && report.is_failure()) {
+            super::notify::notify(ctx, &report).await;
+        }
+        failing = report.is_failure();
+        tracing::info!("Wait {} seconds for the next round", interval.as_secs());
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Task aborted when dropped.
+struct AbortOnDrop(tokio::task::JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Runs `download` once, reporting its start and result to `--healthcheck-url`.
+async fn download_round(
+    ctx: &super::Context,
+    client: &fanbox_dl::PostClient,
+    stats: &std::sync::Arc<fanbox_dl::TransferStats>,
+    args: &DownloadArgs,
+) -> (anyhow::Result<()>, super::notify::RunReport) {
+    if let Some(url) = &args.healthcheck_url {
+        super::notify::ping_healthcheck(url, "/start", String::new()).await;
+    }
+    // Posts and files may have changed since the previous round
+    client.clear_recent_requests();
+    let started_datetime = chrono::Utc::now();
+    let start = stats.snapshot();
+    let mut archived = std::collections::BTreeMap::new();
+    let result = download(ctx, client, stats, args, &mut archived).await;
+    let end = stats.snapshot();
+    super::progress::log_summary(&start, &end);
+    let run = fanbox_dl::archive::RunState {
+        started_datetime,
+        finished_datetime: chrono::Utc::now(),
+        posts: archived.values().sum::<usize>() as u64,
+        files: end.files.saturating_sub(start.files),
+        bytes: end.bytes.saturating_sub(start.bytes),
+    };
+    if let Err(e) =
+        fanbox_dl::archive::StateDb::open(&ctx.dest_dir).and_then(|db| db.record_run(&run))
+    {
+        tracing::warn!("Failed to record the run: {}", e);
+    }
+    let report = super::notify::RunReport::new(&result, archived);
+    if let Some(url) = &args.healthcheck_url {
+        let suffix = if report.is_failure() { "/fail" } else { "" };
+        super::notify::ping_healthcheck(url, suffix, report.body()).await;
+    }
+    (result, report)
+}
+
+/// Archives posts as requested by the arguments, counting new or updated posts of each creator
+/// into `archived`.
+async fn download(
+    ctx: &super::Context,
+    client: &fanbox_dl::PostClient,
+    stats: &std::sync::Arc<fanbox_dl::TransferStats>,
+    args: &DownloadArgs,
+    archived: &mut std::collections::BTreeMap<String, usize>,
+) -> anyhow::Result<()> {
+    // Invalid recipients fail before downloading rather than after it
+    let backup_options = backup_options(ctx, args)?;
+    let state = RunState::open(ctx, args, stats)?;
+    let result = download_posts(ctx, client, args, &state, archived).await;
+    if let Some(sync_manifest) = &state.sync_manifest {
+        sync_manifest.flush()?;
+    }
+    if let Some(path) = &args.failed_list {
+        state.failures.write_list(path)?;
+    }
+    result?;
+    state.failures.finish()?;
+    if state.is_capped() {
+        // The journal is kept to resume from the posts which aren't archived yet
+        tracing::warn!(
+            "Stopped after downloading --max-total-bytes. Run again with --resume to continue"
+        );
+        return Ok(());
+    }
+    state.finish()?;
+    if let Some(base_url) = &args.sitemap_base_url {
+        let path = fanbox_dl::archive::write_sitemap(&ctx.dest_dir, base_url)?;
+        tracing::info!("Wrote sitemap {}", path.display());
+    }
+    if let Some(backup_dir) = &args.backup_dir {
+        let dest_dir = ctx.dest_dir.clone();
+        let backup_dir = backup_dir.clone();
+        let backup = tokio::task::spawn_blocking(move || {
+            fanbox_dl::archive::write_differential_backup(&dest_dir, &backup_dir, &backup_options)
+        })
+        .await??;
+        match backup {
+            Some(backup) => tracing::info!(
+                "Wrote backup {} with {} changed files ({} bytes)",
+                backup.path.display(),
+                backup.files,
+                backup.bytes
+            ),
+            None => tracing::info!("No files changed since the previous backup"),
+        }
+    }
+    Ok(())
+}
+
+/// Returns options of the backup to `--backup-dir`, encrypted to `--backup-recipient` or
+/// `backup_recipients` in the config file.
+fn backup_options(
+    ctx: &super::Context,
+    args: &DownloadArgs,
+) -> anyhow::Result<fanbox_dl::archive::BackupOptions> {
+    let recipients = if args.backup_recipient.is_empty() {
+        &ctx.backup_recipients
+    } else {
+        &args.backup_recipient
+    };
+    let recipients = if args.backup_dir.is_none() || recipients.is_empty() {
+        None
+    } else {
+        Some(std::sync::Arc::new(fanbox_dl::archive::Recipients::parse(
+            recipients,
+        )?))
+    };
+    Ok(fanbox_dl::archive::BackupOptions { recipients })
+}
+
+async fn download_posts(
+    ctx: &super::Context,
+    client: &fanbox_dl::PostClient,
+    args: &DownloadArgs,
+    state: &RunState,
+    archived: &mut std::collections::BTreeMap<String, usize>,
+) -> anyhow::Result<()> {
+    if let Some(path) = &args.retry_failed {
+        return retry_failed(ctx, client, args, state, path, archived).await;
+    }
+    if args.start_page_url.is_some() && args.creator_id.len() > 1 {
+        anyhow::bail!("--start-page-url can't be used with multiple creators");
+    }
+    let mut creator_ids = Vec::new();
+    for input in &args.creator_id {
+        let creator_id = super::resolve_creator_id(client, input).await?;
+        if !creator_ids.contains(&creator_id) {
+            creator_ids.push(creator_id);
+        }
+    }
+    let mut listed_ids = Vec::new();
+    if args.all_supporting {
+        listed_ids.extend(client.list_supporting_creators().await?);
+    }
+    if args.all_following {
+        listed_ids.extend(
+            client
+                .list_following_creators()
+                .await?
+                .into_iter()
+                .map(|following| following.creator_id),
+        );
+    }
+    for creator_id in listed_ids {
+        if !creator_ids.contains(&creator_id) && !is_excluded(ctx, args, &creator_id) {
+            creator_ids.push(creator_id);
+        }
+    }
+    if args.home {
+        let items = client.home_items();
+        download_feed(ctx, client, args, state, items, true, archived).await?;
+    } else if args.bell {
+        let items = client.bell_items();
+        download_feed(ctx, client, args, state, items, true, archived).await?;
+    } else if let (Some(tag), true) = (
+        &args.tag,
+        args.creator_id.is_empty() && !args.all_supporting && !args.all_following,
+    ) {
+        let items = client.tagged_items(tag, None);
+        let stop = args.stop_at_existing;
+        download_feed(ctx, client, args, state, items, stop, archived).await?;
+    }
+    if let Some(tag) = &args.tag {
+        for creator_id in &creator_ids {
+            let user_id = client.get_creator(creator_id).await?.user.user_id;
+            let items = client.tagged_items(tag, Some(&user_id));
+            let stop = args.stop_at_existing;
+            download_feed(ctx, client, args, state, items, stop, archived).await?;
+        }
+        return Ok(());
+    }
+    let mut untagged_creator_ids = Vec::new();
+    for creator_id in creator_ids {
+        match ctx.creator_config(&creator_id).tag {
+            Some(tag) => {
+                let user_id = client.get_creator(&creator_id).await?.user.user_id;
+                let items = client.tagged_items(&tag, Some(&user_id));
+                let stop = args.stop_at_existing;
+                download_feed(ctx, client, args, state, items, stop, archived).await?;
+            }
+            None => untagged_creator_ids.push(creator_id),
+        }
+    }
+    let counts: Vec<_> = futures::stream::iter(&untagged_creator_ids)
+        .map(|creator_id| async move {
+            let count = download_creator(ctx, client, args, creator_id, state).await?;
+            Ok::<_, anyhow::Error>((creator_id, count))
+        })
+        .buffer_unordered(args.parallel_creators.unwrap_or(1).max(1))
+        .try_collect()
+        .await?;
+    for (creator_id, count) in counts {
+        if count > 0 {
+            *archived.entry(creator_id.clone()).or_default() += count;
+        }
+    }
+    Ok(())
+}
+
+/// Archives again only the posts in the list written by `--failed-list`.
+async fn retry_failed(
+    ctx: &super::Context,
+    client: &fanbox_dl::PostClient,
+    args: &DownloadArgs,
+    state: &RunState,
+    path: &std::path::Path,
+    archived: &mut std::collections::BTreeMap<String, usize>,
+) -> anyhow::Result<()> {
+    use anyhow::Context as _;
+
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let mut post_ids = std::collections::BTreeMap::<_, Vec<_>>::new();
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let failed: FailedPost = serde_json::from_str(line)
+            .with_context(|| format!("failed to parse {}", path.display()))?;
+        post_ids
+            .entry(failed.creator_id)
+            .or_default()
+            .push(failed.post_id);
+    }
+    for (creator_id, post_ids) in post_ids {
+        tracing::info!("Retry {} posts of {}", post_ids.len(), creator_id);
+        let dest_dir = prepare_creator(ctx, client, args, &creator_id).await?;
+        let options = archive_options(ctx, args, &creator_id, &dest_dir, state)?;
+        for post_id in post_ids {
+            if state.is_capped() {
+                break;
+            }
+            let result = archive_post(client, &dest_dir, &post_id, &options).await;
+            if state.failures.check(&creator_id, &post_id, result)? {
+                *archived.entry(creator_id.clone()).or_default() += 1;
+            }
+        }
+        finish_creator(ctx, client, args, &dest_dir, &creator_id, &options).await?;
+    }
+    Ok(())
+}
+
+/// Fetches and archives the post, returning whether it's new or updated since archived.
+async fn archive_post(
+    client: &fanbox_dl::PostClient,
+    dest_dir: &std::path::Path,
+    post_id: &str,
+    options: &fanbox_dl::archive::ArchiveOptions,
+) -> anyhow::Result<bool> {
+    let post = client.get_post(post_id).await;
+    archive_fetched_post(client, dest_dir, post, options).await
+}
+
+/// Archives the fetched post, returning whether it's new or updated since archived.
+///
+/// Posts which aren't in the response cache are skipped with `--offline`.
+async fn archive_fetched_post(
+    client: &fanbox_dl::PostClient,
+    dest_dir: &std::path::Path,
+    post: Result<fanbox_dl::Post, fanbox_dl::Error>,
+    options: &fanbox_dl::archive::ArchiveOptions,
+) -> anyhow::Result<bool> {
+    let post = match post {
+        Ok(post) => post,
+        Err(e @ fanbox_dl::Error::NotCached { .. }) => {
+            tracing::warn!("Skip post: {}", e);
+            return Ok(false);
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let new = is_new_post(dest_dir, &post);
+    fanbox_dl::archive::archive_post(client, dest_dir, post, options).await?;
+    Ok(new)
+}
+
+/// State shared by creators and feeds archived in a run.
+#[derive(Debug)]
+pub(super) struct RunState {
+    journal: std::sync::Arc<fanbox_dl::archive::Journal>,
+    /// Manifest of synced posts with `--sync`
+    sync_manifest: Option<std::sync::Arc<fanbox_dl::archive::SyncManifest>>,
+    failures: FailureRate,
+    /// Permits of posts downloaded concurrently across creators, bounded by `--jobs`
+    jobs: tokio::sync::Semaphore,
+    byte_cap: Option<ByteCap>,
+}
+
+impl RunState {
+    /// Opens the journal and the manifest of the run in the destination directory.
+    pub(super) fn open(
+        ctx: &super::Context,
+        args: &DownloadArgs,
+        stats: &std::sync::Arc<fanbox_dl::TransferStats>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            journal: std::sync::Arc::new(fanbox_dl::archive::Journal::open(
+                &ctx.dest_dir,
+                args.resume,
+            )?),
+            sync_manifest: if args.sync
+                || args.output_template.is_some()
+                || ctx
+                    .creators
+                    .values()
+                    .any(|config| config.output_template.is_some())
+            {
+                Some(std::sync::Arc::new(fanbox_dl::archive::SyncManifest::open(
+                    &ctx.dest_dir,
+                )?))
+            } else {
+                None
+            },
+            failures: FailureRate::new(args.max_failure_rate),
+            jobs: tokio::sync::Semaphore::new(args.jobs()),
+            byte_cap: args.max_total_bytes.map(|max| ByteCap {
+                stats: stats.clone(),
+                start: stats.snapshot().bytes,
+                max,
+            }),
+        })
+    }
+
+    /// Removes the journal after the run is completed.
+    pub(super) fn finish(self) -> anyhow::Result<()> {
+        if let Ok(journal) = std::sync::Arc::try_unwrap(self.journal) {
+            journal.finish()?;
+        }
+        Ok(())
+    }
+
+    /// Returns whether `--max-total-bytes` is reached, after which no more posts are started.
+    fn is_capped(&self) -> bool {
+        self.byte_cap
+            .as_ref()
+            .is_some_and(|cap| cap.stats.snapshot().bytes.saturating_sub(cap.start) >= cap.max)
+    }
+}
+
+/// Bytes downloaded in a run allowed by `--max-total-bytes`.
+#[derive(Debug)]
+struct ByteCap {
+    stats: std::sync::Arc<fanbox_dl::TransferStats>,
+    /// Bytes downloaded by the client before the run
+    start: u64,
+    max: u64,
+}
+
+/// Parses a size in bytes with an optional unit, e.g. 500MB or 50GiB.
+fn parse_size(s: &str) -> anyhow::Result<u64> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid size {}", s))?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1000,
+        "m" | "mb" => 1000_u64.pow(2),
+        "g" | "gb" => 1000_u64.pow(3),
+        "t" | "tb" => 1000_u64.pow(4),
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "tib" => 1 << 40,
+        _ => anyhow::bail!("invalid size unit {} in {}", unit, s),
+    };
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// Number of posts archived before `--max-failure-rate` can abort the run, so that a few early
+/// failures don't.
+const MIN_FAILURE_RATE_POSTS: usize = 10;
+
+/// Post failed to be archived, written by `--failed-list` and read by `--retry-failed`.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct FailedPost {
+    creator_id: String,
+    post_id: String,
+    url: String,
+    error: String,
+}
+
+/// Counts and records posts failed to be archived, which are skipped with `--max-failure-rate`.
+#[derive(Debug)]
+struct FailureRate {
+    max: Option<f64>,
+    attempted: std::sync::atomic::AtomicUsize,
+    failed: std::sync::atomic::AtomicUsize,
+    posts: std::sync::Mutex<Vec<FailedPost>>,
+}
+
+impl FailureRate {
+    fn new(max: Option<f64>) -> Self {
+        Self {
+            max,
+            attempted: std::sync::atomic::AtomicUsize::new(0),
+            failed: std::sync::atomic::AtomicUsize::new(0),
+            posts: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the result of archiving the post, where its failure is logged and skipped as
+    /// `T::default()` unless the failure rate exceeds `--max-failure-rate`.
+    fn check<T>(
+        &self,
+        creator_id: &str,
+        post_id: &str,
+        result: anyhow::Result<T>,
+    ) -> anyhow::Result<T>
+    where
+        T: Default,
+    {
+        use std::sync::atomic::Ordering;
+
+        let attempted = self.attempted.fetch_add(1, Ordering::Relaxed) + 1;
+        let e = match result {
+            Ok(value) => return Ok(value),
+            Err(e) => e,
+        };
+        self.posts.lock().unwrap().push(FailedPost {
+            creator_id: creator_id.to_owned(),
+            post_id: post_id.to_owned(),
+            url: format!("https://{}.fanbox.cc/posts/{}", creator_id, post_id),
+            error: format!("{:#}", e),
+        });
+        let max = match self.max {
+            Some(max) => max,
+            None => return Err(e),
+        };
+        let failed = self.failed.fetch_add(1, Ordering::Relaxed) + 1;
+        tracing::error!("Failed to archive post {}: {:#}", post_id, e);
+        if attempted >= MIN_FAILURE_RATE_POSTS && failed as f64 > max * attempted as f64 {
+            anyhow::bail!(
+                "{} of {} posts failed, exceeding --max-failure-rate {}",
+                failed,
+                attempted,
+                max
+            );
+        }
+        Ok(T::default())
+    }
+
+    /// Fails if the failure rate of the whole run exceeds `--max-failure-rate`.
+    fn finish(&self) -> anyhow::Result<()> {
+        use std::sync::atomic::Ordering;
+
+        let failed = self.failed.load(Ordering::Relaxed);
+        let max = match self.max {
+            Some(max) if failed > 0 => max,
+            _ => return Ok(()),
+        };
+        let attempted = self.attempted.load(Ordering::Relaxed);
+        if failed as f64 > max * attempted as f64 {
+            anyhow::bail!(
+                "{} of {} posts failed, exceeding --max-failure-rate {}",
+                failed,
+                attempted,
+                max
+            );
+        }
+        tracing::warn!("{} of {} posts failed", failed, attempted);
+        Ok(())
+    }
+
+    /// Writes the failed posts to `path` as JSON lines.
+    fn write_list(&self, path: &std::path::Path) -> anyhow::Result<()> {
+        use anyhow::Context as _;
+
+        let mut content = String::new();
+        for post in self.posts.lock().unwrap().iter() {
+            content.push_str(&serde_json::to_string(post)?);
+            content.push('\n');
+        }
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        Ok(())
+    }
+}
+
+fn is_excluded(ctx: &super::Context, args: &DownloadArgs, creator_id: &str) -> bool {
+    let excluded = args.exclude_creator.iter().any(|id| id == creator_id)
+        || ctx.exclude_creators.iter().any(|id| id == creator_id);
+    if excluded {
+        tracing::info!("Exclude creator {}", creator_id);
+    }
+    excluded
+}
+
+/// Archives posts listed across creators, stopping at the first post which is already archived
+/// and up-to-date if `stop_at_archived` is set.
+async fn download_feed<S>(
+    ctx: &super::Context,
+    client: &fanbox_dl::PostClient,
+    args: &DownloadArgs,
+    state: &RunState,
+    items: S,
+    stop_at_archived: bool,
+    archived: &mut std::collections::BTreeMap<String, usize>,
+) -> anyhow::Result<()>
+where
+    S: futures::stream::Stream<Item = Result<fanbox_dl::FeedItem, fanbox_dl::Error>>,
+{
+    let journal = &state.journal;
+    // Creators are prepared when their first post is found
+    let mut creators = std::collections::BTreeMap::new();
+    let mut excluded = std::collections::HashSet::new();
+    futures::pin_mut!(items);
+    while let Some(feed_item) = items.try_next().await? {
+        if state.is_capped() {
+            break;
+        }
+        let creator_id = feed_item.creator_id;
+        let item = feed_item.item;
+        if excluded.contains(&creator_id) {
+            continue;
+        }
+        let dest_dir = ctx.creator_dest_dir(&creator_id);
+        let up_to_date = is_up_to_date(&dest_dir, state, &item);
+        if stop_at_archived && up_to_date {
+            tracing::info!("Stop at already archived post {}", item.id);
+            break;
+        }
+        if up_to_date && !args.refresh {
+            tracing::debug!("Skip post {} unchanged since archived", item.id);
+            continue;
+        }
+        if !creators.contains_key(&creator_id) {
+            if is_excluded(ctx, args, &creator_id) {
+                excluded.insert(creator_id);
+                continue;
+            }
+            let dest_dir = prepare_creator(ctx, client, args, &creator_id).await?;
+            let options = archive_options(ctx, args, &creator_id, &dest_dir, state)?;
+            creators.insert(creator_id.clone(), (dest_dir, options));
+        }
+        let (dest_dir, options) = &creators[&creator_id];
+        if journal.is_post_completed(&item.id) {
+            tracing::debug!("Skip post {} completed in the previous run", item.id);
+            continue;
+        }
+        let result = archive_post(client, dest_dir, &item.id, options).await;
+        if state.failures.check(&creator_id, &item.id, result)? {
+            *archived.entry(creator_id).or_default() += 1;
+        }
+    }
+    for (creator_id, (dest_dir, options)) in &creators {
+        finish_creator(ctx, client, args, dest_dir, creator_id, options).await?;
+    }
+    Ok(())
+}
+
+#[tracing::instrument(skip_all, fields(creator_id))]
+async fn download_creator(
+    ctx: &super::Context,
+    client: &fanbox_dl::PostClient,
+    args: &DownloadArgs,
+    creator_id: &str,
+    state: &RunState,
+) -> anyhow::Result<usize> {
+    let journal = &state.journal;
+    let dest_dir = prepare_creator(ctx, client, args, creator_id).await?;
+    if args.covers_only {
+        let items: Vec<_> = client
+            .pages(creator_id, None)
+            .await?
+            .map_err(anyhow::Error::from)
+            .map_ok(|page| {
+                futures::stream::iter(page.items.into_iter().map(Ok::<_, anyhow::Error>))
+            })
+            .try_flatten()
+            .try_collect()
+            .await?;
+        let path =
+            fanbox_dl::archive::archive_covers(client, &dest_dir, creator_id, &items, ctx.lang)
+                .await?;
+        tracing::info!("Wrote cover gallery {}", path.display());
+        return Ok(0);
+    }
+    let options = archive_options(ctx, args, creator_id, &dest_dir, state)?;
+    let start = args
+        .start_page_url
+        .as_ref()
+        .map(|url| fanbox_dl::PageCursor {
+            index: 0,
+            url: url.clone(),
+        });
+    let post_ids = match journal.queued_posts(creator_id) {
+        Some(post_ids) => {
+            tracing::info!("Resume {} posts queued in the previous run", post_ids.len());
+            futures::stream::iter(post_ids.into_iter().map(Ok)).boxed_local()
+        }
+        None => {
+            let pages = client.pages(creator_id, start.as_ref()).await?;
+            pages
+                .map_err(anyhow::Error::from)
+                .map_ok(|page| {
+                    tracing::info!("Processing page {} {}", page.cursor.index, page.cursor.url);
+                    futures::stream::iter(page.items.into_iter().map(Ok::<_, anyhow::Error>))
+                })
+                .try_flatten()
+                .map_ok(|item| {
+                    let up_to_date = is_up_to_date(&dest_dir, state, &item);
+                    (item, up_to_date)
+                })
+                .try_take_while(|(item, up_to_date)| {
+                    let stop = args.stop_at_existing && *up_to_date;
+                    if stop {
+                        tracing::info!("Stop at already archived post {}", item.id);
+                    }
+                    futures::future::ready(Ok(!stop))
+                })
+                .and_then(|(item, up_to_date)| async move {
+                    // post.info isn't requested for posts unchanged since archived
+                    if up_to_date && !args.refresh {
+                        tracing::debug!("Skip post {} unchanged since archived", item.id);
+                        return Ok(None);
+                    }
+                    journal.enqueue_post(creator_id, &item.id)?;
+                    Ok(Some(item.id))
+                })
+                // Queued posts are archived without listing again when resumed after this
+                .chain(futures::stream::once(async move {
+                    journal.complete_listing(creator_id)?;
+                    Ok(None)
+                }))
+                .try_filter_map(|post_id| futures::future::ready(Ok(post_id)))
+                .boxed_local()
+        }
+    };
+    // Metadata of the following posts is fetched while media of the current ones are downloaded
+    let posts = post_ids
+        .try_filter(|post_id| {
+            let completed = journal.is_post_completed(post_id);
+            if completed {
+                tracing::debug!("Skip post {} completed in the previous run", post_id);
+            }
+            futures::future::ready(!completed)
+        })
+        .map_ok(|post_id| async move {
+            tracing::debug!("Getting post {}", post_id);
+            let post = client.get_post(&post_id).await;
+            Ok((post_id, post))
+        })
+        .try_buffered(args.prefetch.unwrap_or(4).max(1))
+        .try_take_while(|_| futures::future::ready(Ok(!state.is_capped())));
+    let archived = std::sync::atomic::AtomicUsize::new(0);
+    let jobs_per_creator = args.jobs_per_creator.unwrap_or(args.jobs()).max(1);
+    posts
+        .try_for_each_concurrent(jobs_per_creator, |(post_id, post)| {
+            let dest_dir = &dest_dir;
+            let options = &options;
+            let archived = &archived;
+            async move {
+                let _permit = state.jobs.acquire().await?;
+                let result = archive_fetched_post(client, dest_dir, post, options).await;
+                if state.failures.check(creator_id, &post_id, result)? {
+                    archived.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                }
+                Ok(())
+            }
+        })
+        .await?;
+
+    finish_creator(ctx, client, args, &dest_dir, creator_id, &options).await?;
+    Ok(archived.into_inner())
+}
+
+/// Returns whether the listed post is archived and hasn't been updated since then, looking up
+/// the manifest of `--sync` before metadata of the post.
+fn is_up_to_date(
+    dest_dir: &std::path::Path,
+    state: &RunState,
+    item: &fanbox_dl::ListCreatorItem,
+) -> bool {
+    if let Some(sync_manifest) = &state.sync_manifest {
+        if sync_manifest.is_up_to_date(&item.id, item.updated_datetime) {
+            return true;
+        }
+    }
+    fanbox_dl::archive::archived_updated_datetime(dest_dir, &item.id)
+        .is_some_and(|t| t >= item.updated_datetime)
+}
+
+/// Returns whether the post isn't archived yet or has been updated since archived.
+fn is_new_post(dest_dir: &std::path::Path, post: &fanbox_dl::Post) -> bool {
+    fanbox_dl::archive::archived_updated_datetime(dest_dir, &post.info.id)
+        .is_none_or(|t| t < post.info.updated_datetime)
+}
+
+/// Creates the destination directory of the creator and records the creator.
+pub(super) async fn prepare_creator(
+    ctx: &super::Context,
+    client: &fanbox_dl::PostClient,
+    args: &DownloadArgs,
+    creator_id: &str,
+) -> anyhow::Result<std::path::PathBuf> {
+    let dest_dir = ctx.creator_dest_dir(creator_id);
+    std::fs::create_dir_all(&dest_dir)?;
+    if let Some(version) = fanbox_dl::archive::check_format_version(&dest_dir)? {
+        tracing::warn!(
+            "{} is in format version {}. Run `fanbox-dl migrate` to upgrade it to {}",
+            dest_dir.display(),
+            version,
+            fanbox_dl::archive::FORMAT_VERSION
+        );
+    }
+    if !ctx.offline {
+        record_creator(client, &dest_dir, creator_id, args.relink_renamed).await?;
+    }
+    Ok(dest_dir)
+}
+
+/// Merges the options with the settings of the creator in the config file.
+pub(super) fn archive_options(
+    ctx: &super::Context,
+    args: &DownloadArgs,
+    creator_id: &str,
+    dest_dir: &std::path::Path,
+    state: &RunState,
+) -> anyhow::Result<fanbox_dl::archive::ArchiveOptions> {
+    use anyhow::Context as _;
+
+    let creator_config = ctx.creator_config(creator_id);
+    // --layout given on the command line takes precedence over the template in the config file
+    let path_template = match (&args.output_template, &creator_config.output_template) {
+        (Some(template), _) => Some(template.clone()),
+        (None, Some(template)) if args.layout.is_none() => Some(
+            template
+                .parse()
+                .with_context(|| format!("invalid output_template of creator {}", creator_id))?,
+        ),
+        (None, _) => None,
+    };
+    let index_name = args
+        .index_name
+        .clone()
+        .or_else(|| creator_config.index_name.clone());
+    let lightbox = args.lightbox || creator_config.lightbox.unwrap_or(false);
+    let mut renderers: Vec<std::sync::Arc<dyn fanbox_dl::archive::Renderer>> = args
+        .renderer
+        .iter()
+        .map(
+            |renderer| -> std::sync::Arc<dyn fanbox_dl::archive::Renderer> {
+                match renderer {
+                    RendererArg::Html => std::sync::Arc::new(fanbox_dl::archive::HtmlRenderer {
+                        lang: ctx.lang,
+                        lightbox,
+                        index_name: index_name.clone(),
+                    }),
+                    RendererArg::Markdown => {
+                        std::sync::Arc::new(fanbox_dl::archive::MarkdownRenderer)
+                    }
+                }
+            },
+        )
+        .collect();
+    let mut hooks: Vec<std::sync::Arc<dyn fanbox_dl::archive::Hook>> = Vec::new();
+    for path in &args.plugin {
+        let plugin = std::sync::Arc::new(fanbox_dl::archive::WasmPlugin::load(path)?);
+        hooks.push(plugin.clone());
+        renderers.push(plugin);
+    }
+    for path in args
+        .script
+        .iter()
+        .chain(&ctx.scripts)
+        .chain(&creator_config.scripts)
+    {
+        hooks.push(std::sync::Arc::new(fanbox_dl::archive::ScriptHook::load(
+            path,
+        )?));
+    }
+    Ok(fanbox_dl::archive::ArchiveOptions {
+        dump_unknown: args.dump_unknown || creator_config.dump_unknown.unwrap_or(false),
+        format: args
+            .format
+            .or(creator_config.format)
+            .map_or_else(Default::default, Into::into),
+        no_index: args.no_index || creator_config.no_index.unwrap_or(false),
+        index_name,
+        lang: ctx.lang,
+        layout: args
+            .layout
+            .or(creator_config.layout)
+            .map_or_else(Default::default, Into::into),
+        path_template,
+        lightbox,
+        description: args.description || creator_config.description.unwrap_or(false),
+        nfo: args.nfo || creator_config.nfo.unwrap_or(false),
+        embeds_json: args.embeds_json || creator_config.embeds_json.unwrap_or(false),
+        embed_downloader: args
+            .embed_downloader
+            .as_ref()
+            .map(|command| command.split_whitespace().map(str::to_owned).collect()),
+        image_size: args
+            .image_size
+            .or(creator_config.image_size)
+            .map_or_else(Default::default, Into::into),
+        original_covers: args.original_covers || creator_config.original_covers.unwrap_or(false),
+        convert_images: args
+            .convert_images
+            .or(creator_config.convert_images)
+            .map(Into::into),
+        render_only: ctx.offline,
+        link_duplicate_covers: args.link_duplicate_covers
+            || creator_config.link_duplicate_covers.unwrap_or(false),
+        dedup_index_dir: (args.dedup_media || creator_config.dedup_media.unwrap_or(false))
+            .then(|| ctx.dest_dir.clone()),
+        on_conflict: args
+            .on_conflict
+            .or(creator_config.on_conflict)
+            .map_or_else(Default::default, Into::into),
+        concurrency: args.concurrency.or(creator_config.concurrency).unwrap_or(2),
+        fsync: args.fsync,
+        protect: args.protect || creator_config.protect.unwrap_or(false),
+        journal: Some(state.journal.clone()),
+        sync_manifest: state.sync_manifest.clone(),
+        download_log: Some(std::sync::Arc::new(fanbox_dl::archive::DownloadLog::open(
+            dest_dir,
+        )?)),
+        renderers,
+        hooks,
+        ..Default::default()
+    })
+}
+
+/// Writes navigation links, the manifest, the feed and the creator page of the creator's archived
+/// posts.
+pub(super) async fn finish_creator(
+    ctx: &super::Context,
+    client: &fanbox_dl::PostClient,
+    args: &DownloadArgs,
+    dest_dir: &std::path::Path,
+    creator_id: &str,
+    options: &fanbox_dl::archive::ArchiveOptions,
+) -> anyhow::Result<()> {
+    if options.format == fanbox_dl::archive::Format::Html && !options.no_index {
+        let updated =
+            fanbox_dl::archive::write_post_navigation(dest_dir, creator_id, options.lang)?;
+        tracing::debug!("Updated navigation links of {} pages", updated);
+    }
+
+    let path = fanbox_dl::archive::write_posts_manifest(dest_dir, creator_id)?;
+    tracing::debug!("Wrote manifest {}", path.display());
+
+    if args.feed || args.feed_base_url.is_some() {
+        let path = fanbox_dl::archive::write_atom_feed(
+            dest_dir,
+            creator_id,
+            args.feed_base_url.as_deref(),
+        )?;
+        tracing::info!("Wrote feed {}", path.display());
+    }
+
+    if args.book {
+        let path = fanbox_dl::archive::write_book(dest_dir, creator_id, options.lang)?;
+        tracing::info!("Wrote book {}", path.display());
+    }
+
+    // The creator's profile can't be fetched offline
+    if args.creator_page && !ctx.offline {
+        let creator = client.get_creator(creator_id).await?;
+        let path = fanbox_dl::archive::write_creator_page(client, dest_dir, &creator, options.lang)
+            .await?;
+        tracing::info!("Wrote creator page {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Records the creator in the state database and detects the rename of creator id.
+async fn record_creator(
+    client: &fanbox_dl::PostClient,
+    dest_dir: &std::path::Path,
+    creator_id: &str,
+    relink_renamed: bool,
+) -> anyhow::Result<()> {
+    let creator = client.get_creator(creator_id).await?;
+    let state_db = fanbox_dl::archive::StateDb::open(dest_dir)?;
+    if let Some(old_creator_id) = state_db.record_creator(&creator.user.user_id, creator_id)? {
+        if relink_renamed {
+            for (from, to) in
+                fanbox_dl::archive::relink_creator(dest_dir, &old_creator_id, creator_id)?
+            {
+                tracing::info!("Moved {} to {}", from.display(), to.display());
+            }
+        } else {
+            tracing::warn!(
+                "Creator {} was renamed to {}. Run with --relink-renamed to move its files",
+                old_creator_id,
+                creator_id
+            );
+        }
+    }
+    Ok(())
+}
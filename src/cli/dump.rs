@@ -0,0 +1,52 @@
+use futures::stream::TryStreamExt as _;
+
+#[derive(Debug, clap::Args)]
+pub struct DumpArgs {
+    /// Creator id, pixiv user id (also as pixiv:<id>), or URL of the creator on pixiv or FANBOX. Required unless
+    /// --archived is given
+    #[clap(short, long, required_unless_present = "archived")]
+    creator_id: Option<String>,
+    /// Print metadata of posts in the downloaded archive without network access, limited to the
+    /// creator when given
+    #[clap(long)]
+    archived: bool,
+}
+
+/// Prints metadata of posts including their bodies as JSON lines, in the same form as
+/// metadata.json of archived posts.
+pub async fn run(ctx: &super::Context, args: DumpArgs) -> anyhow::Result<()> {
+    use std::io::Write as _;
+
+    if args.archived {
+        let dest_dir = ctx.dest_dir.clone();
+        let posts =
+            tokio::task::spawn_blocking(move || fanbox_dl::archive::load_archived_posts(&dest_dir))
+                .await??;
+        let stdout = std::io::stdout();
+        let mut stdout = stdout.lock();
+        for archived in posts {
+            let creator_id = &archived.post.info.creator_id;
+            if args.creator_id.as_ref().is_none_or(|id| id == creator_id) {
+                serde_json::to_writer(&mut stdout, &archived.post)?;
+                writeln!(stdout)?;
+            }
+        }
+        return Ok(());
+    }
+
+    let client = ctx.client()?;
+    let creator_id = match &args.creator_id {
+        Some(creator_id) => super::resolve_creator_id(&client, creator_id).await?,
+        None => anyhow::bail!("--creator-id is required without --archived"),
+    };
+    let items = client.paginate_creator(&creator_id).await?;
+    futures::pin_mut!(items);
+    while let Some(item) = items.try_next().await? {
+        let post = client.get_post(&item.id).await?;
+        let stdout = std::io::stdout();
+        let mut stdout = stdout.lock();
+        serde_json::to_writer(&mut stdout, &post)?;
+        writeln!(stdout)?;
+    }
+    Ok(())
+}
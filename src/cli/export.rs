@@ -0,0 +1,66 @@
+#[derive(Debug, clap::Args)]
+pub struct ExportArgs {
+    /// Directory to write exported files into
+    out_dir: std::path::PathBuf,
+    /// Format to export posts into
+    #[clap(long, value_enum)]
+    format: ExportFormatArg,
+    /// Export posts of this creator only
+    #[clap(short, long)]
+    creator_id: Option<String>,
+    /// Encrypt exported files in the age format to this age or SSH public key, or keys in this
+    /// file one per line, written as <FILE>.age. Can be repeated
+    #[clap(
+        long,
+        value_name = "RECIPIENT",
+        env = "FANBOX_DL_EXPORT_RECIPIENTS",
+        value_delimiter = ','
+    )]
+    recipient: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum ExportFormatArg {
+    Markdown,
+    Epub,
+    Cbz,
+    Zip,
+    SingleFile,
+    Mhtml,
+    Json,
+}
+
+impl From<ExportFormatArg> for fanbox_dl::archive::ExportFormat {
+    fn from(format: ExportFormatArg) -> Self {
+        match format {
+            ExportFormatArg::Markdown => Self::Markdown,
+            ExportFormatArg::Epub => Self::Epub,
+            ExportFormatArg::Cbz => Self::Cbz,
+            ExportFormatArg::Zip => Self::Zip,
+            ExportFormatArg::SingleFile => Self::SingleFile,
+            ExportFormatArg::Mhtml => Self::Mhtml,
+            ExportFormatArg::Json => Self::Json,
+        }
+    }
+}
+
+pub async fn run(ctx: &super::Context, args: ExportArgs) -> anyhow::Result<()> {
+    let dest_dir = ctx.dest_dir.clone();
+    let out_dir = args.out_dir.clone();
+    let options = fanbox_dl::archive::ExportOptions {
+        creator_id: args.creator_id,
+        recipients: if args.recipient.is_empty() {
+            None
+        } else {
+            Some(std::sync::Arc::new(fanbox_dl::archive::Recipients::parse(
+                &args.recipient,
+            )?))
+        },
+    };
+    let count = tokio::task::spawn_blocking(move || {
+        fanbox_dl::archive::export(&dest_dir, &out_dir, args.format.into(), &options)
+    })
+    .await??;
+    tracing::info!("Wrote {} files to {}", count, args.out_dir.display());
+    Ok(())
+}
@@ -0,0 +1,50 @@
+#[derive(Debug, clap::Args)]
+pub struct GcArgs {
+    /// Remove temporary files which haven't been modified for the given hours
+    #[clap(long, default_value = "24")]
+    min_age_hours: u64,
+    /// Replace duplicated files with hard links
+    #[clap(long)]
+    hard_link: bool,
+    /// Only report what would be removed or linked
+    #[clap(long)]
+    dry_run: bool,
+}
+
+/// Removes stale temporary files and reports or compacts duplicated files in the archive.
+pub async fn run(ctx: &super::Context, args: GcArgs) -> anyhow::Result<()> {
+    let dest_dir = ctx.dest_dir.clone();
+    tokio::task::spawn_blocking(move || gc(&dest_dir, &args)).await?
+}
+
+fn gc(dest_dir: &std::path::Path, args: &GcArgs) -> anyhow::Result<()> {
+    let min_age = std::time::Duration::from_secs(args.min_age_hours * 60 * 60);
+    for path in fanbox_dl::archive::find_stale_temporaries(dest_dir, min_age)? {
+        if args.dry_run {
+            tracing::info!("Would remove {}", path.display());
+        } else {
+            tracing::info!("Remove {}", path.display());
+            std::fs::remove_file(&path)?;
+        }
+    }
+
+    let mut reclaimable = 0;
+    for duplicates in fanbox_dl::archive::find_duplicates(dest_dir)? {
+        let paths: Vec<_> = duplicates
+            .paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        println!("{}\t{}", duplicates.size, paths.join("\t"));
+        reclaimable += duplicates.size * (duplicates.paths.len() as u64 - 1);
+        if args.hard_link && !args.dry_run {
+            fanbox_dl::archive::link_duplicates(&duplicates)?;
+        }
+    }
+    if args.hard_link && !args.dry_run {
+        tracing::info!("Reclaimed {} bytes by hard links", reclaimable);
+    } else {
+        tracing::info!("{} bytes can be reclaimed by --hard-link", reclaimable);
+    }
+    Ok(())
+}
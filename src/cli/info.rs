@@ -0,0 +1,141 @@
+#[derive(Debug, clap::Args)]
+pub struct InfoArgs {
+    /// Post URL (e.g. https://creator.fanbox.cc/posts/123) or post ID
+    post: String,
+    /// Print the full post as JSON
+    #[clap(long)]
+    json: bool,
+}
+
+pub async fn run(ctx: &super::Context, args: InfoArgs) -> anyhow::Result<()> {
+    let client = ctx.client()?;
+    let post = client.get_post(parse_post_id(&args.post)).await?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&post)?);
+    } else {
+        print_post(&post);
+    }
+    Ok(())
+}
+
+/// Extracts the post ID from post URLs such as `https://creator.fanbox.cc/posts/123` and
+/// `https://www.fanbox.cc/@creator/posts/123`. Other inputs are returned as is.
+pub fn parse_post_id(post: &str) -> &str {
+    match post.rsplit_once("/posts/") {
+        Some((_, rest)) => rest.split(['/', '?', '#']).next().unwrap_or(rest),
+        None => post,
+    }
+}
+
+fn print_post(post: &fanbox_dl::Post) {
+    let info = &post.info;
+    println!("Title:   {}", info.title);
+    println!(
+        "URL:     https://{}.fanbox.cc/posts/{}",
+        info.creator_id, info.id
+    );
+    println!("Updated: {}", info.updated_datetime);
+    if let Some(cover_image_url) = &info.cover_image_url {
+        println!("Cover:   {}", cover_image_url);
+    }
+    match &post.body {
+        None => println!("Type:    (restricted)"),
+        Some(fanbox_dl::PostBody::Image(image)) => {
+            println!("Type:    image");
+            print_images(image.body.images.iter());
+        }
+        Some(fanbox_dl::PostBody::File(file)) => {
+            println!("Type:    file");
+            print_files(file.body.files.iter());
+        }
+        Some(fanbox_dl::PostBody::Article(article)) => {
+            println!("Type:    article ({} blocks)", article.body.blocks.len());
+            print_images(article.body.image_map.values());
+            print_files(article.body.file_map.values());
+            if !article.body.embed_map.is_empty() {
+                println!("Embeds:");
+                for embed in article.body.embed_map.values() {
+                    match embed {
+                        fanbox_dl::Embed::Twitter(twitter) => {
+                            println!("  twitter {}", twitter.content_id)
+                        }
+                        fanbox_dl::Embed::Fanbox(fanbox) => {
+                            println!("  fanbox {}", fanbox.content_id)
+                        }
+                        fanbox_dl::Embed::Youtube(youtube) => {
+                            println!("  youtube {}", youtube.content_id)
+                        }
+                        fanbox_dl::Embed::Vimeo(vimeo) => println!("  vimeo {}", vimeo.content_id),
+                        fanbox_dl::Embed::Soundcloud(soundcloud) => {
+                            println!("  soundcloud {}", soundcloud.content_id)
+                        }
+                        fanbox_dl::Embed::Unknown(raw) => println!("  unknown {}", raw),
+                        _ => println!("  unsupported"),
+                    }
+                }
+            }
+            if !article.body.url_embed_map.is_empty() {
+                println!("URL embeds:");
+                for url_embed in article.body.url_embed_map.values() {
+                    match url_embed {
+                        fanbox_dl::UrlEmbed::Default(default) => println!("  {}", default.url),
+                        fanbox_dl::UrlEmbed::Html(_) | fanbox_dl::UrlEmbed::HtmlCard(_) => {
+                            println!("  (html)")
+                        }
+                        fanbox_dl::UrlEmbed::Unknown(raw) => println!("  unknown {}", raw),
+                        _ => println!("  unsupported"),
+                    }
+                }
+            }
+        }
+        Some(fanbox_dl::PostBody::Text(_)) => println!("Type:    text"),
+        Some(fanbox_dl::PostBody::Video(video)) => {
+            println!("Type:    video");
+            match &video.body.video {
+                fanbox_dl::Video::Youtube(youtube) => {
+                    println!("Video:   youtube {}", youtube.video_id)
+                }
+                fanbox_dl::Video::Vimeo(vimeo) => println!("Video:   vimeo {}", vimeo.video_id),
+                fanbox_dl::Video::Soundcloud(soundcloud) => {
+                    println!("Video:   soundcloud {}", soundcloud.video_id)
+                }
+                fanbox_dl::Video::Unknown(raw) => println!("Video:   unknown {}", raw),
+                _ => println!("Video:   unsupported"),
+            }
+        }
+        Some(fanbox_dl::PostBody::Unknown(raw)) => println!("Type:    unknown {}", raw),
+        Some(_) => println!("Type:    unsupported"),
+    }
+}
+
+fn print_images<'a, I>(images: I)
+where
+    I: ExactSizeIterator<Item = &'a fanbox_dl::Image>,
+{
+    if images.len() == 0 {
+        return;
+    }
+    println!("Images:");
+    for image in images {
+        println!("  {}.{} {}", image.id, image.extension, image.original_url);
+    }
+}
+
+fn print_files<'a, I>(files: I)
+where
+    I: ExactSizeIterator<Item = &'a fanbox_dl::File>,
+{
+    if files.len() == 0 {
+        return;
+    }
+    println!("Files:");
+    for file in files {
+        match file.size {
+            Some(size) => println!(
+                "  {}.{} ({} bytes) {}",
+                file.name, file.extension, size, file.url
+            ),
+            None => println!("  {}.{} {}", file.name, file.extension, file.url),
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use futures::stream::TryStreamExt as _;
+
+#[derive(Debug, clap::Args)]
+pub struct ListArgs {
+    /// Creator id, pixiv user id (also as pixiv:<id>), or URL of the creator on pixiv or FANBOX
+    #[clap(short, long)]
+    creator_id: String,
+}
+
+pub async fn run(ctx: &super::Context, args: ListArgs) -> anyhow::Result<()> {
+    use std::io::Write as _;
+
+    let client = ctx.client()?;
+    let creator_id = super::resolve_creator_id(&client, &args.creator_id).await?;
+    let items = client.paginate_creator(&creator_id).await?;
+    futures::pin_mut!(items);
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+    while let Some(item) = items.try_next().await? {
+        serde_json::to_writer(&mut stdout, &item)?;
+        writeln!(stdout)?;
+    }
+    Ok(())
+}
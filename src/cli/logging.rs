@@ -0,0 +1,220 @@
+use anyhow::Context as _;
+use tracing_subscriber::layer::SubscriberExt as _;
+use tracing_subscriber::util::SubscriberInitExt as _;
+use tracing_subscriber::Layer as _;
+
+/// Installed subscriber, which must be shut down before exiting to flush pending logs.
+#[derive(Debug)]
+pub struct Logger {
+    #[cfg(feature = "otel")]
+    tracer_provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Logger {
+    pub fn shutdown(self) {
+        #[cfg(feature = "otel")]
+        if let Some(tracer_provider) = self.tracer_provider {
+            if let Err(e) = tracer_provider.shutdown() {
+                tracing::warn!("Failed to flush spans: {}", e);
+            }
+        }
+    }
+}
+
+/// Number of log files kept with `log_max_size` unless `log_max_files` is given.
+const DEFAULT_MAX_SIZE_FILES: usize = 5;
+
+/// Installs the subscriber printing logs to stdout, or stderr if `stderr` is set, at the
+/// verbosity given by `-q`/`-v` or RUST_LOG, and writing debug logs to `--log-file` or `log_file`
+/// in the config file.
+pub fn init(
+    global: &super::GlobalArgs,
+    config: &super::config::Config,
+    stderr: bool,
+) -> anyhow::Result<Logger> {
+    let file_layer = match global.log_file.as_ref().or(config.log_file.as_ref()) {
+        Some(path) => {
+            let level = if global.verbose >= 2 {
+                "trace"
+            } else {
+                "debug"
+            };
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(log_writer(path, config)?)
+                    .with_filter(tracing_subscriber::EnvFilter::new(level)),
+            )
+        }
+        None => None,
+    };
+    let console_writer = if stderr {
+        tracing_subscriber::fmt::writer::BoxMakeWriter::new(std::io::stderr)
+    } else {
+        tracing_subscriber::fmt::writer::BoxMakeWriter::new(std::io::stdout)
+    };
+    let registry = tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(console_writer)
+                .with_filter(console_filter(global)),
+        )
+        .with(file_layer);
+
+    #[cfg(feature = "otel")]
+    {
+        let (otel_layer, tracer_provider) = match super::otel::layer()? {
+            Some((layer, tracer_provider)) => (
+                Some(layer.with_filter(console_filter(global))),
+                Some(tracer_provider),
+            ),
+            None => (None, None),
+        };
+        registry.with(otel_layer).init();
+        Ok(Logger { tracer_provider })
+    }
+    #[cfg(not(feature = "otel"))]
+    {
+        registry.init();
+        Ok(Logger {})
+    }
+}
+
+/// Opens the log file rotated as `log_rotation` or `log_max_size` in the config file.
+fn log_writer(
+    path: &std::path::Path,
+    config: &super::config::Config,
+) -> anyhow::Result<tracing_subscriber::fmt::writer::BoxMakeWriter> {
+    use tracing_appender::rolling::Rotation;
+
+    match (config.log_rotation, config.log_max_size) {
+        (Some(_), Some(_)) => {
+            anyhow::bail!("log_rotation and log_max_size can't be set at the same time")
+        }
+        (Some(rotation), None) => {
+            let file_name = path
+                .file_name()
+                .with_context(|| format!("invalid log file {}", path.display()))?;
+            let mut builder = tracing_appender::rolling::RollingFileAppender::builder()
+                .rotation(match rotation {
+                    super::config::LogRotation::Hourly => Rotation::HOURLY,
+                    super::config::LogRotation::Daily => Rotation::DAILY,
+                    super::config::LogRotation::Weekly => Rotation::WEEKLY,
+                })
+                .filename_prefix(file_name.to_string_lossy());
+            if let Some(max_files) = config.log_max_files {
+                builder = builder.max_log_files(max_files);
+            }
+            let dir = match path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir,
+                _ => std::path::Path::new("."),
+            };
+            let appender = builder
+                .build(dir)
+                .with_context(|| format!("failed to open log file {}", path.display()))?;
+            Ok(tracing_subscriber::fmt::writer::BoxMakeWriter::new(
+                appender,
+            ))
+        }
+        (None, Some(max_size)) => {
+            let file = SizeRotatingFile::open(
+                path,
+                max_size,
+                config.log_max_files.unwrap_or(DEFAULT_MAX_SIZE_FILES),
+            )
+            .with_context(|| format!("failed to open log file {}", path.display()))?;
+            Ok(tracing_subscriber::fmt::writer::BoxMakeWriter::new(
+                std::sync::Mutex::new(file),
+            ))
+        }
+        (None, None) => {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("failed to open log file {}", path.display()))?;
+            Ok(tracing_subscriber::fmt::writer::BoxMakeWriter::new(
+                std::sync::Mutex::new(file),
+            ))
+        }
+    }
+}
+
+/// Log file renamed to `<path>.1` when it would exceed `max_size` bytes, shifting older ones to
+/// `<path>.2`, `<path>.3` and so on up to `<path>.<max_files>`.
+#[derive(Debug)]
+struct SizeRotatingFile {
+    path: std::path::PathBuf,
+    max_size: u64,
+    max_files: usize,
+    file: std::fs::File,
+    size: u64,
+}
+
+impl SizeRotatingFile {
+    fn open(path: &std::path::Path, max_size: u64, max_files: usize) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_size,
+            max_files,
+            file,
+            size,
+        })
+    }
+
+    fn rotated_path(&self, n: usize) -> std::path::PathBuf {
+        let mut path = self.path.as_os_str().to_owned();
+        path.push(format!(".{}", n));
+        std::path::PathBuf::from(path)
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        if self.max_files == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            for n in (1..self.max_files).rev() {
+                match std::fs::rename(self.rotated_path(n), self.rotated_path(n + 1)) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+                    _ => {}
+                }
+            }
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        *self = Self::open(&self.path, self.max_size, self.max_files)?;
+        Ok(())
+    }
+}
+
+impl std::io::Write for SizeRotatingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Returns the filter of console logs, where RUST_LOG takes precedence over `-q`/`-v`.
+fn console_filter(global: &super::GlobalArgs) -> tracing_subscriber::EnvFilter {
+    if std::env::var_os("RUST_LOG").is_some() {
+        return tracing_subscriber::EnvFilter::from_default_env();
+    }
+    let level = match (global.quiet, global.verbose) {
+        (true, _) => "warn",
+        (false, 0) => "info",
+        (false, 1) => "debug",
+        (false, _) => "trace",
+    };
+    tracing_subscriber::EnvFilter::new(level)
+}
@@ -0,0 +1,18 @@
+#[derive(Debug, clap::Args)]
+pub struct ManArgs {
+    /// Directory to write fanbox-dl.1 and fanbox-dl-<SUBCOMMAND>.1 to
+    #[clap(value_name = "DIR")]
+    out_dir: std::path::PathBuf,
+}
+
+/// Writes man pages of the command and its subcommands generated by clap_mangen.
+pub fn run(command: &clap::Command, args: ManArgs) -> anyhow::Result<()> {
+    use anyhow::Context as _;
+
+    std::fs::create_dir_all(&args.out_dir)
+        .with_context(|| format!("failed to create directory {}", args.out_dir.display()))?;
+    clap_mangen::generate_to(command.clone(), &args.out_dir)
+        .with_context(|| format!("failed to write man pages to {}", args.out_dir.display()))?;
+    tracing::info!("Wrote man pages to {}", args.out_dir.display());
+    Ok(())
+}
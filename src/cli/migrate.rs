@@ -0,0 +1,73 @@
+use futures::stream::TryStreamExt as _;
+
+#[derive(Debug, clap::Args)]
+pub struct MigrateArgs {
+    /// Creator id, pixiv user id (also as pixiv:<id>), or URL of the creator on pixiv or FANBOX
+    #[clap(short, long, requires = "from")]
+    creator_id: Option<String>,
+    /// Directory downloaded by the Go version of fanbox-dl (its --save-dir)
+    #[clap(long, requires = "creator_id")]
+    from: Option<std::path::PathBuf>,
+}
+
+/// Upgrades archives written by older versions to the current format, or with --from, imports
+/// files downloaded by the Go version.
+pub async fn run(ctx: &super::Context, args: MigrateArgs) -> anyhow::Result<()> {
+    match (args.creator_id, args.from) {
+        (Some(creator_id), Some(from)) => import_go_layout(ctx, &creator_id, &from).await,
+        _ => upgrade_formats(ctx).await,
+    }
+}
+
+/// Upgrades DEST_DIR and destination directories of creators in the config file.
+async fn upgrade_formats(ctx: &super::Context) -> anyhow::Result<()> {
+    let mut dest_dirs = vec![ctx.dest_dir.clone()];
+    for creator_id in ctx.creators.keys() {
+        let dest_dir = ctx.creator_dest_dir(creator_id);
+        if !dest_dirs.contains(&dest_dir) {
+            dest_dirs.push(dest_dir);
+        }
+    }
+    for dest_dir in dest_dirs {
+        let dir = dest_dir.clone();
+        let from =
+            tokio::task::spawn_blocking(move || fanbox_dl::archive::upgrade_format(&dir)).await??;
+        if from == fanbox_dl::archive::FORMAT_VERSION {
+            tracing::info!("{} is up-to-date", dest_dir.display());
+        } else {
+            tracing::info!(
+                "Upgraded {} from format version {} to {}",
+                dest_dir.display(),
+                from,
+                fanbox_dl::archive::FORMAT_VERSION
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Imports files downloaded by the Go version, then archives posts downloading only missing media.
+async fn import_go_layout(
+    ctx: &super::Context,
+    creator_id: &str,
+    from: &std::path::Path,
+) -> anyhow::Result<()> {
+    let client = ctx.client()?;
+    let options = fanbox_dl::archive::ArchiveOptions {
+        on_conflict: fanbox_dl::archive::ConflictPolicy::Skip,
+        ..Default::default()
+    };
+    let creator_id = super::resolve_creator_id(&client, creator_id).await?;
+    let dest_dir = ctx.creator_dest_dir(&creator_id);
+    let go_layout = fanbox_dl::archive::GoLayout::scan(from, &creator_id)?;
+    let items = client.paginate_creator(&creator_id).await?;
+    futures::pin_mut!(items);
+    let mut imported = 0;
+    while let Some(item) = items.try_next().await? {
+        let post = client.get_post(&item.id).await?;
+        imported += go_layout.import(&dest_dir, &post)?;
+        fanbox_dl::archive::archive_post(&client, &dest_dir, post, &options).await?;
+    }
+    tracing::info!("Imported {} files from {}", imported, from.display());
+    Ok(())
+}
@@ -0,0 +1,462 @@
+pub mod agent;
+pub mod completions;
+pub mod config;
+pub mod creators;
+pub mod diff;
+pub mod download;
+pub mod dump;
+pub mod export;
+pub mod gc;
+pub mod info;
+pub mod list;
+pub mod logging;
+pub mod man;
+pub mod migrate;
+pub mod notify;
+pub mod obsidian;
+#[cfg(feature = "otel")]
+pub mod otel;
+pub mod progress;
+pub mod render;
+pub mod schema;
+pub mod schema_check;
+pub mod search;
+pub mod serve;
+pub mod stats;
+pub mod verify;
+
+#[derive(Debug, clap::Args)]
+pub struct GlobalArgs {
+    /// Value of FANBOXSESSID cookie
+    #[clap(long, env = "FANBOXSESSID", hide_env_values = true, global = true)]
+    pub session_id: Option<String>,
+    /// Read FANBOXSESSID from a cookie file exported from a browser logged in to FANBOX, in the
+    /// Netscape format of cookies.txt or JSON, instead of --session-id
+    #[clap(long, global = true, value_name = "PATH")]
+    pub cookie_file: Option<std::path::PathBuf>,
+    /// Import FANBOXSESSID from cookies of a browser profile logged in to FANBOX instead of
+    /// --session-id, given as firefox:PROFILE_DIR or chromium:PROFILE_DIR
+    #[cfg(feature = "browser-cookies")]
+    #[clap(
+        long,
+        global = true,
+        value_name = "BROWSER:PROFILE_DIR",
+        conflicts_with = "cookie_file"
+    )]
+    pub cookies_from_browser: Option<String>,
+    /// Path to the config file
+    #[clap(long, global = true)]
+    pub config: Option<std::path::PathBuf>,
+    /// Directory to store downloaded posts [default: .]
+    #[clap(short, long, global = true)]
+    pub dest_dir: Option<std::path::PathBuf>,
+    /// Referer sent on media downloads: post, top, none or a URL [default: post]
+    #[clap(long, global = true)]
+    pub referer: Option<String>,
+    /// Accept-Language header sent to the API [default: ja]
+    #[clap(long, global = true)]
+    pub accept_language: Option<String>,
+    /// Seconds to wait between API calls and downloads [default: 0]
+    #[clap(long, global = true)]
+    pub interval: Option<f64>,
+    /// Randomize each interval by up to this ratio of it, e.g. 0.4 for ±40% [default: 0]
+    #[clap(long, global = true)]
+    pub jitter: Option<f64>,
+    /// Language of labels in generated pages [default: en]
+    #[clap(long, value_enum, global = true)]
+    pub lang: Option<LangArg>,
+    /// Bytes buffered by each download before written to disk [default: 65536]
+    #[clap(long, global = true)]
+    pub copy_buffer_size: Option<usize>,
+    /// Cap of bytes buffered by concurrent downloads in total, e.g. for low-memory devices
+    #[clap(long, global = true)]
+    pub memory_limit: Option<usize>,
+    /// Listing pages of a creator fetched at the same time [default: 4]
+    #[clap(long, global = true)]
+    pub listing_concurrency: Option<usize>,
+    /// Times to retry requests failing with 429 or 5xx responses, timeouts or connection errors,
+    /// waiting 1, 2, 4... seconds in between [default: 3]
+    #[clap(long, global = true)]
+    pub retries: Option<u32>,
+    /// Cache responses of post listings and post.info on disk for this many seconds, so that
+    /// repeated runs don't call the API again [default: no cache]
+    #[clap(long, global = true, value_name = "SECONDS")]
+    pub cache_ttl: Option<u64>,
+    /// Directory of the response cache [default: <dest-dir>/.fanbox-dl-cache]
+    #[clap(long, global = true)]
+    pub cache_dir: Option<std::path::PathBuf>,
+    /// Never access the network, reading API responses from the response cache regardless of
+    /// --cache-ttl and rendering pages from media already downloaded
+    #[clap(long, global = true)]
+    pub offline: bool,
+    /// Record HTTP requests and responses without cookies to this cassette file, for reproducing
+    /// issues with --replay-http
+    #[clap(long, global = true, value_name = "CASSETTE")]
+    pub record_http: Option<std::path::PathBuf>,
+    /// Answer HTTP requests with responses recorded by --record-http instead of sending them
+    #[clap(
+        long,
+        global = true,
+        value_name = "CASSETTE",
+        conflicts_with = "record_http"
+    )]
+    pub replay_http: Option<std::path::PathBuf>,
+    /// Print only warnings and errors. RUST_LOG takes precedence over this
+    #[clap(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+    /// Print debug logs, or trace logs with -vv. RUST_LOG takes precedence over this
+    #[clap(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+    /// Also append debug logs to this file, or trace logs with -vv, regardless of the console.
+    /// Rotated as log_rotation or log_max_size in the config file
+    #[clap(long, global = true, value_name = "PATH")]
+    pub log_file: Option<std::path::PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LangArg {
+    En,
+    Ja,
+}
+
+impl From<LangArg> for fanbox_dl::archive::Lang {
+    fn from(lang: LangArg) -> Self {
+        match lang {
+            LangArg::En => Self::En,
+            LangArg::Ja => Self::Ja,
+        }
+    }
+}
+
+/// Name of the response cache directory in the destination directory
+const CACHE_DIR_NAME: &str = ".fanbox-dl-cache";
+
+/// Global options merged with the config file.
+#[derive(Debug)]
+pub struct Context {
+    session: Option<Session>,
+    pub dest_dir: std::path::PathBuf,
+    /// Whether `dest_dir` is given by the command-line, which takes precedence over the creators'
+    dest_dir_from_args: bool,
+    creators: std::collections::HashMap<String, config::CreatorConfig>,
+    /// Creators skipped with --all-supporting, given by the config file
+    pub exclude_creators: Vec<String>,
+    /// Rhai scripts given by the config file, which hook into archiving of every creator's posts
+    pub scripts: Vec<std::path::PathBuf>,
+    /// Recipients of encrypted backups, given by the config file
+    pub backup_recipients: Vec<String>,
+    /// Email notification of failed runs, given by the config file
+    pub email: Option<config::EmailConfig>,
+    /// Push notifications of new posts, given by the config file
+    pub ntfy: Option<config::NtfyConfig>,
+    pub gotify: Option<config::GotifyConfig>,
+    pub referer: fanbox_dl::RefererPolicy,
+    pub accept_language: String,
+    pub interval: std::time::Duration,
+    pub jitter: f64,
+    pub lang: fanbox_dl::archive::Lang,
+    pub copy_buffer_size: Option<usize>,
+    pub memory_limit: Option<usize>,
+    pub listing_concurrency: Option<usize>,
+    pub retries: Option<u32>,
+    pub cache_ttl: Option<std::time::Duration>,
+    pub cache_dir: std::path::PathBuf,
+    pub offline: bool,
+    pub record_http: Option<std::path::PathBuf>,
+    pub replay_http: Option<std::path::PathBuf>,
+}
+
+impl Context {
+    pub fn new(
+        global: GlobalArgs,
+        config: config::Config,
+        legacy_session_id: Option<String>,
+    ) -> anyhow::Result<Self> {
+        use anyhow::Context as _;
+
+        let session = session(&global, legacy_session_id, &config)?;
+        let dest_dir_from_args = global.dest_dir.is_some();
+        let dest_dir = global
+            .dest_dir
+            .or(config.dest_dir)
+            .unwrap_or_else(|| std::path::PathBuf::from("."));
+        let referer = match global.referer.or(config.referer) {
+            Some(referer) => parse_referer(&referer)?,
+            None => fanbox_dl::RefererPolicy::default(),
+        };
+        let accept_language = global
+            .accept_language
+            .or(config.accept_language)
+            .unwrap_or_else(|| fanbox_dl::DEFAULT_ACCEPT_LANGUAGE.to_owned());
+        let interval = global.interval.or(config.interval).unwrap_or(0.0);
+        let interval = std::time::Duration::try_from_secs_f64(interval)
+            .with_context(|| format!("invalid interval {}", interval))?;
+        let jitter = global.jitter.or(config.jitter).unwrap_or(0.0);
+        let lang = global
+            .lang
+            .or(config.lang)
+            .map_or_else(Default::default, Into::into);
+        let cache_dir = global
+            .cache_dir
+            .or(config.cache_dir)
+            .unwrap_or_else(|| dest_dir.join(CACHE_DIR_NAME));
+        Ok(Self {
+            session,
+            dest_dir,
+            dest_dir_from_args,
+            creators: config.creators,
+            exclude_creators: config.exclude_creators,
+            scripts: config.scripts,
+            backup_recipients: config.backup_recipients,
+            email: config.email,
+            ntfy: config.ntfy,
+            gotify: config.gotify,
+            referer,
+            accept_language,
+            interval,
+            jitter,
+            lang,
+            copy_buffer_size: global.copy_buffer_size.or(config.copy_buffer_size),
+            memory_limit: global.memory_limit.or(config.memory_limit),
+            listing_concurrency: global.listing_concurrency.or(config.listing_concurrency),
+            retries: global.retries.or(config.retries),
+            cache_ttl: global
+                .cache_ttl
+                .or(config.cache_ttl)
+                .map(std::time::Duration::from_secs),
+            cache_dir,
+            offline: global.offline,
+            record_http: global.record_http,
+            replay_http: global.replay_http,
+        })
+    }
+
+    /// Returns settings of the creator in the config file.
+    pub fn creator_config(&self, creator_id: &str) -> config::CreatorConfig {
+        self.creators.get(creator_id).cloned().unwrap_or_default()
+    }
+
+    /// Returns ids of creators which have settings in the config file.
+    pub fn configured_creator_ids(&self) -> Vec<String> {
+        let mut creator_ids: Vec<_> = self.creators.keys().cloned().collect();
+        creator_ids.sort();
+        creator_ids
+    }
+
+    /// Returns the directory storing the creator's posts.
+    pub fn creator_dest_dir(&self, creator_id: &str) -> std::path::PathBuf {
+        if self.dest_dir_from_args {
+            return self.dest_dir.clone();
+        }
+        self.creators
+            .get(creator_id)
+            .and_then(|creator| creator.dest_dir.clone())
+            .unwrap_or_else(|| self.dest_dir.clone())
+    }
+
+    pub fn client(&self) -> anyhow::Result<fanbox_dl::PostClient> {
+        use anyhow::Context as _;
+
+        use fanbox_dl::SessionStore as _;
+
+        let session_id = match &self.session {
+            Some(Session::Id(session_id)) => session_id.clone(),
+            Some(Session::CookieFile(cookie_file)) => cookie_file.session_id()?,
+            #[cfg(feature = "browser-cookies")]
+            Some(Session::Browser(profile)) => profile.session_id()?,
+            // Requests are never sent offline
+            None if self.offline || self.replay_http.is_some() => String::new(),
+            None => anyhow::bail!(
+                "FANBOXSESSID is required: set --session-id, FANBOXSESSID environment variable, --cookie-file or session_id in the config file"
+            ),
+        };
+        let mut client = fanbox_dl::PostClient::new(&session_id)
+            .context("failed to build fanbox-dl client")?
+            .referer(self.referer.clone())
+            .accept_language(&self.accept_language)
+            .interval(self.interval)
+            .jitter(self.jitter);
+        if let Some(bytes) = self.copy_buffer_size {
+            client = client.copy_buffer_size(bytes);
+        }
+        if let Some(bytes) = self.memory_limit {
+            client = client.memory_limit(bytes);
+        }
+        if let Some(concurrency) = self.listing_concurrency {
+            client = client.listing_concurrency(concurrency);
+        }
+        if let Some(retries) = self.retries {
+            client = client.retries(retries);
+        }
+        if let Some(ttl) = self.cache_ttl {
+            client = client.response_cache(&self.cache_dir, ttl);
+        } else if self.offline {
+            client = client.response_cache(&self.cache_dir, std::time::Duration::ZERO);
+        }
+        client = client.offline(self.offline);
+        if let Some(path) = &self.record_http {
+            let layer = fanbox_dl::vcr::RecordLayer::create(path)
+                .with_context(|| format!("failed to open {}", path.display()))?;
+            client = client.layer(layer);
+        }
+        if let Some(path) = &self.replay_http {
+            let layer = fanbox_dl::vcr::ReplayLayer::open(path)
+                .with_context(|| format!("failed to load {}", path.display()))?;
+            client = client.layer(layer);
+        }
+        Ok(client)
+    }
+}
+
+/// Resolves the creator id given by `-c`, which may also be a pixiv user id, a pixiv user URL or
+/// a FANBOX URL.
+///
+/// Digits are taken as a creator id first, since creator ids may be numeric, and as a pixiv user
+/// id when no such creator exists. `pixiv:<user id>` is always a pixiv user id.
+pub async fn resolve_creator_id(
+    client: &fanbox_dl::PostClient,
+    input: &str,
+) -> anyhow::Result<String> {
+    let user_id = match parse_creator_input(input) {
+        CreatorInput::CreatorId(creator_id) => return Ok(creator_id.to_owned()),
+        CreatorInput::UserId(user_id) => user_id,
+        CreatorInput::Digits(digits) => match client.get_creator(digits).await {
+            Ok(creator) => return Ok(creator.creator_id),
+            Err(e) if is_missing_creator(&e) => {
+                tracing::debug!("No creator {} exists, try it as a pixiv user id", digits);
+                digits
+            }
+            Err(e) => return Err(e.into()),
+        },
+    };
+    let creator = client.get_creator_by_user_id(user_id).await?;
+    tracing::info!(
+        "Resolved pixiv user {} to creator {}",
+        user_id,
+        creator.creator_id
+    );
+    Ok(creator.creator_id)
+}
+
+/// Returns whether creator.get failed because the creator doesn't exist, for which FANBOX
+/// answers 400 or 404.
+fn is_missing_creator(error: &fanbox_dl::Error) -> bool {
+    match error {
+        fanbox_dl::Error::NotFound { .. } | fanbox_dl::Error::ApiError { .. } => true,
+        fanbox_dl::Error::HttpStatusError { status, .. } => *status == 400 || *status == 404,
+        _ => false,
+    }
+}
+
+enum CreatorInput<'a> {
+    CreatorId(&'a str),
+    UserId(&'a str),
+    /// Either a numeric creator id or a pixiv user id
+    Digits(&'a str),
+}
+
+fn parse_creator_input(input: &str) -> CreatorInput<'_> {
+    let is_user_id = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if is_user_id(input) {
+        return CreatorInput::Digits(input);
+    }
+    if let Some(user_id) = input.strip_prefix("pixiv:").filter(|id| is_user_id(id)) {
+        return CreatorInput::UserId(user_id);
+    }
+    let rest = match input
+        .strip_prefix("https://")
+        .or_else(|| input.strip_prefix("http://"))
+    {
+        Some(rest) => rest,
+        None => return CreatorInput::CreatorId(input),
+    };
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    fn first_segment(path: &str) -> &str {
+        path.split(['/', '?', '#']).next().unwrap_or("")
+    }
+    if host == "www.pixiv.net" || host == "pixiv.net" {
+        // users/<id>, en/users/<id>, fanbox/creator/<id> or member.php?id=<id>
+        let path = path.strip_prefix("en/").unwrap_or(path);
+        let user_id = path
+            .strip_prefix("users/")
+            .or_else(|| path.strip_prefix("fanbox/creator/"))
+            .map(first_segment)
+            .or_else(|| {
+                path.strip_prefix("member.php?")?
+                    .split('&')
+                    .find_map(|param| param.strip_prefix("id="))
+            });
+        if let Some(user_id) = user_id.filter(|id| is_user_id(id)) {
+            return CreatorInput::UserId(user_id);
+        }
+    } else if host == "www.fanbox.cc" || host == "fanbox.cc" {
+        if let Some(creator_id) = path.strip_prefix('@').map(first_segment) {
+            return CreatorInput::CreatorId(creator_id);
+        }
+    } else if let Some(creator_id) = host.strip_suffix(".fanbox.cc") {
+        return CreatorInput::CreatorId(creator_id);
+    }
+    CreatorInput::CreatorId(input)
+}
+
+/// Where FANBOXSESSID is read from, which is done whenever a client is built so that refreshed
+/// cookies are picked up.
+#[derive(Debug)]
+enum Session {
+    Id(String),
+    CookieFile(fanbox_dl::CookieFile),
+    #[cfg(feature = "browser-cookies")]
+    Browser(fanbox_dl::BrowserProfile),
+}
+
+/// Returns the browser profile or the cookie file given by the command-line, --session-id, and
+/// then the session of the config file.
+fn session(
+    global: &GlobalArgs,
+    legacy_session_id: Option<String>,
+    config: &config::Config,
+) -> anyhow::Result<Option<Session>> {
+    #[cfg(feature = "browser-cookies")]
+    if let Some(spec) = &global.cookies_from_browser {
+        return Ok(Some(Session::Browser(parse_browser_profile(spec)?)));
+    }
+    if let Some(path) = &global.cookie_file {
+        return Ok(Some(Session::CookieFile(fanbox_dl::CookieFile::new(path))));
+    }
+    let session_id = global
+        .session_id
+        .clone()
+        .or(legacy_session_id)
+        .or_else(|| config.session_id.clone());
+    Ok(match (session_id, &config.cookie_file) {
+        (Some(session_id), _) => Some(Session::Id(session_id)),
+        (None, Some(path)) => Some(Session::CookieFile(fanbox_dl::CookieFile::new(path))),
+        (None, None) => None,
+    })
+}
+
+#[cfg(feature = "browser-cookies")]
+fn parse_browser_profile(spec: &str) -> anyhow::Result<fanbox_dl::BrowserProfile> {
+    let (browser, dir) = match spec.split_once(':') {
+        Some(("firefox", dir)) => (fanbox_dl::Browser::Firefox, dir),
+        Some(("chromium" | "chrome", dir)) => (fanbox_dl::Browser::Chromium, dir),
+        _ => anyhow::bail!(
+            "invalid browser profile {}: expected firefox:PROFILE_DIR or chromium:PROFILE_DIR",
+            spec
+        ),
+    };
+    Ok(fanbox_dl::BrowserProfile::new(browser, dir))
+}
+
+fn parse_referer(s: &str) -> anyhow::Result<fanbox_dl::RefererPolicy> {
+    match s {
+        "post" => Ok(fanbox_dl::RefererPolicy::Post),
+        "top" => Ok(fanbox_dl::RefererPolicy::Top),
+        "none" => Ok(fanbox_dl::RefererPolicy::None),
+        url if url.starts_with("https://") || url.starts_with("http://") => {
+            Ok(fanbox_dl::RefererPolicy::Custom(url.to_owned()))
+        }
+        _ => anyhow::bail!("invalid referer {}: expected post, top, none or a URL", s),
+    }
+}
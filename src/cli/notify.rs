@@ -0,0 +1,203 @@
+/// Outcome of a `download` run reported by notifications.
+#[derive(Debug, Default)]
+pub struct RunReport {
+    /// Error which stopped the run
+    pub error: Option<String>,
+    /// Whether the run failed because FANBOX rejected the session
+    pub session_expired: bool,
+    /// Number of new or updated posts archived, keyed by creator id
+    pub archived: std::collections::BTreeMap<String, usize>,
+}
+
+impl RunReport {
+    pub fn new(
+        result: &anyhow::Result<()>,
+        archived: std::collections::BTreeMap<String, usize>,
+    ) -> Self {
+        Self {
+            error: result.as_ref().err().map(|e| format!("{:#}", e)),
+            session_expired: result.as_ref().is_err_and(is_session_expired),
+            archived,
+        }
+    }
+
+    pub fn is_failure(&self) -> bool {
+        self.error.is_some()
+    }
+
+    fn subject(&self) -> &'static str {
+        if self.session_expired {
+            "fanbox-dl: FANBOXSESSID has expired"
+        } else if self.is_failure() {
+            "fanbox-dl: download failed"
+        } else {
+            "fanbox-dl: download finished"
+        }
+    }
+
+    pub fn body(&self) -> String {
+        let mut body = format!("Finished at {}\n", chrono::Local::now().to_rfc3339());
+        if self.session_expired {
+            body.push_str(
+                "\nFANBOX rejected the session. Update FANBOXSESSID to resume archiving.\n",
+            );
+        }
+        if !self.archived.is_empty() {
+            body.push('\n');
+            body.push_str(&self.archived_lines());
+        }
+        if let Some(error) = &self.error {
+            body.push_str(&format!("\nError: {}\n", error));
+        }
+        body
+    }
+
+    /// Returns lines like "3 new posts archived from creator X".
+    fn archived_lines(&self) -> String {
+        self.archived
+            .iter()
+            .map(|(creator_id, count)| {
+                let posts = if *count == 1 { "post" } else { "posts" };
+                format!("{} new {} archived from {}\n", count, posts, creator_id)
+            })
+            .collect()
+    }
+}
+
+/// Returns whether the error is caused by FANBOX rejecting the session.
+fn is_session_expired(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<fanbox_dl::Error>(),
+            Some(
+                fanbox_dl::Error::HttpStatusError { status: 401, .. }
+                    | fanbox_dl::Error::Unauthorized { .. }
+            )
+        )
+    })
+}
+
+/// Sends notifications of the run configured in the config file. Failures to notify are only
+/// logged so that they don't hide the result of the run.
+pub async fn notify(ctx: &super::Context, report: &RunReport) {
+    if let Some(email) = &ctx.email {
+        if report.is_failure() {
+            if let Err(e) = send_email(email, report).await {
+                tracing::warn!("Failed to send email notification: {:#}", e);
+            }
+        }
+    }
+    if report.archived.is_empty() {
+        return;
+    }
+    if let Some(ntfy) = &ctx.ntfy {
+        if let Err(e) = send_ntfy(ntfy, report).await {
+            tracing::warn!("Failed to send ntfy notification: {:#}", e);
+        }
+    }
+    if let Some(gotify) = &ctx.gotify {
+        if let Err(e) = send_gotify(gotify, report).await {
+            tracing::warn!("Failed to send Gotify notification: {:#}", e);
+        }
+    }
+}
+
+/// Pings the monitoring URL with `suffix` appended, e.g. `/start` or `/fail` of healthchecks.io.
+/// Failures to ping are only logged.
+pub async fn ping_healthcheck(url: &str, suffix: &str, body: String) {
+    let url = format!("{}{}", url.trim_end_matches('/'), suffix);
+    let result = reqwest::Client::new()
+        .post(&url)
+        .timeout(std::time::Duration::from_secs(10))
+        .body(body)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status);
+    match result {
+        Ok(_) => tracing::debug!("Pinged {}", url),
+        Err(e) => tracing::warn!("Failed to ping {}: {}", url, e),
+    }
+}
+
+/// Publishes new posts to the ntfy topic.
+async fn send_ntfy(config: &super::config::NtfyConfig, report: &RunReport) -> anyhow::Result<()> {
+    let mut request = reqwest::Client::new()
+        .post(&config.url)
+        .header("Title", "fanbox-dl")
+        .body(report.archived_lines());
+    if let Some(priority) = config.priority {
+        request = request.header("Priority", priority.to_string());
+    }
+    if let Some(token) = &config.token {
+        request = request.bearer_auth(token);
+    }
+    request.send().await?.error_for_status()?;
+    tracing::info!("Sent ntfy notification to {}", config.url);
+    Ok(())
+}
+
+/// Posts new posts as a message of the Gotify application.
+async fn send_gotify(
+    config: &super::config::GotifyConfig,
+    report: &RunReport,
+) -> anyhow::Result<()> {
+    let url = format!("{}/message", config.url.trim_end_matches('/'));
+    let mut message = serde_json::json!({
+        "title": "fanbox-dl",
+        "message": report.archived_lines(),
+    });
+    if let Some(priority) = config.priority {
+        message["priority"] = priority.into();
+    }
+    reqwest::Client::new()
+        .post(&url)
+        .header("X-Gotify-Key", &config.token)
+        .json(&message)
+        .send()
+        .await?
+        .error_for_status()?;
+    tracing::info!("Sent Gotify notification to {}", config.url);
+    Ok(())
+}
+
+async fn send_email(config: &super::config::EmailConfig, report: &RunReport) -> anyhow::Result<()> {
+    use anyhow::Context as _;
+    use lettre::AsyncTransport as _;
+
+    let mut message = lettre::Message::builder()
+        .from(config.from.parse().context("invalid from address")?)
+        .subject(report.subject());
+    for to in &config.to {
+        message = message.to(to
+            .parse()
+            .with_context(|| format!("invalid to address {}", to))?);
+    }
+    let message = message.body(report.body())?;
+
+    let mut transport = match config.security.unwrap_or_default() {
+        super::config::SmtpSecurity::Starttls => {
+            lettre::AsyncSmtpTransport::<lettre::Tokio1Executor>::starttls_relay(&config.smtp_host)?
+        }
+        super::config::SmtpSecurity::Tls => {
+            lettre::AsyncSmtpTransport::<lettre::Tokio1Executor>::relay(&config.smtp_host)?
+        }
+        super::config::SmtpSecurity::None => {
+            lettre::AsyncSmtpTransport::<lettre::Tokio1Executor>::builder_dangerous(
+                &config.smtp_host,
+            )
+        }
+    };
+    if let Some(port) = config.smtp_port {
+        transport = transport.port(port);
+    }
+    if let (Some(username), Some(password)) = (&config.username, &config.password) {
+        transport =
+            transport.credentials(lettre::transport::smtp::authentication::Credentials::new(
+                username.clone(),
+                password.clone(),
+            ));
+    }
+    transport.build().send(message).await?;
+    tracing::info!("Sent email notification to {}", config.to.join(", "));
+    Ok(())
+}
@@ -0,0 +1,16 @@
+#[derive(Debug, clap::Args)]
+pub struct ObsidianArgs {
+    /// Directory of the Obsidian vault to export notes into
+    vault_dir: std::path::PathBuf,
+}
+
+pub async fn run(ctx: &super::Context, args: ObsidianArgs) -> anyhow::Result<()> {
+    let dest_dir = ctx.dest_dir.clone();
+    let vault_dir = args.vault_dir.clone();
+    let count = tokio::task::spawn_blocking(move || {
+        fanbox_dl::archive::export_obsidian(&dest_dir, &vault_dir)
+    })
+    .await??;
+    tracing::info!("Exported {} posts to {}", count, args.vault_dir.display());
+    Ok(())
+}
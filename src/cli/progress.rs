@@ -0,0 +1,62 @@
+/// Logs the speed of downloads in progress and their ETA every `interval` until aborted.
+pub fn spawn_reporter(
+    stats: std::sync::Arc<fanbox_dl::TransferStats>,
+    interval: std::time::Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        ticker.tick().await;
+        let mut previous = stats.snapshot();
+        loop {
+            ticker.tick().await;
+            let current = stats.snapshot();
+            let speed = current.speed_since(&previous);
+            let eta = match current.eta(speed) {
+                Some(eta) if current.remaining_bytes > 0 => format!(", ETA {}s", eta.as_secs()),
+                _ => String::new(),
+            };
+            tracing::info!(
+                "Downloaded {} files ({}) at {}/s, {} in progress{}",
+                current.files,
+                format_bytes(current.bytes as f64),
+                format_bytes(speed),
+                current.active_files,
+                eta
+            );
+            previous = current;
+        }
+    })
+}
+
+/// Logs files and bytes downloaded between the snapshots and their average speed.
+pub fn log_summary(start: &fanbox_dl::TransferSnapshot, end: &fanbox_dl::TransferSnapshot) {
+    let files = end.files - start.files;
+    if files == 0 {
+        return;
+    }
+    tracing::info!(
+        "Downloaded {} files ({}) in {} seconds, {}/s on average",
+        files,
+        format_bytes(end.bytes.saturating_sub(start.bytes) as f64),
+        end.elapsed.saturating_sub(start.elapsed).as_secs(),
+        format_bytes(end.speed_since(start))
+    );
+}
+
+/// Formats bytes in binary units, e.g. 1.5 MiB.
+pub(crate) fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{:.0} {}", value, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
@@ -0,0 +1,78 @@
+#[derive(Debug, clap::Args)]
+pub struct RenderArgs {
+    /// Render from metadata.json stored in post directories without network access, instead of
+    /// fetching the latest metadata of posts
+    #[clap(long)]
+    from_raw: bool,
+    /// Render posts of this creator only
+    #[clap(short, long)]
+    creator_id: Option<String>,
+    /// File name of HTML pages, where {id} is replaced by the post id [default: index.html]
+    #[clap(long, value_name = "NAME")]
+    index_name: Option<String>,
+    /// Embed a lightbox viewer with keyboard navigation into pages of image and article posts
+    #[clap(long)]
+    lightbox: bool,
+}
+
+/// Renders HTML pages of archived posts again from media already downloaded.
+pub async fn run(ctx: &super::Context, args: RenderArgs) -> anyhow::Result<()> {
+    let client = if args.from_raw {
+        // Requests are never sent since pages are rendered with render_only
+        fanbox_dl::PostClient::new("")?
+    } else {
+        ctx.client()?
+    };
+    let dest_dir = ctx.dest_dir.clone();
+    let posts: Vec<_> =
+        tokio::task::spawn_blocking(move || fanbox_dl::archive::load_archived_posts(&dest_dir))
+            .await??
+            .into_iter()
+            .filter(|archived| {
+                args.creator_id
+                    .as_ref()
+                    .is_none_or(|creator_id| archived.post.info.creator_id == *creator_id)
+            })
+            .collect();
+
+    let mut creator_ids = Vec::new();
+    for archived in &posts {
+        let creator_id = &archived.post.info.creator_id;
+        let creator_config = ctx.creator_config(creator_id);
+        let options = fanbox_dl::archive::ArchiveOptions {
+            index_name: args
+                .index_name
+                .clone()
+                .or_else(|| creator_config.index_name.clone()),
+            lang: ctx.lang,
+            lightbox: args.lightbox || creator_config.lightbox.unwrap_or(false),
+            description: creator_config.description.unwrap_or(false),
+            nfo: creator_config.nfo.unwrap_or(false),
+            render_only: true,
+            ..Default::default()
+        };
+        let post = if args.from_raw {
+            archived.post.clone()
+        } else {
+            match client.get_post(&archived.post.info.id).await {
+                Ok(post) => post,
+                Err(e @ fanbox_dl::Error::NotCached { .. }) => {
+                    tracing::warn!("Skip {}: {}", archived.dir.display(), e);
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        };
+        let archive_dir = archived.dir.parent().unwrap_or(&ctx.dest_dir);
+        tracing::info!("Render {}", archived.dir.display());
+        fanbox_dl::archive::archive_post(&client, archive_dir, post, &options).await?;
+        if !creator_ids.contains(creator_id) {
+            creator_ids.push(creator_id.clone());
+        }
+    }
+    for creator_id in creator_ids {
+        fanbox_dl::archive::write_post_navigation(&ctx.dest_dir, &creator_id, ctx.lang)?;
+    }
+    tracing::info!("Rendered {} posts", posts.len());
+    Ok(())
+}
@@ -0,0 +1,6 @@
+/// Prints JSON Schema of the post metadata.
+pub fn run() -> anyhow::Result<()> {
+    let schema = fanbox_dl::post_schema();
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
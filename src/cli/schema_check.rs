@@ -0,0 +1,155 @@
+use futures::stream::{StreamExt as _, TryStreamExt as _};
+
+#[derive(Debug, clap::Args)]
+pub struct SchemaCheckArgs {
+    /// Creator id, pixiv user id (also as pixiv:<id>), or URL of the creator on pixiv or FANBOX. Can be repeated
+    /// [default: creators in the config file]
+    #[clap(short, long)]
+    creator_id: Vec<String>,
+    /// Number of recent posts checked for each creator
+    #[clap(long, default_value = "5")]
+    posts: usize,
+    /// Print the report as JSON
+    #[clap(long)]
+    json: bool,
+}
+
+/// Number of example posts kept for each finding
+const MAX_EXAMPLES: usize = 3;
+
+/// Unsupported types, unknown fields and parse errors found in sampled posts.
+#[derive(Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Report {
+    checked_posts: usize,
+    restricted_posts: usize,
+    /// Keyed by the error message
+    errors: std::collections::BTreeMap<String, Finding>,
+    /// Keyed by the kind and the type, e.g. `article block: new_block`
+    unknown_types: std::collections::BTreeMap<String, Finding>,
+    /// Keyed by the path of the field, e.g. `body.blocks[].style`
+    unknown_fields: std::collections::BTreeMap<String, Finding>,
+}
+
+#[derive(Debug, Default, serde::Serialize)]
+struct Finding {
+    count: usize,
+    /// URLs of posts having it
+    examples: Vec<String>,
+}
+
+impl Finding {
+    fn add(&mut self, post_url: &str) {
+        self.count += 1;
+        if self.examples.len() < MAX_EXAMPLES {
+            self.examples.push(post_url.to_owned());
+        }
+    }
+}
+
+impl Report {
+    fn has_drift(&self) -> bool {
+        !self.errors.is_empty() || !self.unknown_types.is_empty() || !self.unknown_fields.is_empty()
+    }
+
+    fn check_post(&mut self, post_url: &str, raw: &serde_json::Value) {
+        if raw["body"].is_null() {
+            self.restricted_posts += 1;
+            return;
+        }
+        self.checked_posts += 1;
+        let post: fanbox_dl::Post = match serde_json::from_value(raw.clone()) {
+            Ok(post) => post,
+            Err(e) => {
+                self.errors.entry(e.to_string()).or_default().add(post_url);
+                return;
+            }
+        };
+        for (kind, raw) in post.unknowns() {
+            let tag = raw
+                .get("type")
+                .or_else(|| raw.get("serviceProvider"))
+                .and_then(|tag| tag.as_str())
+                .unwrap_or("(untyped)");
+            self.unknown_types
+                .entry(format!("{}: {}", kind, tag))
+                .or_default()
+                .add(post_url);
+        }
+        match fanbox_dl::unknown_fields::<fanbox_dl::Post>(raw) {
+            Ok(fields) => {
+                for field in fields {
+                    self.unknown_fields.entry(field).or_default().add(post_url);
+                }
+            }
+            Err(e) => self.errors.entry(e.to_string()).or_default().add(post_url),
+        }
+    }
+
+    fn print(&self) {
+        println!(
+            "Checked {} posts ({} restricted posts skipped)",
+            self.checked_posts, self.restricted_posts
+        );
+        for (title, findings) in [
+            ("Parse errors", &self.errors),
+            ("Unknown types", &self.unknown_types),
+            ("Unknown fields", &self.unknown_fields),
+        ] {
+            if findings.is_empty() {
+                continue;
+            }
+            println!("{}:", title);
+            for (key, finding) in findings {
+                println!("  {} ({} posts)", key, finding.count);
+                for example in &finding.examples {
+                    println!("    {}", example);
+                }
+            }
+        }
+        if !self.has_drift() {
+            println!("No API drift found");
+        }
+    }
+}
+
+/// Fetches recent posts of the creators and reports types and fields the models don't support,
+/// failing when any is found.
+pub async fn run(ctx: &super::Context, args: SchemaCheckArgs) -> anyhow::Result<()> {
+    let client = ctx.client()?;
+    let inputs = if args.creator_id.is_empty() {
+        ctx.configured_creator_ids()
+    } else {
+        args.creator_id
+    };
+    if inputs.is_empty() {
+        anyhow::bail!("no creators to check: give --creator-id or creators in the config file");
+    }
+
+    let mut report = Report::default();
+    for input in inputs {
+        let creator_id = super::resolve_creator_id(&client, &input).await?;
+        let items: Vec<_> = client
+            .paginate_creator(&creator_id)
+            .await?
+            .take(args.posts)
+            .try_collect()
+            .await?;
+        tracing::info!("Check {} posts of {}", items.len(), creator_id);
+        for item in items {
+            let raw = client.get_post_raw(&item.id).await?;
+            let post_url = format!("https://{}.fanbox.cc/posts/{}", creator_id, item.id);
+            report.check_post(&post_url, &raw);
+        }
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        report.print();
+    }
+    if report.has_drift() {
+        anyhow::bail!("API drift found");
+    }
+    Ok(())
+}
@@ -0,0 +1,51 @@
+#[derive(Debug, clap::Args)]
+pub struct SearchArgs {
+    /// Keyword searched in titles and text of archived posts
+    query: String,
+}
+
+pub async fn run(ctx: &super::Context, args: SearchArgs) -> anyhow::Result<()> {
+    let dest_dir = ctx.dest_dir.clone();
+    let hits = tokio::task::spawn_blocking(move || search(&dest_dir, &args.query)).await??;
+    for hit in hits {
+        println!(
+            "{}\t{}\t{}\t{}",
+            hit.updated_datetime.format("%Y-%m-%d"),
+            hit.creator_id,
+            hit.title,
+            hit.dir.display()
+        );
+    }
+    Ok(())
+}
+
+/// Queries the search index, or scans metadata.json files when the index doesn't exist.
+fn search(
+    dest_dir: &std::path::Path,
+    query: &str,
+) -> Result<Vec<fanbox_dl::archive::SearchHit>, fanbox_dl::archive::Error> {
+    if dest_dir
+        .join(fanbox_dl::archive::STATE_DB_FILE_NAME)
+        .is_file()
+    {
+        return fanbox_dl::archive::SearchIndex::open(dest_dir)?.search(query);
+    }
+    tracing::info!("Search index is not found. Scan metadata of archived posts instead");
+    let query = query.to_lowercase();
+    Ok(fanbox_dl::archive::load_archived_posts(dest_dir)?
+        .into_iter()
+        .filter(|archived| {
+            archived.post.info.title.to_lowercase().contains(&query)
+                || fanbox_dl::archive::post_text(&archived.post)
+                    .to_lowercase()
+                    .contains(&query)
+        })
+        .map(|archived| fanbox_dl::archive::SearchHit {
+            post_id: archived.post.info.id,
+            creator_id: archived.post.info.creator_id,
+            title: archived.post.info.title,
+            updated_datetime: archived.post.info.updated_datetime,
+            dir: archived.dir,
+        })
+        .collect())
+}
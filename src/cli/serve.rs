@@ -0,0 +1,317 @@
+use fanbox_dl::archive::Label;
+
+#[derive(Debug, clap::Args)]
+pub struct ServeArgs {
+    /// Address to listen on
+    #[clap(long, default_value = "127.0.0.1:8080")]
+    listen: std::net::SocketAddr,
+}
+
+/// How long archived posts loaded for pages are reused. Loading reads metadata of all
+/// posts in DEST_DIR, so new posts show up in pages after this delay instead.
+const POSTS_TTL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// State shared by requests.
+struct Server {
+    /// Canonical path of DEST_DIR, which served files must be under
+    dest_dir: std::path::PathBuf,
+    lang: fanbox_dl::archive::Lang,
+    /// Archived posts and when they were loaded
+    posts: tokio::sync::Mutex<
+        Option<(
+            std::time::Instant,
+            std::sync::Arc<Vec<fanbox_dl::archive::ArchivedPost>>,
+        )>,
+    >,
+}
+
+pub async fn run(ctx: &super::Context, args: ServeArgs) -> anyhow::Result<()> {
+    use anyhow::Context as _;
+
+    let dest_dir = tokio::fs::canonicalize(&ctx.dest_dir)
+        .await
+        .with_context(|| format!("failed to resolve {}", ctx.dest_dir.display()))?;
+    let server = std::sync::Arc::new(Server {
+        dest_dir,
+        lang: ctx.lang,
+        posts: Default::default(),
+    });
+    let make_service = hyper::service::make_service_fn(move |_| {
+        let server = server.clone();
+        async move {
+            Ok::<_, std::convert::Infallible>(hyper::service::service_fn(move |req| {
+                let server = server.clone();
+                async move { Ok::<_, std::convert::Infallible>(handle(&server, req).await) }
+            }))
+        }
+    });
+    let server = hyper::Server::try_bind(&args.listen)?.serve(make_service);
+    tracing::info!(
+        "Serving {} on http://{}",
+        ctx.dest_dir.display(),
+        server.local_addr()
+    );
+    server.await?;
+    Ok(())
+}
+
+async fn handle(server: &Server, req: hyper::Request<hyper::Body>) -> hyper::Response<hyper::Body> {
+    if req.method() != hyper::Method::GET && req.method() != hyper::Method::HEAD {
+        return text_response(
+            hyper::StatusCode::METHOD_NOT_ALLOWED,
+            "Method Not Allowed".to_owned(),
+        );
+    }
+    let path = req.uri().path();
+    let result = if path == "/" {
+        creators_page(server).await
+    } else if let Some(creator_id) = path.strip_prefix("/creators/") {
+        let creator_id = percent_encoding::percent_decode_str(creator_id).decode_utf8_lossy();
+        posts_page(server, &creator_id).await
+    } else if path == "/search" {
+        let query = form_urlencoded::parse(req.uri().query().unwrap_or("").as_bytes())
+            .find(|(key, _)| key == "q")
+            .map(|(_, value)| value.into_owned())
+            .unwrap_or_default();
+        search_page(server, &query).await
+    } else if let Some(file_path) = path.strip_prefix("/files/") {
+        file_response(&server.dest_dir, file_path).await
+    } else {
+        Ok(not_found())
+    };
+    result.unwrap_or_else(|e| {
+        tracing::error!("Failed to handle {}: {:#}", req.uri(), e);
+        text_response(
+            hyper::StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal Server Error".to_owned(),
+        )
+    })
+}
+
+/// Returns archived posts, loaded again once they are older than `POSTS_TTL`.
+async fn load_posts(
+    server: &Server,
+) -> anyhow::Result<std::sync::Arc<Vec<fanbox_dl::archive::ArchivedPost>>> {
+    // Concurrent requests wait for the posts being loaded rather than loading them again
+    let mut cached = server.posts.lock().await;
+    if let Some((loaded_at, posts)) = cached.as_ref() {
+        if loaded_at.elapsed() < POSTS_TTL {
+            return Ok(posts.clone());
+        }
+    }
+    let dest_dir = server.dest_dir.clone();
+    let posts = std::sync::Arc::new(
+        tokio::task::spawn_blocking(move || fanbox_dl::archive::load_archived_posts(&dest_dir))
+            .await??,
+    );
+    *cached = Some((std::time::Instant::now(), posts.clone()));
+    Ok(posts)
+}
+
+async fn creators_page(server: &Server) -> anyhow::Result<hyper::Response<hyper::Body>> {
+    let lang = server.lang;
+    let posts = load_posts(server).await?;
+    let mut creators: Vec<(&str, usize)> = Vec::new();
+    for archived in posts.iter() {
+        let creator_id = archived.post.info.creator_id.as_str();
+        match creators.iter_mut().find(|(id, _)| *id == creator_id) {
+            Some((_, count)) => *count += 1,
+            None => creators.push((creator_id, 1)),
+        }
+    }
+    creators.sort();
+
+    let mut lines = vec![
+        format!("<h1>{}</h1>", lang.label(Label::Creators)),
+        "<ul>".to_owned(),
+    ];
+    for (creator_id, count) in creators {
+        lines.push(format!(
+            "<li><a href='/creators/{}'>{}</a> ({})</li>",
+            percent_encode(creator_id),
+            escape_html(creator_id),
+            lang.post_count(count)
+        ));
+    }
+    lines.push("</ul>".to_owned());
+    Ok(html_response(lang, "fanbox-dl", &lines))
+}
+
+async fn posts_page(
+    server: &Server,
+    creator_id: &str,
+) -> anyhow::Result<hyper::Response<hyper::Body>> {
+    let posts = load_posts(server).await?;
+    let posts: Vec<_> = posts
+        .iter()
+        .filter(|archived| archived.post.info.creator_id == creator_id)
+        .collect();
+    if posts.is_empty() {
+        return Ok(not_found());
+    }
+    let mut lines = vec![format!("<h1>{}</h1>", escape_html(creator_id))];
+    lines.extend(post_list(&server.dest_dir, &posts));
+    Ok(html_response(server.lang, creator_id, &lines))
+}
+
+async fn search_page(server: &Server, query: &str) -> anyhow::Result<hyper::Response<hyper::Body>> {
+    let lang = server.lang;
+    let query_lower = query.to_lowercase();
+    let posts = load_posts(server).await?;
+    let posts: Vec<_> = posts
+        .iter()
+        .filter(|archived| {
+            archived
+                .post
+                .info
+                .title
+                .to_lowercase()
+                .contains(&query_lower)
+                || fanbox_dl::archive::post_text(&archived.post)
+                    .to_lowercase()
+                    .contains(&query_lower)
+        })
+        .collect();
+    let mut lines = vec![format!(
+        "<h1>{}: {} ({})</h1>",
+        lang.label(Label::Search),
+        escape_html(query),
+        lang.post_count(posts.len())
+    )];
+    lines.extend(post_list(&server.dest_dir, &posts));
+    Ok(html_response(lang, query, &lines))
+}
+
+fn post_list(
+    dest_dir: &std::path::Path,
+    posts: &[&fanbox_dl::archive::ArchivedPost],
+) -> Vec<String> {
+    let mut lines = vec!["<ul>".to_owned()];
+    for archived in posts {
+        let info = &archived.post.info;
+        let href = archived
+            .dir
+            .strip_prefix(dest_dir)
+            .unwrap_or(&archived.dir)
+            .iter()
+            .map(|component| percent_encode(&component.to_string_lossy()))
+            .collect::<Vec<_>>()
+            .join("/");
+        lines.push(format!(
+            "<li>{} <a href='/files/{}/{}'>{}</a> ({})</li>",
+            info.updated_datetime.format("%Y-%m-%d"),
+            href,
+            percent_encode(&archived.index_file_name()),
+            escape_html(&info.title),
+            escape_html(&info.creator_id)
+        ));
+    }
+    lines.push("</ul>".to_owned());
+    lines
+}
+
+/// Serves the file at `file_path` relative to `dest_dir`, which must be canonical.
+///
+/// Files resolved outside `dest_dir` through symbolic links and files of fanbox-dl itself, e.g.
+/// the state database, journals and partial downloads, are not found.
+async fn file_response(
+    dest_dir: &std::path::Path,
+    file_path: &str,
+) -> anyhow::Result<hyper::Response<hyper::Body>> {
+    let mut path = dest_dir.to_path_buf();
+    for segment in file_path.split('/') {
+        let segment = percent_encoding::percent_decode_str(segment).decode_utf8_lossy();
+        if segment.is_empty() || segment == "." || segment == ".." || segment.contains('\\') {
+            return Ok(not_found());
+        }
+        path.push(segment.as_ref());
+    }
+    let path = match tokio::fs::canonicalize(&path).await {
+        Ok(path) => path,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(not_found()),
+        Err(e) => return Err(e.into()),
+    };
+    match path.strip_prefix(dest_dir) {
+        Ok(relative) if !is_private(relative) => {}
+        _ => return Ok(not_found()),
+    }
+    let file = match tokio::fs::File::open(&path).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(not_found()),
+        Err(e) => return Err(e.into()),
+    };
+    if !file.metadata().await?.is_file() {
+        return Ok(not_found());
+    }
+    let content_type = mime_guess::from_path(&path).first_or_octet_stream();
+    let mut content_type = content_type.essence_str().to_owned();
+    if content_type.starts_with("text/") {
+        content_type.push_str("; charset=utf-8");
+    }
+    Ok(hyper::Response::builder()
+        .header(hyper::header::CONTENT_TYPE, content_type)
+        .body(hyper::Body::wrap_stream(tokio_util::io::ReaderStream::new(
+            file,
+        )))?)
+}
+
+/// Returns whether the path relative to DEST_DIR is a file of fanbox-dl not to be served, i.e.
+/// hidden files such as the journal, the state database, the download log or a partial download.
+fn is_private(relative: &std::path::Path) -> bool {
+    let is_hidden = relative
+        .iter()
+        .any(|component| component.to_string_lossy().starts_with('.'));
+    let name = relative
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    is_hidden
+        || name.starts_with(fanbox_dl::archive::STATE_DB_FILE_NAME)
+        || name == fanbox_dl::archive::DOWNLOAD_LOG_FILE_NAME
+        || relative
+            .extension()
+            .is_some_and(|extension| extension == fanbox_dl::archive::TEMPORARY_EXTENSION)
+}
+
+fn html_response(
+    lang: fanbox_dl::archive::Lang,
+    title: &str,
+    lines: &[String],
+) -> hyper::Response<hyper::Body> {
+    let html = format!(
+        "<!DOCTYPE html>\n<html lang='{}'>\n<head>\n<meta charset='utf-8'>\n<title>{}</title>\n</head>\n<body>\n<p><a href='/'>{}</a></p>\n<form action='/search'><input type='search' name='q'><input type='submit' value='{}'></form>\n{}\n</body>\n</html>\n",
+        lang.tag(),
+        escape_html(title),
+        lang.label(Label::Creators),
+        lang.label(Label::Search),
+        lines.join("\n")
+    );
+    hyper::Response::builder()
+        .header(hyper::header::CONTENT_TYPE, "text/html; charset=utf-8")
+        .body(hyper::Body::from(html))
+        .unwrap()
+}
+
+fn text_response(status: hyper::StatusCode, body: String) -> hyper::Response<hyper::Body> {
+    hyper::Response::builder()
+        .status(status)
+        .header(hyper::header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(hyper::Body::from(body))
+        .unwrap()
+}
+
+fn not_found() -> hyper::Response<hyper::Body> {
+    text_response(hyper::StatusCode::NOT_FOUND, "Not Found".to_owned())
+}
+
+fn percent_encode(s: &str) -> String {
+    percent_encoding::utf8_percent_encode(s, percent_encoding::NON_ALPHANUMERIC).to_string()
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\'', "&#39;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,147 @@
+#[derive(Debug, clap::Args)]
+pub struct StatsArgs {
+    /// Number of the biggest files printed
+    #[clap(long, default_value = "10")]
+    top: usize,
+    /// Print the statistics as JSON
+    #[clap(long)]
+    json: bool,
+    /// Print the report as CSV instead of the summary, e.g. to chart it in a spreadsheet
+    #[clap(long, value_enum, value_name = "REPORT", conflicts_with = "json")]
+    csv: Option<CsvReport>,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum CsvReport {
+    /// Number of published posts per month with a column for each creator
+    PostsByMonth,
+    /// Posts, files and bytes archived by each run of the download command, with the total
+    /// bytes archived so far
+    Runs,
+}
+
+/// Prints numbers of creators, posts and files of the archive, bytes per creator, post types,
+/// the date coverage and the biggest files, or the report given by `--csv`.
+pub async fn run(ctx: &super::Context, args: StatsArgs) -> anyhow::Result<()> {
+    use super::progress::format_bytes;
+
+    if let Some(CsvReport::Runs) = args.csv {
+        let runs = fanbox_dl::archive::StateDb::open(&ctx.dest_dir)?.runs()?;
+        println!("started,finished,posts,files,bytes,total_bytes");
+        let mut total_bytes = 0;
+        for run in runs {
+            total_bytes += run.bytes;
+            println!(
+                "{},{},{},{},{},{}",
+                run.started_datetime.to_rfc3339(),
+                run.finished_datetime.to_rfc3339(),
+                run.posts,
+                run.files,
+                run.bytes,
+                total_bytes
+            );
+        }
+        return Ok(());
+    }
+
+    let dest_dir = ctx.dest_dir.clone();
+    let stats =
+        tokio::task::spawn_blocking(move || fanbox_dl::archive::archive_stats(&dest_dir, args.top))
+            .await??;
+    if args.csv.is_some() {
+        print_posts_by_month(&stats);
+        return Ok(());
+    }
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+
+    println!(
+        "{} creators, {} posts, {} files, {}",
+        stats.creators.len(),
+        stats.posts,
+        stats.files,
+        format_bytes(stats.bytes as f64)
+    );
+    if let (Some(oldest), Some(newest)) = (stats.oldest, stats.newest) {
+        println!(
+            "Published from {} to {}",
+            oldest.format("%Y-%m-%d"),
+            newest.format("%Y-%m-%d")
+        );
+    }
+    if !stats.creators.is_empty() {
+        println!("Creators:");
+        let mut creators: Vec<_> = stats.creators.iter().collect();
+        creators.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes).then_with(|| a.0.cmp(b.0)));
+        for (creator_id, creator) in creators {
+            println!(
+                "  {}\t{} posts\t{} files\t{}",
+                creator_id,
+                creator.posts,
+                creator.files,
+                format_bytes(creator.bytes as f64)
+            );
+        }
+    }
+    if !stats.post_kinds.is_empty() {
+        println!("Post types:");
+        for (kind, count) in &stats.post_kinds {
+            println!("  {}\t{}", kind, count);
+        }
+    }
+    if !stats.biggest_files.is_empty() {
+        println!("Biggest files:");
+        for file in &stats.biggest_files {
+            println!(
+                "  {}\t{}",
+                format_bytes(file.size as f64),
+                file.path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Prints the number of posts of each creator per month as CSV, including months without posts.
+fn print_posts_by_month(stats: &fanbox_dl::archive::ArchiveStats) {
+    let creator_ids: Vec<_> = stats.creators.keys().collect();
+    let mut header = vec!["month"];
+    header.extend(creator_ids.iter().map(|creator_id| creator_id.as_str()));
+    println!("{}", header.join(","));
+
+    let (first, last) = match (
+        stats.monthly_posts.keys().next(),
+        stats.monthly_posts.keys().next_back(),
+    ) {
+        (Some(first), Some(last)) => (parse_month(first), parse_month(last)),
+        _ => return,
+    };
+    let (mut year, mut month) = first;
+    while (year, month) <= last {
+        let key = format!("{:04}-{:02}", year, month);
+        let counts = stats.monthly_posts.get(&key);
+        let mut row = vec![key];
+        row.extend(creator_ids.iter().map(|creator_id| {
+            counts
+                .and_then(|counts| counts.get(*creator_id))
+                .copied()
+                .unwrap_or(0)
+                .to_string()
+        }));
+        println!("{}", row.join(","));
+        if month == 12 {
+            year += 1;
+            month = 1;
+        } else {
+            month += 1;
+        }
+    }
+}
+
+/// Parses `YYYY-MM` into the year and the month.
+fn parse_month(key: &str) -> (i32, u32) {
+    let (year, month) = key.split_once('-').unwrap_or((key, "1"));
+    (year.parse().unwrap_or(0), month.parse().unwrap_or(1))
+}
@@ -0,0 +1,81 @@
+#[derive(Debug, clap::Args)]
+pub struct VerifyArgs {
+    /// Download broken files again
+    #[clap(long)]
+    redownload: bool,
+    /// Also check that files of posts exist and their mtimes and sizes match the post metadata
+    /// and the download log, without network access
+    #[clap(long, conflicts_with = "redownload")]
+    offline: bool,
+    /// Check as --offline and also hash every file to compare with the checksum in the download
+    /// log
+    #[clap(long, conflicts_with = "redownload")]
+    deep: bool,
+    /// Number of threads checking files with --offline or --deep [default: number of CPUs]
+    #[clap(short, long)]
+    jobs: Option<usize>,
+}
+
+/// Reports media files of the archive which can't be decoded, optionally downloading them again.
+pub async fn run(ctx: &super::Context, args: VerifyArgs) -> anyhow::Result<()> {
+    if args.offline || args.deep {
+        let dest_dir = ctx.dest_dir.clone();
+        let mut options = fanbox_dl::archive::AuditOptions {
+            deep: args.deep,
+            ..Default::default()
+        };
+        if let Some(jobs) = args.jobs {
+            options.jobs = jobs;
+        }
+        let count = tokio::task::spawn_blocking(move || {
+            fanbox_dl::archive::audit_archive(&dest_dir, &options, |issue| {
+                println!("{}\t{}", issue.path.display(), issue.kind);
+            })
+        })
+        .await??;
+        tracing::info!("{} inconsistent files found", count);
+    }
+
+    let dest_dir = ctx.dest_dir.clone();
+    let broken =
+        tokio::task::spawn_blocking(move || fanbox_dl::archive::find_broken_files(&dest_dir))
+            .await??;
+    for file in &broken {
+        println!("{}\t{}", file.path.display(), file.reason);
+    }
+    if !args.redownload {
+        tracing::info!("{} broken files found", broken.len());
+        return Ok(());
+    }
+
+    let client = ctx.client()?;
+    let mut remaining = 0;
+    for file in broken {
+        tracing::info!("Download {} again", file.url);
+        client
+            .download_media_to(
+                &file.url,
+                &file.path,
+                &file.post.updated_datetime,
+                Some(&format!(
+                    "https://{}.fanbox.cc/posts/{}",
+                    file.post.creator_id, file.post.id
+                )),
+            )
+            .await?;
+        let path = file.path.clone();
+        if let Some(reason) =
+            tokio::task::spawn_blocking(move || fanbox_dl::archive::check_file(&path)).await??
+        {
+            tracing::error!("{} is still broken: {}", file.path.display(), reason);
+            remaining += 1;
+        }
+    }
+    if remaining > 0 {
+        anyhow::bail!(
+            "{} files are still broken after downloading again",
+            remaining
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,226 @@
+//! C ABI of the downloader engine.
+//!
+//! Strings returned by these functions are owned by the caller and must be released with
+//! `fanbox_string_free`. When a function fails, it returns NULL (or a negative value) and the
+//! error message is available from `fanbox_last_error` on the same thread.
+
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+
+/// Opaque client handle.
+pub struct FanboxClient {
+    runtime: tokio::runtime::Runtime,
+    client: crate::PostClient,
+}
+
+thread_local! {
+    static LAST_ERROR: std::cell::RefCell<Option<CString>> = const { std::cell::RefCell::new(None) };
+}
+
+fn set_last_error<E>(e: E)
+where
+    E: std::fmt::Display,
+{
+    let message = CString::new(e.to_string().replace('\0', "")).unwrap();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+unsafe fn to_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        set_last_error("unexpected NULL argument");
+        return None;
+    }
+    match CStr::from_ptr(s).to_str() {
+        Ok(s) => Some(s),
+        Err(e) => {
+            set_last_error(e);
+            None
+        }
+    }
+}
+
+fn to_json_string<T>(value: &T) -> *mut c_char
+where
+    T: serde::Serialize,
+{
+    match serde_json::to_string(value) {
+        // JSON strings never contain NUL
+        Ok(json) => CString::new(json).unwrap().into_raw(),
+        Err(e) => {
+            set_last_error(e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Returns the error message of the last failed call on this thread, or NULL.
+///
+/// The returned string is valid until the next call on this thread.
+#[no_mangle]
+pub extern "C" fn fanbox_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| {
+        last_error
+            .borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Releases a string returned by this library.
+///
+/// # Safety
+/// `s` must be NULL or a string returned by this library which is not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn fanbox_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Creates a client authenticated with the FANBOXSESSID cookie value.
+///
+/// # Safety
+/// `session_id` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn fanbox_client_new(session_id: *const c_char) -> *mut FanboxClient {
+    let session_id = match to_str(session_id) {
+        Some(s) => s,
+        None => return std::ptr::null_mut(),
+    };
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            set_last_error(e);
+            return std::ptr::null_mut();
+        }
+    };
+    match crate::PostClient::new(session_id) {
+        Ok(client) => Box::into_raw(Box::new(FanboxClient { runtime, client })),
+        Err(e) => {
+            set_last_error(e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Releases a client created by `fanbox_client_new`.
+///
+/// # Safety
+/// `client` must be NULL or a client returned by `fanbox_client_new` which is not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn fanbox_client_free(client: *mut FanboxClient) {
+    if !client.is_null() {
+        drop(Box::from_raw(client));
+    }
+}
+
+/// Lists posts of the creator as a JSON array of listing items, newest first.
+///
+/// # Safety
+/// `client` must be a valid client and `creator_id` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn fanbox_list_posts(
+    client: *const FanboxClient,
+    creator_id: *const c_char,
+) -> *mut c_char {
+    use futures::stream::TryStreamExt as _;
+
+    let client = match client.as_ref() {
+        Some(client) => client,
+        None => {
+            set_last_error("unexpected NULL client");
+            return std::ptr::null_mut();
+        }
+    };
+    let creator_id = match to_str(creator_id) {
+        Some(s) => s,
+        None => return std::ptr::null_mut(),
+    };
+    let result = client.runtime.block_on(async {
+        client
+            .client
+            .paginate_creator(creator_id)
+            .await?
+            .try_collect::<Vec<_>>()
+            .await
+    });
+    match result {
+        Ok(items) => to_json_string(&items),
+        Err(e) => {
+            set_last_error(e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Fetches the post as a JSON object.
+///
+/// # Safety
+/// `client` must be a valid client and `post_id` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn fanbox_get_post_json(
+    client: *const FanboxClient,
+    post_id: *const c_char,
+) -> *mut c_char {
+    let client = match client.as_ref() {
+        Some(client) => client,
+        None => {
+            set_last_error("unexpected NULL client");
+            return std::ptr::null_mut();
+        }
+    };
+    let post_id = match to_str(post_id) {
+        Some(s) => s,
+        None => return std::ptr::null_mut(),
+    };
+    match client.runtime.block_on(client.client.get_post(post_id)) {
+        Ok(post) => to_json_string(&post),
+        Err(e) => {
+            set_last_error(e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Downloads `url` to `path` and sets its mtime to `mtime` (seconds since the UNIX epoch).
+///
+/// Returns 0 on success and -1 on failure.
+///
+/// # Safety
+/// `client` must be a valid client and `url` and `path` must be NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn fanbox_download_file(
+    client: *const FanboxClient,
+    url: *const c_char,
+    path: *const c_char,
+    mtime: i64,
+) -> c_int {
+    let client = match client.as_ref() {
+        Some(client) => client,
+        None => {
+            set_last_error("unexpected NULL client");
+            return -1;
+        }
+    };
+    let (url, path) = match (to_str(url), to_str(path)) {
+        (Some(url), Some(path)) => (url, path),
+        _ => return -1,
+    };
+    let mtime = match chrono::TimeZone::timestamp_opt(&chrono::Utc, mtime, 0).single() {
+        Some(mtime) => mtime,
+        None => {
+            set_last_error(format!("invalid mtime: {}", mtime));
+            return -1;
+        }
+    };
+    match client
+        .runtime
+        .block_on(client.client.download_to(url, path, &mtime))
+    {
+        Ok(()) => 0,
+        Err(e) => {
+            set_last_error(e);
+            -1
+        }
+    }
+}
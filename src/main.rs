@@ -1,488 +1,123 @@
-use anyhow::Context as _;
-use clap::Parser as _;
-use futures::stream::TryStreamExt as _;
+use clap::{Args as _, CommandFactory as _, FromArgMatches as _};
 
+mod cli;
+
+/// Command-line arguments. Arguments of `download` are also accepted without subcommand.
 #[derive(Debug, clap::Parser)]
+#[clap(subcommand_negates_reqs = true)]
 struct Args {
-    #[clap(env = "FANBOXSESSID")]
-    session_id: String,
-    #[clap(short, long)]
-    creator_id: String,
-    #[clap(short, long, default_value = ".")]
-    dest_dir: std::path::PathBuf,
+    #[clap(flatten)]
+    global: cli::GlobalArgs,
+    #[clap(subcommand)]
+    command: Option<Command>,
+    /// Value of FANBOXSESSID cookie, kept for compatibility with the invocation without subcommand
+    #[clap(hide = true, value_name = "SESSION_ID")]
+    legacy_session_id: Option<String>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Command {
+    /// Serve a JSON-RPC protocol over stdio to be driven by GUI frontends, writing logs to stderr
+    Agent,
+    /// Print the completion script of the shell
+    Completions(cli::completions::CompletionsArgs),
+    /// Print creators you support or follow
+    Creators(cli::creators::CreatorsArgs),
+    /// Compare the downloaded archive with posts of creators without downloading anything
+    Diff(cli::diff::DiffArgs),
+    /// Download posts of the creator (default)
+    Download(Box<cli::download::DownloadArgs>),
+    /// Print metadata of posts of the creator including their bodies as JSON lines without
+    /// downloading media
+    Dump(cli::dump::DumpArgs),
+    /// Convert the downloaded archive into Markdown, EPUB, CBZ, zip, HTML, MHTML or JSON files
+    /// without network access
+    Export(cli::export::ExportArgs),
+    /// Remove stale temporary files and report or hard-link duplicated files in the archive
+    Gc(cli::gc::GcArgs),
+    /// Print contents of a post without downloading it
+    Info(cli::info::InfoArgs),
+    /// Print posts of the creator as JSON lines without downloading them
+    List(cli::list::ListArgs),
+    /// Write man pages of fanbox-dl and its subcommands
+    Man(cli::man::ManArgs),
+    /// Upgrade the archive to the current format, or import files downloaded by the Go version
+    Migrate(cli::migrate::MigrateArgs),
+    /// Export the downloaded archive as notes of an Obsidian vault
+    Obsidian(cli::obsidian::ObsidianArgs),
+    /// Render HTML pages of the downloaded archive again without downloading media
+    Render(cli::render::RenderArgs),
+    /// Print JSON Schema of the post metadata
+    Schema,
+    /// Check recent posts of creators against the models and report unsupported types and
+    /// fields of the API
+    SchemaCheck(cli::schema_check::SchemaCheckArgs),
+    /// Search titles and text of the downloaded archive
+    Search(cli::search::SearchArgs),
+    /// Serve the downloaded archive over HTTP
+    Serve(cli::serve::ServeArgs),
+    /// Summarize the downloaded archive without reading contents of files
+    Stats(cli::stats::StatsArgs),
+    /// Report media files of the archive which can't be decoded
+    Verify(cli::verify::VerifyArgs),
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    if std::env::var_os("RUST_LOG").is_none() {
-        std::env::set_var("RUST_LOG", "info");
-    }
-    tracing_subscriber::fmt::init();
-    let args = Args::parse();
-
-    let client =
-        fanbox_dl::PostClient::new(&args.session_id).context("failed to build fanbox-dl client")?;
-
-    let items = client.paginate_creator(&args.creator_id).await?;
-    futures::pin_mut!(items);
-    while let Some(item) = items.try_next().await? {
-        tracing::debug!("Getting post {}", item.id);
-        let post = client.get_post(&item.id).await?;
-        if let Some(body) = post.body {
-            let dest_dir = args.dest_dir.join(&post.info.id);
-            std::fs::create_dir_all(&dest_dir)
-                .with_context(|| format!("failed to create directory: {}", dest_dir.display()))?;
-            match body {
-                fanbox_dl::PostBody::Image(image_body) => {
-                    download_image_post(&client, dest_dir, post.info, image_body.body).await?
-                }
-                fanbox_dl::PostBody::Article(article_body) => {
-                    download_article_post(&client, dest_dir, post.info, article_body.body).await?
-                }
-                fanbox_dl::PostBody::File(file_body) => {
-                    download_file_post(&client, dest_dir, post.info, file_body.body).await?
-                }
-                fanbox_dl::PostBody::Text(text_body) => {
-                    download_text_post(&client, dest_dir, post.info, text_body.body).await?
-                }
-                fanbox_dl::PostBody::Video(video_body) => {
-                    download_video_post(&client, dest_dir, post.info, video_body.body).await?
-                }
-                fanbox_dl::PostBody::Unknown => {
-                    tracing::warn!(
-                        "Unknown post type https://{}.fanbox.cc/posts/{}",
-                        args.creator_id,
-                        post.info.id
-                    );
-                }
-            }
-        } else {
-            tracing::warn!(
-                "You don't have permission to see post https://{}.fanbox.cc/posts/{}",
-                args.creator_id,
-                post.info.id
-            );
+    let mut command = cli::download::DownloadArgs::augment_args(Args::command());
+    let matches = command.get_matches_mut();
+    let args = Args::from_arg_matches(&matches)?;
+
+    let config = match &args.global.config {
+        Some(path) => cli::config::Config::load(path)?,
+        None => cli::config::Config::default(),
+    };
+    // stdout is reserved for the protocol
+    let log_to_stderr = matches!(args.command, Some(Command::Agent));
+    let logger = cli::logging::init(&args.global, &config, log_to_stderr)?;
+    let ctx = cli::Context::new(args.global, config, args.legacy_session_id)?;
+    let result = match args.command {
+        Some(Command::Agent) => cli::agent::run(ctx).await,
+        Some(Command::Completions(completions_args)) => {
+            cli::completions::run(&mut command, completions_args)
         }
-    }
-
-    Ok(())
-}
-
-async fn download_image_post(
-    client: &fanbox_dl::PostClient,
-    dest_dir: std::path::PathBuf,
-    info: fanbox_dl::PostInfo,
-    body: fanbox_dl::PostBodyImageBody,
-) -> anyhow::Result<()> {
-    let span = tracing::info_span!("image", id = %info.id);
-    let _enter = span.enter();
-
-    let mut index_lines = Vec::new();
-    index_lines.push(format!(
-        "<h1><a href='https://{}.fanbox.cc/posts/{}'>{}</a></h1>",
-        info.creator_id, info.id, info.title
-    ));
-
-    if let Some(cover_image_url) = info.cover_image_url {
-        tracing::info!("Download cover image {}", cover_image_url);
-        client
-            .download_to(
-                &cover_image_url,
-                dest_dir.join("cover_image.jpeg"),
-                &info.updated_datetime,
-            )
-            .await
-            .with_context(|| format!("failed to download {}", cover_image_url))?;
-        index_lines.push("<p>".to_owned());
-        index_lines.push(format!(
-            "<img alt='{}' src='./cover_image.jpeg'>",
-            cover_image_url
-        ));
-        index_lines.push("</p>".to_owned());
-    }
-
-    for image in body.images {
-        tracing::info!("Download image {}", image.original_url);
-        let path = dest_dir.join(format!("{}.{}", image.id, image.extension));
-        client
-            .download_to(&image.original_url, path, &info.updated_datetime)
-            .await
-            .with_context(|| format!("failed to download {}", image.original_url))?;
-        index_lines.push(format!(
-            "<p><img alt='{}' src='./{}.{}' style='width: 100%;'></p>",
-            image.original_url, image.id, image.extension
-        ));
-    }
-
-    index_lines.push(format!("<p>{}</p>", body.text));
-
-    let index_path = dest_dir.join("index.html");
-    tokio::fs::write(&index_path, index_lines.join("\n").as_bytes())
-        .await
-        .with_context(|| format!("failed to write {}", index_path.display()))?;
-    filetime::set_file_mtime(
-        &index_path,
-        filetime::FileTime::from_unix_time(
-            info.updated_datetime.timestamp(),
-            info.updated_datetime.timestamp_subsec_nanos(),
-        ),
-    )
-    .with_context(|| format!("failed to update mtime {}", index_path.display()))?;
-
-    Ok(())
-}
-
-async fn download_article_post(
-    client: &fanbox_dl::PostClient,
-    dest_dir: std::path::PathBuf,
-    info: fanbox_dl::PostInfo,
-    body: fanbox_dl::PostBodyArticleBody,
-) -> anyhow::Result<()> {
-    let span = tracing::info_span!("article", id = %info.id);
-    let _enter = span.enter();
-
-    let mut index_lines = Vec::new();
-    index_lines.push(format!(
-        "<h1><a href='https://{}.fanbox.cc/posts/{}'>{}</a></h1>",
-        info.creator_id, info.id, info.title
-    ));
-
-    if let Some(cover_image_url) = info.cover_image_url {
-        tracing::info!("Download cover image {}", cover_image_url);
-        client
-            .download_to(
-                &cover_image_url,
-                dest_dir.join("cover_image.jpeg"),
-                &info.updated_datetime,
-            )
-            .await
-            .with_context(|| format!("failed to download {}", cover_image_url))?;
-        index_lines.push(format!(
-            "<p><img alt='{}' src='./cover_image.jpeg'></p>",
-            cover_image_url
-        ));
-    }
-
-    for block in body.blocks {
-        index_lines.push("<p>".to_owned());
-        match block {
-            fanbox_dl::ArticleBlock::P(p_block) => {
-                index_lines.push(p_block.text);
-            }
-            fanbox_dl::ArticleBlock::Header(header_block) => {
-                index_lines.push(format!("<h2>{}</h2>", header_block.text));
-            }
-            fanbox_dl::ArticleBlock::Image(image_block) => {
-                if let Some(image) = body.image_map.get(&image_block.image_id) {
-                    tracing::info!("Download image {}", image.original_url);
-                    let path = dest_dir.join(format!("{}.{}", image.id, image.extension));
-                    client
-                        .download_to(&image.original_url, path, &info.updated_datetime)
-                        .await
-                        .with_context(|| format!("failed to download {}", image.original_url))?;
-                    index_lines.push(format!(
-                        "<img alt='{}' src='./{}.{}' style='width: 100%;'>",
-                        image.original_url, image.id, image.extension
-                    ));
-                } else {
-                    tracing::warn!(
-                        "image {} is not available in imageMap",
-                        image_block.image_id
-                    );
-                }
-            }
-            fanbox_dl::ArticleBlock::File(file_block) => {
-                if let Some(file) = body.file_map.get(&file_block.file_id) {
-                    tracing::info!("Download file {}", file.url);
-                    let path = dest_dir.join(format!("{}.{}", file.id, file.extension));
-                    client
-                        .download_to(&file.url, path, &info.updated_datetime)
-                        .await
-                        .with_context(|| format!("failed to download {}", file.url))?;
-                    index_lines.push(format!(
-                        "<a href='./{}.{}'>{}</a>",
-                        file.id, file.extension, file.name
-                    ));
-                } else {
-                    tracing::warn!("file {} is not available in fileMap", file_block.file_id);
-                }
-            }
-            fanbox_dl::ArticleBlock::Embed(embed_block) => {
-                if let Some(embed) = body.embed_map.get(&embed_block.embed_id) {
-                    match embed {
-                        fanbox_dl::Embed::Twitter(twitter) => {
-                            // embedMap doesn't have screen name but /*/status/:id is redirected to
-                            // the currect URL.
-                            index_lines.push(format!(
-                                "<a href='https://twitter.com/unknown/status/{}'>twitter:{}</a>",
-                                twitter.content_id, twitter.content_id
-                            ));
-                        }
-                        fanbox_dl::Embed::Fanbox(fanbox) => {
-                            let parts: Vec<_> = fanbox.content_id.split('/').collect();
-                            if parts.len() == 4 && parts[0] == "creator" && parts[2] == "post" {
-                                let post = client.get_post(parts[3]).await.with_context(|| {
-                                    format!("failed to get embeded fanbox post: {}", parts[3])
-                                })?;
-                                index_lines.push(format!(
-                                    "<a href='{}'>{}</a>",
-                                    post.info.creator_id, post.info.title,
-                                ));
-                            } else {
-                                tracing::warn!(
-                                    "Unsupported embed content_id of fanbox: {}",
-                                    fanbox.content_id
-                                );
-                            }
-                        }
-                        fanbox_dl::Embed::Youtube(youtube) => {
-                            index_lines.push(format!(
-                                "<a href='https://www.youtube.com/watch?v={}'>https://www.youtube.com/watch?v={}</a>",
-                                youtube.content_id, youtube.content_id
-                            ));
-                        }
-                        fanbox_dl::Embed::Vimeo(vimeo) => {
-                            index_lines.push(format!(
-                                "<a href='https://vimeo.com/{}'>https://vimeo.com/{}</a>",
-                                vimeo.content_id, vimeo.content_id
-                            ));
-                        }
-                        fanbox_dl::Embed::Unknown => {
-                            tracing::warn!("Unknown serviceProvider was found in embedMap https://{}.fanbox.cc/posts/{}", info.creator_id, info.id);
-                        }
-                    }
-                } else {
-                    tracing::warn!(
-                        "embed {} is not available in embedMap",
-                        embed_block.embed_id
-                    );
-                }
-            }
-            fanbox_dl::ArticleBlock::UrlEmbed(url_embed_block) => {
-                if let Some(url_embed) = body.url_embed_map.get(&url_embed_block.url_embed_id) {
-                    match url_embed {
-                        fanbox_dl::UrlEmbed::Default(default) => {
-                            index_lines
-                                .push(format!("<a href='{}'>{}</a>", default.url, default.url));
-                        }
-                        fanbox_dl::UrlEmbed::Html(html) | fanbox_dl::UrlEmbed::HtmlCard(html) => {
-                            index_lines.push(html.html.to_owned());
-                        }
-                        fanbox_dl::UrlEmbed::Unknown => {
-                            tracing::warn!("Unknown type was found in urlEmbedMap https://{}.fanbox.cc/posts/{}", info.creator_id, info.id);
-                        }
-                    }
-                } else {
-                    tracing::warn!(
-                        "url_embed {} is not available in urlEmbedMap",
-                        url_embed_block.url_embed_id
-                    );
-                }
-            }
-            fanbox_dl::ArticleBlock::Unknown => {
-                tracing::warn!(
-                    "Unknown block was found in artcle post https://{}.fanbox.cc/posts/{}",
-                    info.creator_id,
-                    info.id
-                );
-            }
+        Some(Command::Creators(creators_args)) => cli::creators::run(&ctx, creators_args).await,
+        Some(Command::Diff(diff_args)) => cli::diff::run(&ctx, diff_args).await,
+        Some(Command::Download(download_args)) => cli::download::run(&ctx, *download_args).await,
+        Some(Command::Dump(dump_args)) => cli::dump::run(&ctx, dump_args).await,
+        Some(Command::Export(export_args)) => cli::export::run(&ctx, export_args).await,
+        Some(Command::Gc(gc_args)) => cli::gc::run(&ctx, gc_args).await,
+        Some(Command::Info(info_args)) => cli::info::run(&ctx, info_args).await,
+        Some(Command::List(list_args)) => cli::list::run(&ctx, list_args).await,
+        Some(Command::Man(man_args)) => cli::man::run(&command, man_args),
+        Some(Command::Migrate(migrate_args)) => cli::migrate::run(&ctx, migrate_args).await,
+        Some(Command::Obsidian(obsidian_args)) => cli::obsidian::run(&ctx, obsidian_args).await,
+        Some(Command::Render(render_args)) => cli::render::run(&ctx, render_args).await,
+        Some(Command::Schema) => cli::schema::run(),
+        Some(Command::SchemaCheck(schema_check_args)) => {
+            cli::schema_check::run(&ctx, schema_check_args).await
         }
-        index_lines.push("</p>".to_owned());
-    }
-
-    let index_path = dest_dir.join("index.html");
-    tokio::fs::write(&index_path, index_lines.join("\n").as_bytes())
-        .await
-        .with_context(|| format!("failed to write {}", index_path.display()))?;
-    filetime::set_file_mtime(
-        &index_path,
-        filetime::FileTime::from_unix_time(
-            info.updated_datetime.timestamp(),
-            info.updated_datetime.timestamp_subsec_nanos(),
-        ),
-    )
-    .with_context(|| format!("failed to update mtime {}", index_path.display()))?;
-
-    Ok(())
-}
-
-async fn download_file_post(
-    client: &fanbox_dl::PostClient,
-    dest_dir: std::path::PathBuf,
-    info: fanbox_dl::PostInfo,
-    body: fanbox_dl::PostBodyFileBody,
-) -> anyhow::Result<()> {
-    let span = tracing::info_span!("file", id = %info.id);
-    let _enter = span.enter();
-
-    let mut index_lines = Vec::new();
-    index_lines.push(format!(
-        "<h1><a href='https://{}.fanbox.cc/posts/{}'>{}</a></h1>",
-        info.creator_id, info.id, info.title
-    ));
-
-    if let Some(cover_image_url) = info.cover_image_url {
-        tracing::info!("Download cover image {}", cover_image_url);
-        client
-            .download_to(
-                &cover_image_url,
-                dest_dir.join("cover_image.jpeg"),
-                &info.updated_datetime,
-            )
-            .await
-            .with_context(|| format!("failed to download {}", cover_image_url))?;
-        index_lines.push("<p>".to_owned());
-        index_lines.push(format!(
-            "<img alt='{}' src='./cover_image.jpeg'>",
-            cover_image_url
-        ));
-        index_lines.push("</p>".to_owned());
-    }
-
-    for file in body.files {
-        tracing::info!("Download file {}", file.url);
-        let path = dest_dir.join(format!("{}.{}", file.id, file.extension));
-        client
-            .download_to(&file.url, path, &info.updated_datetime)
-            .await
-            .with_context(|| format!("failed to download {}", file.url))?;
-        index_lines.push("<p>".to_owned());
-        index_lines.push(format!(
-            "<a href='./{}.{}'>{}</a>",
-            file.id, file.extension, file.name
-        ));
-        index_lines.push("</p>".to_owned());
-    }
-
-    index_lines.push(format!("<p>{}</p>", body.text));
-
-    let index_path = dest_dir.join("index.html");
-    tokio::fs::write(&index_path, index_lines.join("\n").as_bytes())
-        .await
-        .with_context(|| format!("failed to write {}", index_path.display()))?;
-    filetime::set_file_mtime(
-        &index_path,
-        filetime::FileTime::from_unix_time(
-            info.updated_datetime.timestamp(),
-            info.updated_datetime.timestamp_subsec_nanos(),
-        ),
-    )
-    .with_context(|| format!("failed to update mtime {}", index_path.display()))?;
-
-    Ok(())
-}
-
-async fn download_text_post(
-    client: &fanbox_dl::PostClient,
-    dest_dir: std::path::PathBuf,
-    info: fanbox_dl::PostInfo,
-    body: fanbox_dl::PostBodyTextBody,
-) -> anyhow::Result<()> {
-    let span = tracing::info_span!("text", id = %info.id);
-    let _enter = span.enter();
-
-    let mut index_lines = Vec::new();
-    index_lines.push(format!(
-        "<h1><a href='https://{}.fanbox.cc/posts/{}'>{}</a></h1>",
-        info.creator_id, info.id, info.title
-    ));
-
-    if let Some(cover_image_url) = info.cover_image_url {
-        tracing::info!("Download cover image {}", cover_image_url);
-        client
-            .download_to(
-                &cover_image_url,
-                dest_dir.join("cover_image.jpeg"),
-                &info.updated_datetime,
-            )
-            .await
-            .with_context(|| format!("failed to download {}", cover_image_url))?;
-        index_lines.push("<p>".to_owned());
-        index_lines.push(format!(
-            "<img alt='{}' src='./cover_image.jpeg'>",
-            cover_image_url
-        ));
-        index_lines.push("</p>".to_owned());
-    }
-
-    index_lines.push(format!("<p>{}</p>", body.text));
-
-    let index_path = dest_dir.join("index.html");
-    tokio::fs::write(&index_path, index_lines.join("\n").as_bytes())
-        .await
-        .with_context(|| format!("failed to write {}", index_path.display()))?;
-    filetime::set_file_mtime(
-        &index_path,
-        filetime::FileTime::from_unix_time(
-            info.updated_datetime.timestamp(),
-            info.updated_datetime.timestamp_subsec_nanos(),
-        ),
-    )
-    .with_context(|| format!("failed to update mtime {}", index_path.display()))?;
-
-    Ok(())
-}
-
-async fn download_video_post(
-    client: &fanbox_dl::PostClient,
-    dest_dir: std::path::PathBuf,
-    info: fanbox_dl::PostInfo,
-    body: fanbox_dl::PostBodyVideoBody,
-) -> anyhow::Result<()> {
-    let span = tracing::info_span!("video", id = %info.id);
-    let _enter = span.enter();
-
-    let mut index_lines = Vec::new();
-    index_lines.push(format!(
-        "<h1><a href='https://{}.fanbox.cc/posts/{}'>{}</a></h1>",
-        info.creator_id, info.id, info.title
-    ));
-
-    if let Some(cover_image_url) = info.cover_image_url {
-        tracing::info!("Download cover image {}", cover_image_url);
-        client
-            .download_to(
-                &cover_image_url,
-                dest_dir.join("cover_image.jpeg"),
-                &info.updated_datetime,
+        Some(Command::Search(search_args)) => cli::search::run(&ctx, search_args).await,
+        Some(Command::Serve(serve_args)) => cli::serve::run(&ctx, serve_args).await,
+        Some(Command::Stats(stats_args)) => cli::stats::run(&ctx, stats_args).await,
+        Some(Command::Verify(verify_args)) => cli::verify::run(&ctx, verify_args).await,
+        None => {
+            let download_args = cli::download::DownloadArgs::from_arg_matches(&matches)?;
+            cli::download::run(&ctx, download_args).await
+        }
+    };
+    logger.shutdown();
+    result.map_err(|e| {
+        let unauthorized = e.chain().any(|cause| {
+            matches!(
+                cause.downcast_ref::<fanbox_dl::Error>(),
+                Some(fanbox_dl::Error::Unauthorized { .. })
             )
-            .await
-            .with_context(|| format!("failed to download {}", cover_image_url))?;
-        index_lines.push("<p>".to_owned());
-        index_lines.push(format!(
-            "<img alt='{}' src='./cover_image.jpeg'>",
-            cover_image_url
-        ));
-        index_lines.push("</p>".to_owned());
-    }
-
-    index_lines.push("<p>".to_owned());
-    match body.video {
-        fanbox_dl::Video::Youtube(youtube) => index_lines.push(format!(
-            "<a href='https://www.youtube.com/watch?v={}'>https://www.youtube.com/watch?v={}</a>",
-            youtube.video_id, youtube.video_id
-        )),
-        fanbox_dl::Video::Vimeo(vimeo) => index_lines.push(format!(
-            "<a href='https://vimeo.com/{}'>https://vimeo.com/{}</a>",
-            vimeo.video_id, vimeo.video_id
-        )),
-    }
-    index_lines.push("</p>".to_owned());
-
-    index_lines.push(format!("<p>{}</p>", body.text));
-
-    let index_path = dest_dir.join("index.html");
-    tokio::fs::write(&index_path, index_lines.join("\n").as_bytes())
-        .await
-        .with_context(|| format!("failed to write {}", index_path.display()))?;
-    filetime::set_file_mtime(
-        &index_path,
-        filetime::FileTime::from_unix_time(
-            info.updated_datetime.timestamp(),
-            info.updated_datetime.timestamp_subsec_nanos(),
-        ),
-    )
-    .with_context(|| format!("failed to update mtime {}", index_path.display()))?;
-
-    Ok(())
+        });
+        if unauthorized {
+            e.context("the session is invalid or has expired: log in to FANBOX again and update FANBOXSESSID or the cookie file")
+        } else {
+            e
+        }
+    })
 }